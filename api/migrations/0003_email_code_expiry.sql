@@ -0,0 +1,2 @@
+-- Confirmation codes expire, older codes are treated as already expired
+ALTER TABLE email_codes ADD COLUMN expires_at INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,4 @@
+-- Capture time and position read from the EXIF tags of uploaded pictures
+ALTER TABLE pictures ADD COLUMN taken_at TEXT;
+ALTER TABLE pictures ADD COLUMN latitude REAL;
+ALTER TABLE pictures ADD COLUMN longitude REAL;
@@ -0,0 +1,2 @@
+-- Country the licence plate was issued in, existing reports are Czech
+ALTER TABLE reports ADD COLUMN plate_country TEXT NOT NULL DEFAULT 'CZ';
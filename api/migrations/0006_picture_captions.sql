@@ -0,0 +1,2 @@
+-- Optional caption of each report picture, the picture at position 0 is the primary evidence
+ALTER TABLE report_pictures ADD COLUMN caption TEXT;
@@ -0,0 +1,14 @@
+-- Published copy of each picture with faces and bystander plates blurred
+ALTER TABLE pictures ADD COLUMN redacted TEXT;
+
+-- Blurred regions as fractions of the upright picture, `kind` is face, plate or manual
+CREATE TABLE picture_regions (
+    picture_id INTEGER NOT NULL REFERENCES pictures(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    x REAL NOT NULL,
+    y REAL NOT NULL,
+    width REAL NOT NULL,
+    height REAL NOT NULL
+);
+
+CREATE INDEX picture_regions_picture ON picture_regions(picture_id);
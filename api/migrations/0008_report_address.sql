@@ -0,0 +1,6 @@
+-- Address point nearest to the GPS position, resolved by the offline geocoder
+ALTER TABLE reports ADD COLUMN address_street TEXT;
+ALTER TABLE reports ADD COLUMN address_house_number TEXT;
+ALTER TABLE reports ADD COLUMN address_city TEXT;
+ALTER TABLE reports ADD COLUMN address_district TEXT;
+ALTER TABLE reports ADD COLUMN address_postcode TEXT;
@@ -0,0 +1,16 @@
+-- Processing status of reports and how it got there
+ALTER TABLE reports ADD COLUMN status TEXT NOT NULL DEFAULT 'submitted';
+
+CREATE TABLE report_status_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    report_id INTEGER NOT NULL REFERENCES reports(id) ON DELETE CASCADE,
+    status TEXT NOT NULL,
+    note TEXT,
+    changed_by INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    changed_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%S', 'now'))
+);
+
+CREATE INDEX report_status_history_report_id ON report_status_history(report_id);
+
+INSERT INTO report_status_history (report_id, status, changed_by, changed_at)
+SELECT id, 'submitted', reporter_id, created_at FROM reports;
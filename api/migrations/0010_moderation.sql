@@ -0,0 +1,4 @@
+-- Rejection reasons of status changes and the moderation queue
+ALTER TABLE report_status_history ADD COLUMN reason TEXT;
+
+CREATE INDEX reports_status ON reports(status, created_at);
@@ -0,0 +1,28 @@
+-- Emails forwarding reports to the authorities and the replies and bounces
+-- threaded back onto them
+CREATE TABLE report_forwards (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    report_id INTEGER NOT NULL REFERENCES reports(id) ON DELETE CASCADE,
+    authority TEXT NOT NULL,
+    recipient TEXT NOT NULL,
+    message_id TEXT NOT NULL UNIQUE,
+    status TEXT NOT NULL,
+    error TEXT,
+    sent_by INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    sent_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%S', 'now'))
+);
+
+CREATE INDEX report_forwards_report_id ON report_forwards(report_id);
+
+CREATE TABLE report_forward_messages (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    forward_id INTEGER NOT NULL REFERENCES report_forwards(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    message_id TEXT UNIQUE,
+    sender TEXT,
+    subject TEXT,
+    body TEXT NOT NULL,
+    received_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%S', 'now'))
+);
+
+CREATE INDEX report_forward_messages_forward_id ON report_forward_messages(forward_id);
@@ -0,0 +1,3 @@
+-- Finding other reports of the same car, plates are stored normalized so
+-- dropping the spaces gives the plate as parsed
+CREATE INDEX reports_plate ON reports(plate_country, replace(plate, ' ', ''), observed_at);
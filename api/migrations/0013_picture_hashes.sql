@@ -0,0 +1,3 @@
+-- Perceptual hashes of the upright original, 64 bits stored as signed integers
+ALTER TABLE pictures ADD COLUMN dhash INTEGER;
+ALTER TABLE pictures ADD COLUMN phash INTEGER;
@@ -0,0 +1,3 @@
+-- Why a picture uploaded before hashing could not be hashed, so it is not
+-- tried again
+ALTER TABLE pictures ADD COLUMN hash_error TEXT;
@@ -0,0 +1,213 @@
+//! Password hashing and bearer token authentication.
+
+use crate::db::Db;
+use crate::error::ApiError;
+use crate::permissions::{self, Required};
+use crate::settings;
+use actix_web::web::{self, Data};
+use actix_web::{dev, FromRequest, HttpRequest};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::SaltString;
+use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use time::OffsetDateTime;
+use tracing::error;
+
+/// Hash a password into a PHC string
+pub fn hash_password(password: &str) -> Result<String, ApiError> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|h| h.to_string())
+        .map_err(|e| {
+            error!("Failed to hash password: {e}");
+            ApiError::InternalServerError(e.to_string())
+        })
+}
+
+/// Check a password against a stored PHC string
+pub fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .map(|h| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &h)
+                .is_ok()
+        })
+        .unwrap_or(false)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Claims {
+    /// User id
+    pub sub: i64,
+    /// Token id, used for revocation
+    pub jti: String,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+/// Issues and checks JWT tokens
+#[derive(Clone)]
+pub struct Tokens {
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    lifetime: time::Duration,
+}
+
+impl Tokens {
+    pub fn new(settings: &settings::Auth) -> Self {
+        Self {
+            encoding: EncodingKey::from_secret(settings.jwt_secret.as_bytes()),
+            decoding: DecodingKey::from_secret(settings.jwt_secret.as_bytes()),
+            lifetime: time::Duration::hours(settings.token_lifetime_hours),
+        }
+    }
+
+    /// Create a signed token for the user
+    pub fn issue(&self, user_id: i64) -> Result<String, ApiError> {
+        let now = OffsetDateTime::now_utc();
+        let claims = Claims {
+            sub: user_id,
+            jti: uuid::Uuid::new_v4().to_string(),
+            iat: now.unix_timestamp(),
+            exp: (now + self.lifetime).unix_timestamp(),
+        };
+        jsonwebtoken::encode(&Header::default(), &claims, &self.encoding).map_err(|e| {
+            error!("Failed to sign token: {e}");
+            ApiError::InternalServerError(e.to_string())
+        })
+    }
+
+    /// Verify signature and expiry of a token
+    pub fn decode(&self, token: &str) -> Result<Claims, ApiError> {
+        jsonwebtoken::decode::<Claims>(token, &self.decoding, &Validation::default())
+            .map(|data| data.claims)
+            .map_err(|_| ApiError::Unauthorized("Invalid token".to_string()))
+    }
+}
+
+/// User authenticated by a valid, not revoked, bearer token
+#[derive(Clone, Debug)]
+pub struct AuthenticatedUser {
+    pub id: i64,
+    pub token: String,
+    pub claims: Claims,
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = ApiError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut dev::Payload) -> Self::Future {
+        let bearer = BearerAuth::from_request(req, payload).into_inner();
+        let tokens = req.app_data::<Data<Tokens>>().cloned();
+        let db = req.app_data::<Data<Db>>().cloned();
+        Box::pin(async move {
+            let (Some(tokens), Some(db)) = (tokens, db) else {
+                error!("Authentication is not configured");
+                return Err(ApiError::InternalServerError(
+                    "Authentication is not configured".to_string(),
+                ));
+            };
+            let token = bearer
+                .map_err(|_| ApiError::Unauthorized("Missing token".to_string()))?
+                .token()
+                .to_string();
+            let claims = tokens.decode(&token)?;
+            let jti = claims.jti.clone();
+            if web::block(move || db.is_token_revoked(&jti)).await?? {
+                return Err(ApiError::Unauthorized("Token revoked".to_string()));
+            }
+            Ok(Self {
+                id: claims.sub,
+                token,
+                claims,
+            })
+        })
+    }
+}
+
+/// Authenticated user holding the permission `P`, otherwise 403
+pub struct Authorized<P> {
+    pub user: AuthenticatedUser,
+    /// All permissions of the user
+    pub permissions: Vec<String>,
+    permission: PhantomData<P>,
+}
+
+impl<P: Required + 'static> FromRequest for Authorized<P> {
+    type Error = ApiError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut dev::Payload) -> Self::Future {
+        let user = AuthenticatedUser::from_request(req, payload);
+        let db = req.app_data::<Data<Db>>().cloned();
+        Box::pin(async move {
+            let user = user.await?;
+            let Some(db) = db else {
+                error!("Authorization is not configured");
+                return Err(ApiError::InternalServerError(
+                    "Authorization is not configured".to_string(),
+                ));
+            };
+            let id = user.id;
+            let permissions = web::block(move || db.user_permissions(id)).await??;
+            if !permissions::granted(&permissions, P::NAME) {
+                return Err(ApiError::Forbidden(format!(
+                    "Missing permission {}",
+                    P::NAME
+                )));
+            }
+            Ok(Self {
+                user,
+                permissions,
+                permission: PhantomData,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens() -> Tokens {
+        Tokens::new(&settings::Auth {
+            jwt_secret: "0123456789abcdef0123456789abcdef".to_string(),
+            token_lifetime_hours: 1,
+        })
+    }
+
+    #[test]
+    fn password_roundtrip() {
+        let hash = hash_password("correct horse").unwrap();
+        assert!(verify_password("correct horse", &hash));
+        assert!(!verify_password("battery staple", &hash));
+        assert!(!verify_password("correct horse", "not a hash"));
+    }
+
+    #[test]
+    fn token_roundtrip() {
+        let tokens = tokens();
+        let claims = tokens.decode(&tokens.issue(7).unwrap()).unwrap();
+        assert_eq!(claims.sub, 7);
+    }
+
+    #[test]
+    fn token_wrong_secret() {
+        let token = tokens().issue(7).unwrap();
+        let other = Tokens::new(&settings::Auth {
+            jwt_secret: "fedcba9876543210fedcba9876543210".to_string(),
+            token_lifetime_hours: 1,
+        });
+        assert!(matches!(
+            other.decode(&token),
+            Err(ApiError::Unauthorized(_))
+        ));
+    }
+}
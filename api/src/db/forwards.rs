@@ -0,0 +1,207 @@
+use super::{ForwardRepository, Result, Sqlite};
+use crate::types::forward::{Forward, ForwardStatus, InboundMessage, MessageKind};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row};
+
+const FORWARD_COLUMNS: &str =
+    "id, report_id, authority, recipient, message_id, status, error, sent_by, sent_at";
+
+/// Email about to be recorded for a report
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NewForward {
+    pub report_id: i64,
+    pub authority: String,
+    pub recipient: String,
+    pub message_id: String,
+    pub status: ForwardStatus,
+    pub error: Option<String>,
+    pub sent_by: Option<i64>,
+}
+
+/// Reply or bounce about to be threaded onto a forward
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NewMessage {
+    pub kind: MessageKind,
+    /// `Message-ID` of the received email, without the angle brackets
+    pub message_id: Option<String>,
+    pub sender: Option<String>,
+    pub subject: Option<String>,
+    pub body: String,
+}
+
+fn from_row(row: &Row) -> rusqlite::Result<Forward> {
+    Ok(Forward {
+        id: row.get(0)?,
+        report_id: row.get(1)?,
+        authority: row.get(2)?,
+        recipient: row.get(3)?,
+        message_id: row.get(4)?,
+        status: ForwardStatus::from_name(&row.get::<_, String>(5)?).unwrap_or_default(),
+        error: row.get(6)?,
+        sent_by: row.get(7)?,
+        sent_at: row.get(8)?,
+        messages: Vec::new(),
+    })
+}
+
+fn with_messages(conn: &Connection, mut forward: Forward) -> rusqlite::Result<Forward> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT id, kind, sender, subject, body, received_at FROM report_forward_messages
+         WHERE forward_id = ?1 ORDER BY received_at, id",
+    )?;
+    forward.messages = stmt
+        .query_map([forward.id], |row| {
+            Ok(InboundMessage {
+                id: row.get(0)?,
+                kind: MessageKind::from_name(&row.get::<_, String>(1)?).unwrap_or_default(),
+                sender: row.get(2)?,
+                subject: row.get(3)?,
+                body: row.get(4)?,
+                received_at: row.get(5)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(forward)
+}
+
+fn get_forward(conn: &Connection, id: i64) -> rusqlite::Result<Forward> {
+    let forward = conn.query_row(
+        &format!("SELECT {FORWARD_COLUMNS} FROM report_forwards WHERE id = ?1"),
+        [id],
+        from_row,
+    )?;
+    with_messages(conn, forward)
+}
+
+impl ForwardRepository for Sqlite {
+    fn create_forward(&self, forward: &NewForward) -> Result<Forward> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO report_forwards (report_id, authority, recipient, message_id, status, error, sent_by)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                forward.report_id,
+                forward.authority,
+                forward.recipient,
+                forward.message_id,
+                forward.status.as_str(),
+                forward.error,
+                forward.sent_by
+            ],
+        )?;
+        Ok(get_forward(&conn, conn.last_insert_rowid())?)
+    }
+
+    fn report_forwards(&self, report_id: i64) -> Result<Vec<Forward>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT {FORWARD_COLUMNS} FROM report_forwards WHERE report_id = ?1 ORDER BY sent_at, id"
+        ))?;
+        let forwards = stmt
+            .query_map([report_id], from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(forwards
+            .into_iter()
+            .map(|f| with_messages(&conn, f))
+            .collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    fn find_forward(&self, message_ids: &[String]) -> Result<Option<Forward>> {
+        if message_ids.is_empty() {
+            return Ok(None);
+        }
+        let conn = self.conn()?;
+        let placeholders = vec!["?"; message_ids.len()].join(", ");
+        let forward = conn
+            .query_row(
+                &format!(
+                    "SELECT {FORWARD_COLUMNS} FROM report_forwards WHERE message_id IN ({placeholders})
+                     ORDER BY sent_at DESC, id DESC LIMIT 1"
+                ),
+                params_from_iter(message_ids),
+                from_row,
+            )
+            .optional()?;
+        Ok(forward.map(|f| with_messages(&conn, f)).transpose()?)
+    }
+
+    fn add_forward_message(&self, forward_id: i64, message: &NewMessage) -> Result<Forward> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO report_forward_messages (forward_id, kind, message_id, sender, subject, body)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                forward_id,
+                message.kind.as_str(),
+                message.message_id,
+                message.sender,
+                message.subject,
+                message.body
+            ],
+        )?;
+        let status = match message.kind {
+            MessageKind::Reply => ForwardStatus::Replied,
+            MessageKind::Bounce => ForwardStatus::Bounced,
+        };
+        tx.execute(
+            "UPDATE report_forwards SET status = ?1 WHERE id = ?2",
+            params![status.as_str(), forward_id],
+        )?;
+        let forward = get_forward(&tx, forward_id)?;
+        tx.commit()?;
+        Ok(forward)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{test_db, ReportRepository, StorageError};
+    use crate::types::report::{NewReport, ReportStatus};
+
+    #[test]
+    fn thread_messages() {
+        let db = test_db();
+        let report = db
+            .create_report(&NewReport::default(), None, None, ReportStatus::Submitted)
+            .unwrap();
+        let forward = db
+            .create_forward(&NewForward {
+                report_id: report.id,
+                authority: "City police".to_string(),
+                recipient: "police@example.com".to_string(),
+                message_id: "report-1.a@example.com".to_string(),
+                ..NewForward::default()
+            })
+            .unwrap();
+        assert_eq!(forward.status, ForwardStatus::Sent);
+        assert_eq!(
+            db.find_forward(&["other@example.com".to_string()]).unwrap(),
+            None
+        );
+        let found = db
+            .find_forward(&[
+                "other@example.com".to_string(),
+                "report-1.a@example.com".to_string(),
+            ])
+            .unwrap();
+        assert_eq!(found, Some(forward.clone()));
+
+        let reply = NewMessage {
+            kind: MessageKind::Reply,
+            message_id: Some("reply@police.example.com".to_string()),
+            sender: Some("police@example.com".to_string()),
+            subject: Some("Re: report".to_string()),
+            body: "We are on it".to_string(),
+        };
+        let replied = db.add_forward_message(forward.id, &reply).unwrap();
+        assert_eq!(replied.status, ForwardStatus::Replied);
+        assert_eq!(replied.messages[0].body, "We are on it");
+        assert!(matches!(
+            db.add_forward_message(forward.id, &reply),
+            Err(StorageError::Conflict(_))
+        ));
+        let forwards = db.report_forwards(report.id).unwrap();
+        assert_eq!(forwards, vec![replied]);
+    }
+}
@@ -0,0 +1,97 @@
+//! Embedded schema migrations.
+//!
+//! Migrations are applied in order and the number of applied migrations is
+//! tracked in SQLite's `user_version` pragma. Never edit a released migration,
+//! append a new one instead.
+
+use rusqlite::Connection;
+use tracing::info;
+
+const MIGRATIONS: &[(&str, &str)] = &[
+    (
+        "0001_initial",
+        include_str!("../../migrations/0001_initial.sql"),
+    ),
+    ("0002_auth", include_str!("../../migrations/0002_auth.sql")),
+    (
+        "0003_email_code_expiry",
+        include_str!("../../migrations/0003_email_code_expiry.sql"),
+    ),
+    (
+        "0004_picture_metadata",
+        include_str!("../../migrations/0004_picture_metadata.sql"),
+    ),
+    (
+        "0005_plate_country",
+        include_str!("../../migrations/0005_plate_country.sql"),
+    ),
+    (
+        "0006_picture_captions",
+        include_str!("../../migrations/0006_picture_captions.sql"),
+    ),
+    (
+        "0007_picture_redaction",
+        include_str!("../../migrations/0007_picture_redaction.sql"),
+    ),
+    (
+        "0008_report_address",
+        include_str!("../../migrations/0008_report_address.sql"),
+    ),
+    (
+        "0009_report_status",
+        include_str!("../../migrations/0009_report_status.sql"),
+    ),
+    (
+        "0010_moderation",
+        include_str!("../../migrations/0010_moderation.sql"),
+    ),
+    (
+        "0011_forwarding",
+        include_str!("../../migrations/0011_forwarding.sql"),
+    ),
+    (
+        "0012_plate_index",
+        include_str!("../../migrations/0012_plate_index.sql"),
+    ),
+    (
+        "0013_picture_hashes",
+        include_str!("../../migrations/0013_picture_hashes.sql"),
+    ),
+    (
+        "0014_picture_hash_errors",
+        include_str!("../../migrations/0014_picture_hash_errors.sql"),
+    ),
+];
+
+/// Schema version of the connected database
+pub fn version(conn: &Connection) -> rusqlite::Result<usize> {
+    conn.pragma_query_value(None, "user_version", |row| row.get(0))
+}
+
+/// Apply all pending migrations, returns how many were applied
+pub fn run(conn: &mut Connection) -> rusqlite::Result<usize> {
+    let current = version(conn)?;
+    let mut applied = 0;
+    for (index, (name, sql)) in MIGRATIONS.iter().enumerate().skip(current) {
+        info!("Applying migration {name}");
+        let tx = conn.transaction()?;
+        tx.execute_batch(sql)?;
+        tx.pragma_update(None, "user_version", index + 1)?;
+        tx.commit()?;
+        applied += 1;
+    }
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrate_twice() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        assert_eq!(run(&mut conn).unwrap(), MIGRATIONS.len());
+        assert_eq!(run(&mut conn).unwrap(), 0);
+        assert_eq!(version(&conn).unwrap(), MIGRATIONS.len());
+    }
+}
@@ -0,0 +1,287 @@
+//! Persistent storage.
+//!
+//! Handlers talk to the database through the repository traits below so the
+//! backend can be swapped; [`Sqlite`] implements all of them and is used both
+//! for local development and for tests (with an in-memory database).
+
+mod forwards;
+pub mod migrations;
+mod pictures;
+mod reports;
+mod roles;
+mod tokens;
+mod users;
+
+pub use forwards::{NewForward, NewMessage};
+pub use pictures::{HashedPicture, NewPicture, Picture};
+pub use reports::ReportFilter;
+pub use roles::{Permission, Role};
+pub use users::{Email, EmailCode, User};
+
+use crate::phash::PictureHash;
+use crate::settings;
+use crate::types::forward::Forward;
+use crate::types::geocode::Address;
+use crate::types::picture::BlurRegion;
+use crate::types::report::{NewReport, Report, ReportStatus, StatusChange};
+use r2d2_sqlite::SqliteConnectionManager;
+use std::sync::Arc;
+use thiserror::Error as ThisError;
+
+pub type Pool = r2d2::Pool<SqliteConnectionManager>;
+
+/// Shared handle to the storage backend
+pub type Db = Arc<dyn Repository>;
+
+/// Errors returned by the repositories
+#[derive(ThisError, Debug)]
+pub enum StorageError {
+    #[error("Not found")]
+    NotFound,
+
+    #[error("{0}")]
+    Conflict(String),
+
+    #[error("Connection pool error: {0}")]
+    Pool(#[from] r2d2::Error),
+
+    #[error("Database error: {0}")]
+    Sqlite(rusqlite::Error),
+}
+
+impl From<rusqlite::Error> for StorageError {
+    fn from(e: rusqlite::Error) -> Self {
+        match e {
+            rusqlite::Error::QueryReturnedNoRows => Self::NotFound,
+            rusqlite::Error::SqliteFailure(f, message)
+                if f.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE =>
+            {
+                Self::Conflict(message.unwrap_or_else(|| "Already exists".to_string()))
+            }
+            e => Self::Sqlite(e),
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, StorageError>;
+
+pub trait ReportRepository {
+    /// Store a validated report in its first status together with its
+    /// pictures and the address resolved from its position
+    fn create_report(
+        &self,
+        report: &NewReport,
+        resolved_address: Option<&Address>,
+        reporter_id: Option<i64>,
+        status: ReportStatus,
+    ) -> Result<Report>;
+    fn get_report(&self, id: i64) -> Result<Report>;
+    /// Page of the reports matching the filter, newest first, and how many
+    /// match on all pages
+    fn search_reports(&self, filter: &ReportFilter) -> Result<(Vec<Report>, u64)>;
+    /// Status changes and amendments of a report, oldest first
+    fn report_history(&self, id: i64) -> Result<Vec<StatusChange>>;
+    /// Move a report from status `from` to `to` with a note and reason for
+    /// the history, a report in any other status is a conflict
+    fn change_report_status(
+        &self,
+        id: i64,
+        from: ReportStatus,
+        to: ReportStatus,
+        note_and_reason: (Option<&str>, Option<&str>),
+        changed_by: Option<i64>,
+    ) -> Result<Report>;
+    /// Reports of the car with this plate, without spaces, observed between
+    /// `from` and `to` in any of the statuses, oldest first
+    fn plate_reports(
+        &self,
+        country: &str,
+        plate: &str,
+        observed: (&str, &str),
+        statuses: &[ReportStatus],
+    ) -> Result<Vec<Report>>;
+    /// Append pictures to a report in moderation, noting it in its history
+    fn add_report_pictures(
+        &self,
+        id: i64,
+        pictures: &[(String, Option<String>)],
+        note: &str,
+        changed_by: Option<i64>,
+    ) -> Result<Report>;
+    /// Note something in the history of a report, keeping its status
+    fn note_report(&self, id: i64, note: &str, changed_by: Option<i64>) -> Result<()>;
+    /// Replace the details and pictures of a draft or submitted report
+    fn amend_report(
+        &self,
+        id: i64,
+        report: &NewReport,
+        resolved_address: Option<&Address>,
+        changed_by: Option<i64>,
+    ) -> Result<Report>;
+}
+
+pub trait UserRepository {
+    /// Create a user with a single primary, unverified email
+    fn create_user(&self, username: &str, password_hash: &str, email: &str) -> Result<User>;
+    fn get_user(&self, id: i64) -> Result<User>;
+    fn find_user_by_username(&self, username: &str) -> Result<Option<User>>;
+    fn list_users(&self) -> Result<Vec<User>>;
+}
+
+pub trait EmailRepository {
+    fn list_emails(&self, user_id: i64) -> Result<Vec<Email>>;
+    fn find_email(&self, email: &str) -> Result<Option<Email>>;
+    /// Store a confirmation code for an email, valid until `expires_at` (unix time)
+    fn create_email_code(&self, email_id: i64, code: &str, expires_at: i64) -> Result<()>;
+    fn find_email_code(&self, code: &str) -> Result<EmailCode>;
+    /// Most recently created code of an email, used for resend throttling
+    fn latest_email_code(&self, email_id: i64) -> Result<Option<EmailCode>>;
+    /// Mark the email as verified and drop its codes
+    fn verify_email(&self, email_id: i64) -> Result<Email>;
+}
+
+pub trait TokenRepository {
+    /// Reject the token with this id until it expires on its own
+    fn revoke_token(&self, jti: &str, expires_at: i64) -> Result<()>;
+    fn is_token_revoked(&self, jti: &str) -> Result<bool>;
+}
+
+pub trait RoleRepository {
+    fn list_roles(&self) -> Result<Vec<Role>>;
+    fn get_role(&self, id: i64) -> Result<Role>;
+    fn find_role_by_name(&self, name: &str) -> Result<Option<Role>>;
+    /// Create a role granting the named permissions
+    fn create_role(&self, name: &str, description: &str, permissions: &[String]) -> Result<Role>;
+    /// Replace name, description and permissions of a role
+    fn update_role(
+        &self,
+        id: i64,
+        name: &str,
+        description: &str,
+        permissions: &[String],
+    ) -> Result<Role>;
+    fn delete_role(&self, id: i64) -> Result<()>;
+    fn user_roles(&self, user_id: i64) -> Result<Vec<Role>>;
+    fn set_user_roles(&self, user_id: i64, role_ids: &[i64]) -> Result<()>;
+}
+
+pub trait PermissionRepository {
+    fn list_permissions(&self) -> Result<Vec<Permission>>;
+    /// Insert the permission if it does not exist yet
+    fn ensure_permission(&self, name: &str, description: &str) -> Result<Permission>;
+    /// Names of all permissions granted to a user through their roles
+    fn user_permissions(&self, user_id: i64) -> Result<Vec<String>>;
+}
+
+pub trait PictureRepository {
+    fn create_picture(&self, picture: &NewPicture) -> Result<Picture>;
+    fn get_picture(&self, path: &str) -> Result<Picture>;
+    /// Record the published copy of a picture and replace its blurred regions
+    fn set_redaction(&self, path: &str, redacted: &str, regions: &[BlurRegion]) -> Result<()>;
+    fn picture_regions(&self, path: &str) -> Result<Vec<BlurRegion>>;
+    /// Record the perceptual hashes of a picture uploaded before they existed
+    fn set_picture_hash(&self, path: &str, hash: PictureHash) -> Result<()>;
+    /// Hashed pictures of all reports but one, in report order
+    fn hashed_report_pictures(&self, except_report: i64) -> Result<Vec<HashedPicture>>;
+    /// Record why a picture uploaded before hashing existed could not be hashed
+    fn set_picture_hash_error(&self, path: &str, error: &str) -> Result<()>;
+    /// Pictures uploaded before hashing existed that were not tried yet
+    fn unhashed_pictures(&self) -> Result<Vec<String>>;
+}
+
+pub trait ForwardRepository {
+    /// Record an email sent to an authority, or refused by the mail server
+    fn create_forward(&self, forward: &NewForward) -> Result<Forward>;
+    /// Emails sent for a report with their replies and bounces, oldest first
+    fn report_forwards(&self, report_id: i64) -> Result<Vec<Forward>>;
+    /// Latest forward sent with any of these message ids
+    fn find_forward(&self, message_ids: &[String]) -> Result<Option<Forward>>;
+    /// Thread a reply or bounce onto a forward and update its status, a
+    /// message threaded before is a conflict
+    fn add_forward_message(&self, forward_id: i64, message: &NewMessage) -> Result<Forward>;
+}
+
+/// Everything the handlers need from storage
+pub trait Repository:
+    ReportRepository
+    + UserRepository
+    + EmailRepository
+    + RoleRepository
+    + PermissionRepository
+    + PictureRepository
+    + TokenRepository
+    + ForwardRepository
+    + Send
+    + Sync
+{
+}
+
+impl<T> Repository for T where
+    T: ReportRepository
+        + UserRepository
+        + EmailRepository
+        + RoleRepository
+        + PermissionRepository
+        + PictureRepository
+        + TokenRepository
+        + ForwardRepository
+        + Send
+        + Sync
+{
+}
+
+/// SQLite backed storage
+#[derive(Clone)]
+pub struct Sqlite {
+    pool: Pool,
+}
+
+impl Sqlite {
+    pub fn new(pool: Pool) -> Self {
+        Self { pool }
+    }
+
+    fn conn(&self) -> Result<r2d2::PooledConnection<SqliteConnectionManager>> {
+        Ok(self.pool.get()?)
+    }
+
+    /// Shared handle for the request handlers
+    pub fn into_handle(self) -> Db {
+        Arc::new(self)
+    }
+
+    /// Bring the schema up to date, returns the number of applied migrations
+    pub fn migrate(&self) -> Result<usize> {
+        let mut conn = self.conn()?;
+        Ok(migrations::run(&mut conn)?)
+    }
+}
+
+/// Open the connection pool described by the database settings
+pub fn connect(settings: &settings::Database) -> std::result::Result<Pool, r2d2::Error> {
+    let path = settings
+        .url
+        .strip_prefix("sqlite://")
+        .unwrap_or(&settings.url);
+    // Every in-memory connection is a separate database, so share a single one
+    let (manager, size) = if path == ":memory:" {
+        (SqliteConnectionManager::memory(), 1)
+    } else {
+        (SqliteConnectionManager::file(path), settings.pool_size)
+    };
+    let manager = manager.with_init(|c| c.execute_batch("PRAGMA foreign_keys = ON;"));
+    r2d2::Pool::builder().max_size(size).build(manager)
+}
+
+/// Migrated in-memory storage for tests
+#[cfg(test)]
+pub fn test_db() -> Sqlite {
+    let pool = connect(&settings::Database {
+        url: ":memory:".to_string(),
+        pool_size: 1,
+    })
+    .unwrap();
+    let db = Sqlite::new(pool);
+    db.migrate().unwrap();
+    db
+}
@@ -0,0 +1,336 @@
+use super::{PictureRepository, Result, Sqlite, StorageError};
+use crate::phash::PictureHash;
+use common::types::picture::{BlurRegion, RegionKind};
+use rusqlite::{params, Row};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NewPicture {
+    pub path: String,
+    pub uploader_id: Option<i64>,
+    pub content_type: String,
+    pub size: i64,
+    /// Capture time and position from the EXIF tags
+    pub taken_at: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub hash: Option<PictureHash>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Picture {
+    pub id: i64,
+    pub path: String,
+    pub uploader_id: Option<i64>,
+    pub content_type: String,
+    pub size: i64,
+    pub created_at: String,
+    pub taken_at: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Name of the copy with faces and bystander plates blurred
+    pub redacted: Option<String>,
+    pub hash: Option<PictureHash>,
+    /// Why it could not be hashed, for pictures uploaded before hashing
+    pub hash_error: Option<String>,
+}
+
+/// Picture of a report with its perceptual hashes
+#[derive(Clone, Debug, PartialEq)]
+pub struct HashedPicture {
+    pub report_id: i64,
+    pub path: String,
+    pub hash: PictureHash,
+}
+
+/// Hashes are stored bit for bit as SQLite's signed integers
+fn hash_columns(hash: Option<PictureHash>) -> (Option<i64>, Option<i64>) {
+    hash.map_or((None, None), |h| {
+        (Some(h.difference as i64), Some(h.dct as i64))
+    })
+}
+
+fn hash_from(row: &Row, first: usize) -> rusqlite::Result<Option<PictureHash>> {
+    let difference: Option<i64> = row.get(first)?;
+    let dct: Option<i64> = row.get(first + 1)?;
+    Ok(difference.zip(dct).map(|(difference, dct)| PictureHash {
+        difference: difference as u64,
+        dct: dct as u64,
+    }))
+}
+
+fn from_row(row: &Row) -> rusqlite::Result<Picture> {
+    Ok(Picture {
+        id: row.get(0)?,
+        path: row.get(1)?,
+        uploader_id: row.get(2)?,
+        content_type: row.get(3)?,
+        size: row.get(4)?,
+        created_at: row.get(5)?,
+        taken_at: row.get(6)?,
+        latitude: row.get(7)?,
+        longitude: row.get(8)?,
+        redacted: row.get(9)?,
+        hash: hash_from(row, 10)?,
+        hash_error: row.get(12)?,
+    })
+}
+
+impl PictureRepository for Sqlite {
+    fn create_picture(&self, picture: &NewPicture) -> Result<Picture> {
+        let (dhash, phash) = hash_columns(picture.hash);
+        self.conn()?.execute(
+            "INSERT INTO pictures (path, uploader_id, content_type, size, taken_at, latitude, longitude,
+                                   dhash, phash)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                picture.path,
+                picture.uploader_id,
+                picture.content_type,
+                picture.size,
+                picture.taken_at,
+                picture.latitude,
+                picture.longitude,
+                dhash,
+                phash
+            ],
+        )?;
+        self.get_picture(&picture.path)
+    }
+
+    fn get_picture(&self, path: &str) -> Result<Picture> {
+        Ok(self.conn()?.query_row(
+            "SELECT id, path, uploader_id, content_type, size, created_at, taken_at, latitude, longitude,
+                    redacted, dhash, phash, hash_error
+             FROM pictures WHERE path = ?1",
+            [path],
+            from_row,
+        )?)
+    }
+
+    fn set_redaction(&self, path: &str, redacted: &str, regions: &[BlurRegion]) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let id: i64 = tx.query_row(
+            "UPDATE pictures SET redacted = ?2 WHERE path = ?1 RETURNING id",
+            params![path, redacted],
+            |row| row.get(0),
+        )?;
+        tx.execute("DELETE FROM picture_regions WHERE picture_id = ?1", [id])?;
+        for region in regions {
+            tx.execute(
+                "INSERT INTO picture_regions (picture_id, kind, x, y, width, height)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    id,
+                    region.kind.as_str(),
+                    region.x,
+                    region.y,
+                    region.width,
+                    region.height
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn picture_regions(&self, path: &str) -> Result<Vec<BlurRegion>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT r.kind, r.x, r.y, r.width, r.height
+             FROM picture_regions r JOIN pictures p ON p.id = r.picture_id
+             WHERE p.path = ?1 ORDER BY r.rowid",
+        )?;
+        let regions = stmt
+            .query_map([path], |row| {
+                let kind: String = row.get(0)?;
+                Ok(BlurRegion {
+                    // Unknown kinds can only come from manual edits, treat them as drawn by hand
+                    kind: RegionKind::from_name(&kind).unwrap_or(RegionKind::Manual),
+                    x: row.get(1)?,
+                    y: row.get(2)?,
+                    width: row.get(3)?,
+                    height: row.get(4)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(regions)
+    }
+
+    fn set_picture_hash(&self, path: &str, hash: PictureHash) -> Result<()> {
+        let (dhash, phash) = hash_columns(Some(hash));
+        let changed = self.conn()?.execute(
+            "UPDATE pictures SET dhash = ?2, phash = ?3 WHERE path = ?1",
+            params![path, dhash, phash],
+        )?;
+        if changed == 0 {
+            return Err(StorageError::NotFound);
+        }
+        Ok(())
+    }
+
+    fn hashed_report_pictures(&self, except_report: i64) -> Result<Vec<HashedPicture>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT rp.report_id, p.path, p.dhash, p.phash
+             FROM report_pictures rp JOIN pictures p ON p.path = rp.picture
+             WHERE rp.report_id != ?1 AND p.dhash IS NOT NULL AND p.phash IS NOT NULL
+             ORDER BY rp.report_id, rp.position",
+        )?;
+        let pictures = stmt
+            .query_map([except_report], |row| {
+                Ok(HashedPicture {
+                    report_id: row.get(0)?,
+                    path: row.get(1)?,
+                    hash: hash_from(row, 2)?.expect("only hashed pictures are selected"),
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(pictures)
+    }
+
+    fn set_picture_hash_error(&self, path: &str, error: &str) -> Result<()> {
+        let changed = self.conn()?.execute(
+            "UPDATE pictures SET hash_error = ?2 WHERE path = ?1",
+            params![path, error],
+        )?;
+        if changed == 0 {
+            return Err(StorageError::NotFound);
+        }
+        Ok(())
+    }
+
+    fn unhashed_pictures(&self) -> Result<Vec<String>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT path FROM pictures
+             WHERE (dhash IS NULL OR phash IS NULL) AND hash_error IS NULL
+             ORDER BY id",
+        )?;
+        let paths = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(paths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{test_db, ReportRepository};
+    use crate::types::report::{NewReport, ReportStatus};
+
+    #[test]
+    fn metadata_roundtrip() {
+        let db = test_db();
+        let picture = db
+            .create_picture(&NewPicture {
+                path: "a.jpg".to_string(),
+                content_type: "image/jpeg".to_string(),
+                size: 10,
+                taken_at: Some("2023-05-01T12:34:56".to_string()),
+                latitude: Some(50.08),
+                longitude: Some(14.42),
+                ..NewPicture::default()
+            })
+            .unwrap();
+        assert_eq!(picture.taken_at.as_deref(), Some("2023-05-01T12:34:56"));
+        assert_eq!(picture.latitude, Some(50.08));
+        assert_eq!(db.get_picture("a.jpg").unwrap(), picture);
+    }
+
+    #[test]
+    fn hashes_of_report_pictures() {
+        let db = test_db();
+        let hash = PictureHash {
+            difference: u64::MAX,
+            dct: 1,
+        };
+        for (path, hash) in [("a.jpg", Some(hash)), ("b.jpg", None)] {
+            db.create_picture(&NewPicture {
+                path: path.to_string(),
+                content_type: "image/jpeg".to_string(),
+                hash,
+                ..NewPicture::default()
+            })
+            .unwrap();
+        }
+        assert_eq!(db.get_picture("a.jpg").unwrap().hash, Some(hash));
+        let pictures = vec![("a.jpg".to_string(), None), ("b.jpg".to_string(), None)];
+        let report = NewReport {
+            pictures,
+            ..NewReport::default()
+        };
+        let first = db
+            .create_report(&report, None, None, ReportStatus::Submitted)
+            .unwrap();
+        let second = db
+            .create_report(&report, None, None, ReportStatus::Submitted)
+            .unwrap();
+        let hashed = db.hashed_report_pictures(second.id).unwrap();
+        assert_eq!(
+            hashed,
+            vec![HashedPicture {
+                report_id: first.id,
+                path: "a.jpg".to_string(),
+                hash,
+            }]
+        );
+        // Pictures that failed to hash are not tried again
+        db.create_picture(&NewPicture {
+            path: "c.jpg".to_string(),
+            content_type: "image/jpeg".to_string(),
+            ..NewPicture::default()
+        })
+        .unwrap();
+        db.set_picture_hash_error("c.jpg", "Not found").unwrap();
+        assert_eq!(
+            db.get_picture("c.jpg").unwrap().hash_error.as_deref(),
+            Some("Not found")
+        );
+        assert_eq!(db.unhashed_pictures().unwrap(), vec!["b.jpg"]);
+        db.set_picture_hash("b.jpg", hash).unwrap();
+        assert_eq!(db.hashed_report_pictures(second.id).unwrap().len(), 2);
+        assert!(db.unhashed_pictures().unwrap().is_empty());
+        assert!(matches!(
+            db.set_picture_hash("missing.jpg", hash),
+            Err(StorageError::NotFound)
+        ));
+    }
+
+    #[test]
+    fn redaction_replaces_regions() {
+        let db = test_db();
+        db.create_picture(&NewPicture {
+            path: "a.jpg".to_string(),
+            content_type: "image/jpeg".to_string(),
+            size: 10,
+            ..NewPicture::default()
+        })
+        .unwrap();
+        assert_eq!(db.get_picture("a.jpg").unwrap().redacted, None);
+        let face = BlurRegion {
+            kind: RegionKind::Face,
+            x: 0.1,
+            y: 0.2,
+            width: 0.3,
+            height: 0.4,
+        };
+        let manual = BlurRegion {
+            kind: RegionKind::Manual,
+            ..face
+        };
+        db.set_redaction("a.jpg", "b.jpg", &[face, manual]).unwrap();
+        assert_eq!(db.picture_regions("a.jpg").unwrap(), vec![face, manual]);
+        db.set_redaction("a.jpg", "c.jpg", &[manual]).unwrap();
+        assert_eq!(db.picture_regions("a.jpg").unwrap(), vec![manual]);
+        assert_eq!(
+            db.get_picture("a.jpg").unwrap().redacted.as_deref(),
+            Some("c.jpg")
+        );
+        assert!(matches!(
+            db.set_redaction("missing.jpg", "c.jpg", &[]),
+            Err(crate::db::StorageError::NotFound)
+        ));
+    }
+}
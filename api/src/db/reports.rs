@@ -0,0 +1,664 @@
+use super::{ReportRepository, Result, Sqlite, StorageError};
+use crate::types::geocode::Address;
+use crate::types::report::{NewReport, Report, ReportStatus, StatusChange};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, Row, Transaction};
+
+const COLUMNS: &str =
+    "id, plate, plate_country, latitude, longitude, address, observed_at, description, reporter_id, created_at,
+     address_street, address_house_number, address_city, address_district, address_postcode, status";
+
+/// Which reports to list and which page of them
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReportFilter {
+    pub reporter_id: Option<i64>,
+    /// Reports in any of these statuses, all when empty
+    pub statuses: Vec<ReportStatus>,
+    /// Part of the plate, case and spaces are ignored
+    pub plate: Option<String>,
+    /// Observed on or after this day, `YYYY-MM-DD`
+    pub from: Option<String>,
+    /// Observed on or before this day, `YYYY-MM-DD`
+    pub to: Option<String>,
+    /// Oldest first, as the moderation queue is worked through
+    pub oldest_first: bool,
+    /// Starting at 1
+    pub page: u32,
+    pub per_page: u32,
+}
+
+impl Default for ReportFilter {
+    fn default() -> Self {
+        Self {
+            reporter_id: None,
+            statuses: Vec::new(),
+            plate: None,
+            from: None,
+            to: None,
+            oldest_first: false,
+            page: 1,
+            per_page: 20,
+        }
+    }
+}
+
+impl ReportFilter {
+    /// `WHERE` clause and its parameters
+    fn condition(&self) -> (String, Vec<Value>) {
+        let mut clauses = vec!["1 = 1".to_string()];
+        if !self.statuses.is_empty() {
+            let names = self
+                .statuses
+                .iter()
+                .map(|s| format!("'{}'", s.as_str()))
+                .collect::<Vec<_>>();
+            clauses.push(format!("status IN ({})", names.join(", ")));
+        }
+        let mut values = Vec::new();
+        let mut push = |clause: &str, value: Value| {
+            values.push(value);
+            clauses.push(clause.replace('?', &format!("?{}", values.len())));
+        };
+        if let Some(reporter_id) = self.reporter_id {
+            push("reporter_id = ?", reporter_id.into());
+        }
+        if let Some(plate) = &self.plate {
+            let plate = plate.replace(' ', "").to_uppercase();
+            push("instr(replace(upper(plate), ' ', ''), ?) > 0", plate.into());
+        }
+        if let Some(from) = &self.from {
+            push("observed_at >= ?", from.clone().into());
+        }
+        if let Some(to) = &self.to {
+            push("observed_at < date(?, '+1 day')", to.clone().into());
+        }
+        (clauses.join(" AND "), values)
+    }
+}
+
+fn from_row(row: &Row) -> rusqlite::Result<Report> {
+    Ok(Report {
+        id: row.get(0)?,
+        plate: row.get(1)?,
+        country: row.get(2)?,
+        latitude: row.get(3)?,
+        longitude: row.get(4)?,
+        address: row.get(5)?,
+        resolved_address: resolved_address(row)?,
+        observed_at: row.get(6)?,
+        description: row.get(7)?,
+        pictures: Vec::new(),
+        reporter_id: row.get(8)?,
+        created_at: row.get(9)?,
+        status: ReportStatus::from_name(&row.get::<_, String>(15)?).unwrap_or_default(),
+    })
+}
+
+fn resolved_address(row: &Row) -> rusqlite::Result<Option<Address>> {
+    let (Some(house_number), Some(city)) = (row.get(11)?, row.get(12)?) else {
+        return Ok(None);
+    };
+    Ok(Some(Address {
+        street: row.get(10)?,
+        house_number,
+        city,
+        district: row.get(13)?,
+        postcode: row.get(14)?,
+    }))
+}
+
+fn with_pictures(conn: &Connection, mut report: Report) -> rusqlite::Result<Report> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT picture, caption FROM report_pictures WHERE report_id = ?1 ORDER BY position",
+    )?;
+    report.pictures = stmt
+        .query_map([report.id], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(report)
+}
+
+/// Add pictures after the `first` position
+fn insert_pictures(
+    tx: &Transaction,
+    id: i64,
+    first: usize,
+    pictures: &[(String, Option<String>)],
+) -> Result<()> {
+    for (position, (picture, caption)) in pictures.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO report_pictures (report_id, position, picture, caption)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![id, first + position, picture, caption],
+        )?;
+    }
+    Ok(())
+}
+
+fn current_status(tx: &Transaction, id: i64) -> Result<ReportStatus> {
+    let status = tx.query_row("SELECT status FROM reports WHERE id = ?1", [id], |row| {
+        row.get::<_, String>(0)
+    })?;
+    Ok(ReportStatus::from_name(&status).unwrap_or_default())
+}
+
+fn record_status(
+    tx: &Transaction,
+    id: i64,
+    status: ReportStatus,
+    (note, reason): (Option<&str>, Option<&str>),
+    changed_by: Option<i64>,
+) -> Result<()> {
+    tx.execute(
+        "INSERT INTO report_status_history (report_id, status, note, reason, changed_by)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![id, status.as_str(), note, reason, changed_by],
+    )?;
+    Ok(())
+}
+
+/// Error for a report that is not in the expected status, or does not exist
+fn unexpected_status(tx: &Transaction, id: i64) -> StorageError {
+    match tx.query_row("SELECT status FROM reports WHERE id = ?1", [id], |row| {
+        row.get::<_, String>(0)
+    }) {
+        Ok(status) => StorageError::Conflict(format!(
+            "The report is {}",
+            ReportStatus::from_name(&status)
+                .unwrap_or_default()
+                .label()
+                .to_lowercase()
+        )),
+        Err(e) => e.into(),
+    }
+}
+
+impl ReportRepository for Sqlite {
+    fn create_report(
+        &self,
+        report: &NewReport,
+        resolved_address: Option<&Address>,
+        reporter_id: Option<i64>,
+        status: ReportStatus,
+    ) -> Result<Report> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO reports (plate, plate_country, latitude, longitude, address, observed_at, description, reporter_id,
+                                  address_street, address_house_number, address_city, address_district, address_postcode, status)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            params![
+                report.plate,
+                report.country,
+                report.latitude,
+                report.longitude,
+                report.address,
+                report.observed_at,
+                report.description,
+                reporter_id,
+                resolved_address.and_then(|a| a.street.as_ref()),
+                resolved_address.map(|a| &a.house_number),
+                resolved_address.map(|a| &a.city),
+                resolved_address.and_then(|a| a.district.as_ref()),
+                resolved_address.and_then(|a| a.postcode.as_ref()),
+                status.as_str(),
+            ],
+        )?;
+        let id = tx.last_insert_rowid();
+        insert_pictures(&tx, id, 0, &report.pictures)?;
+        record_status(&tx, id, status, (None, None), reporter_id)?;
+        tx.commit()?;
+        drop(conn);
+        self.get_report(id)
+    }
+
+    fn get_report(&self, id: i64) -> Result<Report> {
+        let conn = self.conn()?;
+        let report = conn.query_row(
+            &format!("SELECT {COLUMNS} FROM reports WHERE id = ?1"),
+            [id],
+            from_row,
+        )?;
+        Ok(with_pictures(&conn, report)?)
+    }
+
+    fn search_reports(&self, filter: &ReportFilter) -> Result<(Vec<Report>, u64)> {
+        let conn = self.conn()?;
+        let (condition, values) = filter.condition();
+        let total = conn.query_row(
+            &format!("SELECT COUNT(*) FROM reports WHERE {condition}"),
+            params_from_iter(&values),
+            |row| row.get(0),
+        )?;
+        let per_page = filter.per_page.max(1);
+        let order = if filter.oldest_first { "ASC" } else { "DESC" };
+        let offset = u64::from(filter.page.max(1) - 1) * u64::from(per_page);
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {COLUMNS} FROM reports WHERE {condition}
+             ORDER BY created_at {order}, id {order} LIMIT {per_page} OFFSET {offset}"
+        ))?;
+        let reports = stmt
+            .query_map(params_from_iter(&values), from_row)?
+            .collect::<rusqlite::Result<Vec<Report>>>()?;
+        let reports = reports
+            .into_iter()
+            .map(|r| with_pictures(&conn, r))
+            .collect::<rusqlite::Result<Vec<Report>>>()?;
+        Ok((reports, total))
+    }
+
+    fn report_history(&self, id: i64) -> Result<Vec<StatusChange>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT status, note, reason, changed_by, changed_at FROM report_status_history
+             WHERE report_id = ?1 ORDER BY changed_at, id",
+        )?;
+        let history = stmt
+            .query_map([id], |row| {
+                Ok(StatusChange {
+                    status: ReportStatus::from_name(&row.get::<_, String>(0)?).unwrap_or_default(),
+                    note: row.get(1)?,
+                    reason: row.get(2)?,
+                    changed_by: row.get(3)?,
+                    changed_at: row.get(4)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(history)
+    }
+
+    fn change_report_status(
+        &self,
+        id: i64,
+        from: ReportStatus,
+        to: ReportStatus,
+        (note, reason): (Option<&str>, Option<&str>),
+        changed_by: Option<i64>,
+    ) -> Result<Report> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let changed = tx.execute(
+            "UPDATE reports SET status = ?1 WHERE id = ?2 AND status = ?3",
+            params![to.as_str(), id, from.as_str()],
+        )?;
+        if changed == 0 {
+            return Err(unexpected_status(&tx, id));
+        }
+        record_status(&tx, id, to, (note, reason), changed_by)?;
+        tx.commit()?;
+        drop(conn);
+        self.get_report(id)
+    }
+
+    fn plate_reports(
+        &self,
+        country: &str,
+        plate: &str,
+        (from, to): (&str, &str),
+        statuses: &[ReportStatus],
+    ) -> Result<Vec<Report>> {
+        let conn = self.conn()?;
+        let names = statuses
+            .iter()
+            .map(|s| format!("'{}'", s.as_str()))
+            .collect::<Vec<_>>();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {COLUMNS} FROM reports
+             WHERE plate_country = ?1 AND replace(plate, ' ', '') = ?2
+               AND observed_at >= ?3 AND observed_at <= ?4 AND status IN ({})
+             ORDER BY observed_at, id",
+            names.join(", ")
+        ))?;
+        let reports = stmt
+            .query_map(params![country, plate, from, to], from_row)?
+            .collect::<rusqlite::Result<Vec<Report>>>()?;
+        Ok(reports
+            .into_iter()
+            .map(|r| with_pictures(&conn, r))
+            .collect::<rusqlite::Result<Vec<Report>>>()?)
+    }
+
+    fn add_report_pictures(
+        &self,
+        id: i64,
+        pictures: &[(String, Option<String>)],
+        note: &str,
+        changed_by: Option<i64>,
+    ) -> Result<Report> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let status = current_status(&tx, id)?;
+        if !ReportStatus::QUEUE.contains(&status) {
+            return Err(unexpected_status(&tx, id));
+        }
+        let next: usize = tx.query_row(
+            "SELECT COALESCE(MAX(position) + 1, 0) FROM report_pictures WHERE report_id = ?1",
+            [id],
+            |row| row.get(0),
+        )?;
+        insert_pictures(&tx, id, next, pictures)?;
+        record_status(&tx, id, status, (Some(note), None), changed_by)?;
+        tx.commit()?;
+        drop(conn);
+        self.get_report(id)
+    }
+
+    fn note_report(&self, id: i64, note: &str, changed_by: Option<i64>) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let status = current_status(&tx, id)?;
+        record_status(&tx, id, status, (Some(note), None), changed_by)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn amend_report(
+        &self,
+        id: i64,
+        report: &NewReport,
+        resolved_address: Option<&Address>,
+        changed_by: Option<i64>,
+    ) -> Result<Report> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let changed = tx.execute(
+            "UPDATE reports SET plate = ?1, plate_country = ?2, latitude = ?3, longitude = ?4, address = ?5,
+                                observed_at = ?6, description = ?7, address_street = ?8, address_house_number = ?9,
+                                address_city = ?10, address_district = ?11, address_postcode = ?12
+             WHERE id = ?13 AND status IN (?14, ?15)",
+            params![
+                report.plate,
+                report.country,
+                report.latitude,
+                report.longitude,
+                report.address,
+                report.observed_at,
+                report.description,
+                resolved_address.and_then(|a| a.street.as_ref()),
+                resolved_address.map(|a| &a.house_number),
+                resolved_address.map(|a| &a.city),
+                resolved_address.and_then(|a| a.district.as_ref()),
+                resolved_address.and_then(|a| a.postcode.as_ref()),
+                id,
+                ReportStatus::Draft.as_str(),
+                ReportStatus::Submitted.as_str(),
+            ],
+        )?;
+        if changed == 0 {
+            return Err(unexpected_status(&tx, id));
+        }
+        tx.execute("DELETE FROM report_pictures WHERE report_id = ?1", [id])?;
+        insert_pictures(&tx, id, 0, &report.pictures)?;
+        let status = current_status(&tx, id)?;
+        record_status(&tx, id, status, (Some("Amended"), None), changed_by)?;
+        tx.commit()?;
+        drop(conn);
+        self.get_report(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{test_db, NewPicture, PictureRepository, UserRepository};
+
+    #[test]
+    fn create_and_list() {
+        let db = test_db();
+        let user = db.create_user("reporter", "hash", "r@example.com").unwrap();
+        for path in ["a.jpg", "b.jpg"] {
+            db.create_picture(&NewPicture {
+                path: path.to_string(),
+                uploader_id: Some(user.id),
+                content_type: "image/jpeg".to_string(),
+                size: 1,
+                ..NewPicture::default()
+            })
+            .unwrap();
+        }
+        let report = NewReport {
+            plate: "1AB 2345".to_string(),
+            country: "CZ".to_string(),
+            address: Some("Vinohradská 12".to_string()),
+            observed_at: "2023-06-01T12:30:00".to_string(),
+            pictures: vec![
+                ("b.jpg".to_string(), Some("Blocked crossing".to_string())),
+                ("a.jpg".to_string(), None),
+            ],
+            ..NewReport::default()
+        };
+        let address = Address {
+            street: Some("Vinohradská".to_string()),
+            house_number: "1511/12".to_string(),
+            city: "Praha".to_string(),
+            district: Some("Praha 2".to_string()),
+            postcode: None,
+        };
+        let stored = db
+            .create_report(
+                &report,
+                Some(&address),
+                Some(user.id),
+                ReportStatus::Submitted,
+            )
+            .unwrap();
+        assert_eq!(stored.reporter_id, Some(user.id));
+        assert_eq!(stored.resolved_address, Some(address));
+        assert_eq!(stored.pictures, report.pictures);
+        assert_eq!(db.get_report(stored.id).unwrap(), stored);
+        assert_eq!(stored.status, ReportStatus::Submitted);
+        let history = db.report_history(stored.id).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].changed_by, Some(user.id));
+        let mine = ReportFilter {
+            reporter_id: Some(user.id),
+            ..ReportFilter::default()
+        };
+        assert_eq!(db.search_reports(&mine).unwrap(), (vec![stored], 1));
+    }
+
+    fn submit(db: &Sqlite, plate: &str, observed_at: &str, reporter_id: i64) -> Report {
+        let report = NewReport {
+            plate: plate.to_string(),
+            country: "CZ".to_string(),
+            observed_at: observed_at.to_string(),
+            ..NewReport::default()
+        };
+        db.create_report(&report, None, Some(reporter_id), ReportStatus::Submitted)
+            .unwrap()
+    }
+
+    #[test]
+    fn search() {
+        let db = test_db();
+        let user = db.create_user("reporter", "hash", "r@example.com").unwrap();
+        let other = db.create_user("other", "hash", "o@example.com").unwrap();
+        let first = submit(&db, "1AB 2345", "2023-06-01T12:30:00", user.id);
+        let second = submit(&db, "2CD 6789", "2023-06-02T08:00:00", user.id);
+        let third = submit(&db, "1AB 2345", "2023-06-03T23:59:00", user.id);
+        submit(&db, "1AB 2345", "2023-06-01T10:00:00", other.id);
+        let search = |filter: ReportFilter| {
+            let (reports, total) = db
+                .search_reports(&ReportFilter {
+                    reporter_id: Some(user.id),
+                    ..filter
+                })
+                .unwrap();
+            (reports.iter().map(|r| r.id).collect::<Vec<_>>(), total)
+        };
+        let page = |page| ReportFilter {
+            page,
+            per_page: 2,
+            ..ReportFilter::default()
+        };
+        assert_eq!(search(page(1)), (vec![third.id, second.id], 3));
+        assert_eq!(search(page(2)), (vec![first.id], 3));
+        assert_eq!(search(page(3)), (vec![], 3));
+        let plate = ReportFilter {
+            plate: Some("1ab23".to_string()),
+            ..ReportFilter::default()
+        };
+        assert_eq!(search(plate), (vec![third.id, first.id], 2));
+        let days = ReportFilter {
+            from: Some("2023-06-02".to_string()),
+            to: Some("2023-06-03".to_string()),
+            ..ReportFilter::default()
+        };
+        assert_eq!(search(days), (vec![third.id, second.id], 2));
+        db.change_report_status(
+            second.id,
+            ReportStatus::Submitted,
+            ReportStatus::Withdrawn,
+            (None, None),
+            Some(user.id),
+        )
+        .unwrap();
+        let withdrawn = ReportFilter {
+            statuses: vec![ReportStatus::Withdrawn],
+            ..ReportFilter::default()
+        };
+        assert_eq!(search(withdrawn), (vec![second.id], 1));
+        let queue = ReportFilter {
+            statuses: ReportStatus::QUEUE.to_vec(),
+            oldest_first: true,
+            ..ReportFilter::default()
+        };
+        assert_eq!(search(queue), (vec![first.id, third.id], 2));
+    }
+
+    #[test]
+    fn status_and_amendments() {
+        let db = test_db();
+        let user = db.create_user("reporter", "hash", "r@example.com").unwrap();
+        let report = submit(&db, "1AB 2345", "2023-06-01T12:30:00", user.id);
+        let amended = NewReport {
+            plate: "1AB 2346".to_string(),
+            country: "CZ".to_string(),
+            observed_at: "2023-06-01T12:35:00".to_string(),
+            description: Some("Corrected plate".to_string()),
+            ..NewReport::default()
+        };
+        let stored = db
+            .amend_report(report.id, &amended, None, Some(user.id))
+            .unwrap();
+        assert_eq!(stored.plate, "1AB 2346");
+        assert_eq!(stored.created_at, report.created_at);
+        let withdrawn = db
+            .change_report_status(
+                report.id,
+                ReportStatus::Submitted,
+                ReportStatus::Withdrawn,
+                (Some("Mistake"), None),
+                Some(user.id),
+            )
+            .unwrap();
+        assert_eq!(withdrawn.status, ReportStatus::Withdrawn);
+        assert!(matches!(
+            db.amend_report(report.id, &amended, None, Some(user.id)),
+            Err(StorageError::Conflict(_))
+        ));
+        assert!(matches!(
+            db.change_report_status(
+                report.id,
+                ReportStatus::Submitted,
+                ReportStatus::Withdrawn,
+                (None, None),
+                None
+            ),
+            Err(StorageError::Conflict(_))
+        ));
+        assert!(matches!(
+            db.amend_report(report.id + 1, &amended, None, None),
+            Err(StorageError::NotFound)
+        ));
+        let history = db.report_history(report.id).unwrap();
+        let notes = history
+            .iter()
+            .map(|c| (c.status, c.note.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            notes,
+            vec![
+                (ReportStatus::Submitted, None),
+                (ReportStatus::Submitted, Some("Amended")),
+                (ReportStatus::Withdrawn, Some("Mistake")),
+            ]
+        );
+    }
+
+    #[test]
+    fn same_plate_and_added_pictures() {
+        let db = test_db();
+        let user = db.create_user("reporter", "hash", "r@example.com").unwrap();
+        for path in ["a.jpg", "b.jpg"] {
+            db.create_picture(&NewPicture {
+                path: path.to_string(),
+                content_type: "image/jpeg".to_string(),
+                size: 1,
+                ..NewPicture::default()
+            })
+            .unwrap();
+        }
+        let early = submit(&db, "1AB 2345", "2023-06-01T12:30:00", user.id);
+        let late = submit(&db, "1AB 2345", "2023-06-01T14:00:00", user.id);
+        submit(&db, "1AB 2346", "2023-06-01T12:40:00", user.id);
+        let live = [ReportStatus::Submitted, ReportStatus::UnderReview];
+        let found = db
+            .plate_reports(
+                "CZ",
+                "1AB2345",
+                ("2023-06-01T12:00:00", "2023-06-01T13:00:00"),
+                &live,
+            )
+            .unwrap();
+        assert_eq!(found, vec![early.clone()]);
+        let found = db
+            .plate_reports(
+                "CZ",
+                "1AB2345",
+                ("2023-06-01T00:00:00", "2023-06-02T00:00:00"),
+                &[ReportStatus::Withdrawn],
+            )
+            .unwrap();
+        assert!(found.is_empty());
+
+        let pictures = vec![("a.jpg".to_string(), None), ("b.jpg".to_string(), None)];
+        db.add_report_pictures(early.id, &pictures[..1], "Added", None)
+            .unwrap();
+        let stored = db
+            .add_report_pictures(early.id, &pictures[1..], "Added", None)
+            .unwrap();
+        assert_eq!(stored.pictures, pictures);
+        db.note_report(late.id, "Likely duplicate", None).unwrap();
+        let history = db.report_history(late.id).unwrap();
+        assert_eq!(history[1].status, ReportStatus::Submitted);
+        assert_eq!(history[1].note.as_deref(), Some("Likely duplicate"));
+        db.change_report_status(
+            late.id,
+            ReportStatus::Submitted,
+            ReportStatus::Withdrawn,
+            (None, None),
+            None,
+        )
+        .unwrap();
+        assert!(matches!(
+            db.add_report_pictures(late.id, &pictures, "Added", None),
+            Err(StorageError::Conflict(_))
+        ));
+    }
+
+    #[test]
+    fn unknown_picture() {
+        let db = test_db();
+        let report = NewReport {
+            plate: "1AB 2345".to_string(),
+            country: "CZ".to_string(),
+            observed_at: "2023-06-01T12:30:00".to_string(),
+            pictures: vec![("missing.jpg".to_string(), None)],
+            ..NewReport::default()
+        };
+        assert!(db
+            .create_report(&report, None, None, ReportStatus::Submitted)
+            .is_err());
+        assert!(matches!(db.get_report(1), Err(StorageError::NotFound)));
+    }
+}
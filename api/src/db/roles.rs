@@ -0,0 +1,277 @@
+use super::{PermissionRepository, Result, RoleRepository, Sqlite, StorageError};
+use rusqlite::{params, Connection, OptionalExtension, Row, Transaction};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Permission {
+    pub id: i64,
+    pub name: String,
+    pub description: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Role {
+    pub id: i64,
+    pub name: String,
+    pub description: String,
+    pub permissions: Vec<Permission>,
+}
+
+fn permission_from_row(row: &Row) -> rusqlite::Result<Permission> {
+    Ok(Permission {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+    })
+}
+
+fn role_from_row(row: &Row) -> rusqlite::Result<Role> {
+    Ok(Role {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+        permissions: Vec::new(),
+    })
+}
+
+fn with_permissions(conn: &Connection, mut role: Role) -> rusqlite::Result<Role> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT p.id, p.name, p.description FROM permissions p
+         JOIN role_permissions rp ON rp.permission_id = p.id
+         WHERE rp.role_id = ?1 ORDER BY p.name",
+    )?;
+    role.permissions = stmt
+        .query_map([role.id], permission_from_row)?
+        .collect::<rusqlite::Result<Vec<Permission>>>()?;
+    Ok(role)
+}
+
+/// Replace the permissions of a role, every name must already exist
+fn set_permissions(tx: &Transaction, role_id: i64, permissions: &[String]) -> Result<()> {
+    tx.execute("DELETE FROM role_permissions WHERE role_id = ?1", [role_id])?;
+    for name in permissions {
+        let inserted = tx.execute(
+            "INSERT OR IGNORE INTO role_permissions (role_id, permission_id)
+             SELECT ?1, id FROM permissions WHERE name = ?2",
+            params![role_id, name],
+        )?;
+        if inserted == 0
+            && !tx.query_row(
+                "SELECT EXISTS(SELECT 1 FROM permissions WHERE name = ?1)",
+                [name],
+                |row| row.get::<_, bool>(0),
+            )?
+        {
+            return Err(StorageError::NotFound);
+        }
+    }
+    Ok(())
+}
+
+impl RoleRepository for Sqlite {
+    fn list_roles(&self) -> Result<Vec<Role>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare("SELECT id, name, description FROM roles ORDER BY name")?;
+        let roles = stmt
+            .query_map([], role_from_row)?
+            .collect::<rusqlite::Result<Vec<Role>>>()?;
+        Ok(roles
+            .into_iter()
+            .map(|r| with_permissions(&conn, r))
+            .collect::<rusqlite::Result<Vec<Role>>>()?)
+    }
+
+    fn get_role(&self, id: i64) -> Result<Role> {
+        let conn = self.conn()?;
+        let role = conn.query_row(
+            "SELECT id, name, description FROM roles WHERE id = ?1",
+            [id],
+            role_from_row,
+        )?;
+        Ok(with_permissions(&conn, role)?)
+    }
+
+    fn find_role_by_name(&self, name: &str) -> Result<Option<Role>> {
+        let conn = self.conn()?;
+        let role = conn
+            .query_row(
+                "SELECT id, name, description FROM roles WHERE name = ?1",
+                [name],
+                role_from_row,
+            )
+            .optional()?;
+        Ok(role.map(|r| with_permissions(&conn, r)).transpose()?)
+    }
+
+    fn create_role(&self, name: &str, description: &str, permissions: &[String]) -> Result<Role> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO roles (name, description) VALUES (?1, ?2)",
+            params![name, description],
+        )?;
+        let id = tx.last_insert_rowid();
+        set_permissions(&tx, id, permissions)?;
+        tx.commit()?;
+        drop(conn);
+        self.get_role(id)
+    }
+
+    fn update_role(
+        &self,
+        id: i64,
+        name: &str,
+        description: &str,
+        permissions: &[String],
+    ) -> Result<Role> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let changed = tx.execute(
+            "UPDATE roles SET name = ?2, description = ?3 WHERE id = ?1",
+            params![id, name, description],
+        )?;
+        if changed == 0 {
+            return Err(StorageError::NotFound);
+        }
+        set_permissions(&tx, id, permissions)?;
+        tx.commit()?;
+        drop(conn);
+        self.get_role(id)
+    }
+
+    fn delete_role(&self, id: i64) -> Result<()> {
+        let changed = self
+            .conn()?
+            .execute("DELETE FROM roles WHERE id = ?1", [id])?;
+        if changed == 0 {
+            return Err(StorageError::NotFound);
+        }
+        Ok(())
+    }
+
+    fn user_roles(&self, user_id: i64) -> Result<Vec<Role>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT r.id, r.name, r.description FROM roles r
+             JOIN user_roles ur ON ur.role_id = r.id
+             WHERE ur.user_id = ?1 ORDER BY r.name",
+        )?;
+        let roles = stmt
+            .query_map([user_id], role_from_row)?
+            .collect::<rusqlite::Result<Vec<Role>>>()?;
+        Ok(roles
+            .into_iter()
+            .map(|r| with_permissions(&conn, r))
+            .collect::<rusqlite::Result<Vec<Role>>>()?)
+    }
+
+    fn set_user_roles(&self, user_id: i64, role_ids: &[i64]) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM user_roles WHERE user_id = ?1", [user_id])?;
+        for role_id in role_ids {
+            tx.execute(
+                "INSERT INTO user_roles (user_id, role_id) VALUES (?1, ?2)",
+                params![user_id, role_id],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+impl PermissionRepository for Sqlite {
+    fn list_permissions(&self) -> Result<Vec<Permission>> {
+        let conn = self.conn()?;
+        let mut stmt =
+            conn.prepare("SELECT id, name, description FROM permissions ORDER BY name")?;
+        let permissions = stmt
+            .query_map([], permission_from_row)?
+            .collect::<rusqlite::Result<Vec<Permission>>>()?;
+        Ok(permissions)
+    }
+
+    fn ensure_permission(&self, name: &str, description: &str) -> Result<Permission> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT OR IGNORE INTO permissions (name, description) VALUES (?1, ?2)",
+            params![name, description],
+        )?;
+        Ok(conn.query_row(
+            "SELECT id, name, description FROM permissions WHERE name = ?1",
+            [name],
+            permission_from_row,
+        )?)
+    }
+
+    fn user_permissions(&self, user_id: i64) -> Result<Vec<String>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT DISTINCT p.name FROM permissions p
+             JOIN role_permissions rp ON rp.permission_id = p.id
+             JOIN user_roles ur ON ur.role_id = rp.role_id
+             WHERE ur.user_id = ?1 ORDER BY p.name",
+        )?;
+        let permissions = stmt
+            .query_map([user_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(permissions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{test_db, UserRepository};
+
+    #[test]
+    fn roles_and_permissions() {
+        let db = test_db();
+        db.ensure_permission("reports.review", "Review reports")
+            .unwrap();
+        db.ensure_permission("reports.review", "ignored").unwrap();
+        db.ensure_permission("users.list", "List users").unwrap();
+        assert_eq!(db.list_permissions().unwrap().len(), 2);
+
+        let role = db
+            .create_role("moderator", "Moderates", &["reports.review".to_string()])
+            .unwrap();
+        assert_eq!(role.permissions.len(), 1);
+        assert_eq!(
+            db.find_role_by_name("moderator").unwrap(),
+            Some(role.clone())
+        );
+        assert_eq!(db.find_role_by_name("nobody").unwrap(), None);
+
+        let user = db.create_user("mod", "hash", "m@example.com").unwrap();
+        db.set_user_roles(user.id, &[role.id]).unwrap();
+        assert_eq!(
+            db.user_permissions(user.id).unwrap(),
+            vec!["reports.review"]
+        );
+
+        let role = db
+            .update_role(
+                role.id,
+                "moderator",
+                "Moderates",
+                &["reports.review".to_string(), "users.list".to_string()],
+            )
+            .unwrap();
+        assert_eq!(role.permissions.len(), 2);
+        assert_eq!(db.user_roles(user.id).unwrap(), vec![role.clone()]);
+
+        db.delete_role(role.id).unwrap();
+        assert!(db.user_permissions(user.id).unwrap().is_empty());
+        assert!(matches!(db.get_role(role.id), Err(StorageError::NotFound)));
+    }
+
+    #[test]
+    fn unknown_permission() {
+        let db = test_db();
+        let error = db
+            .create_role("broken", "", &["does.not.exist".to_string()])
+            .unwrap_err();
+        assert!(matches!(error, StorageError::NotFound));
+        assert!(db.list_roles().unwrap().is_empty());
+    }
+}
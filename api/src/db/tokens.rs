@@ -0,0 +1,50 @@
+use super::{Result, Sqlite, TokenRepository};
+use rusqlite::params;
+use time::OffsetDateTime;
+
+impl TokenRepository for Sqlite {
+    fn revoke_token(&self, jti: &str, expires_at: i64) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "DELETE FROM revoked_tokens WHERE expires_at < ?1",
+            [OffsetDateTime::now_utc().unix_timestamp()],
+        )?;
+        conn.execute(
+            "INSERT OR REPLACE INTO revoked_tokens (jti, expires_at) VALUES (?1, ?2)",
+            params![jti, expires_at],
+        )?;
+        Ok(())
+    }
+
+    fn is_token_revoked(&self, jti: &str) -> Result<bool> {
+        Ok(self.conn()?.query_row(
+            "SELECT EXISTS(SELECT 1 FROM revoked_tokens WHERE jti = ?1)",
+            [jti],
+            |row| row.get(0),
+        )?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    #[test]
+    fn revoke() {
+        let db = test_db();
+        let expires = OffsetDateTime::now_utc().unix_timestamp() + 60;
+        assert!(!db.is_token_revoked("a").unwrap());
+        db.revoke_token("a", expires).unwrap();
+        assert!(db.is_token_revoked("a").unwrap());
+    }
+
+    #[test]
+    fn expired_are_purged() {
+        let db = test_db();
+        db.revoke_token("old", 0).unwrap();
+        db.revoke_token("new", OffsetDateTime::now_utc().unix_timestamp() + 60)
+            .unwrap();
+        assert!(!db.is_token_revoked("old").unwrap());
+    }
+}
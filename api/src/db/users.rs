@@ -0,0 +1,234 @@
+use super::{EmailRepository, Result, Sqlite, UserRepository};
+use rusqlite::{params, OptionalExtension, Row};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct User {
+    pub id: i64,
+    pub username: String,
+    pub password_hash: String,
+    pub created_at: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Email {
+    pub id: i64,
+    pub user_id: i64,
+    pub email: String,
+    pub verified: bool,
+    pub primary: bool,
+}
+
+/// Email confirmation code, times are unix timestamps
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EmailCode {
+    pub code: String,
+    pub email_id: i64,
+    pub created_at: i64,
+    pub expires_at: i64,
+}
+
+const EMAIL_CODE_COLUMNS: &str =
+    "code, email_id, CAST(strftime('%s', created_at) AS INTEGER), expires_at";
+
+fn user_from_row(row: &Row) -> rusqlite::Result<User> {
+    Ok(User {
+        id: row.get(0)?,
+        username: row.get(1)?,
+        password_hash: row.get(2)?,
+        created_at: row.get(3)?,
+    })
+}
+
+fn email_from_row(row: &Row) -> rusqlite::Result<Email> {
+    Ok(Email {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        email: row.get(2)?,
+        verified: row.get(3)?,
+        primary: row.get(4)?,
+    })
+}
+
+fn email_code_from_row(row: &Row) -> rusqlite::Result<EmailCode> {
+    Ok(EmailCode {
+        code: row.get(0)?,
+        email_id: row.get(1)?,
+        created_at: row.get(2)?,
+        expires_at: row.get(3)?,
+    })
+}
+
+impl UserRepository for Sqlite {
+    fn create_user(&self, username: &str, password_hash: &str, email: &str) -> Result<User> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO users (username, password_hash) VALUES (?1, ?2)",
+            params![username, password_hash],
+        )?;
+        let id = tx.last_insert_rowid();
+        tx.execute(
+            "INSERT INTO emails (user_id, email, is_primary) VALUES (?1, ?2, 1)",
+            params![id, email],
+        )?;
+        tx.commit()?;
+        drop(conn);
+        self.get_user(id)
+    }
+
+    fn get_user(&self, id: i64) -> Result<User> {
+        Ok(self.conn()?.query_row(
+            "SELECT id, username, password_hash, created_at FROM users WHERE id = ?1",
+            [id],
+            user_from_row,
+        )?)
+    }
+
+    fn find_user_by_username(&self, username: &str) -> Result<Option<User>> {
+        Ok(self
+            .conn()?
+            .query_row(
+                "SELECT id, username, password_hash, created_at FROM users WHERE username = ?1",
+                [username],
+                user_from_row,
+            )
+            .optional()?)
+    }
+
+    fn list_users(&self) -> Result<Vec<User>> {
+        let conn = self.conn()?;
+        let mut stmt =
+            conn.prepare("SELECT id, username, password_hash, created_at FROM users ORDER BY id")?;
+        let users = stmt
+            .query_map([], user_from_row)?
+            .collect::<rusqlite::Result<Vec<User>>>()?;
+        Ok(users)
+    }
+}
+
+impl EmailRepository for Sqlite {
+    fn list_emails(&self, user_id: i64) -> Result<Vec<Email>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, user_id, email, verified, is_primary FROM emails WHERE user_id = ?1 ORDER BY id",
+        )?;
+        let emails = stmt
+            .query_map([user_id], email_from_row)?
+            .collect::<rusqlite::Result<Vec<Email>>>()?;
+        Ok(emails)
+    }
+
+    fn find_email(&self, email: &str) -> Result<Option<Email>> {
+        Ok(self
+            .conn()?
+            .query_row(
+                "SELECT id, user_id, email, verified, is_primary FROM emails WHERE email = ?1",
+                [email],
+                email_from_row,
+            )
+            .optional()?)
+    }
+
+    fn create_email_code(&self, email_id: i64, code: &str, expires_at: i64) -> Result<()> {
+        self.conn()?.execute(
+            "INSERT INTO email_codes (code, email_id, expires_at) VALUES (?1, ?2, ?3)",
+            params![code, email_id, expires_at],
+        )?;
+        Ok(())
+    }
+
+    fn find_email_code(&self, code: &str) -> Result<EmailCode> {
+        Ok(self.conn()?.query_row(
+            &format!("SELECT {EMAIL_CODE_COLUMNS} FROM email_codes WHERE code = ?1"),
+            [code],
+            email_code_from_row,
+        )?)
+    }
+
+    fn latest_email_code(&self, email_id: i64) -> Result<Option<EmailCode>> {
+        Ok(self
+            .conn()?
+            .query_row(
+                &format!(
+                    "SELECT {EMAIL_CODE_COLUMNS} FROM email_codes WHERE email_id = ?1
+                     ORDER BY created_at DESC, rowid DESC LIMIT 1"
+                ),
+                [email_id],
+                email_code_from_row,
+            )
+            .optional()?)
+    }
+
+    fn verify_email(&self, email_id: i64) -> Result<Email> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let email = tx.query_row(
+            "SELECT id, user_id, email, verified, is_primary FROM emails WHERE id = ?1",
+            [email_id],
+            email_from_row,
+        )?;
+        tx.execute("UPDATE emails SET verified = 1 WHERE id = ?1", [email.id])?;
+        tx.execute("DELETE FROM email_codes WHERE email_id = ?1", [email.id])?;
+        tx.commit()?;
+        Ok(Email {
+            verified: true,
+            ..email
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{test_db, StorageError};
+
+    #[test]
+    fn create_and_find() {
+        let db = test_db();
+        let user = db.create_user("Reporter", "hash", "r@example.com").unwrap();
+        assert_eq!(
+            db.find_user_by_username("reporter").unwrap(),
+            Some(user.clone())
+        );
+        assert_eq!(db.list_users().unwrap(), vec![user.clone()]);
+        let emails = db.list_emails(user.id).unwrap();
+        assert_eq!(emails.len(), 1);
+        assert!(emails[0].primary);
+        assert!(!emails[0].verified);
+    }
+
+    #[test]
+    fn duplicate_username() {
+        let db = test_db();
+        db.create_user("reporter", "hash", "a@example.com").unwrap();
+        let error = db
+            .create_user("REPORTER", "hash", "b@example.com")
+            .unwrap_err();
+        assert!(matches!(error, StorageError::Conflict(_)));
+    }
+
+    #[test]
+    fn email_codes() {
+        let db = test_db();
+        let user = db.create_user("reporter", "hash", "r@example.com").unwrap();
+        let email = db.list_emails(user.id).unwrap().remove(0);
+        assert_eq!(db.latest_email_code(email.id).unwrap(), None);
+        db.create_email_code(email.id, "first", 100).unwrap();
+        db.create_email_code(email.id, "second", 200).unwrap();
+
+        let code = db.find_email_code("first").unwrap();
+        assert_eq!(code.email_id, email.id);
+        assert_eq!(code.expires_at, 100);
+        assert!(code.created_at > 0);
+        let latest = db.latest_email_code(email.id).unwrap().unwrap();
+        assert_eq!(latest.code, "second");
+
+        let confirmed = db.verify_email(email.id).unwrap();
+        assert!(confirmed.verified);
+        assert!(db.find_email("r@example.com").unwrap().unwrap().verified);
+        assert!(matches!(
+            db.find_email_code("first"),
+            Err(StorageError::NotFound)
+        ));
+    }
+}
@@ -0,0 +1,510 @@
+//! Complaints to the municipal police, rendered as PDF.
+//!
+//! The text comes from a plain text template with `{{ name }}` placeholders,
+//! see [`Complaint::values`]. Lines starting with `# ` are headings, blank
+//! lines separate paragraphs and a line holding only `{{ pictures }}` places
+//! the pictures, which otherwise follow the text. The built-in template
+//! `api/templates/complaint.txt` is replaced per municipality by `<city>.txt`
+//! files in the configured directory and everywhere else by `default.txt`.
+//! Text is set in the bundled DejaVu Sans, so rendering doesn't depend on
+//! fonts installed on the server.
+
+use crate::mailer::templates::render;
+use crate::types::report::{Report, DATETIME_FORMAT};
+use image::codecs::jpeg::JpegDecoder;
+use image::{ColorType, ImageDecoder};
+use printpdf::{
+    ColorBits, ColorSpace, Image, ImageFilter, ImageTransform, ImageXObject, IndirectFontRef, Mm,
+    PdfDocument, PdfDocumentReference, PdfLayerReference, Px,
+};
+use std::collections::HashMap;
+use std::io::{self, Cursor};
+use std::path::Path;
+use thiserror::Error as ThisError;
+use time::format_description::FormatItem;
+use time::macros::format_description;
+use time::{Date, PrimitiveDateTime};
+
+const DEFAULT_TEMPLATE: &str = include_str!("../templates/complaint.txt");
+const REGULAR_FONT: &[u8] = include_bytes!("../fonts/DejaVuSans.ttf");
+const BOLD_FONT: &[u8] = include_bytes!("../fonts/DejaVuSans-Bold.ttf");
+
+/// Template line replaced by the pictures
+const PICTURES: &str = "{{ pictures }}";
+/// Shown for values a report doesn't have
+const MISSING: &str = "—";
+
+const DAY: &[FormatItem<'static>] =
+    format_description!("[day padding:none]. [month padding:none]. [year]");
+const TIME: &[FormatItem<'static>] =
+    format_description!("[day padding:none]. [month padding:none]. [year] [hour]:[minute]");
+
+// A4 page, sizes are in millimeters unless they are font sizes in points
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 20.0;
+const TEXT_SIZE: f32 = 11.0;
+const HEADING_SIZE: f32 = 15.0;
+const CAPTION_SIZE: f32 = 9.0;
+/// Line height relative to the font size
+const LEADING: f32 = 1.4;
+/// Millimeters per point
+const POINT: f32 = 25.4 / 72.0;
+/// Tallest picture, so two fit on a page with their captions
+const MAX_PICTURE_HEIGHT: f32 = 110.0;
+/// Resolution pictures are placed at before scaling
+const DPI: f32 = 300.0;
+
+#[derive(ThisError, Debug)]
+pub enum DocumentError {
+    #[error("Failed to read complaint templates: {0}")]
+    Templates(#[from] io::Error),
+
+    #[error("Failed to write PDF: {0}")]
+    Pdf(#[from] printpdf::Error),
+
+    #[error("Bundled font can't be read")]
+    Font,
+
+    #[error("Failed to read picture: {0}")]
+    Picture(#[from] image::ImageError),
+}
+
+/// Complaint templates, by lower case municipality name
+pub struct Templates {
+    default: String,
+    municipalities: HashMap<String, String>,
+}
+
+impl Default for Templates {
+    fn default() -> Self {
+        Self {
+            default: DEFAULT_TEMPLATE.to_string(),
+            municipalities: HashMap::new(),
+        }
+    }
+}
+
+impl Templates {
+    /// Read the `.txt` templates of a directory
+    pub fn load(directory: &Path) -> Result<Self, DocumentError> {
+        let mut templates = Self::default();
+        for entry in std::fs::read_dir(directory)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|e| e != "txt") {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let template = std::fs::read_to_string(&path)?;
+            if name == "default" {
+                templates.default = template;
+            } else {
+                templates
+                    .municipalities
+                    .insert(name.to_lowercase(), template);
+            }
+        }
+        Ok(templates)
+    }
+
+    /// Number of municipalities with a template of their own
+    pub fn len(&self) -> usize {
+        self.municipalities.len()
+    }
+
+    /// Template of a municipality, the default one for others
+    pub fn get(&self, city: Option<&str>) -> &str {
+        city.and_then(|c| self.municipalities.get(&c.to_lowercase()))
+            .unwrap_or(&self.default)
+    }
+}
+
+/// Reporter signing a complaint
+pub struct Reporter {
+    pub name: String,
+    pub email: Option<String>,
+}
+
+/// Published JPEG copy of a picture with its caption
+pub type CaptionedPicture = (Vec<u8>, Option<String>);
+
+/// Everything a complaint shows
+pub struct Complaint<'a> {
+    pub report: &'a Report,
+    /// Missing for anonymous reports
+    pub reporter: Option<Reporter>,
+    pub pictures: Vec<CaptionedPicture>,
+    /// Day the complaint is issued
+    pub date: Date,
+}
+
+/// `1. 6. 2023 12:30` for a stored `2023-06-01T12:30:00`
+fn display_time(time: &str) -> String {
+    PrimitiveDateTime::parse(time, DATETIME_FORMAT)
+        .ok()
+        .and_then(|t| t.format(TIME).ok())
+        .unwrap_or_else(|| time.to_string())
+}
+
+impl Complaint<'_> {
+    /// Municipality the report was observed in
+    fn city(&self) -> Option<&str> {
+        self.report
+            .resolved_address
+            .as_ref()
+            .map(|a| a.city.as_str())
+    }
+
+    /// Values of the template placeholders
+    fn values(&self) -> Vec<(&'static str, String)> {
+        let report = self.report;
+        let or_missing = |value: Option<String>| {
+            value
+                .filter(|v| !v.trim().is_empty())
+                .unwrap_or_else(|| MISSING.to_string())
+        };
+        vec![
+            ("report_id", report.id.to_string()),
+            ("plate", report.plate.clone()),
+            ("country", report.country.clone()),
+            ("city", or_missing(self.city().map(str::to_string))),
+            (
+                "address",
+                or_missing(
+                    report
+                        .resolved_address
+                        .as_ref()
+                        .map(ToString::to_string)
+                        .or_else(|| report.address.clone()),
+                ),
+            ),
+            (
+                "position",
+                or_missing(
+                    report
+                        .latitude
+                        .zip(report.longitude)
+                        .map(|(latitude, longitude)| format!("{latitude:.5}, {longitude:.5}")),
+                ),
+            ),
+            ("observed_at", display_time(&report.observed_at)),
+            ("description", or_missing(report.description.clone())),
+            (
+                "reporter_name",
+                or_missing(self.reporter.as_ref().map(|r| r.name.clone())),
+            ),
+            (
+                "reporter_email",
+                or_missing(self.reporter.as_ref().and_then(|r| r.email.clone())),
+            ),
+            (
+                "date",
+                self.date
+                    .format(DAY)
+                    .unwrap_or_else(|_| self.date.to_string()),
+            ),
+        ]
+    }
+
+    /// Render the template of the report's municipality into a PDF
+    pub fn render(&self, templates: &Templates) -> Result<Vec<u8>, DocumentError> {
+        let values = self.values();
+        let values = values
+            .iter()
+            .map(|(key, value)| (*key, value.as_str()))
+            .collect::<Vec<_>>();
+        let mut layout = Layout::new(&format!("{} {}", self.report.plate, self.report.id))?;
+        let mut pictures_placed = false;
+        for line in self.template(templates).lines() {
+            if line.trim() == PICTURES {
+                self.place_pictures(&mut layout)?;
+                pictures_placed = true;
+            } else if let Some(heading) = line.strip_prefix("# ") {
+                layout.space(TEXT_SIZE * POINT);
+                layout.text(&render(heading, &values, false), true, HEADING_SIZE);
+                layout.space(TEXT_SIZE * POINT);
+            } else if line.trim().is_empty() {
+                layout.space(TEXT_SIZE * POINT * 0.8);
+            } else {
+                for text in render(line, &values, false).lines() {
+                    layout.text(text, false, TEXT_SIZE);
+                }
+            }
+        }
+        if !pictures_placed {
+            self.place_pictures(&mut layout)?;
+        }
+        layout.finish()
+    }
+
+    fn template<'t>(&self, templates: &'t Templates) -> &'t str {
+        templates.get(self.city())
+    }
+
+    fn place_pictures(&self, layout: &mut Layout) -> Result<(), DocumentError> {
+        for (index, (jpeg, caption)) in self.pictures.iter().enumerate() {
+            let caption = format!("{}. {}", index + 1, caption.as_deref().unwrap_or_default());
+            layout.picture(jpeg, caption.trim())?;
+        }
+        Ok(())
+    }
+}
+
+/// Embedded font with the metrics needed to wrap text
+struct Font {
+    face: ttf_parser::Face<'static>,
+    reference: IndirectFontRef,
+}
+
+impl Font {
+    fn load(doc: &PdfDocumentReference, data: &'static [u8]) -> Result<Self, DocumentError> {
+        Ok(Self {
+            face: ttf_parser::Face::parse(data, 0).map_err(|_| DocumentError::Font)?,
+            reference: doc.add_external_font(data)?,
+        })
+    }
+
+    /// Width of a text in millimeters
+    fn width(&self, text: &str, size: f32) -> f32 {
+        let units = text
+            .chars()
+            .filter_map(|c| self.face.glyph_index(c))
+            .filter_map(|g| self.face.glyph_hor_advance(g))
+            .map(u32::from)
+            .sum::<u32>();
+        units as f32 / f32::from(self.face.units_per_em()) * size * POINT
+    }
+
+    /// Lines at most `width` wide, words longer than that are broken
+    fn wrap(&self, text: &str, size: f32, width: f32) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut line = String::new();
+        for word in text.split_whitespace() {
+            let joined = if line.is_empty() {
+                word.to_string()
+            } else {
+                format!("{line} {word}")
+            };
+            if self.width(&joined, size) <= width {
+                line = joined;
+                continue;
+            }
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            for c in word.chars() {
+                line.push(c);
+                if self.width(&line, size) > width && line.chars().count() > 1 {
+                    line.pop();
+                    lines.push(std::mem::replace(&mut line, c.to_string()));
+                }
+            }
+        }
+        if !line.is_empty() {
+            lines.push(line);
+        }
+        lines
+    }
+}
+
+/// Pages filled from the top, one block after another
+struct Layout {
+    doc: PdfDocumentReference,
+    layer: PdfLayerReference,
+    regular: Font,
+    bold: Font,
+    /// Top of the next block, from the bottom of the page
+    y: f32,
+}
+
+impl Layout {
+    fn new(title: &str) -> Result<Self, DocumentError> {
+        let (doc, page, layer) = PdfDocument::new(title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Text");
+        let layer = doc.get_page(page).get_layer(layer);
+        Ok(Self {
+            regular: Font::load(&doc, REGULAR_FONT)?,
+            bold: Font::load(&doc, BOLD_FONT)?,
+            doc,
+            layer,
+            y: PAGE_HEIGHT - MARGIN,
+        })
+    }
+
+    /// Continue on a new page unless `height` still fits on this one
+    fn reserve(&mut self, height: f32) {
+        if self.y - height < MARGIN && self.y < PAGE_HEIGHT - MARGIN {
+            let (page, layer) = self.doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Text");
+            self.layer = self.doc.get_page(page).get_layer(layer);
+            self.y = PAGE_HEIGHT - MARGIN;
+        }
+    }
+
+    /// Vertical gap, dropped at the top of a page
+    fn space(&mut self, height: f32) {
+        if self.y < PAGE_HEIGHT - MARGIN {
+            self.y -= height;
+        }
+    }
+
+    fn text(&mut self, text: &str, bold: bool, size: f32) {
+        let width = PAGE_WIDTH - 2.0 * MARGIN;
+        let line_height = size * LEADING * POINT;
+        let lines = if bold { &self.bold } else { &self.regular }.wrap(text, size, width);
+        for line in lines {
+            self.reserve(line_height);
+            let font = if bold { &self.bold } else { &self.regular };
+            let baseline = self.y - size * POINT;
+            self.layer
+                .use_text(line, size, Mm(MARGIN), Mm(baseline), &font.reference);
+            self.y -= line_height;
+        }
+    }
+
+    /// A JPEG scaled to the text width with its caption below
+    fn picture(&mut self, jpeg: &[u8], caption: &str) -> Result<(), DocumentError> {
+        let decoder = JpegDecoder::new(Cursor::new(jpeg))?;
+        let (width, height) = decoder.dimensions();
+        let color_space = match decoder.color_type() {
+            ColorType::L8 => ColorSpace::Greyscale,
+            _ => ColorSpace::Rgb,
+        };
+        let available = PAGE_WIDTH - 2.0 * MARGIN;
+        let scale = (available / width as f32).min(MAX_PICTURE_HEIGHT / height as f32);
+        let (shown_width, shown_height) = (width as f32 * scale, height as f32 * scale);
+        self.space(TEXT_SIZE * POINT);
+        self.reserve(shown_height + CAPTION_SIZE * LEADING * POINT);
+        let image = Image::from(ImageXObject {
+            width: Px(width as usize),
+            height: Px(height as usize),
+            color_space,
+            bits_per_component: ColorBits::Bit8,
+            interpolate: true,
+            image_data: jpeg.to_vec(),
+            image_filter: Some(ImageFilter::DCT),
+            smask: None,
+            clipping_bbox: None,
+        });
+        // Size at `DPI` in millimeters, scaled to the shown size
+        let natural = width as f32 / DPI * 25.4;
+        image.add_to_layer(
+            self.layer.clone(),
+            ImageTransform {
+                translate_x: Some(Mm(MARGIN + (available - shown_width) / 2.0)),
+                translate_y: Some(Mm(self.y - shown_height)),
+                scale_x: Some(shown_width / natural),
+                scale_y: Some(shown_width / natural),
+                dpi: Some(DPI),
+                ..ImageTransform::default()
+            },
+        );
+        self.y -= shown_height + 1.0;
+        self.text(caption, false, CAPTION_SIZE);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Vec<u8>, DocumentError> {
+        Ok(self.doc.save_to_bytes()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::geocode::Address;
+    use crate::uploads::test_jpeg;
+
+    fn report() -> Report {
+        Report {
+            id: 7,
+            plate: "1AB 2345".to_string(),
+            country: "CZ".to_string(),
+            latitude: Some(49.19522),
+            longitude: Some(16.60796),
+            resolved_address: Some(Address {
+                street: Some("Náměstí Svobody".to_string()),
+                house_number: "1".to_string(),
+                city: "Brno".to_string(),
+                district: None,
+                postcode: Some("60200".to_string()),
+            }),
+            observed_at: "2023-06-01T12:30:00".to_string(),
+            description: Some("Parkuje na přechodu".to_string()),
+            ..Report::default()
+        }
+    }
+
+    #[test]
+    fn template_per_municipality() {
+        let directory =
+            std::env::temp_dir().join(format!("carreporter-templates-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("Brno.txt"), "Magistrát {{ city }}").unwrap();
+        std::fs::write(directory.join("notes.md"), "ignored").unwrap();
+        let templates = Templates::load(&directory).unwrap();
+        assert_eq!(templates.len(), 1);
+        assert_eq!(templates.get(Some("BRNO")), "Magistrát {{ city }}");
+        assert_eq!(templates.get(Some("Praha")), DEFAULT_TEMPLATE);
+        assert_eq!(templates.get(None), DEFAULT_TEMPLATE);
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn values_filled_in() {
+        let report = report();
+        let complaint = Complaint {
+            report: &report,
+            reporter: None,
+            pictures: Vec::new(),
+            date: time::macros::date!(2023 - 06 - 02),
+        };
+        let values = complaint.values();
+        let values = values
+            .iter()
+            .map(|(key, value)| (*key, value.as_str()))
+            .collect::<Vec<_>>();
+        let text = render(DEFAULT_TEMPLATE, &values, false);
+        assert!(text.contains("Městská policie Brno"));
+        assert!(text.contains("Náměstí Svobody 1"));
+        assert!(text.contains("49.19522, 16.60796"));
+        assert!(text.contains("1. 6. 2023 12:30"));
+        assert!(text.contains("Dne 2. 6. 2023"));
+        assert!(text.contains(&format!("Oznamovatel: {MISSING}")));
+        assert_eq!(text.matches("{{").count(), 1, "only the pictures are left");
+    }
+
+    #[test]
+    fn long_words_wrapped() {
+        let (doc, _, _) = PdfDocument::new("test", Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Text");
+        let font = Font::load(&doc, REGULAR_FONT).unwrap();
+        let width = font.width("Příliš žluťoučký", TEXT_SIZE);
+        let lines = font.wrap("Příliš žluťoučký kůň úpěl", TEXT_SIZE, width);
+        assert_eq!(lines, vec!["Příliš žluťoučký", "kůň úpěl"]);
+        let lines = font.wrap("abcdefghij", TEXT_SIZE, font.width("abcd", TEXT_SIZE));
+        assert_eq!(lines, vec!["abcd", "efgh", "ij"]);
+    }
+
+    #[test]
+    fn rendered_with_pictures() {
+        let report = report();
+        let complaint = Complaint {
+            report: &report,
+            reporter: Some(Reporter {
+                name: "Jiří".to_string(),
+                email: Some("jiri@example.com".to_string()),
+            }),
+            pictures: vec![
+                (test_jpeg(1), Some("Zepředu".to_string())),
+                (test_jpeg(2), None),
+                (test_jpeg(3), None),
+            ],
+            date: time::macros::date!(2023 - 06 - 02),
+        };
+        let pdf = complaint.render(&Templates::default()).unwrap();
+        assert!(pdf.starts_with(b"%PDF"));
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains("DCTDecode"));
+        assert!(text.contains("DejaVuSans"));
+    }
+}
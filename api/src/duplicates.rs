@@ -0,0 +1,272 @@
+//! Reports of the same car, matched by the plate without spaces.
+//!
+//! A new report duplicates one still in moderation when the car was observed
+//! within `duplicates.window_minutes` of it and `duplicates.distance_meters`
+//! away, or at the same address when either has no position. The plate
+//! history groups the reports of the last `duplicates.history_days` into
+//! spots the same way.
+
+use crate::db::{Db, Result};
+use crate::geocode::{distance, fold};
+use crate::settings::Duplicates;
+use crate::types::duplicate::{Duplicate, PlateHistory, PlateReport, PlateSpot};
+use crate::types::report::{Report, ReportStatus, DATETIME_FORMAT};
+use time::{Duration, OffsetDateTime, PrimitiveDateTime};
+
+/// Reports counted in the history of a plate, drafts, withdrawn and rejected
+/// reports are no evidence
+const HISTORY_STATUSES: &[ReportStatus] = &[
+    ReportStatus::Submitted,
+    ReportStatus::UnderReview,
+    ReportStatus::Forwarded,
+    ReportStatus::Resolved,
+];
+
+/// Plate as matched, spaces are not part of it
+pub fn plate_key(plate: &str) -> String {
+    plate.replace(' ', "")
+}
+
+fn format(time: PrimitiveDateTime) -> Option<String> {
+    time.format(DATETIME_FORMAT).ok()
+}
+
+/// Meters between two reports, `Some(None)` when they share the address but
+/// not both have a position and `None` when they are not at the same spot
+fn separation(a: &Report, b: &Report, max_distance: f64) -> Option<Option<f64>> {
+    let positions = a.latitude.zip(a.longitude).zip(b.latitude.zip(b.longitude));
+    if let Some((a, b)) = positions {
+        let meters = distance(a, b);
+        return (meters <= max_distance).then_some(Some(meters));
+    }
+    let address = |r: &Report| {
+        r.resolved_address
+            .as_ref()
+            .map(|a| a.to_string())
+            .or_else(|| r.address.clone())
+            .map(|a| fold(a.trim()))
+    };
+    match (address(a), address(b)) {
+        (Some(a), Some(b)) if a == b => Some(None),
+        _ => None,
+    }
+}
+
+/// Reports in moderation the report likely duplicates, nearest first
+pub fn find(db: &Db, settings: &Duplicates, report: &Report) -> Result<Vec<Duplicate>> {
+    let Ok(observed) = PrimitiveDateTime::parse(&report.observed_at, DATETIME_FORMAT) else {
+        return Ok(Vec::new());
+    };
+    let window = Duration::minutes(settings.window_minutes);
+    let (Some(from), Some(to)) = (format(observed - window), format(observed + window)) else {
+        return Ok(Vec::new());
+    };
+    let candidates = db.plate_reports(
+        &report.country,
+        &plate_key(&report.plate),
+        (&from, &to),
+        ReportStatus::QUEUE,
+    )?;
+    let mut duplicates = candidates
+        .into_iter()
+        .filter(|c| c.id != report.id)
+        .filter_map(|c| {
+            let distance_meters = separation(report, &c, settings.distance_meters)?;
+            Some(Duplicate {
+                id: c.id,
+                observed_at: c.observed_at,
+                distance_meters,
+                pictures: c.pictures.len(),
+                status: c.status,
+            })
+        })
+        .collect::<Vec<_>>();
+    duplicates.sort_by(|a, b| {
+        let key = |d: &Duplicate| d.distance_meters.unwrap_or_default();
+        key(a).total_cmp(&key(b))
+    });
+    Ok(duplicates)
+}
+
+/// Reports of a plate over the last days grouped by spot, each spot is where
+/// its oldest report was observed
+pub fn history(db: &Db, settings: &Duplicates, country: &str, plate: &str) -> Result<PlateHistory> {
+    let now = OffsetDateTime::now_utc();
+    let now = PrimitiveDateTime::new(now.date(), now.time());
+    // Observations carry local time, which may be ahead of UTC
+    let from = format(now - Duration::days(settings.history_days)).unwrap_or_default();
+    let to = format(now + Duration::days(1)).unwrap_or_default();
+    let reports = db.plate_reports(country, &plate_key(plate), (&from, &to), HISTORY_STATUSES)?;
+    let total = reports.len();
+    let mut spots: Vec<(Report, Vec<PlateReport>)> = Vec::new();
+    for report in reports {
+        let entry = PlateReport {
+            id: report.id,
+            observed_at: report.observed_at.clone(),
+            status: report.status,
+        };
+        match spots
+            .iter_mut()
+            .find(|(first, _)| separation(first, &report, settings.distance_meters).is_some())
+        {
+            Some((_, reports)) => reports.push(entry),
+            None => spots.push((report, vec![entry])),
+        }
+    }
+    spots.sort_by_key(|(_, reports)| std::cmp::Reverse(reports.len()));
+    Ok(PlateHistory {
+        plate: plate.to_string(),
+        country: country.to_string(),
+        days: settings.history_days,
+        total,
+        spots: spots
+            .into_iter()
+            .map(|(first, reports)| PlateSpot {
+                latitude: first.latitude,
+                longitude: first.longitude,
+                address: first
+                    .resolved_address
+                    .as_ref()
+                    .map(|a| a.to_string())
+                    .or(first.address),
+                reports,
+            })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::types::report::NewReport;
+
+    const SETTINGS: Duplicates = Duplicates {
+        distance_meters: 50.0,
+        window_minutes: 60,
+        history_days: 30,
+    };
+
+    fn report(
+        db: &Db,
+        plate: &str,
+        position: Option<(f64, f64)>,
+        observed_at: &str,
+        status: ReportStatus,
+    ) -> Report {
+        let report = NewReport {
+            plate: plate.to_string(),
+            country: "CZ".to_string(),
+            latitude: position.map(|p| p.0),
+            longitude: position.map(|p| p.1),
+            address: Some("Vinohradská 12, Praha".to_string()),
+            observed_at: observed_at.to_string(),
+            ..NewReport::default()
+        };
+        db.create_report(&report, None, None, status).unwrap()
+    }
+
+    #[test]
+    fn nearby_duplicates() {
+        let db = db::test_db().into_handle();
+        let here = Some((50.0755, 14.4378));
+        let same = report(
+            &db,
+            "1AB 2345",
+            here,
+            "2023-06-01T12:00:00",
+            ReportStatus::Submitted,
+        );
+        let near = report(
+            &db,
+            "1AB2345",
+            Some((50.0757, 14.4378)),
+            "2023-06-01T12:50:00",
+            ReportStatus::UnderReview,
+        );
+        let unplaced = report(
+            &db,
+            "1AB 2345",
+            None,
+            "2023-06-01T11:30:00",
+            ReportStatus::Submitted,
+        );
+        report(
+            &db,
+            "1AB 2345",
+            Some((50.08, 14.44)),
+            "2023-06-01T12:10:00",
+            ReportStatus::Submitted,
+        );
+        report(
+            &db,
+            "1AB 2345",
+            here,
+            "2023-06-01T14:00:00",
+            ReportStatus::Submitted,
+        );
+        report(
+            &db,
+            "1AB 2345",
+            here,
+            "2023-06-01T12:05:00",
+            ReportStatus::Forwarded,
+        );
+        report(
+            &db,
+            "2CD 6789",
+            here,
+            "2023-06-01T12:05:00",
+            ReportStatus::Submitted,
+        );
+
+        let new = Report {
+            plate: "1AB 2345".to_string(),
+            country: "CZ".to_string(),
+            latitude: Some(50.0755),
+            longitude: Some(14.4378),
+            address: Some("vinohradská 12, praha".to_string()),
+            observed_at: "2023-06-01T12:30:00".to_string(),
+            ..Report::default()
+        };
+        let found = find(&db, &SETTINGS, &new).unwrap();
+        let ids = found.iter().map(|d| d.id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![unplaced.id, same.id, near.id]);
+        assert_eq!(found[0].distance_meters, None);
+        assert_eq!(found[1].distance_meters, Some(0.0));
+        assert!(found[2].distance_meters.unwrap() > 20.0);
+
+        let found = find(&db, &SETTINGS, &same).unwrap();
+        assert!(found.iter().all(|d| d.id != same.id));
+    }
+
+    #[test]
+    fn history_by_spot() {
+        let db = db::test_db().into_handle();
+        let now = OffsetDateTime::now_utc();
+        let recent = |days: i64| {
+            let time = now - Duration::days(days);
+            format(PrimitiveDateTime::new(time.date(), time.time())).unwrap()
+        };
+        let home = Some((50.0755, 14.4378));
+        let work = Some((49.1950, 16.6080));
+        let first = report(&db, "1AB 2345", work, &recent(20), ReportStatus::Forwarded);
+        let second = report(&db, "1AB 2345", home, &recent(10), ReportStatus::Resolved);
+        let third = report(&db, "1AB 2345", home, &recent(2), ReportStatus::Submitted);
+        report(&db, "1AB 2345", home, &recent(40), ReportStatus::Forwarded);
+        report(&db, "1AB 2345", home, &recent(1), ReportStatus::Withdrawn);
+        report(&db, "1AB 2345", home, &recent(1), ReportStatus::Draft);
+
+        let history = history(&db, &SETTINGS, "CZ", "1AB 2345").unwrap();
+        assert_eq!(history.total, 3);
+        assert_eq!(history.days, 30);
+        let spots = history
+            .spots
+            .iter()
+            .map(|s| s.reports.iter().map(|r| r.id).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(spots, vec![vec![second.id, third.id], vec![first.id]]);
+        assert_eq!(history.spots[0].latitude, Some(50.0755));
+        assert_eq!(history.spots[1].reports[0].status, ReportStatus::Forwarded);
+    }
+}
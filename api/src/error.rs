@@ -0,0 +1,125 @@
+//! Error type returned by request handlers
+
+use crate::db::StorageError;
+use crate::types::auth::ApiResult;
+use crate::types::ErrorInfo;
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use thiserror::Error as ThisError;
+use tracing::{error, info};
+
+/// Errors a handler can respond with, mirroring the frontend's `Error`
+#[derive(ThisError, Debug, Clone, PartialEq)]
+pub enum ApiError {
+    /// 400
+    #[error("Bad Request")]
+    BadRequest,
+
+    /// 401
+    #[error("{0}")]
+    Unauthorized(String),
+
+    /// 403
+    #[error("{0}")]
+    Forbidden(String),
+
+    /// 404
+    #[error("Not Found")]
+    NotFound,
+
+    /// 409
+    #[error("{0}")]
+    Conflict(String),
+
+    /// 422
+    #[error("Unprocessable Entity: {0:?}")]
+    UnprocessableEntity(ErrorInfo),
+
+    /// 500
+    #[error("Internal Server Error: {0}")]
+    InternalServerError(String),
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::BadRequest => StatusCode::BAD_REQUEST,
+            Self::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Self::Forbidden(_) => StatusCode::FORBIDDEN,
+            Self::NotFound => StatusCode::NOT_FOUND,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::UnprocessableEntity(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::InternalServerError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut response = HttpResponse::build(self.status_code());
+        match self {
+            Self::UnprocessableEntity(info) => response.json(info),
+            Self::InternalServerError(_) => response.json(ApiResult {
+                result: "Internal Server Error".to_string(),
+            }),
+            e => response.json(ApiResult {
+                result: e.to_string(),
+            }),
+        }
+    }
+}
+
+impl From<ErrorInfo> for ApiError {
+    fn from(info: ErrorInfo) -> Self {
+        Self::UnprocessableEntity(info)
+    }
+}
+
+impl From<StorageError> for ApiError {
+    fn from(e: StorageError) -> Self {
+        match e {
+            StorageError::NotFound => Self::NotFound,
+            StorageError::Conflict(message) => Self::Conflict(message),
+            e => {
+                error!("Storage error: {e}");
+                Self::InternalServerError(e.to_string())
+            }
+        }
+    }
+}
+
+impl From<actix_web::error::BlockingError> for ApiError {
+    fn from(e: actix_web::error::BlockingError) -> Self {
+        error!("Blocking task failed: {e}");
+        Self::InternalServerError(e.to_string())
+    }
+}
+
+impl From<actix_multipart::MultipartError> for ApiError {
+    fn from(e: actix_multipart::MultipartError) -> Self {
+        info!("Malformed multipart request: {e}");
+        Self::BadRequest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unprocessable_status() {
+        let error = ApiError::UnprocessableEntity(ErrorInfo::default());
+        assert_eq!(error.status_code(), StatusCode::UNPROCESSABLE_ENTITY)
+    }
+
+    #[test]
+    fn internal_error_hides_detail() {
+        let error = ApiError::InternalServerError("secret path".to_string());
+        let response = error.error_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    #[test]
+    fn storage_not_found() {
+        let error: ApiError = StorageError::NotFound.into();
+        assert_eq!(error, ApiError::NotFound)
+    }
+}
@@ -0,0 +1,314 @@
+//! Emailing approved reports to the authority in charge of the place and
+//! threading the replies and bounces back onto them.
+//!
+//! The authority is the first one whose polygon contains the position of the
+//! report, otherwise the first one listing its district or city. Outgoing
+//! emails get a `Message-ID` of their own so answers can be matched through
+//! `In-Reply-To` and `References`, bounces through the original headers they
+//! quote.
+
+use crate::db::NewMessage;
+use crate::mailer::{templates, MailerError};
+use crate::settings::Authority;
+use crate::types::forward::MessageKind;
+use crate::types::report::Report;
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MultiPart};
+use lettre::Message;
+use mail_parser::{MessageParser, MimeHeaders};
+
+/// Prefix of the local part of our message ids
+const ID_PREFIX: &str = "report-";
+
+/// Whether a `[latitude, longitude]` polygon contains the position, by
+/// counting the edges a ray to the east crosses
+fn contains(polygon: &[[f64; 2]], latitude: f64, longitude: f64) -> bool {
+    let mut inside = false;
+    let mut previous = polygon.len() - 1;
+    for (current, &[lat_a, lon_a]) in polygon.iter().enumerate() {
+        let [lat_b, lon_b] = polygon[previous];
+        if (lat_a > latitude) != (lat_b > latitude)
+            && longitude < lon_a + (latitude - lat_a) / (lat_b - lat_a) * (lon_b - lon_a)
+        {
+            inside = !inside;
+        }
+        previous = current;
+    }
+    inside
+}
+
+/// Authority in charge of the place of a report
+pub fn authority_for<'a>(authorities: &'a [Authority], report: &Report) -> Option<&'a Authority> {
+    let by_position = report
+        .latitude
+        .zip(report.longitude)
+        .and_then(|(lat, lon)| {
+            authorities
+                .iter()
+                .find(|a| !a.polygon.is_empty() && contains(&a.polygon, lat, lon))
+        });
+    by_position.or_else(|| {
+        let address = report.resolved_address.as_ref()?;
+        let places = [address.district.as_deref(), Some(address.city.as_str())];
+        places.into_iter().flatten().find_map(|place| {
+            authorities.iter().find(|a| {
+                a.districts
+                    .iter()
+                    .any(|d| d.trim().to_lowercase() == place.to_lowercase())
+            })
+        })
+    })
+}
+
+/// New message id for an email about a report, without the angle brackets
+pub fn message_id(report_id: i64, from: &Mailbox) -> String {
+    format!(
+        "{ID_PREFIX}{report_id}.{}@{}",
+        uuid::Uuid::new_v4().simple(),
+        from.email.domain()
+    )
+}
+
+/// Email to an authority with the complaint and the published pictures attached
+pub fn message(
+    from: &Mailbox,
+    reply_to: Option<&Mailbox>,
+    authority: &Authority,
+    report: &Report,
+    message_id: &str,
+    complaint: Vec<u8>,
+    pictures: Vec<Vec<u8>>,
+) -> Result<Message, MailerError> {
+    let to = Mailbox::new(Some(authority.name.clone()), authority.email.parse()?);
+    let content = templates::forward_report(report);
+    let mut body = MultiPart::mixed()
+        .multipart(MultiPart::alternative_plain_html(
+            content.text,
+            content.html,
+        ))
+        .singlepart(Attachment::new(format!("report-{}.pdf", report.id)).body(
+            complaint,
+            ContentType::parse("application/pdf").expect("valid content type"),
+        ));
+    for (index, picture) in pictures.into_iter().enumerate() {
+        body = body.singlepart(Attachment::new(format!("photo-{}.jpg", index + 1)).body(
+            picture,
+            ContentType::parse("image/jpeg").expect("valid content type"),
+        ));
+    }
+    let mut builder = Message::builder()
+        .from(from.clone())
+        .to(to)
+        .subject(content.subject)
+        .message_id(Some(format!("<{message_id}>")));
+    if let Some(reply_to) = reply_to {
+        builder = builder.reply_to(reply_to.clone());
+    }
+    Ok(builder.multipart(body)?)
+}
+
+/// Received email with the ids of the messages it refers to
+#[derive(Clone, Debug, PartialEq)]
+pub struct Inbound {
+    /// Our message ids first mentioned in the headers, then quoted anywhere
+    pub references: Vec<String>,
+    pub message: NewMessage,
+}
+
+/// Our message ids quoted anywhere in a raw email, as bounces attach the
+/// headers of the undelivered message
+fn quoted_ids(raw: &str) -> Vec<String> {
+    raw.match_indices(&format!("<{ID_PREFIX}"))
+        .filter_map(|(start, _)| {
+            let rest = &raw[start + 1..];
+            let id = &rest[..rest.find('>')?];
+            (!id.contains(char::is_whitespace) && id.contains('@')).then(|| id.to_string())
+        })
+        .collect()
+}
+
+/// Parse a raw RFC 822 email received in reply to a forwarded report
+pub fn parse_inbound(raw: &[u8]) -> Option<Inbound> {
+    let parsed = MessageParser::default().parse(raw)?;
+    let headers = [parsed.in_reply_to(), parsed.references()];
+    let quoted = quoted_ids(&String::from_utf8_lossy(raw));
+    let mut references: Vec<String> = Vec::new();
+    for id in headers
+        .iter()
+        .flat_map(|h| h.as_text_list().unwrap_or_default())
+        .map(str::to_string)
+        .chain(quoted)
+    {
+        if !references.contains(&id) {
+            references.push(id);
+        }
+    }
+    let sender = parsed
+        .from()
+        .and_then(|a| a.first())
+        .and_then(|a| a.address())
+        .map(str::to_string);
+    let delivery_report = parsed.content_type().is_some_and(|c| {
+        c.ctype().eq_ignore_ascii_case("multipart")
+            && c.subtype()
+                .is_some_and(|s| s.eq_ignore_ascii_case("report"))
+    });
+    let from_daemon = sender.as_deref().is_some_and(|s| {
+        let local = s.split('@').next().unwrap_or_default().to_lowercase();
+        local == "mailer-daemon" || local == "postmaster"
+    });
+    let kind = if delivery_report || from_daemon {
+        MessageKind::Bounce
+    } else {
+        MessageKind::Reply
+    };
+    let body = parsed
+        .body_text(0)
+        .map(|b| b.trim().to_string())
+        .unwrap_or_default();
+    Some(Inbound {
+        references,
+        message: NewMessage {
+            kind,
+            message_id: parsed.message_id().map(str::to_string),
+            sender,
+            subject: parsed.subject().map(str::to_string),
+            body,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::geocode::Address;
+
+    fn authority(name: &str, districts: &[&str], polygon: &[[f64; 2]]) -> Authority {
+        Authority {
+            name: name.to_string(),
+            email: format!("{}@example.com", name.to_lowercase()),
+            districts: districts.iter().map(|d| d.to_string()).collect(),
+            polygon: polygon.to_vec(),
+        }
+    }
+
+    #[test]
+    fn polygon_contains() {
+        let square = [[0.0, 0.0], [0.0, 2.0], [2.0, 2.0], [2.0, 0.0]];
+        assert!(contains(&square, 1.0, 1.0));
+        assert!(!contains(&square, 3.0, 1.0));
+        assert!(!contains(&square, 1.0, -0.5));
+        let triangle = [[0.0, 0.0], [2.0, 0.0], [0.0, 2.0]];
+        assert!(contains(&triangle, 0.5, 0.5));
+        assert!(!contains(&triangle, 1.5, 1.5));
+    }
+
+    #[test]
+    fn authority_by_position_then_district() {
+        let authorities = [
+            authority("Prague", &["Praha 2", "Praha 3"], &[]),
+            authority(
+                "Brno",
+                &["Brno"],
+                &[[49.1, 16.5], [49.3, 16.5], [49.3, 16.7], [49.1, 16.7]],
+            ),
+        ];
+        let mut report = Report {
+            latitude: Some(49.195),
+            longitude: Some(16.608),
+            ..Report::default()
+        };
+        assert_eq!(authority_for(&authorities, &report).unwrap().name, "Brno");
+
+        report.latitude = Some(50.075);
+        report.longitude = Some(14.437);
+        assert!(authority_for(&authorities, &report).is_none());
+        report.resolved_address = Some(Address {
+            city: "Praha".to_string(),
+            district: Some("praha 2".to_string()),
+            ..Address::default()
+        });
+        assert_eq!(authority_for(&authorities, &report).unwrap().name, "Prague");
+    }
+
+    #[test]
+    fn outgoing_message() {
+        let from: Mailbox = "Car Reporter <noreply@example.com>".parse().unwrap();
+        let report = Report {
+            id: 7,
+            plate: "1AB 2345".to_string(),
+            ..Report::default()
+        };
+        let id = message_id(report.id, &from);
+        assert!(id.starts_with("report-7."));
+        assert!(id.ends_with("@example.com"));
+        let message = message(
+            &from,
+            Some(&"replies@example.com".parse().unwrap()),
+            &authority("Police", &["Praha 2"], &[]),
+            &report,
+            &id,
+            b"%PDF-1.3".to_vec(),
+            vec![vec![0xff, 0xd8]],
+        )
+        .unwrap();
+        let raw = String::from_utf8(message.formatted()).unwrap();
+        assert!(raw.contains(&format!("Message-ID: <{id}>")));
+        assert!(raw.contains("Reply-To: replies@example.com"));
+        assert!(raw.contains("To: Police <police@example.com>"));
+        assert!(raw.contains("filename=\"report-7.pdf\""));
+        assert!(raw.contains("filename=\"photo-1.jpg\""));
+    }
+
+    #[test]
+    fn reply_threaded() {
+        let raw = b"From: Police <police@example.com>\r\n\
+            To: replies@example.com\r\n\
+            Subject: Re: report\r\n\
+            Message-ID: <answer@police.example.com>\r\n\
+            In-Reply-To: <report-7.abc@example.com>\r\n\
+            References: <report-7.abc@example.com>\r\n\
+            \r\n\
+            We fined the driver.\r\n";
+        let inbound = parse_inbound(raw).unwrap();
+        assert_eq!(inbound.references, vec!["report-7.abc@example.com"]);
+        assert_eq!(inbound.message.kind, MessageKind::Reply);
+        assert_eq!(
+            inbound.message.sender.as_deref(),
+            Some("police@example.com")
+        );
+        assert_eq!(
+            inbound.message.message_id.as_deref(),
+            Some("answer@police.example.com")
+        );
+        assert_eq!(inbound.message.body, "We fined the driver.");
+    }
+
+    #[test]
+    fn bounce_detected() {
+        let raw = b"From: Mail Delivery System <MAILER-DAEMON@mx.example.com>\r\n\
+            To: noreply@example.com\r\n\
+            Subject: Undelivered Mail Returned to Sender\r\n\
+            MIME-Version: 1.0\r\n\
+            Content-Type: multipart/report; report-type=delivery-status; boundary=\"b\"\r\n\
+            \r\n\
+            --b\r\n\
+            Content-Type: text/plain\r\n\
+            \r\n\
+            The mailbox does not exist.\r\n\
+            --b\r\n\
+            Content-Type: message/delivery-status\r\n\
+            \r\n\
+            Final-Recipient: rfc822; police@example.com\r\n\
+            Status: 5.1.1\r\n\
+            --b\r\n\
+            Content-Type: text/rfc822-headers\r\n\
+            \r\n\
+            Message-ID: <report-7.abc@example.com>\r\n\
+            --b--\r\n";
+        let inbound = parse_inbound(raw).unwrap();
+        assert_eq!(inbound.message.kind, MessageKind::Bounce);
+        assert_eq!(inbound.references, vec!["report-7.abc@example.com"]);
+        assert_eq!(inbound.message.body, "The mailbox does not exist.");
+    }
+}
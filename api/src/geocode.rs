@@ -0,0 +1,304 @@
+//! Offline geocoding against address points imported at startup.
+//!
+//! The dataset is a UTF-8 text file with one address point per line and
+//! `;` separated columns named by a header line. `latitude`, `longitude`
+//! (WGS84), `house_number` and `city` are required, `street`, `district` and
+//! `postcode` are optional and other columns are ignored, so RÚIAN or OSM
+//! exports converted to WGS84 can be loaded without trimming them.
+
+use crate::types::geocode::{Address, Place};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use thiserror::Error as ThisError;
+
+/// Side of a grid cell in degrees, about 110 m of latitude
+const CELL: f64 = 0.001;
+const EARTH_RADIUS: f64 = 6_371_000.0;
+const METERS_PER_DEGREE: f64 = EARTH_RADIUS * std::f64::consts::PI / 180.0;
+
+#[derive(ThisError, Debug)]
+pub enum DatasetError {
+    #[error("Failed to read address points: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Address points have no `{0}` column")]
+    MissingColumn(&'static str),
+
+    #[error("Invalid address point on line {0}: {1}")]
+    Invalid(usize, String),
+}
+
+struct Point {
+    latitude: f64,
+    longitude: f64,
+    address: Address,
+    /// Lower case text without diacritics matched by searches
+    text: String,
+}
+
+/// Address points indexed by a grid of [`CELL`] sized cells
+#[derive(Default)]
+pub struct Geocoder {
+    points: Vec<Point>,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    /// Farthest address point reverse geocoding accepts, in meters
+    max_distance: f64,
+}
+
+fn cell(latitude: f64, longitude: f64) -> (i32, i32) {
+    (
+        (latitude / CELL).floor() as i32,
+        (longitude / CELL).floor() as i32,
+    )
+}
+
+/// Great circle distance in meters
+pub fn distance((lat1, lon1): (f64, f64), (lat2, lon2): (f64, f64)) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (lon2 - lon1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * a.sqrt().asin()
+}
+
+/// Lower case and strip the diacritics of Czech, Slovak and German names
+pub fn fold(text: &str) -> String {
+    text.chars()
+        .flat_map(char::to_lowercase)
+        .map(|c| match c {
+            'á' | 'ä' => 'a',
+            'č' => 'c',
+            'ď' => 'd',
+            'é' | 'ě' => 'e',
+            'í' => 'i',
+            'ĺ' | 'ľ' => 'l',
+            'ň' => 'n',
+            'ó' | 'ô' | 'ö' => 'o',
+            'ř' | 'ŕ' => 'r',
+            'š' => 's',
+            'ť' => 't',
+            'ú' | 'ů' | 'ü' => 'u',
+            'ý' => 'y',
+            'ž' => 'z',
+            c if c.is_alphanumeric() || c == '/' => c,
+            _ => ' ',
+        })
+        .collect()
+}
+
+fn searchable(address: &Address) -> String {
+    let parts = [
+        address.street.as_deref(),
+        Some(&address.house_number),
+        Some(&address.city),
+        address.district.as_deref(),
+        address.postcode.as_deref(),
+    ];
+    fold(&parts.into_iter().flatten().collect::<Vec<_>>().join(" "))
+}
+
+impl Geocoder {
+    /// Load address points from a file, see the module documentation for the format
+    pub fn load(path: &Path, max_distance: f64) -> Result<Self, DatasetError> {
+        Self::read(BufReader::new(File::open(path)?), max_distance)
+    }
+
+    pub fn read(reader: impl BufRead, max_distance: f64) -> Result<Self, DatasetError> {
+        let mut lines = reader.lines();
+        let header = lines.next().transpose()?.unwrap_or_default();
+        let columns = header
+            .trim_start_matches('\u{feff}')
+            .split(';')
+            .map(|c| c.trim().to_lowercase())
+            .collect::<Vec<_>>();
+        let column = |name: &'static str| columns.iter().position(|c| c == name);
+        let required = |name| column(name).ok_or(DatasetError::MissingColumn(name));
+        let (latitude, longitude) = (required("latitude")?, required("longitude")?);
+        let (house_number, city) = (required("house_number")?, required("city")?);
+        let (street, district, postcode) =
+            (column("street"), column("district"), column("postcode"));
+
+        let mut geocoder = Self {
+            max_distance,
+            ..Self::default()
+        };
+        for (index, line) in lines.enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let values = line.split(';').map(str::trim).collect::<Vec<_>>();
+            let value = |index: Option<usize>| {
+                index
+                    .and_then(|i| values.get(i))
+                    .filter(|v| !v.is_empty())
+                    .map(|v| v.to_string())
+            };
+            let invalid = |reason: &str| DatasetError::Invalid(index + 2, reason.to_string());
+            let coordinate = |column, limit: f64| {
+                value(Some(column))
+                    .and_then(|v| v.parse::<f64>().ok())
+                    .filter(|v| v.abs() <= limit)
+                    .ok_or_else(|| invalid("coordinates are missing or out of range"))
+            };
+            let point = (coordinate(latitude, 90.0)?, coordinate(longitude, 180.0)?);
+            let address = Address {
+                street: value(street),
+                house_number: value(Some(house_number))
+                    .ok_or_else(|| invalid("no house number"))?,
+                city: value(Some(city)).ok_or_else(|| invalid("no city"))?,
+                district: value(district),
+                postcode: value(postcode),
+            };
+            geocoder.insert(point, address);
+        }
+        Ok(geocoder)
+    }
+
+    fn insert(&mut self, (latitude, longitude): (f64, f64), address: Address) {
+        self.cells
+            .entry(cell(latitude, longitude))
+            .or_default()
+            .push(self.points.len());
+        self.points.push(Point {
+            latitude,
+            longitude,
+            text: searchable(&address),
+            address,
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    fn place(&self, point: &Point, from: Option<(f64, f64)>) -> Place {
+        Place {
+            address: point.address.clone(),
+            latitude: point.latitude,
+            longitude: point.longitude,
+            distance: from.map(|f| distance(f, (point.latitude, point.longitude))),
+        }
+    }
+
+    /// Nearest address point no farther than the configured distance
+    pub fn reverse(&self, latitude: f64, longitude: f64) -> Option<Place> {
+        let (row, column) = cell(latitude, longitude);
+        let rows = (self.max_distance / (CELL * METERS_PER_DEGREE)).ceil() as i32;
+        // Cells narrow towards the poles, more of them cover the same distance
+        let shrink = latitude.to_radians().cos().max(0.01);
+        let columns = (self.max_distance / (CELL * METERS_PER_DEGREE * shrink)).ceil() as i32;
+        let from = (latitude, longitude);
+        (row - rows..=row + rows)
+            .flat_map(|r| (column - columns..=column + columns).map(move |c| (r, c)))
+            .filter_map(|key| self.cells.get(&key))
+            .flatten()
+            .map(|&i| {
+                (
+                    i,
+                    distance(from, (self.points[i].latitude, self.points[i].longitude)),
+                )
+            })
+            .filter(|(_, d)| *d <= self.max_distance)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| self.place(&self.points[i], Some(from)))
+    }
+
+    /// Address points containing every word of `query`, ignoring case and
+    /// diacritics. Sorted by distance from `near` when given, by address otherwise.
+    pub fn search(&self, query: &str, near: Option<(f64, f64)>, limit: usize) -> Vec<Place> {
+        let words = fold(query);
+        let words = words.split_whitespace().collect::<Vec<_>>();
+        if words.is_empty() {
+            return Vec::new();
+        }
+        let mut found = self
+            .points
+            .iter()
+            .filter(|p| {
+                words
+                    .iter()
+                    .all(|w| p.text.split(' ').any(|t| t.starts_with(w)))
+            })
+            .map(|p| self.place(p, near))
+            .collect::<Vec<_>>();
+        if near.is_some() {
+            found.sort_by(|a, b| {
+                a.distance
+                    .unwrap_or_default()
+                    .total_cmp(&b.distance.unwrap_or_default())
+            });
+        } else {
+            found.sort_by_cached_key(|p| p.address.to_string());
+        }
+        found.truncate(limit);
+        found
+    }
+}
+
+#[cfg(test)]
+pub fn test_geocoder() -> Geocoder {
+    Geocoder::read(DATASET.as_bytes(), 100.0).unwrap()
+}
+
+#[cfg(test)]
+const DATASET: &str = "\u{feff}latitude;longitude;street;house_number;city;district;postcode;source
+50.07553;14.43780;Vinohradská;1511/12;Praha;Praha 2;12000;ruian
+50.07590;14.43850;Vinohradská;1896/14;Praha;Praha 2;12000;ruian
+50.08160;14.42770;Hybernská;1009/24;Praha;Praha 1;11000;ruian
+49.60012;15.58031;;15;Lhota;;58291;osm
+";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reverse() {
+        let geocoder = test_geocoder();
+        assert_eq!(geocoder.len(), 4);
+        let place = geocoder.reverse(50.07555, 14.43785).unwrap();
+        assert_eq!(place.address.to_string(), "Vinohradská 1511/12, Praha 2");
+        assert!(place.distance.unwrap() < 5.0);
+        // Across a cell border
+        let place = geocoder.reverse(50.0760, 14.4392).unwrap();
+        assert_eq!(place.address.house_number, "1896/14");
+        assert_eq!(geocoder.reverse(50.0790, 14.4330), None);
+        assert_eq!(geocoder.reverse(-33.86, 151.21), None);
+    }
+
+    #[test]
+    fn search() {
+        let geocoder = test_geocoder();
+        let found = geocoder.search("vinohradska 1511", None, 10);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].distance, None);
+        let found = geocoder.search("Praha", Some((50.0816, 14.4277)), 2);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].address.street.as_deref(), Some("Hybernská"));
+        assert_eq!(found[0].distance, Some(0.0));
+        assert_eq!(
+            geocoder.search("lhota", None, 10)[0].address.to_string(),
+            "Lhota 15"
+        );
+        assert!(geocoder.search("  ", None, 10).is_empty());
+        assert!(geocoder.search("Brno", None, 10).is_empty());
+    }
+
+    #[test]
+    fn invalid_dataset() {
+        let missing = Geocoder::read("latitude;longitude;city\n".as_bytes(), 100.0);
+        assert!(matches!(
+            missing,
+            Err(DatasetError::MissingColumn("house_number"))
+        ));
+        let invalid = Geocoder::read(
+            "latitude;longitude;house_number;city\n50;14;1;Praha\n95;14;2;Praha\n".as_bytes(),
+            100.0,
+        );
+        assert!(matches!(invalid, Err(DatasetError::Invalid(3, _))));
+        assert!((distance((50.0, 14.0), (50.0, 14.001)) - 71.5).abs() < 0.5);
+    }
+}
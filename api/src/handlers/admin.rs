@@ -0,0 +1,370 @@
+//! Administration of users, roles and permissions.
+
+use crate::auth::Authorized;
+use crate::db::{Db, StorageError};
+use crate::error::ApiError;
+use crate::permissions::{self, ManageRoles, ManageUsers};
+use crate::types::admin::{Id, Permission, Role, RoleInfo, User, UserDetail, UserRoles};
+use crate::types::auth::{ApiResult, EmailDetail};
+use crate::types::{ErrorInfo, Validate};
+use actix_web::web::{self, Data, Json};
+use actix_web::Either;
+use serde::Deserialize;
+use tracing::info;
+
+#[derive(Deserialize, Debug)]
+pub struct UserQuery {
+    pub id: Option<i64>,
+}
+
+/// Refuse to hand out permissions the acting user does not hold
+fn check_grantable<'a>(
+    granted: &[String],
+    permissions: impl IntoIterator<Item = &'a str>,
+) -> Result<(), ApiError> {
+    for name in permissions {
+        if !permissions::granted(granted, name) {
+            return Err(ApiError::Forbidden(format!(
+                "Cannot grant permission {name} you do not have"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Map storage errors of role writes to user facing errors
+fn role_error(e: StorageError) -> ApiError {
+    match e {
+        StorageError::NotFound => {
+            let mut errors = ErrorInfo::default();
+            errors.add("permissions", "Unknown permission");
+            errors.into()
+        }
+        StorageError::Conflict(_) => ApiError::Conflict("Role name is already used".to_string()),
+        e => e.into(),
+    }
+}
+
+/// List all users, or the detail of one with `?id=`
+pub async fn users(
+    _auth: Authorized<ManageUsers>,
+    db: Data<Db>,
+    query: web::Query<UserQuery>,
+) -> Result<Either<Json<Vec<User>>, Json<UserDetail>>, ApiError> {
+    let id = query.into_inner().id;
+    web::block(move || {
+        if let Some(id) = id {
+            let user = db.get_user(id)?;
+            let emails = db
+                .list_emails(id)?
+                .into_iter()
+                .map(|e| EmailDetail {
+                    email: e.email,
+                    verified: e.verified,
+                    primary: e.primary,
+                })
+                .collect();
+            let roles = db.user_roles(id)?.into_iter().map(|r| r.name).collect();
+            return Ok(Either::Right(Json(UserDetail {
+                id,
+                emails,
+                username: user.username,
+                roles,
+            })));
+        }
+        let users = db
+            .list_users()?
+            .into_iter()
+            .map(|u| {
+                let email = db
+                    .list_emails(u.id)?
+                    .into_iter()
+                    .find(|e| e.primary)
+                    .map(|e| e.email)
+                    .unwrap_or_default();
+                Ok(User {
+                    id: u.id,
+                    email,
+                    username: u.username,
+                })
+            })
+            .collect::<Result<Vec<_>, ApiError>>()?;
+        Ok(Either::Left(Json(users)))
+    })
+    .await?
+}
+
+/// Replace the roles of a user
+pub async fn edit_user_roles(
+    auth: Authorized<ManageUsers>,
+    db: Data<Db>,
+    user_roles: Json<UserRoles>,
+) -> Result<Json<ApiResult>, ApiError> {
+    let user_roles = user_roles.into_inner();
+    web::block(move || {
+        db.get_user(user_roles.id)?;
+        let roles = db.list_roles()?;
+        let mut errors = ErrorInfo::default();
+        let mut role_ids = Vec::new();
+        for name in &user_roles.roles {
+            match roles.iter().find(|r| &r.name == name) {
+                Some(role) => {
+                    check_grantable(
+                        &auth.permissions,
+                        role.permissions.iter().map(|p| p.name.as_str()),
+                    )?;
+                    role_ids.push(role.id);
+                }
+                None => errors.add("roles", &format!("Unknown role {name}")),
+            }
+        }
+        errors.into_result()?;
+        db.set_user_roles(user_roles.id, &role_ids)?;
+        info!(
+            "User {} set roles of user {} to {:?}",
+            auth.user.id, user_roles.id, user_roles.roles
+        );
+        Ok::<_, ApiError>(())
+    })
+    .await??;
+    Ok(Json(ApiResult::new("Roles updated")))
+}
+
+/// List all roles with their permissions
+pub async fn roles(
+    _auth: Authorized<ManageRoles>,
+    db: Data<Db>,
+) -> Result<Json<Vec<Role>>, ApiError> {
+    let roles = web::block(move || db.list_roles()).await??;
+    Ok(Json(roles.into_iter().map(Role::from).collect()))
+}
+
+pub async fn create_role(
+    auth: Authorized<ManageRoles>,
+    db: Data<Db>,
+    role: Json<RoleInfo>,
+) -> Result<Json<ApiResult>, ApiError> {
+    let role = role.into_inner().validate()?;
+    check_grantable(
+        &auth.permissions,
+        role.permissions.iter().map(String::as_str),
+    )?;
+    let created = web::block(move || {
+        db.create_role(&role.name, &role.description, &role.permission_list())
+            .map_err(role_error)
+    })
+    .await??;
+    info!("User {} created role {}", auth.user.id, created.name);
+    Ok(Json(ApiResult::new("Role created")))
+}
+
+pub async fn edit_role(
+    auth: Authorized<ManageRoles>,
+    db: Data<Db>,
+    role: Json<RoleInfo>,
+) -> Result<Json<ApiResult>, ApiError> {
+    let role = role.into_inner().validate()?;
+    let Some(id) = role.id else {
+        let mut errors = ErrorInfo::default();
+        errors.add("id", "Role id is required");
+        return Err(errors.into());
+    };
+    check_grantable(
+        &auth.permissions,
+        role.permissions.iter().map(String::as_str),
+    )?;
+    web::block(move || {
+        db.get_role(id)?;
+        db.update_role(id, &role.name, &role.description, &role.permission_list())
+            .map_err(role_error)
+    })
+    .await??;
+    info!("User {} edited role {id}", auth.user.id);
+    Ok(Json(ApiResult::new("Role updated")))
+}
+
+pub async fn delete_role(
+    auth: Authorized<ManageRoles>,
+    db: Data<Db>,
+    role: Json<Id>,
+) -> Result<Json<ApiResult>, ApiError> {
+    let id = role.into_inner().id;
+    web::block(move || db.delete_role(id)).await??;
+    info!("User {} deleted role {id}", auth.user.id);
+    Ok(Json(ApiResult::new("Role deleted")))
+}
+
+/// List all permissions that can be assigned to roles
+pub async fn permissions(
+    _auth: Authorized<ManageRoles>,
+    db: Data<Db>,
+) -> Result<Json<Vec<Permission>>, ApiError> {
+    let permissions = web::block(move || db.list_permissions()).await??;
+    Ok(Json(
+        permissions.into_iter().map(Permission::from).collect(),
+    ))
+}
+
+/// Routes of the `/admin` scope
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/admin")
+            .route("/users", web::get().to(users))
+            .route("/users", web::put().to(edit_user_roles))
+            .route("/roles", web::get().to(roles))
+            .route("/roles", web::post().to(create_role))
+            .route("/roles", web::put().to(edit_role))
+            .route("/roles", web::delete().to(delete_role))
+            .route("/permissions", web::get().to(permissions)),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::testing;
+    use actix_web::{http, test};
+    use std::collections::HashSet;
+
+    fn bearer(token: &str) -> (http::header::HeaderName, String) {
+        (http::header::AUTHORIZATION, format!("Bearer {token}"))
+    }
+
+    #[actix_web::test]
+    async fn requires_permission() {
+        let db = db::test_db().into_handle();
+        let (_, token) = testing::user_with_permissions(&db, "reporter", &[]);
+        let app = testing::init(db, configure).await;
+
+        let req = test::TestRequest::get().uri("/admin/users").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+
+        let req = test::TestRequest::get()
+            .uri("/admin/users")
+            .insert_header(bearer(&token))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+        let result: ApiResult = test::read_body_json(resp).await;
+        assert_eq!(result.result, "Missing permission users.manage");
+    }
+
+    #[actix_web::test]
+    async fn super_admin_manages_roles() {
+        let db = db::test_db().into_handle();
+        crate::seed::run(&db, None).unwrap();
+        let (admin_id, token) =
+            testing::user_with_permissions(&db, "root", &[permissions::SUPER_ADMIN]);
+        let app = testing::init(db.clone(), configure).await;
+
+        let req = test::TestRequest::get()
+            .uri("/admin/permissions")
+            .insert_header(bearer(&token))
+            .to_request();
+        let list: Vec<Permission> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(list.len(), permissions::ALL.len());
+
+        let req = test::TestRequest::post()
+            .uri("/admin/roles")
+            .insert_header(bearer(&token))
+            .set_json(RoleInfo {
+                id: None,
+                name: "user admin".to_string(),
+                description: "Manages users".to_string(),
+                permissions: HashSet::from([permissions::MANAGE_USERS.to_string()]),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let req = test::TestRequest::post()
+            .uri("/admin/roles")
+            .insert_header(bearer(&token))
+            .set_json(RoleInfo {
+                name: "broken".to_string(),
+                permissions: HashSet::from(["no.such".to_string()]),
+                ..RoleInfo::default()
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNPROCESSABLE_ENTITY);
+
+        let req = test::TestRequest::get()
+            .uri("/admin/roles")
+            .insert_header(bearer(&token))
+            .to_request();
+        let roles: Vec<Role> = test::call_and_read_body_json(&app, req).await;
+        let role = roles.iter().find(|r| r.name == "user admin").unwrap();
+
+        let req = test::TestRequest::put()
+            .uri("/admin/users")
+            .insert_header(bearer(&token))
+            .set_json(UserRoles {
+                id: admin_id,
+                roles: HashSet::from(["user admin".to_string(), "root-role".to_string()]),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/admin/users?id={admin_id}"))
+            .insert_header(bearer(&token))
+            .to_request();
+        let detail: UserDetail = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(detail.roles, vec!["root-role", "user admin"]);
+
+        let req = test::TestRequest::delete()
+            .uri("/admin/roles")
+            .insert_header(bearer(&token))
+            .set_json(Id { id: role.id })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        assert_eq!(db.user_roles(admin_id).unwrap().len(), 1);
+    }
+
+    #[actix_web::test]
+    async fn cannot_escalate() {
+        let db = db::test_db().into_handle();
+        crate::seed::run(&db, None).unwrap();
+        let (id, token) = testing::user_with_permissions(
+            &db,
+            "manager",
+            &[permissions::MANAGE_USERS, permissions::MANAGE_ROLES],
+        );
+        let app = testing::init(db, configure).await;
+
+        let req = test::TestRequest::get()
+            .uri("/admin/users")
+            .insert_header(bearer(&token))
+            .to_request();
+        let users: Vec<User> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(users[0].email, "manager@example.com");
+
+        let req = test::TestRequest::put()
+            .uri("/admin/users")
+            .insert_header(bearer(&token))
+            .set_json(UserRoles {
+                id,
+                roles: HashSet::from([crate::seed::ADMIN_ROLE.to_string()]),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::post()
+            .uri("/admin/roles")
+            .insert_header(bearer(&token))
+            .set_json(RoleInfo {
+                name: "everything".to_string(),
+                permissions: HashSet::from([permissions::SUPER_ADMIN.to_string()]),
+                ..RoleInfo::default()
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+    }
+}
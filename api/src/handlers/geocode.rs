@@ -0,0 +1,125 @@
+//! Street addresses of positions and positions of addresses.
+
+use crate::error::ApiError;
+use crate::geocode::Geocoder;
+use crate::types::geocode::Place;
+use crate::types::ErrorInfo;
+use actix_web::web::{self, Data, Json};
+use serde::Deserialize;
+
+/// Results returned by a search unless fewer are asked for
+const SEARCH_LIMIT: usize = 10;
+const MAX_SEARCH_LIMIT: usize = 50;
+
+#[derive(Deserialize, Debug)]
+pub struct Position {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SearchQuery {
+    pub q: String,
+    /// Results closest to this position come first
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub limit: Option<usize>,
+}
+
+fn check_position(latitude: f64, longitude: f64) -> Result<(), ErrorInfo> {
+    let mut errors = ErrorInfo::default();
+    if !(-90.0..=90.0).contains(&latitude) {
+        errors.add("latitude", "Latitude must be between -90 and 90");
+    }
+    if !(-180.0..=180.0).contains(&longitude) {
+        errors.add("longitude", "Longitude must be between -180 and 180");
+    }
+    errors.into_result()
+}
+
+/// Nearest address of a position
+pub async fn reverse(
+    geocoder: Data<Geocoder>,
+    query: web::Query<Position>,
+) -> Result<Json<Place>, ApiError> {
+    check_position(query.latitude, query.longitude)?;
+    geocoder
+        .reverse(query.latitude, query.longitude)
+        .map(Json)
+        .ok_or(ApiError::NotFound)
+}
+
+/// Addresses matching the words of `q`
+pub async fn search(
+    geocoder: Data<Geocoder>,
+    query: web::Query<SearchQuery>,
+) -> Result<Json<Vec<Place>>, ApiError> {
+    let query = query.into_inner();
+    if query.q.trim().is_empty() {
+        let mut errors = ErrorInfo::default();
+        errors.add("q", "Search text is required");
+        return Err(errors.into());
+    }
+    let near = query.latitude.zip(query.longitude);
+    if let Some((latitude, longitude)) = near {
+        check_position(latitude, longitude)?;
+    }
+    let limit = query
+        .limit
+        .unwrap_or(SEARCH_LIMIT)
+        .clamp(1, MAX_SEARCH_LIMIT);
+    let found = web::block(move || geocoder.search(&query.q, near, limit)).await?;
+    Ok(Json(found))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/geocode")
+            .route("/reverse", web::get().to(reverse))
+            .route("/search", web::get().to(search)),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::testing;
+    use actix_web::{http, test};
+
+    #[actix_web::test]
+    async fn reverse_and_search() {
+        let app = testing::init(db::test_db().into_handle(), configure).await;
+
+        let req = test::TestRequest::get()
+            .uri("/geocode/reverse?latitude=50.07555&longitude=14.43785")
+            .to_request();
+        let place: Place = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(place.address.to_string(), "Vinohradská 1511/12, Praha 2");
+
+        let req = test::TestRequest::get()
+            .uri("/geocode/reverse?latitude=10&longitude=10")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::get()
+            .uri("/geocode/reverse?latitude=91&longitude=10")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNPROCESSABLE_ENTITY);
+
+        let req = test::TestRequest::get()
+            .uri("/geocode/search?q=Vinohradsk%C3%A1&limit=1")
+            .to_request();
+        let found: Vec<Place> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].address.street.as_deref(), Some("Vinohradská"));
+
+        let req = test::TestRequest::get()
+            .uri("/geocode/search?q=%20")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNPROCESSABLE_ENTITY);
+    }
+}
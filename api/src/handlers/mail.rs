@@ -0,0 +1,142 @@
+//! Email received for the api, piped in by the mail server of the
+//! `forwarding.reply_to` mailbox.
+
+use crate::db::{Db, StorageError};
+use crate::error::ApiError;
+use crate::forward;
+use crate::settings::Settings;
+use crate::types::auth::ApiResult;
+use actix_web::web::{self, Bytes, Data, Json};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use subtle::ConstantTimeEq;
+use tracing::info;
+
+/// Largest accepted email, replies may quote the pictures sent to them
+const MAX_EMAIL_SIZE: usize = 50 * 1024 * 1024;
+
+/// Thread a raw RFC 822 email onto the forwarded report it replies to or
+/// bounces from. Emails about nothing we sent are accepted and dropped so the
+/// mail server does not retry them.
+pub async fn inbound(
+    auth: Option<BearerAuth>,
+    db: Data<Db>,
+    settings: Data<Settings>,
+    body: Bytes,
+) -> Result<Json<ApiResult>, ApiError> {
+    let authorized = match (&settings.forwarding.inbound_secret, &auth) {
+        // Compared in constant time so the secret cannot be guessed byte by byte
+        (Some(secret), Some(auth)) => bool::from(secret.as_bytes().ct_eq(auth.token().as_bytes())),
+        _ => false,
+    };
+    if !authorized {
+        return Err(ApiError::Unauthorized(
+            "Inbound mail needs the configured secret".to_string(),
+        ));
+    }
+    let inbound = forward::parse_inbound(&body).ok_or(ApiError::BadRequest)?;
+    let result = web::block(move || -> Result<_, ApiError> {
+        let Some(forward) = db.find_forward(&inbound.references)? else {
+            info!(
+                "Dropped inbound email {:?} not replying to a forwarded report",
+                inbound.message.subject
+            );
+            return Ok("Ignored");
+        };
+        match db.add_forward_message(forward.id, &inbound.message) {
+            Ok(forward) => {
+                info!(
+                    "Threaded {} onto report {}",
+                    inbound.message.kind.as_str(),
+                    forward.report_id
+                );
+                Ok("Threaded")
+            }
+            Err(StorageError::Conflict(_)) => Ok("Already threaded"),
+            Err(e) => Err(e.into()),
+        }
+    })
+    .await??;
+    Ok(Json(ApiResult::new(result)))
+}
+
+/// Routes of the `/mail` scope
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/mail")
+            .app_data(web::PayloadConfig::new(MAX_EMAIL_SIZE))
+            .route("/inbound", web::post().to(inbound)),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{self, NewForward};
+    use crate::testing;
+    use crate::types::forward::ForwardStatus;
+    use crate::types::report::{NewReport, ReportStatus};
+    use actix_web::{http, test};
+
+    const SECRET: &str = "0123456789abcdef0123456789abcdef";
+
+    fn inbound(secret: Option<&str>, raw: &str) -> actix_http::Request {
+        let mut req = test::TestRequest::post()
+            .uri("/mail/inbound")
+            .insert_header(("Content-Type", "message/rfc822"))
+            .set_payload(raw.to_string());
+        if let Some(secret) = secret {
+            req = req.insert_header(("Authorization", format!("Bearer {secret}")));
+        }
+        req.to_request()
+    }
+
+    #[actix_web::test]
+    async fn replies_threaded() {
+        let db = db::test_db().into_handle();
+        let report = db
+            .create_report(&NewReport::default(), None, None, ReportStatus::Forwarded)
+            .unwrap();
+        let forward = db
+            .create_forward(&NewForward {
+                report_id: report.id,
+                authority: "City police".to_string(),
+                recipient: "police@example.com".to_string(),
+                message_id: format!("report-{}.abc@example.com", report.id),
+                ..NewForward::default()
+            })
+            .unwrap();
+        let mut settings = testing::settings();
+        settings.forwarding.inbound_secret = Some(SECRET.to_string());
+        let mailer = testing::mailer(&settings);
+        let app = testing::init_with(db.clone(), settings, mailer, configure).await;
+
+        let reply = format!(
+            "From: police@example.com\r\n\
+             Subject: Re: report\r\n\
+             Message-ID: <answer@example.com>\r\n\
+             In-Reply-To: <{}>\r\n\
+             \r\n\
+             The driver was fined.\r\n",
+            forward.message_id
+        );
+        for secret in [None, Some("wrong"), Some(&SECRET[..16])] {
+            let resp = test::call_service(&app, inbound(secret, &reply)).await;
+            assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+        }
+        let result: ApiResult =
+            test::call_and_read_body_json(&app, inbound(Some(SECRET), &reply)).await;
+        assert_eq!(result.result, "Threaded");
+        let result: ApiResult =
+            test::call_and_read_body_json(&app, inbound(Some(SECRET), &reply)).await;
+        assert_eq!(result.result, "Already threaded");
+        let unrelated = reply.replace(&forward.message_id, "other@example.com");
+        let result: ApiResult =
+            test::call_and_read_body_json(&app, inbound(Some(SECRET), &unrelated)).await;
+        assert_eq!(result.result, "Ignored");
+
+        let forwards = db.report_forwards(report.id).unwrap();
+        assert_eq!(forwards[0].status, ForwardStatus::Replied);
+        assert_eq!(forwards[0].messages.len(), 1);
+        assert_eq!(forwards[0].messages[0].body, "The driver was fined.");
+    }
+}
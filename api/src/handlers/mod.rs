@@ -1,3 +1,11 @@
+pub mod admin;
+pub mod geocode;
+pub mod mail;
+pub mod moderation;
+pub mod pictures;
+pub mod reports;
+pub mod users;
+
 use actix_web::middleware::ErrorHandlerResponse;
 use actix_web::{dev, HttpRequest, HttpResponse};
 use tracing::info;
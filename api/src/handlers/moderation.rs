@@ -0,0 +1,763 @@
+//! Moderation queue where submitted reports are reviewed before they are
+//! forwarded to the authorities.
+
+use crate::auth::{AuthenticatedUser, Authorized};
+use crate::db::{Db, NewForward, ReportFilter};
+use crate::document::Templates;
+use crate::duplicates;
+use crate::error::ApiError;
+use crate::forward;
+use crate::handlers::pictures::picture_hash;
+use crate::handlers::reports::{
+    change_status, check_transition, complaint_pdf, published_pictures,
+};
+use crate::mailer::MailerHandle;
+use crate::permissions::ReviewReports;
+use crate::phash::MAX_DISTANCE;
+use crate::settings::Settings;
+use crate::types::duplicate::PlateHistory;
+use crate::types::forward::{Forward, ForwardStatus};
+use crate::types::picture::SimilarPicture;
+use crate::types::report::{
+    Report, ReportDetail, ReportPage, ReportQuery, ReportStatus, StatusUpdate,
+};
+use crate::types::{ErrorInfo, Validate};
+use crate::uploads::PictureStore;
+use actix_web::web::{self, Data, Json};
+use std::collections::hash_map::{Entry, HashMap};
+use tracing::{info, warn};
+
+/// Reports listed per page unless fewer or more are asked for
+const PER_PAGE: u32 = 20;
+const MAX_PER_PAGE: u32 = 100;
+
+/// Trim the note and check the reason against the configured ones, only
+/// statuses that need a reason keep it
+fn check_update(mut update: StatusUpdate, reasons: &[String]) -> Result<StatusUpdate, ErrorInfo> {
+    let mut errors = ErrorInfo::default();
+    update.note = update
+        .note
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty());
+    if update.status.needs_reason() {
+        match &update.reason {
+            Some(reason) if reasons.contains(reason) => {}
+            Some(_) => errors.add("reason", "Unknown rejection reason"),
+            None => errors.add("reason", "Choose a rejection reason"),
+        }
+    } else {
+        update.reason = None;
+    }
+    errors.into_result()?;
+    Ok(update)
+}
+
+/// Reports waiting for a moderator, oldest first. Filtering by `status`
+/// lists reports in that status instead.
+pub async fn queue(
+    _auth: Authorized<ReviewReports>,
+    db: Data<Db>,
+    query: web::Query<ReportQuery>,
+) -> Result<Json<ReportPage>, ApiError> {
+    let query = query.into_inner().validate()?;
+    let filter = ReportFilter {
+        reporter_id: None,
+        statuses: query
+            .status
+            .map_or_else(|| ReportStatus::QUEUE.to_vec(), |s| vec![s]),
+        plate: query.plate,
+        from: query.from,
+        to: query.to,
+        oldest_first: true,
+        page: query.page.unwrap_or(1).max(1),
+        per_page: query.per_page.unwrap_or(PER_PAGE).clamp(1, MAX_PER_PAGE),
+    };
+    let (reports, total) = {
+        let filter = filter.clone();
+        web::block(move || db.search_reports(&filter)).await??
+    };
+    Ok(Json(ReportPage {
+        reports,
+        total,
+        page: filter.page,
+        per_page: filter.per_page,
+    }))
+}
+
+/// Any report with its status history
+pub async fn get(
+    _auth: Authorized<ReviewReports>,
+    db: Data<Db>,
+    id: web::Path<i64>,
+) -> Result<Json<ReportDetail>, ApiError> {
+    let id = id.into_inner();
+    let detail = web::block(move || -> Result<_, ApiError> {
+        Ok(ReportDetail {
+            report: db.get_report(id)?,
+            history: db.report_history(id)?,
+        })
+    })
+    .await??;
+    Ok(Json(detail))
+}
+
+/// Email a report to the authority in charge of its place and mark it
+/// forwarded. The report is marked before the email is sent so only one
+/// moderator can send it. The attempt is recorded even when the mail server
+/// refuses it, the report then goes back to where it was so it can be
+/// forwarded again.
+async fn forward(
+    user_id: i64,
+    db: Data<Db>,
+    settings: Data<Settings>,
+    templates: Data<Templates>,
+    mailer: Data<MailerHandle>,
+    id: i64,
+    mut update: StatusUpdate,
+) -> Result<Report, ApiError> {
+    let store = PictureStore::new(&settings.uploads.directory);
+    let (previous, authority, message_id, message) = {
+        let db = db.clone();
+        let mailer = mailer.clone();
+        web::block(move || -> Result<_, ApiError> {
+            let report = db.get_report(id)?;
+            check_transition(&db, user_id, &report, ReportStatus::Forwarded)?;
+            let Some(authority) = forward::authority_for(&settings.forwarding.authorities, &report)
+            else {
+                let mut errors = ErrorInfo::default();
+                errors.add("status", "No authority handles reports from this place");
+                return Err(errors.into());
+            };
+            let authority = authority.clone();
+            let pictures = published_pictures(&db, &store, &report)?;
+            let complaint = complaint_pdf(&db, &templates, &report, pictures.clone())?;
+            let pictures = pictures.into_iter().map(|(bytes, _)| bytes).collect();
+            let reply_to = settings
+                .forwarding
+                .reply_to
+                .as_ref()
+                .and_then(|r| r.parse().ok());
+            let message_id = forward::message_id(report.id, mailer.from());
+            let message = forward::message(
+                mailer.from(),
+                reply_to.as_ref(),
+                &authority,
+                &report,
+                &message_id,
+                complaint,
+                pictures,
+            )
+            .map_err(|e| ApiError::InternalServerError(e.to_string()))?;
+            // Claim the report, whoever comes second gets a conflict
+            update.note = update
+                .note
+                .or_else(|| Some(format!("Emailed to {}", authority.name)));
+            db.change_report_status(
+                id,
+                report.status,
+                ReportStatus::Forwarded,
+                (update.note.as_deref(), None),
+                Some(user_id),
+            )?;
+            Ok((report.status, authority, message_id, message))
+        })
+        .await??
+    };
+    let error = mailer.deliver(message).await.err().map(|e| e.to_string());
+    web::block(move || {
+        let forward = db.create_forward(&NewForward {
+            report_id: id,
+            authority: authority.name.clone(),
+            recipient: authority.email.clone(),
+            message_id,
+            status: if error.is_some() {
+                ForwardStatus::Failed
+            } else {
+                ForwardStatus::Sent
+            },
+            error,
+            sent_by: Some(user_id),
+        })?;
+        if let Some(error) = forward.error {
+            warn!(
+                "Failed to email report {id} to {}: {error}",
+                forward.recipient
+            );
+            let note = format!("Emailing {} failed", authority.name);
+            db.change_report_status(
+                id,
+                ReportStatus::Forwarded,
+                previous,
+                (Some(&note), None),
+                Some(user_id),
+            )?;
+            return Err(ApiError::InternalServerError(error));
+        }
+        info!("Emailed report {id} to {}", forward.recipient);
+        Ok(db.get_report(id)?)
+    })
+    .await?
+}
+
+/// Move a report to another status, as far as the permissions of the user
+/// allow. Forwarding emails the report to the authority in charge.
+pub async fn set_status(
+    user: AuthenticatedUser,
+    db: Data<Db>,
+    settings: Data<Settings>,
+    templates: Data<Templates>,
+    mailer: Data<MailerHandle>,
+    id: web::Path<i64>,
+    update: Json<StatusUpdate>,
+) -> Result<Json<Report>, ApiError> {
+    let id = id.into_inner();
+    let update = check_update(update.into_inner(), &settings.moderation.rejection_reasons)?;
+    let report = if update.status == ReportStatus::Forwarded {
+        forward(user.id, db, settings, templates, mailer, id, update).await?
+    } else {
+        web::block(move || change_status(&db, user.id, id, &update)).await??
+    };
+    info!(
+        "Report {} is {} now",
+        report.id,
+        report.status.label().to_lowercase()
+    );
+    Ok(Json(report))
+}
+
+/// Emails sent for a report with the replies and bounces threaded onto them
+pub async fn forwards(
+    _auth: Authorized<ReviewReports>,
+    db: Data<Db>,
+    id: web::Path<i64>,
+) -> Result<Json<Vec<Forward>>, ApiError> {
+    let id = id.into_inner();
+    let forwards = web::block(move || -> Result<_, ApiError> {
+        db.get_report(id)?;
+        Ok(db.report_forwards(id)?)
+    })
+    .await??;
+    Ok(Json(forwards))
+}
+
+/// Recent reports of the plate of a report, by spot
+pub async fn plate_history(
+    _auth: Authorized<ReviewReports>,
+    db: Data<Db>,
+    settings: Data<Settings>,
+    id: web::Path<i64>,
+) -> Result<Json<PlateHistory>, ApiError> {
+    let id = id.into_inner();
+    let history = web::block(move || -> Result<_, ApiError> {
+        let report = db.get_report(id)?;
+        Ok(duplicates::history(
+            &db,
+            &settings.duplicates,
+            &report.country,
+            &report.plate,
+        )?)
+    })
+    .await??;
+    Ok(Json(history))
+}
+
+/// Pictures of other reports looking like the pictures of a report, most
+/// alike first
+pub async fn similar_pictures(
+    _auth: Authorized<ReviewReports>,
+    db: Data<Db>,
+    settings: Data<Settings>,
+    id: web::Path<i64>,
+) -> Result<Json<Vec<SimilarPicture>>, ApiError> {
+    let id = id.into_inner();
+    let store = PictureStore::new(&settings.uploads.directory);
+    let similar = web::block(move || -> Result<_, ApiError> {
+        let report = db.get_report(id)?;
+        // Pictures that cannot be hashed, e.g. lost files, are left out
+        let mut own = Vec::new();
+        for (name, _) in &report.pictures {
+            if let Some(hash) = picture_hash(&db, &store, name)? {
+                own.push((name, hash));
+            }
+        }
+        let mut others = HashMap::new();
+        let mut similar = Vec::new();
+        for candidate in db.hashed_report_pictures(id)? {
+            for (name, hash) in &own {
+                let distance = hash.distance(&candidate.hash);
+                if distance > MAX_DISTANCE {
+                    continue;
+                }
+                let other = match others.entry(candidate.report_id) {
+                    Entry::Occupied(e) => e.into_mut(),
+                    Entry::Vacant(e) => e.insert(db.get_report(candidate.report_id)?),
+                };
+                similar.push(SimilarPicture {
+                    picture: name.to_string(),
+                    similar: candidate.path.clone(),
+                    distance,
+                    report_id: other.id,
+                    plate: other.plate.clone(),
+                    observed_at: other.observed_at.clone(),
+                    status: other.status,
+                    same_reporter: report.reporter_id.is_some()
+                        && report.reporter_id == other.reporter_id,
+                });
+            }
+        }
+        similar.sort_by_key(|s| (s.distance, s.report_id));
+        Ok(similar)
+    })
+    .await??;
+    Ok(Json(similar))
+}
+
+/// Reasons a report can be rejected for
+pub async fn reasons(
+    _auth: Authorized<ReviewReports>,
+    settings: Data<Settings>,
+) -> Json<Vec<String>> {
+    Json(settings.moderation.rejection_reasons.clone())
+}
+
+/// Routes of the `/moderation` scope
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/moderation")
+            .route("/reasons", web::get().to(reasons))
+            .route("/reports", web::get().to(queue))
+            .route("/reports/{id}", web::get().to(get))
+            .route("/reports/{id}/status", web::post().to(set_status))
+            .route("/reports/{id}/forwards", web::get().to(forwards))
+            .route("/reports/{id}/plate", web::get().to(plate_history))
+            .route("/reports/{id}/similar", web::get().to(similar_pictures)),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{self, NewPicture};
+    use crate::handlers::pictures::hash_pictures;
+    use crate::permissions::{FORWARD_REPORTS, REVIEW_REPORTS};
+    use crate::phash::test_photo;
+    use crate::settings::Authority;
+    use crate::testing::{self, SmtpStandIn};
+    use crate::types::report::NewReport;
+    use actix_web::{http, test};
+
+    /// Reports are observed in Prague unless moved elsewhere
+    fn submit(db: &Db, plate: &str, reporter_id: i64) -> Report {
+        let report = NewReport {
+            plate: plate.to_string(),
+            country: "CZ".to_string(),
+            latitude: Some(50.0755),
+            longitude: Some(14.4378),
+            observed_at: "2023-06-01T12:30:00".to_string(),
+            ..NewReport::default()
+        };
+        db.create_report(&report, None, Some(reporter_id), ReportStatus::Submitted)
+            .unwrap()
+    }
+
+    /// Test settings with an authority in charge of Prague
+    fn forwarding_settings() -> testing::TestSettings {
+        let mut settings = testing::settings();
+        settings.forwarding.reply_to = Some("replies@example.com".to_string());
+        settings.forwarding.authorities = vec![Authority {
+            name: "City police".to_string(),
+            email: "police@example.com".to_string(),
+            districts: Vec::new(),
+            polygon: vec![[49.9, 14.2], [50.2, 14.2], [50.2, 14.7], [49.9, 14.7]],
+        }];
+        settings
+    }
+
+    fn update(id: i64, token: &str, update: StatusUpdate) -> actix_http::Request {
+        test::TestRequest::post()
+            .uri(&format!("/moderation/reports/{id}/status"))
+            .insert_header(("Authorization", format!("Bearer {token}")))
+            .set_json(update)
+            .to_request()
+    }
+
+    fn to(status: ReportStatus) -> StatusUpdate {
+        StatusUpdate {
+            status,
+            ..StatusUpdate::default()
+        }
+    }
+
+    #[actix_web::test]
+    async fn review_and_forward() {
+        let db = db::test_db().into_handle();
+        let (reporter, reporter_token) = testing::user_with_permissions(&db, "reporter", &[]);
+        let (_, reviewer) = testing::user_with_permissions(&db, "reviewer", &[REVIEW_REPORTS]);
+        let (_, forwarder) =
+            testing::user_with_permissions(&db, "forwarder", &[REVIEW_REPORTS, FORWARD_REPORTS]);
+        let first = submit(&db, "1AB 2345", reporter);
+        let second = submit(&db, "2CD 6789", reporter);
+        let smtp = SmtpStandIn::start();
+        let app = testing::init_with(db, forwarding_settings(), smtp.mailer(), configure).await;
+
+        let queue = |token: &str| {
+            test::TestRequest::get()
+                .uri("/moderation/reports")
+                .insert_header(("Authorization", format!("Bearer {token}")))
+                .to_request()
+        };
+        let resp = test::call_service(&app, queue(&reporter_token)).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+        let page: ReportPage = test::call_and_read_body_json(&app, queue(&reviewer)).await;
+        let ids = page.reports.iter().map(|r| r.id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![first.id, second.id]);
+
+        let resp = test::call_service(
+            &app,
+            update(first.id, &reporter_token, to(ReportStatus::UnderReview)),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+        let taken: Report = test::call_and_read_body_json(
+            &app,
+            update(first.id, &reviewer, to(ReportStatus::UnderReview)),
+        )
+        .await;
+        assert_eq!(taken.status, ReportStatus::UnderReview);
+        let resp = test::call_service(
+            &app,
+            update(first.id, &reviewer, to(ReportStatus::Forwarded)),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+        let resp = test::call_service(
+            &app,
+            update(first.id, &forwarder, to(ReportStatus::Resolved)),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::CONFLICT);
+        let forwarded: Report = test::call_and_read_body_json(
+            &app,
+            update(
+                first.id,
+                &forwarder,
+                StatusUpdate {
+                    status: ReportStatus::Forwarded,
+                    note: Some(" Sent to the city police ".to_string()),
+                    reason: Some("ignored".to_string()),
+                },
+            ),
+        )
+        .await;
+        assert_eq!(forwarded.status, ReportStatus::Forwarded);
+        assert_eq!(smtp.messages().len(), 1);
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/moderation/reports/{}", first.id))
+            .insert_header(("Authorization", format!("Bearer {reviewer}")))
+            .to_request();
+        let detail: ReportDetail = test::call_and_read_body_json(&app, req).await;
+        let last = detail.history.last().unwrap();
+        assert_eq!(last.note.as_deref(), Some("Sent to the city police"));
+        assert_eq!(last.reason, None);
+    }
+
+    #[actix_web::test]
+    async fn reject_with_reason() {
+        let db = db::test_db().into_handle();
+        let (reporter, _) = testing::user_with_permissions(&db, "reporter", &[]);
+        let (_, reviewer) = testing::user_with_permissions(&db, "reviewer", &[REVIEW_REPORTS]);
+        let report = submit(&db, "1AB 2345", reporter);
+        let app = testing::init(db, configure).await;
+
+        let req = test::TestRequest::get()
+            .uri("/moderation/reasons")
+            .insert_header(("Authorization", format!("Bearer {reviewer}")))
+            .to_request();
+        let reasons: Vec<String> = test::call_and_read_body_json(&app, req).await;
+        assert!(reasons.contains(&"Plate not readable".to_string()));
+
+        let _: Report = test::call_and_read_body_json(
+            &app,
+            update(report.id, &reviewer, to(ReportStatus::UnderReview)),
+        )
+        .await;
+        for reason in [None, Some("Because".to_string())] {
+            let resp = test::call_service(
+                &app,
+                update(
+                    report.id,
+                    &reviewer,
+                    StatusUpdate {
+                        status: ReportStatus::Rejected,
+                        note: None,
+                        reason,
+                    },
+                ),
+            )
+            .await;
+            assert_eq!(resp.status(), http::StatusCode::UNPROCESSABLE_ENTITY);
+        }
+        let rejected: Report = test::call_and_read_body_json(
+            &app,
+            update(
+                report.id,
+                &reviewer,
+                StatusUpdate {
+                    status: ReportStatus::Rejected,
+                    note: None,
+                    reason: Some("Plate not readable".to_string()),
+                },
+            ),
+        )
+        .await;
+        assert_eq!(rejected.status, ReportStatus::Rejected);
+        let req = test::TestRequest::get()
+            .uri("/moderation/reports?status=rejected")
+            .insert_header(("Authorization", format!("Bearer {reviewer}")))
+            .to_request();
+        let page: ReportPage = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(page.total, 1);
+    }
+
+    #[actix_web::test]
+    async fn forward_by_email() {
+        let db = db::test_db().into_handle();
+        let (reporter, _) = testing::user_with_permissions(&db, "reporter", &[]);
+        let (_, forwarder) =
+            testing::user_with_permissions(&db, "forwarder", &[REVIEW_REPORTS, FORWARD_REPORTS]);
+        let report = submit(&db, "1AB 2345", reporter);
+        let brno = NewReport {
+            plate: "2CD 6789".to_string(),
+            country: "CZ".to_string(),
+            latitude: Some(49.195),
+            longitude: Some(16.608),
+            observed_at: "2023-06-01T12:30:00".to_string(),
+            ..NewReport::default()
+        };
+        let elsewhere = db
+            .create_report(&brno, None, Some(reporter), ReportStatus::UnderReview)
+            .unwrap();
+        db.change_report_status(
+            report.id,
+            ReportStatus::Submitted,
+            ReportStatus::UnderReview,
+            (None, None),
+            None,
+        )
+        .unwrap();
+        let smtp = SmtpStandIn::start();
+        let app =
+            testing::init_with(db.clone(), forwarding_settings(), smtp.mailer(), configure).await;
+
+        let resp = test::call_service(
+            &app,
+            update(elsewhere.id, &forwarder, to(ReportStatus::Forwarded)),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::UNPROCESSABLE_ENTITY);
+        let errors: ErrorInfo = test::read_body_json(resp).await;
+        assert!(!errors.messages(&["status"]).is_empty());
+
+        // A double click emails the authority once
+        let (first, second) = futures_util::join!(
+            test::call_service(
+                &app,
+                update(report.id, &forwarder, to(ReportStatus::Forwarded))
+            ),
+            test::call_service(
+                &app,
+                update(report.id, &forwarder, to(ReportStatus::Forwarded))
+            ),
+        );
+        let mut statuses = [first.status(), second.status()];
+        statuses.sort();
+        assert_eq!(statuses, [http::StatusCode::OK, http::StatusCode::CONFLICT]);
+        assert_eq!(
+            db.get_report(report.id).unwrap().status,
+            ReportStatus::Forwarded
+        );
+        let messages = smtp.messages();
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].contains("To: \"City police\" <police@example.com>"));
+        assert!(messages[0].contains("Reply-To: replies@example.com"));
+        assert!(messages[0].contains("filename=\"report-"));
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/moderation/reports/{}/forwards", report.id))
+            .insert_header(("Authorization", format!("Bearer {forwarder}")))
+            .to_request();
+        let forwards: Vec<Forward> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(forwards.len(), 1);
+        assert_eq!(forwards[0].status, ForwardStatus::Sent);
+        assert_eq!(forwards[0].recipient, "police@example.com");
+        assert!(messages[0].contains(&format!("Message-ID: <{}>", forwards[0].message_id)));
+        let history = db.report_history(report.id).unwrap();
+        assert_eq!(
+            history.last().unwrap().note.as_deref(),
+            Some("Emailed to City police")
+        );
+    }
+
+    #[actix_web::test]
+    async fn refused_forward_recorded() {
+        let db = db::test_db().into_handle();
+        let (reporter, _) = testing::user_with_permissions(&db, "reporter", &[]);
+        let (_, forwarder) =
+            testing::user_with_permissions(&db, "forwarder", &[REVIEW_REPORTS, FORWARD_REPORTS]);
+        let report = submit(&db, "1AB 2345", reporter);
+        db.change_report_status(
+            report.id,
+            ReportStatus::Submitted,
+            ReportStatus::UnderReview,
+            (None, None),
+            None,
+        )
+        .unwrap();
+        let smtp = SmtpStandIn::refusing();
+        let app =
+            testing::init_with(db.clone(), forwarding_settings(), smtp.mailer(), configure).await;
+
+        let resp = test::call_service(
+            &app,
+            update(report.id, &forwarder, to(ReportStatus::Forwarded)),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(smtp.messages().is_empty());
+        assert_eq!(
+            db.get_report(report.id).unwrap().status,
+            ReportStatus::UnderReview
+        );
+        let forwards = db.report_forwards(report.id).unwrap();
+        assert_eq!(forwards[0].status, ForwardStatus::Failed);
+        assert!(forwards[0].error.is_some());
+        let history = db.report_history(report.id).unwrap();
+        assert_eq!(
+            history.last().unwrap().note.as_deref(),
+            Some("Emailing City police failed")
+        );
+
+        // It can be forwarded again once the mail server takes it
+        let smtp = SmtpStandIn::start();
+        let app =
+            testing::init_with(db.clone(), forwarding_settings(), smtp.mailer(), configure).await;
+        let forwarded: Report = test::call_and_read_body_json(
+            &app,
+            update(report.id, &forwarder, to(ReportStatus::Forwarded)),
+        )
+        .await;
+        assert_eq!(forwarded.status, ReportStatus::Forwarded);
+        assert_eq!(smtp.messages().len(), 1);
+    }
+
+    #[actix_web::test]
+    async fn plate_history_listed() {
+        let db = db::test_db().into_handle();
+        let (reporter, _) = testing::user_with_permissions(&db, "reporter", &[]);
+        let (_, reviewer) = testing::user_with_permissions(&db, "reviewer", &[REVIEW_REPORTS]);
+        let (_, other) = testing::user_with_permissions(&db, "other", &[]);
+        let first = submit(&db, "1AB 2345", reporter);
+        let second = submit(&db, "1AB2345", reporter);
+        submit(&db, "2CD 6789", reporter);
+        let mut settings = testing::settings();
+        // The reports were observed in 2023
+        settings.duplicates.history_days = 100 * 365;
+        let mailer = testing::mailer(&settings);
+        let app = testing::init_with(db, settings, mailer, configure).await;
+        let plate = |token: &str| {
+            test::TestRequest::get()
+                .uri(&format!("/moderation/reports/{}/plate", first.id))
+                .insert_header(("Authorization", format!("Bearer {token}")))
+                .to_request()
+        };
+        let resp = test::call_service(&app, plate(&other)).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+        let history: PlateHistory = test::call_and_read_body_json(&app, plate(&reviewer)).await;
+        assert_eq!((history.plate.as_str(), history.total), ("1AB 2345", 2));
+        let ids = history.spots[0]
+            .reports
+            .iter()
+            .map(|r| r.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![first.id, second.id]);
+    }
+
+    #[actix_web::test]
+    async fn similar_pictures_listed() {
+        let db = db::test_db().into_handle();
+        let (reporter, _) = testing::user_with_permissions(&db, "reporter", &[]);
+        let (other, _) = testing::user_with_permissions(&db, "other", &[]);
+        let (_, reviewer) = testing::user_with_permissions(&db, "reviewer", &[REVIEW_REPORTS]);
+        let settings = testing::settings();
+        let store = PictureStore::new(&settings.uploads.directory);
+        // The same photo saved again, another photo and a lost file
+        let lost = format!("{}.jpg", "0".repeat(64));
+        let mut reports = Vec::new();
+        for (jpeg, reporter_id) in [
+            (Some(test_photo(0, 90)), reporter),
+            (Some(test_photo(0, 50)), other),
+            (Some(test_photo(1, 90)), reporter),
+            (Some(test_photo(0, 90)), reporter),
+            (None, reporter),
+        ] {
+            let name = jpeg.map_or(lost.clone(), |jpeg| store.store(&jpeg).unwrap());
+            if db.get_picture(&name).is_err() {
+                // Uploaded before hashing
+                db.create_picture(&NewPicture {
+                    path: name.clone(),
+                    content_type: "image/jpeg".to_string(),
+                    ..NewPicture::default()
+                })
+                .unwrap();
+            }
+            let report = NewReport {
+                plate: "1AB 2345".to_string(),
+                observed_at: "2023-06-01T12:30:00".to_string(),
+                pictures: vec![(name, None)],
+                ..NewReport::default()
+            };
+            reports.push(
+                db.create_report(&report, None, Some(reporter_id), ReportStatus::Submitted)
+                    .unwrap(),
+            );
+        }
+        // As on startup, the lost file is not tried again
+        assert_eq!(hash_pictures(&db, &store).unwrap(), 3);
+        assert!(db.get_picture(&lost).unwrap().hash_error.is_some());
+        assert_eq!(hash_pictures(&db, &store).unwrap(), 0);
+        let mailer = testing::mailer(&settings);
+        let app = testing::init_with(db.clone(), settings, mailer, configure).await;
+        let similar = |id: i64| {
+            test::TestRequest::get()
+                .uri(&format!("/moderation/reports/{id}/similar"))
+                .insert_header(("Authorization", format!("Bearer {reviewer}")))
+                .to_request()
+        };
+        let found: Vec<SimilarPicture> =
+            test::call_and_read_body_json(&app, similar(reports[1].id)).await;
+        let matches = found
+            .iter()
+            .map(|s| (s.report_id, s.same_reporter))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            matches,
+            vec![(reports[0].id, false), (reports[3].id, false)]
+        );
+        let found: Vec<SimilarPicture> =
+            test::call_and_read_body_json(&app, similar(reports[0].id)).await;
+        let matches = found
+            .iter()
+            .map(|s| (s.report_id, s.distance == 0, s.same_reporter))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            matches,
+            vec![(reports[3].id, true, true), (reports[1].id, false, false)]
+        );
+        assert_eq!(found[0].picture, found[0].similar);
+        assert_eq!(found[1].plate, "1AB 2345");
+        let found: Vec<SimilarPicture> =
+            test::call_and_read_body_json(&app, similar(reports[4].id)).await;
+        assert!(found.is_empty());
+    }
+}
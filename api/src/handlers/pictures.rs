@@ -0,0 +1,634 @@
+//! Upload and download of report pictures.
+//!
+//! Every picture is stored twice: the original as uploaded and a published
+//! copy with faces, bystander plates and the regions drawn by the reporter
+//! blurred (see [`redact`]). Only the copy is served by default. The
+//! perceptual hashes of the original (see [`phash`]) are recorded with it.
+
+use crate::auth::AuthenticatedUser;
+use crate::db::{Db, NewPicture, Picture, StorageError};
+use crate::error::ApiError;
+use crate::permissions::{self, REVIEW_REPORTS, VIEW_ORIGINAL_PICTURES, VIEW_PICTURES};
+use crate::phash::{self, PictureHash};
+use crate::redact;
+use crate::settings::Settings;
+use crate::types::picture::{BlurRegion, PictureUpload, RegionKind};
+use crate::types::ErrorInfo;
+use crate::uploads::{validate_jpeg, PictureStore};
+use actix_files::NamedFile;
+use actix_multipart::Multipart;
+use actix_web::web::{self, Data, Json};
+use common::photo;
+use futures_util::TryStreamExt;
+use image::RgbImage;
+use tracing::{error, warn};
+
+const JPEG: &str = "image/jpeg";
+/// Manual regions accepted per picture
+const MAX_REGIONS: usize = 50;
+/// Largest accepted `regions` form field
+const MAX_REGIONS_SIZE: usize = 64 * 1024;
+
+fn rejected(error: String) -> PictureUpload {
+    PictureUpload {
+        error: Some(error),
+        ..PictureUpload::default()
+    }
+}
+
+fn internal(e: impl std::fmt::Display) -> ApiError {
+    error!("Failed to redact picture: {e}");
+    ApiError::InternalServerError(e.to_string())
+}
+
+/// Check regions drawn by a user, they are always stored as manual
+fn manual_regions(regions: Vec<BlurRegion>) -> Result<Vec<BlurRegion>, ErrorInfo> {
+    let mut errors = ErrorInfo::default();
+    if regions.len() > MAX_REGIONS {
+        errors.add(
+            "regions",
+            &format!("At most {MAX_REGIONS} regions can be blurred"),
+        );
+    }
+    let valid = |r: &BlurRegion| {
+        [r.x, r.y, r.width, r.height].iter().all(|v| v.is_finite())
+            && r.width > 0.0
+            && r.height > 0.0
+    };
+    if !regions.iter().all(valid) {
+        errors.add("regions", "Regions must have a positive size");
+    }
+    errors.into_result()?;
+    Ok(regions
+        .into_iter()
+        .map(|r| BlurRegion {
+            kind: RegionKind::Manual,
+            ..r
+        })
+        .collect())
+}
+
+fn decode(original: &[u8]) -> Result<RgbImage, ApiError> {
+    redact::decode(original).map_err(internal)
+}
+
+/// Render and record the published copy of a picture, the copy it replaces
+/// is deleted, returns all blurred regions
+fn publish(
+    db: &Db,
+    store: &PictureStore,
+    picture: &Picture,
+    image: &RgbImage,
+    manual: &[BlurRegion],
+) -> Result<Vec<BlurRegion>, ApiError> {
+    let mut regions = redact::detect(image);
+    regions.extend_from_slice(manual);
+    let redacted = redact::redact(image, &regions).map_err(internal)?;
+    let name = store.store(&redacted).map_err(internal)?;
+    db.set_redaction(&picture.path, &name, &regions)?;
+    if let Some(previous) = picture
+        .redacted
+        .as_deref()
+        .filter(|p| *p != name && *p != picture.path)
+    {
+        if let Err(e) = store.remove(previous) {
+            warn!("Failed to delete superseded published copy {previous}: {e}");
+        }
+    }
+    Ok(regions)
+}
+
+/// Whether a user may change the regions blurred on a picture, its uploader
+/// and users allowed to view originals may
+fn may_redact(db: &Db, user_id: i64, picture: &Picture) -> Result<bool, ApiError> {
+    Ok(picture.uploader_id == Some(user_id) || {
+        let granted = db.user_permissions(user_id)?;
+        permissions::granted(&granted, VIEW_ORIGINAL_PICTURES)
+    })
+}
+
+/// Manual regions stored for a picture
+fn stored_manual(db: &Db, path: &str) -> Result<Vec<BlurRegion>, ApiError> {
+    Ok(db
+        .picture_regions(path)?
+        .into_iter()
+        .filter(|r| r.kind == RegionKind::Manual)
+        .collect())
+}
+
+/// Validate, store and record one uploaded file
+fn save(
+    db: &Db,
+    store: &PictureStore,
+    uploader_id: Option<i64>,
+    bytes: &[u8],
+    manual: &[BlurRegion],
+) -> Result<PictureUpload, ApiError> {
+    if let Err(e) = validate_jpeg(bytes) {
+        return Ok(rejected(e.to_string()));
+    }
+    let name = store.store(bytes).map_err(|e| {
+        error!("Failed to store picture: {e}");
+        ApiError::InternalServerError(e.to_string())
+    })?;
+    let image = decode(bytes)?;
+    let (picture, manual) = match db.get_picture(&name) {
+        Ok(picture) => {
+            let picture = if picture.hash.is_none() {
+                db.set_picture_hash(&name, phash::hash(&image))?;
+                db.get_picture(&name)?
+            } else {
+                picture
+            };
+            // Anyone holding a copy can upload it again, only those allowed to
+            // redact it have their regions applied
+            let allowed = match uploader_id {
+                Some(user_id) => may_redact(db, user_id, &picture)?,
+                None => false,
+            };
+            (picture, if allowed { manual } else { &[][..] })
+        }
+        Err(StorageError::NotFound) => {
+            // Metadata comes from the stored bytes, not from what the client claims
+            let metadata = photo::metadata(bytes);
+            let picture = db.create_picture(&NewPicture {
+                path: name.clone(),
+                uploader_id,
+                content_type: JPEG.to_string(),
+                size: bytes.len() as i64,
+                taken_at: metadata.taken_at,
+                latitude: metadata.position.map(|p| p.0),
+                longitude: metadata.position.map(|p| p.1),
+                hash: Some(phash::hash(&image)),
+            })?;
+            (picture, manual)
+        }
+        Err(e) => return Err(e.into()),
+    };
+    // The same picture uploaded again only ever gets more blurred
+    let mut regions = stored_manual(db, &picture.path)?;
+    let known = regions.len();
+    for region in manual {
+        if !regions.contains(region) {
+            regions.push(*region);
+        }
+    }
+    let regions = if picture.redacted.is_none() || regions.len() > known {
+        publish(db, store, &picture, &image, &regions)?
+    } else {
+        db.picture_regions(&picture.path)?
+    };
+    Ok(PictureUpload {
+        error: None,
+        path: Some(picture.path),
+        taken_at: picture.taken_at,
+        latitude: picture.latitude,
+        longitude: picture.longitude,
+        regions,
+    })
+}
+
+/// Store the JPEG files of a multipart upload, one result per file
+///
+/// A `regions` field holding a JSON list of [`BlurRegion`]s applies to the
+/// picture following it.
+pub async fn upload(
+    user: Option<AuthenticatedUser>,
+    db: Data<Db>,
+    settings: Data<Settings>,
+    mut payload: Multipart,
+) -> Result<Json<Vec<PictureUpload>>, ApiError> {
+    let limits = &settings.uploads;
+    let store = PictureStore::new(&limits.directory);
+    let uploader_id = user.map(|u| u.id);
+    let mut results = Vec::new();
+    let mut manual = Vec::new();
+    while let Some(mut field) = payload.try_next().await? {
+        if field.name() == "regions" {
+            let mut json = Vec::new();
+            while let Some(chunk) = field.try_next().await? {
+                if json.len() + chunk.len() > MAX_REGIONS_SIZE {
+                    return Err(ApiError::BadRequest);
+                }
+                json.extend_from_slice(&chunk);
+            }
+            let regions = serde_json::from_slice(&json).map_err(|_| ApiError::BadRequest)?;
+            manual = manual_regions(regions)?;
+            continue;
+        }
+        let regions = std::mem::take(&mut manual);
+        if results.len() >= limits.max_files {
+            while field.try_next().await?.is_some() {}
+            results.push(rejected(format!(
+                "Too many pictures, at most {} per upload",
+                limits.max_files
+            )));
+            continue;
+        }
+        let mut bytes = Vec::new();
+        let mut too_large = false;
+        // Keep reading an oversized file so the next one can be parsed
+        while let Some(chunk) = field.try_next().await? {
+            if bytes.len() + chunk.len() > limits.max_file_size {
+                too_large = true;
+                bytes = Vec::new();
+            } else if !too_large {
+                bytes.extend_from_slice(&chunk);
+            }
+        }
+        if too_large {
+            results.push(rejected(format!(
+                "Picture is larger than {} MB",
+                limits.max_file_size / (1024 * 1024)
+            )));
+            continue;
+        }
+        let db = db.clone();
+        let store = store.clone();
+        results.push(web::block(move || save(&db, &store, uploader_id, &bytes, &regions)).await??);
+    }
+    Ok(Json(results))
+}
+
+/// Load a picture its uploader or a user holding any of `allowed` may see
+fn authorized(db: &Db, user_id: i64, name: &str, allowed: &[&str]) -> Result<Picture, ApiError> {
+    let picture = db.get_picture(name)?;
+    if picture.uploader_id != Some(user_id) && {
+        let granted = db.user_permissions(user_id)?;
+        !allowed.iter().any(|p| permissions::granted(&granted, p))
+    } {
+        return Err(ApiError::Forbidden(
+            "Not allowed to view this picture".to_string(),
+        ));
+    }
+    Ok(picture)
+}
+
+fn read(store: &PictureStore, name: &str) -> Result<Vec<u8>, ApiError> {
+    let path = store.path(name).ok_or(ApiError::NotFound)?;
+    std::fs::read(path).map_err(|_| ApiError::NotFound)
+}
+
+/// Name of the published copy of a picture, pictures uploaded before
+/// redaction existed get theirs on first use
+fn published(db: &Db, store: &PictureStore, picture: Picture) -> Result<String, ApiError> {
+    if let Some(redacted) = picture.redacted {
+        return Ok(redacted);
+    }
+    let manual = stored_manual(db, &picture.path)?;
+    publish(
+        db,
+        store,
+        &picture,
+        &decode(&read(store, &picture.path)?)?,
+        &manual,
+    )?;
+    db.get_picture(&picture.path)?
+        .redacted
+        .ok_or(ApiError::NotFound)
+}
+
+/// Perceptual hashes of a picture, none when it cannot be hashed. Pictures
+/// uploaded before hashing existed get theirs on first use, why that failed
+/// is recorded so it is not tried again.
+pub fn picture_hash(
+    db: &Db,
+    store: &PictureStore,
+    name: &str,
+) -> Result<Option<PictureHash>, ApiError> {
+    let picture = db.get_picture(name)?;
+    if picture.hash.is_some() || picture.hash_error.is_some() {
+        return Ok(picture.hash);
+    }
+    match read(store, name).and_then(|bytes| decode(&bytes)) {
+        Ok(image) => {
+            let hash = phash::hash(&image);
+            db.set_picture_hash(name, hash)?;
+            Ok(Some(hash))
+        }
+        Err(e) => {
+            warn!("Failed to hash picture {name}: {e}");
+            db.set_picture_hash_error(name, &e.to_string())?;
+            Ok(None)
+        }
+    }
+}
+
+/// Hash the pictures uploaded before hashing existed, returns how many were
+/// hashed
+pub fn hash_pictures(db: &Db, store: &PictureStore) -> Result<usize, ApiError> {
+    let mut hashed = 0;
+    for name in db.unhashed_pictures()? {
+        if picture_hash(db, store, &name)?.is_some() {
+            hashed += 1;
+        }
+    }
+    Ok(hashed)
+}
+
+/// Bytes of the published copy of a picture
+pub fn published_bytes(db: &Db, store: &PictureStore, name: &str) -> Result<Vec<u8>, ApiError> {
+    let name = published(db, store, db.get_picture(name)?)?;
+    read(store, &name)
+}
+
+/// Serve the published copy of a picture to its uploader, users allowed to
+/// view all pictures and moderators
+pub async fn get(
+    user: AuthenticatedUser,
+    db: Data<Db>,
+    settings: Data<Settings>,
+    name: web::Path<String>,
+) -> Result<NamedFile, ApiError> {
+    let store = PictureStore::new(&settings.uploads.directory);
+    let name = name.into_inner();
+    store.path(&name).ok_or(ApiError::NotFound)?;
+    let redacted = {
+        let store = store.clone();
+        web::block(move || -> Result<String, ApiError> {
+            let picture = authorized(
+                &db,
+                user.id,
+                &name,
+                &[VIEW_PICTURES, VIEW_ORIGINAL_PICTURES, REVIEW_REPORTS],
+            )?;
+            published(&db, &store, picture)
+        })
+        .await??
+    };
+    let path = store.path(&redacted).ok_or(ApiError::NotFound)?;
+    NamedFile::open_async(path)
+        .await
+        .map_err(|_| ApiError::NotFound)
+}
+
+/// Serve a picture as uploaded to its uploader or users allowed to view originals
+pub async fn original(
+    user: AuthenticatedUser,
+    db: Data<Db>,
+    settings: Data<Settings>,
+    name: web::Path<String>,
+) -> Result<NamedFile, ApiError> {
+    let name = name.into_inner();
+    let path = PictureStore::new(&settings.uploads.directory)
+        .path(&name)
+        .ok_or(ApiError::NotFound)?;
+    web::block(move || authorized(&db, user.id, &name, &[VIEW_ORIGINAL_PICTURES])).await??;
+    NamedFile::open_async(path)
+        .await
+        .map_err(|_| ApiError::NotFound)
+}
+
+/// Replace the regions drawn by hand and render the published copy again,
+/// returns all blurred regions
+pub async fn set_regions(
+    user: AuthenticatedUser,
+    db: Data<Db>,
+    settings: Data<Settings>,
+    name: web::Path<String>,
+    regions: Json<Vec<BlurRegion>>,
+) -> Result<Json<Vec<BlurRegion>>, ApiError> {
+    let store = PictureStore::new(&settings.uploads.directory);
+    let name = name.into_inner();
+    let manual = manual_regions(regions.into_inner())?;
+    let regions = web::block(move || {
+        let picture = authorized(&db, user.id, &name, &[VIEW_ORIGINAL_PICTURES])?;
+        publish(
+            &db,
+            &store,
+            &picture,
+            &decode(&read(&store, &name)?)?,
+            &manual,
+        )
+    })
+    .await??;
+    Ok(Json(regions))
+}
+
+/// Routes of the `/pictures` scope
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/pictures")
+            .route("", web::post().to(upload))
+            .route("/{name}", web::get().to(get))
+            .route("/{name}/original", web::get().to(original))
+            .route("/{name}/regions", web::put().to(set_regions)),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::testing;
+    use crate::uploads::{test_jpeg, with_exif};
+    use actix_web::{http, test};
+    use common::photo::PhotoMetadata;
+
+    const BOUNDARY: &str = "PictureBoundary";
+
+    /// Body with `(field name, content)` parts, `picture` parts are sent as files
+    fn multipart(parts: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (i, (name, content)) in parts.iter().enumerate() {
+            let disposition = if *name == "picture" {
+                format!(
+                    "form-data; name=\"picture\"; filename=\"{i}.jpg\"\r\nContent-Type: image/jpeg"
+                )
+            } else {
+                format!("form-data; name=\"{name}\"")
+            };
+            body.extend_from_slice(
+                format!("--{BOUNDARY}\r\nContent-Disposition: {disposition}\r\n\r\n").as_bytes(),
+            );
+            body.extend_from_slice(content);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{BOUNDARY}--\r\n").as_bytes());
+        body
+    }
+
+    fn upload_request(files: &[&[u8]], token: Option<&str>) -> test::TestRequest {
+        let parts = files.iter().map(|f| ("picture", *f)).collect::<Vec<_>>();
+        parts_request(&parts, token)
+    }
+
+    fn parts_request(parts: &[(&str, &[u8])], token: Option<&str>) -> test::TestRequest {
+        let mut req = test::TestRequest::post()
+            .uri("/pictures")
+            .insert_header((
+                http::header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={BOUNDARY}"),
+            ))
+            .set_payload(multipart(parts));
+        if let Some(token) = token {
+            req = req.insert_header((http::header::AUTHORIZATION, format!("Bearer {token}")));
+        }
+        req
+    }
+
+    #[actix_web::test]
+    async fn upload_and_serve() {
+        let db = db::test_db().into_handle();
+        let (_, owner) = testing::user_with_permissions(&db, "owner", &[]);
+        let (_, other) = testing::user_with_permissions(&db, "other", &[]);
+        let (_, viewer) = testing::user_with_permissions(&db, "viewer", &[VIEW_PICTURES]);
+        let (_, auditor) =
+            testing::user_with_permissions(&db, "auditor", &[VIEW_ORIGINAL_PICTURES]);
+        let app = testing::init(db.clone(), configure).await;
+
+        let jpeg = with_exif(
+            &test_jpeg(7),
+            &PhotoMetadata {
+                taken_at: Some("2023-05-01T12:34:56".to_string()),
+                position: Some((50.0, 14.5)),
+            },
+        );
+        let req = upload_request(&[&jpeg, b"not a picture"], Some(&owner)).to_request();
+        let results: Vec<PictureUpload> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(results.len(), 2);
+        let name = results[0].path.clone().unwrap();
+        assert!(name.ends_with(".jpg"));
+        assert_eq!(results[0].taken_at.as_deref(), Some("2023-05-01T12:34:56"));
+        assert_eq!(results[0].latitude, Some(50.0));
+        assert_eq!(
+            results[1].error.as_deref(),
+            Some("Only JPEG pictures are accepted")
+        );
+        let stored = db.get_picture(&name).unwrap();
+        assert_eq!(stored.size, jpeg.len() as i64);
+        assert!(stored.hash.is_some());
+
+        let fetch = |uri: String, token: &str| {
+            test::TestRequest::get()
+                .uri(&uri)
+                .insert_header((http::header::AUTHORIZATION, format!("Bearer {token}")))
+                .to_request()
+        };
+        for (token, status) in [
+            (&owner, http::StatusCode::OK),
+            (&viewer, http::StatusCode::OK),
+            (&auditor, http::StatusCode::OK),
+            (&other, http::StatusCode::FORBIDDEN),
+        ] {
+            let resp = test::call_service(&app, fetch(format!("/pictures/{name}"), token)).await;
+            assert_eq!(resp.status(), status);
+            if status == http::StatusCode::OK {
+                // The published copy is re-encoded without any metadata
+                let published = test::read_body(resp).await;
+                assert_ne!(published, jpeg);
+                assert_eq!(photo::metadata(&published), Default::default());
+            }
+        }
+        for (token, status) in [
+            (&owner, http::StatusCode::OK),
+            (&auditor, http::StatusCode::OK),
+            (&viewer, http::StatusCode::FORBIDDEN),
+        ] {
+            let uri = format!("/pictures/{name}/original");
+            let resp = test::call_service(&app, fetch(uri, token)).await;
+            assert_eq!(resp.status(), status);
+            if status == http::StatusCode::OK {
+                assert_eq!(test::read_body(resp).await, jpeg);
+            }
+        }
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/pictures/{name}"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn manual_regions() {
+        let db = db::test_db().into_handle();
+        let (_, owner) = testing::user_with_permissions(&db, "owner", &[]);
+        let (_, other) = testing::user_with_permissions(&db, "other", &[]);
+        let (_, viewer) = testing::user_with_permissions(&db, "viewer", &[VIEW_PICTURES]);
+        let settings = testing::settings();
+        let store = PictureStore::new(&settings.uploads.directory);
+        let mailer = testing::mailer(&settings);
+        let app = testing::init_with(db.clone(), settings, mailer, configure).await;
+
+        let region = BlurRegion {
+            kind: RegionKind::Face,
+            x: 0.25,
+            y: 0.25,
+            width: 0.5,
+            height: 0.5,
+        };
+        let json = serde_json::to_vec(&[region]).unwrap();
+        let (first, second) = (test_jpeg(3), test_jpeg(4));
+        let parts: [(&str, &[u8]); 3] = [
+            ("regions", &json),
+            ("picture", &first),
+            ("picture", &second),
+        ];
+        let req = parts_request(&parts, Some(&owner)).to_request();
+        let results: Vec<PictureUpload> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(results.len(), 2);
+        // Whatever the client claims, drawn regions are manual
+        let manual = BlurRegion {
+            kind: RegionKind::Manual,
+            ..region
+        };
+        assert_eq!(results[0].regions, vec![manual]);
+        assert!(results[1].regions.is_empty());
+        let name = results[0].path.clone().unwrap();
+        let published = db.get_picture(&name).unwrap().redacted.unwrap();
+        assert_eq!(db.picture_regions(&name).unwrap(), vec![manual]);
+
+        // Someone else uploading a copy cannot change what is blurred
+        let elsewhere = serde_json::to_vec(&[BlurRegion {
+            x: 0.0,
+            y: 0.0,
+            ..region
+        }])
+        .unwrap();
+        let parts: [(&str, &[u8]); 2] = [("regions", &elsewhere), ("picture", &first)];
+        let req = parts_request(&parts, Some(&other)).to_request();
+        let results: Vec<PictureUpload> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(results[0].regions, vec![manual]);
+        assert_eq!(db.get_picture(&name).unwrap().redacted.unwrap(), published);
+
+        let put = |token: &str, body: serde_json::Value| {
+            test::TestRequest::put()
+                .uri(&format!("/pictures/{name}/regions"))
+                .insert_header((http::header::AUTHORIZATION, format!("Bearer {token}")))
+                .set_json(body)
+                .to_request()
+        };
+        let resp = test::call_service(&app, put(&viewer, serde_json::json!([]))).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+        let invalid =
+            serde_json::json!([{ "kind": "manual", "x": 0, "y": 0, "width": 0, "height": 1 }]);
+        let resp = test::call_service(&app, put(&owner, invalid)).await;
+        assert_eq!(resp.status(), http::StatusCode::UNPROCESSABLE_ENTITY);
+        let regions: Vec<BlurRegion> =
+            test::call_and_read_body_json(&app, put(&owner, serde_json::json!([]))).await;
+        assert!(regions.is_empty());
+        assert_ne!(db.get_picture(&name).unwrap().redacted.unwrap(), published);
+        // The superseded copy is deleted
+        assert!(!store.path(&published).unwrap().exists());
+    }
+
+    #[actix_web::test]
+    async fn upload_limits() {
+        let db = db::test_db().into_handle();
+        let mut settings = testing::settings();
+        settings.uploads.max_files = 2;
+        let large = test_jpeg(1);
+        settings.uploads.max_file_size = large.len() - 1;
+        let small = &large[..16];
+        let mailer = testing::mailer(&settings);
+        let app = testing::init_with(db, settings, mailer, configure).await;
+
+        let req = upload_request(&[&large, small, small], None).to_request();
+        let results: Vec<PictureUpload> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(results.len(), 3);
+        assert!(results[0].error.as_ref().unwrap().contains("larger"));
+        assert!(results[1].error.as_ref().unwrap().contains("decoded"));
+        assert!(results[2].error.as_ref().unwrap().contains("Too many"));
+    }
+}
@@ -0,0 +1,931 @@
+use crate::auth::AuthenticatedUser;
+use crate::db::{Db, ReportFilter, StorageError};
+use crate::document::{CaptionedPicture, Complaint, Reporter, Templates};
+use crate::duplicates;
+use crate::error::ApiError;
+use crate::geocode::Geocoder;
+use crate::handlers::pictures::published_bytes;
+use crate::permissions::{self, FORWARD_REPORTS, REVIEW_REPORTS};
+use crate::settings::Settings;
+use crate::types::auth::ApiResult;
+use crate::types::duplicate::Duplicate;
+use crate::types::geocode::Address;
+use crate::types::report::{
+    NewReport, Report, ReportDetail, ReportPage, ReportQuery, ReportStatus, StatusUpdate,
+    Withdrawal, TRANSITIONS,
+};
+use crate::types::{ErrorInfo, Validate};
+use crate::uploads::PictureStore;
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
+use actix_web::web::{self, Data, Json};
+use actix_web::HttpResponse;
+use serde::Deserialize;
+use time::OffsetDateTime;
+use tracing::{error, info};
+
+/// Reports listed per page unless fewer or more are asked for
+const PER_PAGE: u32 = 20;
+const MAX_PER_PAGE: u32 = 100;
+
+fn resolve(geocoder: &Geocoder, report: &NewReport) -> Option<Address> {
+    report
+        .latitude
+        .zip(report.longitude)
+        .and_then(|(latitude, longitude)| geocoder.reverse(latitude, longitude))
+        .map(|place| place.address)
+}
+
+fn check_pictures(db: &Db, report: &NewReport) -> Result<(), ErrorInfo> {
+    let mut errors = ErrorInfo::default();
+    for (picture, _) in &report.pictures {
+        if let Err(StorageError::NotFound) = db.get_picture(picture) {
+            errors.add("pictures", &format!("Unknown picture {picture}"));
+        }
+    }
+    errors.into_result()
+}
+
+/// Check that the user uploaded every picture of a report but those it
+/// already has, anonymous reports only take pictures uploaded anonymously
+fn check_uploader(
+    db: &Db,
+    user_id: Option<i64>,
+    report: &NewReport,
+    existing: Option<&Report>,
+) -> Result<(), ApiError> {
+    let kept =
+        |picture: &String| existing.is_some_and(|r| r.pictures.iter().any(|(p, _)| p == picture));
+    for (picture, _) in &report.pictures {
+        if !kept(picture) && db.get_picture(picture)?.uploader_id != user_id {
+            return Err(ApiError::Forbidden(
+                "Only pictures you uploaded can be used".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Report of the user, others are not allowed to see it
+fn own_report(db: &Db, user_id: i64, id: i64) -> Result<Report, ApiError> {
+    let report = db.get_report(id)?;
+    if report.reporter_id != Some(user_id) {
+        return Err(ApiError::Forbidden(
+            "Not allowed to view this report".to_string(),
+        ));
+    }
+    Ok(report)
+}
+
+/// Report standing for a new one when looking for its duplicates
+fn unsaved(report: &NewReport, resolved: Option<&Address>) -> Report {
+    Report {
+        plate: report.plate.clone(),
+        country: report.country.clone(),
+        latitude: report.latitude,
+        longitude: report.longitude,
+        address: report.address.clone(),
+        resolved_address: resolved.cloned(),
+        observed_at: report.observed_at.clone(),
+        ..Report::default()
+    }
+}
+
+/// Point moderators of a report just sent for review to the reports it
+/// likely duplicates
+fn note_duplicates(db: &Db, settings: &Settings, report: &Report) -> Result<(), ApiError> {
+    let found = duplicates::find(db, &settings.duplicates, report)?;
+    if found.is_empty() {
+        return Ok(());
+    }
+    let ids = found
+        .iter()
+        .map(|d| d.id.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let note = match found.len() {
+        1 => format!("Likely duplicate of report {ids}"),
+        _ => format!("Likely duplicate of reports {ids}"),
+    };
+    info!("Report {} {}", report.id, note.to_lowercase());
+    db.note_report(report.id, &note, None)?;
+    Ok(())
+}
+
+/// Check that the user may move a report along [`TRANSITIONS`] to `to`,
+/// refusing changes the state machine, their permissions or not being the
+/// reporter rule out
+pub fn check_transition(
+    db: &Db,
+    user_id: i64,
+    report: &Report,
+    to: ReportStatus,
+) -> Result<(), ApiError> {
+    let from = report.status;
+    if !TRANSITIONS.iter().any(|t| t.from == from && t.to == to) {
+        return Err(ApiError::Conflict(format!(
+            "A {} report can't be {}",
+            from.label().to_lowercase(),
+            to.label().to_lowercase()
+        )));
+    }
+    let permissions = db.user_permissions(user_id)?;
+    if !from.allows(to, report.reporter_id == Some(user_id), &permissions) {
+        return Err(ApiError::Forbidden(format!(
+            "Not allowed to change a {} report to {}",
+            from.label().to_lowercase(),
+            to.label().to_lowercase()
+        )));
+    }
+    Ok(())
+}
+
+/// Move a report along [`TRANSITIONS`] for the user, see [`check_transition`]
+pub fn change_status(
+    db: &Db,
+    user_id: i64,
+    id: i64,
+    update: &StatusUpdate,
+) -> Result<Report, ApiError> {
+    let report = db.get_report(id)?;
+    check_transition(db, user_id, &report, update.status)?;
+    Ok(db.change_report_status(
+        id,
+        report.status,
+        update.status,
+        (update.note.as_deref(), update.reason.as_deref()),
+        Some(user_id),
+    )?)
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct CreateQuery {
+    /// Keep the report as a draft instead of sending it for review
+    #[serde(default)]
+    pub draft: bool,
+}
+
+/// Submit a new report, or store a draft of a signed in user
+pub async fn create(
+    user: Option<AuthenticatedUser>,
+    db: Data<Db>,
+    settings: Data<Settings>,
+    geocoder: Data<Geocoder>,
+    query: web::Query<CreateQuery>,
+    report: Json<NewReport>,
+) -> Result<HttpResponse, ApiError> {
+    if query.draft && user.is_none() {
+        return Err(ApiError::Unauthorized("Sign in to keep drafts".to_string()));
+    }
+    let status = if query.draft {
+        ReportStatus::Draft
+    } else {
+        ReportStatus::Submitted
+    };
+    let report = report.into_inner().validate()?;
+    let resolved = resolve(&geocoder, &report);
+    let created = web::block(move || -> Result<_, ApiError> {
+        let user_id = user.map(|u| u.id);
+        check_pictures(&db, &report)?;
+        check_uploader(&db, user_id, &report, None)?;
+        let created = db.create_report(&report, resolved.as_ref(), user_id, status)?;
+        if status == ReportStatus::Submitted {
+            note_duplicates(&db, &settings, &created)?;
+        }
+        Ok(created)
+    })
+    .await??;
+    info!(
+        "Report {} created for {} ({})",
+        created.id, created.plate, created.country
+    );
+    Ok(HttpResponse::Created().json(created))
+}
+
+/// Reports in moderation a report about to be submitted likely duplicates,
+/// so the reporter can add their pictures to one of them instead. Only
+/// signed in users can, like adding the pictures.
+pub async fn find_duplicates(
+    _user: AuthenticatedUser,
+    db: Data<Db>,
+    settings: Data<Settings>,
+    geocoder: Data<Geocoder>,
+    report: Json<NewReport>,
+) -> Result<Json<Vec<Duplicate>>, ApiError> {
+    let report = report.into_inner().validate()?;
+    let resolved = resolve(&geocoder, &report);
+    let found = web::block(move || {
+        duplicates::find(
+            &db,
+            &settings.duplicates,
+            &unsaved(&report, resolved.as_ref()),
+        )
+    })
+    .await??;
+    Ok(Json(found))
+}
+
+/// Add the pictures of a report about to be submitted to a report it
+/// duplicates instead of submitting it. Only signed in users can, with
+/// pictures they uploaded themselves.
+pub async fn add_pictures(
+    user: AuthenticatedUser,
+    db: Data<Db>,
+    settings: Data<Settings>,
+    geocoder: Data<Geocoder>,
+    id: web::Path<i64>,
+    report: Json<NewReport>,
+) -> Result<Json<ApiResult>, ApiError> {
+    let id = id.into_inner();
+    let report = report.into_inner().validate()?;
+    if report.pictures.is_empty() {
+        let mut errors = ErrorInfo::default();
+        errors.add("pictures", "Add the pictures to attach");
+        return Err(errors.into());
+    }
+    let resolved = resolve(&geocoder, &report);
+    let count = report.pictures.len();
+    web::block(move || -> Result<_, ApiError> {
+        check_pictures(&db, &report)?;
+        check_uploader(&db, Some(user.id), &report, None)?;
+        let found = duplicates::find(
+            &db,
+            &settings.duplicates,
+            &unsaved(&report, resolved.as_ref()),
+        )?;
+        if !found.iter().any(|d| d.id == id) {
+            db.get_report(id)?;
+            return Err(ApiError::Conflict(
+                "The report is not one this report duplicates".to_string(),
+            ));
+        }
+        Ok(db.add_report_pictures(
+            id,
+            &report.pictures,
+            "Pictures added from a duplicate report",
+            Some(user.id),
+        )?)
+    })
+    .await??;
+    info!("{count} pictures added to report {id} from a duplicate");
+    Ok(Json(ApiResult::new("Pictures added")))
+}
+
+/// Reports submitted by the user, newest first
+pub async fn mine(
+    user: AuthenticatedUser,
+    db: Data<Db>,
+    query: web::Query<ReportQuery>,
+) -> Result<Json<ReportPage>, ApiError> {
+    let query = query.into_inner().validate()?;
+    let filter = ReportFilter {
+        reporter_id: Some(user.id),
+        statuses: query.status.into_iter().collect(),
+        plate: query.plate,
+        from: query.from,
+        to: query.to,
+        oldest_first: false,
+        page: query.page.unwrap_or(1).max(1),
+        per_page: query.per_page.unwrap_or(PER_PAGE).clamp(1, MAX_PER_PAGE),
+    };
+    let (reports, total) = {
+        let filter = filter.clone();
+        web::block(move || db.search_reports(&filter)).await??
+    };
+    Ok(Json(ReportPage {
+        reports,
+        total,
+        page: filter.page,
+        per_page: filter.per_page,
+    }))
+}
+
+/// Report of the user with its status history
+pub async fn get(
+    user: AuthenticatedUser,
+    db: Data<Db>,
+    id: web::Path<i64>,
+) -> Result<Json<ReportDetail>, ApiError> {
+    let id = id.into_inner();
+    let detail = web::block(move || -> Result<_, ApiError> {
+        Ok(ReportDetail {
+            report: own_report(&db, user.id, id)?,
+            history: db.report_history(id)?,
+        })
+    })
+    .await??;
+    Ok(Json(detail))
+}
+
+/// Replace the details of a report that has not been processed yet
+pub async fn amend(
+    user: AuthenticatedUser,
+    db: Data<Db>,
+    geocoder: Data<Geocoder>,
+    id: web::Path<i64>,
+    report: Json<NewReport>,
+) -> Result<Json<Report>, ApiError> {
+    let id = id.into_inner();
+    let report = report.into_inner().validate()?;
+    let resolved = resolve(&geocoder, &report);
+    let amended = web::block(move || -> Result<_, ApiError> {
+        let existing = own_report(&db, user.id, id)?;
+        check_pictures(&db, &report)?;
+        // Pictures added from duplicates of other reporters can stay
+        check_uploader(&db, Some(user.id), &report, Some(&existing))?;
+        Ok(db.amend_report(id, &report, resolved.as_ref(), Some(user.id))?)
+    })
+    .await??;
+    info!("Report {} amended", amended.id);
+    Ok(Json(amended))
+}
+
+/// Take back a report that has not been processed yet
+pub async fn withdraw(
+    user: AuthenticatedUser,
+    db: Data<Db>,
+    id: web::Path<i64>,
+    withdrawal: Json<Withdrawal>,
+) -> Result<Json<Report>, ApiError> {
+    let id = id.into_inner();
+    let update = StatusUpdate {
+        status: ReportStatus::Withdrawn,
+        note: withdrawal
+            .into_inner()
+            .reason
+            .map(|r| r.trim().to_string())
+            .filter(|r| !r.is_empty()),
+        reason: None,
+    };
+    let withdrawn = web::block(move || -> Result<_, ApiError> {
+        own_report(&db, user.id, id)?;
+        change_status(&db, user.id, id, &update)
+    })
+    .await??;
+    info!("Report {} withdrawn", withdrawn.id);
+    Ok(Json(withdrawn))
+}
+
+/// Send a draft for review
+pub async fn submit(
+    user: AuthenticatedUser,
+    db: Data<Db>,
+    settings: Data<Settings>,
+    id: web::Path<i64>,
+) -> Result<Json<Report>, ApiError> {
+    let id = id.into_inner();
+    let update = StatusUpdate {
+        status: ReportStatus::Submitted,
+        ..StatusUpdate::default()
+    };
+    let submitted = web::block(move || -> Result<_, ApiError> {
+        own_report(&db, user.id, id)?;
+        let submitted = change_status(&db, user.id, id, &update)?;
+        note_duplicates(&db, &settings, &submitted)?;
+        Ok(submitted)
+    })
+    .await??;
+    info!("Draft {} submitted", submitted.id);
+    Ok(Json(submitted))
+}
+
+/// Reporter named in the complaint, with their primary address
+fn reporter(db: &Db, user_id: i64) -> Result<Reporter, ApiError> {
+    let user = db.get_user(user_id)?;
+    let emails = db.list_emails(user_id)?;
+    let email = emails
+        .iter()
+        .find(|e| e.primary)
+        .or_else(|| emails.first())
+        .map(|e| e.email.clone());
+    Ok(Reporter {
+        name: user.username,
+        email,
+    })
+}
+
+/// Published copies of the pictures of a report with their captions
+pub fn published_pictures(
+    db: &Db,
+    store: &PictureStore,
+    report: &Report,
+) -> Result<Vec<CaptionedPicture>, ApiError> {
+    report
+        .pictures
+        .iter()
+        .map(|(name, caption)| Ok((published_bytes(db, store, name)?, caption.clone())))
+        .collect()
+}
+
+/// Complaint about a report as a PDF, dated today
+pub fn complaint_pdf(
+    db: &Db,
+    templates: &Templates,
+    report: &Report,
+    pictures: Vec<CaptionedPicture>,
+) -> Result<Vec<u8>, ApiError> {
+    let reporter = report
+        .reporter_id
+        .map(|reporter_id| reporter(db, reporter_id))
+        .transpose()?;
+    let complaint = Complaint {
+        report,
+        reporter,
+        pictures,
+        date: OffsetDateTime::now_utc().date(),
+    };
+    complaint.render(templates).map_err(|e| {
+        error!("Failed to render complaint for report {}: {e}", report.id);
+        ApiError::InternalServerError(e.to_string())
+    })
+}
+
+/// Complaint to the municipal police as a PDF, for the reporter and users
+/// handling reports
+pub async fn document(
+    user: AuthenticatedUser,
+    db: Data<Db>,
+    settings: Data<Settings>,
+    templates: Data<Templates>,
+    id: web::Path<i64>,
+) -> Result<HttpResponse, ApiError> {
+    let id = id.into_inner();
+    let store = PictureStore::new(&settings.uploads.directory);
+    let pdf = web::block(move || -> Result<Vec<u8>, ApiError> {
+        let report = db.get_report(id)?;
+        if report.reporter_id != Some(user.id) {
+            let granted = db.user_permissions(user.id)?;
+            if ![REVIEW_REPORTS, FORWARD_REPORTS]
+                .iter()
+                .any(|p| permissions::granted(&granted, p))
+            {
+                return Err(ApiError::Forbidden(
+                    "Not allowed to view this report".to_string(),
+                ));
+            }
+        }
+        let pictures = published_pictures(&db, &store, &report)?;
+        complaint_pdf(&db, &templates, &report, pictures)
+    })
+    .await??;
+    Ok(HttpResponse::Ok()
+        .content_type("application/pdf")
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Inline,
+            parameters: vec![DispositionParam::Filename(format!("report-{id}.pdf"))],
+        })
+        .body(pdf))
+}
+
+/// Routes of the `/reports` scope
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/reports")
+            .route("", web::post().to(create))
+            .route("/mine", web::get().to(mine))
+            .route("/duplicates", web::post().to(find_duplicates))
+            .route("/{id}", web::get().to(get))
+            .route("/{id}", web::put().to(amend))
+            .route("/{id}/withdraw", web::post().to(withdraw))
+            .route("/{id}/submit", web::post().to(submit))
+            .route("/{id}/pictures", web::post().to(add_pictures))
+            .route("/{id}/document.pdf", web::get().to(document)),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{self, NewPicture};
+    use crate::testing;
+    use crate::uploads::test_jpeg;
+    use actix_web::{http, test};
+
+    fn new_report(plate: &str) -> NewReport {
+        NewReport {
+            plate: plate.to_string(),
+            latitude: Some(50.07555),
+            longitude: Some(14.43785),
+            observed_at: "2023-06-01T12:30".to_string(),
+            ..NewReport::default()
+        }
+    }
+
+    fn submit(token: &str, report: NewReport) -> actix_http::Request {
+        test::TestRequest::post()
+            .uri("/reports")
+            .insert_header(("Authorization", format!("Bearer {token}")))
+            .set_json(report)
+            .to_request()
+    }
+
+    #[actix_web::test]
+    async fn create_report() {
+        let app = testing::init(db::test_db().into_handle(), configure).await;
+        let req = test::TestRequest::post()
+            .uri("/reports")
+            .set_json(new_report("1AB2345"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let report: Report = test::read_body_json(resp).await;
+        assert_eq!(report.plate, "1AB 2345");
+        assert_eq!(report.observed_at, "2023-06-01T12:30:00");
+        assert_eq!(report.status, ReportStatus::Submitted);
+        assert_eq!(
+            report.resolved_address.unwrap().to_string(),
+            "Vinohradská 1511/12, Praha 2"
+        );
+    }
+
+    #[actix_web::test]
+    async fn create_report_invalid() {
+        let app = testing::init(db::test_db().into_handle(), configure).await;
+        let req = test::TestRequest::post()
+            .uri("/reports")
+            .set_json(NewReport::default())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNPROCESSABLE_ENTITY);
+        let errors: ErrorInfo = test::read_body_json(resp).await;
+        assert!(errors.errors.contains_key("plate"));
+    }
+
+    #[actix_web::test]
+    async fn create_report_unknown_picture() {
+        let app = testing::init(db::test_db().into_handle(), configure).await;
+        let req = test::TestRequest::post()
+            .uri("/reports")
+            .set_json(NewReport {
+                plate: "1AB2345".to_string(),
+                address: Some("Vinohradská 12".to_string()),
+                observed_at: "2023-06-01T12:30".to_string(),
+                pictures: vec![("missing.jpg".to_string(), None)],
+                ..NewReport::default()
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNPROCESSABLE_ENTITY);
+        let errors: ErrorInfo = test::read_body_json(resp).await;
+        assert!(errors.errors.contains_key("pictures"));
+    }
+
+    #[actix_web::test]
+    async fn create_report_foreign_picture() {
+        let db = db::test_db().into_handle();
+        let (reporter, token) = testing::user_with_permissions(&db, "reporter", &[]);
+        for (name, uploader_id) in [("own.jpg", Some(reporter)), ("anonymous.jpg", None)] {
+            db.create_picture(&NewPicture {
+                path: name.to_string(),
+                uploader_id,
+                content_type: "image/jpeg".to_string(),
+                ..NewPicture::default()
+            })
+            .unwrap();
+        }
+        let app = testing::init(db, configure).await;
+        let with_picture = |name: &str| NewReport {
+            pictures: vec![(name.to_string(), None)],
+            ..new_report("1AB2345")
+        };
+        let anonymous = |report: NewReport| {
+            test::TestRequest::post()
+                .uri("/reports")
+                .set_json(report)
+                .to_request()
+        };
+        for req in [
+            anonymous(with_picture("own.jpg")),
+            submit(&token, with_picture("anonymous.jpg")),
+        ] {
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+        }
+        for req in [
+            anonymous(with_picture("anonymous.jpg")),
+            submit(&token, with_picture("own.jpg")),
+        ] {
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), http::StatusCode::CREATED);
+        }
+    }
+
+    #[actix_web::test]
+    async fn list_own_reports() {
+        let db = db::test_db().into_handle();
+        let (_, token) = testing::user_with_permissions(&db, "reporter", &[]);
+        let (_, other) = testing::user_with_permissions(&db, "other", &[]);
+        let app = testing::init(db, configure).await;
+        for (token, plate) in [
+            (&token, "1AB2345"),
+            (&token, "2CD6789"),
+            (&other, "1AB2345"),
+        ] {
+            let resp = test::call_service(&app, submit(token, new_report(plate))).await;
+            assert_eq!(resp.status(), http::StatusCode::CREATED);
+        }
+        let list = |query: &str| {
+            test::TestRequest::get()
+                .uri(&format!("/reports/mine?{query}"))
+                .insert_header(("Authorization", format!("Bearer {token}")))
+                .to_request()
+        };
+        let page: ReportPage = test::call_and_read_body_json(&app, list("per_page=1")).await;
+        assert_eq!((page.total, page.per_page, page.pages()), (2, 1, 2));
+        assert_eq!(page.reports[0].plate, "2CD 6789");
+        let page: ReportPage =
+            test::call_and_read_body_json(&app, list("plate=1ab&status=submitted")).await;
+        assert_eq!(page.total, 1);
+        assert_eq!(page.reports[0].plate, "1AB 2345");
+        let resp = test::call_service(&app, list("from=June")).await;
+        assert_eq!(resp.status(), http::StatusCode::UNPROCESSABLE_ENTITY);
+        let req = test::TestRequest::get().uri("/reports/mine").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn amend_and_withdraw() {
+        let db = db::test_db().into_handle();
+        let (_, token) = testing::user_with_permissions(&db, "reporter", &[]);
+        let (other_id, other) = testing::user_with_permissions(&db, "other", &[]);
+        db.create_picture(&NewPicture {
+            path: "foreign.jpg".to_string(),
+            uploader_id: Some(other_id),
+            content_type: "image/jpeg".to_string(),
+            ..NewPicture::default()
+        })
+        .unwrap();
+        let app = testing::init(db.clone(), configure).await;
+        let report: Report =
+            test::call_and_read_body_json(&app, submit(&token, new_report("1AB2345"))).await;
+        let request = |method: http::Method, path: &str, token: &str| {
+            test::TestRequest::default()
+                .method(method)
+                .uri(&format!("/reports/{}{path}", report.id))
+                .insert_header(("Authorization", format!("Bearer {token}")))
+        };
+
+        let resp =
+            test::call_service(&app, request(http::Method::GET, "", &other).to_request()).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+        // Pictures of somebody else cannot be slipped in
+        let foreign = NewReport {
+            pictures: vec![("foreign.jpg".to_string(), None)],
+            ..new_report("1AB2345")
+        };
+        let resp = test::call_service(
+            &app,
+            request(http::Method::PUT, "", &token)
+                .set_json(foreign)
+                .to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+        // Unless a duplicate already added them
+        db.add_report_pictures(
+            report.id,
+            &[("foreign.jpg".to_string(), None)],
+            "Pictures added from a duplicate report",
+            Some(other_id),
+        )
+        .unwrap();
+        let amended: Report = test::call_and_read_body_json(
+            &app,
+            request(http::Method::PUT, "", &token)
+                .set_json(NewReport {
+                    latitude: Some(50.0816),
+                    longitude: Some(14.4277),
+                    pictures: vec![("foreign.jpg".to_string(), None)],
+                    ..new_report("1AB2346")
+                })
+                .to_request(),
+        )
+        .await;
+        assert_eq!(amended.plate, "1AB 2346");
+        assert_eq!(amended.pictures.len(), 1);
+        assert_eq!(
+            amended.resolved_address.unwrap().street.as_deref(),
+            Some("Hybernská")
+        );
+        let withdraw = || {
+            request(http::Method::POST, "/withdraw", &token)
+                .set_json(Withdrawal {
+                    reason: Some(" Wrong car ".to_string()),
+                })
+                .to_request()
+        };
+        let withdrawn: Report = test::call_and_read_body_json(&app, withdraw()).await;
+        assert_eq!(withdrawn.status, ReportStatus::Withdrawn);
+        let resp = test::call_service(&app, withdraw()).await;
+        assert_eq!(resp.status(), http::StatusCode::CONFLICT);
+        let resp = test::call_service(
+            &app,
+            request(http::Method::PUT, "", &token)
+                .set_json(new_report("1AB2345"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::CONFLICT);
+
+        let detail: ReportDetail = test::call_and_read_body_json(
+            &app,
+            request(http::Method::GET, "", &token).to_request(),
+        )
+        .await;
+        assert_eq!(detail.report.status, ReportStatus::Withdrawn);
+        let notes = detail
+            .history
+            .iter()
+            .map(|c| c.note.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            notes,
+            vec![
+                None,
+                Some("Pictures added from a duplicate report"),
+                Some("Amended"),
+                Some("Wrong car")
+            ]
+        );
+    }
+
+    #[actix_web::test]
+    async fn draft_then_submit() {
+        let db = db::test_db().into_handle();
+        let (_, token) = testing::user_with_permissions(&db, "reporter", &[]);
+        let app = testing::init(db, configure).await;
+        let req = test::TestRequest::post()
+            .uri("/reports?draft=true")
+            .set_json(new_report("1AB2345"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+        let req = test::TestRequest::post()
+            .uri("/reports?draft=true")
+            .insert_header(("Authorization", format!("Bearer {token}")))
+            .set_json(new_report("1AB2345"))
+            .to_request();
+        let draft: Report = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(draft.status, ReportStatus::Draft);
+        let submit = || {
+            test::TestRequest::post()
+                .uri(&format!("/reports/{}/submit", draft.id))
+                .insert_header(("Authorization", format!("Bearer {token}")))
+                .to_request()
+        };
+        let submitted: Report = test::call_and_read_body_json(&app, submit()).await;
+        assert_eq!(submitted.status, ReportStatus::Submitted);
+        let resp = test::call_service(&app, submit()).await;
+        assert_eq!(resp.status(), http::StatusCode::CONFLICT);
+    }
+
+    #[actix_web::test]
+    async fn duplicate_pictures_added() {
+        let db = db::test_db().into_handle();
+        let (reporter, token) = testing::user_with_permissions(&db, "reporter", &[]);
+        let (stranger, _) = testing::user_with_permissions(&db, "stranger", &[]);
+        for (name, uploader_id) in [("extra.jpg", reporter), ("foreign.jpg", stranger)] {
+            db.create_picture(&NewPicture {
+                path: name.to_string(),
+                uploader_id: Some(uploader_id),
+                content_type: "image/jpeg".to_string(),
+                ..NewPicture::default()
+            })
+            .unwrap();
+        }
+        let name = "extra.jpg".to_string();
+        let other = db
+            .create_report(&new_report("2CD6789"), None, None, ReportStatus::Submitted)
+            .unwrap();
+        let app = testing::init(db.clone(), configure).await;
+        let post = |uri: String, report: NewReport| {
+            test::TestRequest::post()
+                .uri(&uri)
+                .insert_header(("Authorization", format!("Bearer {token}")))
+                .set_json(report)
+                .to_request()
+        };
+        let first: Report =
+            test::call_and_read_body_json(&app, post("/reports".into(), new_report("1AB2345")))
+                .await;
+        let later = NewReport {
+            observed_at: "2023-06-01T12:50".to_string(),
+            ..new_report("1AB 2345")
+        };
+        let req = test::TestRequest::post()
+            .uri("/reports/duplicates")
+            .set_json(later.clone())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+        let found: Vec<Duplicate> =
+            test::call_and_read_body_json(&app, post("/reports/duplicates".into(), later.clone()))
+                .await;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, first.id);
+        assert_eq!(found[0].distance_meters, Some(0.0));
+
+        let resp = test::call_service(
+            &app,
+            post(format!("/reports/{}/pictures", first.id), later.clone()),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::UNPROCESSABLE_ENTITY);
+        // Pictures of somebody else cannot be added, nor anonymously
+        let foreign = NewReport {
+            pictures: vec![("foreign.jpg".to_string(), None)],
+            ..later.clone()
+        };
+        let resp = test::call_service(
+            &app,
+            post(format!("/reports/{}/pictures", first.id), foreign),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+        let later = NewReport {
+            pictures: vec![(name.clone(), None)],
+            ..later
+        };
+        let req = test::TestRequest::post()
+            .uri(&format!("/reports/{}/pictures", first.id))
+            .set_json(later.clone())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+        let resp = test::call_service(
+            &app,
+            post(format!("/reports/{}/pictures", other.id), later.clone()),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::CONFLICT);
+        let result: ApiResult = test::call_and_read_body_json(
+            &app,
+            post(format!("/reports/{}/pictures", first.id), later.clone()),
+        )
+        .await;
+        assert_eq!(result.result, "Pictures added");
+        assert_eq!(db.get_report(first.id).unwrap().pictures, later.pictures);
+
+        let second: Report =
+            test::call_and_read_body_json(&app, post("/reports".into(), later)).await;
+        let history = db.report_history(second.id).unwrap();
+        assert_eq!(
+            history.last().unwrap().note,
+            Some(format!("Likely duplicate of report {}", first.id))
+        );
+        assert_eq!(db.report_history(first.id).unwrap().len(), 2);
+    }
+
+    #[actix_web::test]
+    async fn complaint_document() {
+        let db = db::test_db().into_handle();
+        let (reporter, token) = testing::user_with_permissions(&db, "reporter", &[]);
+        let (_, other) = testing::user_with_permissions(&db, "other", &[]);
+        let (_, forwarder) = testing::user_with_permissions(&db, "forwarder", &[FORWARD_REPORTS]);
+        let settings = testing::settings();
+        let name = PictureStore::new(&settings.uploads.directory)
+            .store(&test_jpeg(5))
+            .unwrap();
+        db.create_picture(&NewPicture {
+            path: name.clone(),
+            uploader_id: Some(reporter),
+            content_type: "image/jpeg".to_string(),
+            size: 0,
+            taken_at: None,
+            latitude: None,
+            longitude: None,
+            hash: None,
+        })
+        .unwrap();
+        let report = NewReport {
+            pictures: vec![(name.clone(), Some("Zepředu".to_string()))],
+            ..new_report("1AB2345")
+        };
+        let report = db
+            .create_report(&report, None, Some(reporter), ReportStatus::Submitted)
+            .unwrap();
+        let mailer = testing::mailer(&settings);
+        let app = testing::init_with(db.clone(), settings, mailer, configure).await;
+
+        let document = |token: &str| {
+            test::TestRequest::get()
+                .uri(&format!("/reports/{}/document.pdf", report.id))
+                .insert_header(("Authorization", format!("Bearer {token}")))
+                .to_request()
+        };
+        let resp = test::call_service(&app, document(&other)).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+        for token in [&token, &forwarder] {
+            let resp = test::call_service(&app, document(token)).await;
+            assert_eq!(resp.status(), http::StatusCode::OK);
+            assert_eq!(
+                resp.headers().get(http::header::CONTENT_TYPE).unwrap(),
+                "application/pdf"
+            );
+            let pdf = test::read_body(resp).await;
+            assert!(pdf.starts_with(b"%PDF"));
+        }
+        // The redacted copy is embedded, not the original
+        assert!(db.get_picture(&name).unwrap().redacted.is_some());
+    }
+}
@@ -0,0 +1,470 @@
+use crate::auth::{hash_password, verify_password, AuthenticatedUser, Tokens};
+use crate::db::{Db, StorageError};
+use crate::error::ApiError;
+use crate::mailer::{templates, MailerHandle};
+use crate::settings::Settings;
+use crate::types::auth::{
+    ApiResult, EmailConfirmationResult, EmailDetail, EmailResendInfo, LoginInfo, RegisterInfo,
+    RegisterResponse, UserInfo,
+};
+use crate::types::Validate;
+use actix_web::web::{self, Data, Json};
+use rand::distributions::{Alphanumeric, DistString};
+use serde::Deserialize;
+use time::OffsetDateTime;
+use tracing::{info, warn};
+
+#[derive(Deserialize, Debug)]
+pub struct EmailCode {
+    pub code: String,
+}
+
+/// Build the user info returned to the frontend
+fn user_info(db: &Db, user_id: i64, token: String) -> Result<UserInfo, ApiError> {
+    let user = db.get_user(user_id)?;
+    let emails = db
+        .list_emails(user_id)?
+        .into_iter()
+        .map(|e| EmailDetail {
+            email: e.email,
+            verified: e.verified,
+            primary: e.primary,
+        })
+        .collect();
+    Ok(UserInfo {
+        id: user.id,
+        token,
+        username: user.username,
+        emails,
+        permissions: db.user_permissions(user_id)?,
+    })
+}
+
+/// Store a new confirmation code for the email
+fn create_code(db: &Db, settings: &Settings, email_id: i64) -> Result<String, ApiError> {
+    let code = Alphanumeric.sample_string(&mut rand::thread_rng(), 32);
+    let expires_at =
+        OffsetDateTime::now_utc() + time::Duration::hours(settings.mail.code_lifetime_hours);
+    db.create_email_code(email_id, &code, expires_at.unix_timestamp())?;
+    Ok(code)
+}
+
+/// Confirmation email waiting to be sent once the database work is done
+struct Confirmation {
+    username: String,
+    address: String,
+    code: String,
+}
+
+/// Send the confirmation code in the background
+fn send_confirmation(mailer: &MailerHandle, settings: &Settings, confirmation: Confirmation) {
+    let Ok(to) = confirmation.address.parse() else {
+        warn!(
+            "Not sending confirmation to invalid address {}",
+            confirmation.address
+        );
+        return;
+    };
+    let link = format!(
+        "{}/email?code={}",
+        settings.server.public_url.trim_end_matches('/'),
+        confirmation.code
+    );
+    let content = templates::confirm_email(
+        &confirmation.username,
+        &confirmation.address,
+        &link,
+        settings.mail.code_lifetime_hours,
+    );
+    let mailer = mailer.clone();
+    actix_rt::spawn(async move {
+        if let Err(e) = mailer.send(to, content).await {
+            warn!("Failed to send confirmation email: {e}");
+        }
+    });
+}
+
+/// Get current user info
+pub async fn current(user: AuthenticatedUser, db: Data<Db>) -> Result<Json<UserInfo>, ApiError> {
+    let info = web::block(move || user_info(&db, user.id, user.token)).await??;
+    Ok(Json(info))
+}
+
+/// Login a user
+pub async fn login(
+    db: Data<Db>,
+    tokens: Data<Tokens>,
+    login_info: Json<LoginInfo>,
+) -> Result<Json<UserInfo>, ApiError> {
+    let login_info = login_info.into_inner();
+    let info = web::block(move || {
+        let user = db
+            .find_user_by_username(login_info.username.trim())?
+            .filter(|u| verify_password(&login_info.password, &u.password_hash))
+            .ok_or_else(|| ApiError::Unauthorized("Invalid username or password".to_string()))?;
+        info!("User {} logged in", user.id);
+        user_info(&db, user.id, tokens.issue(user.id)?)
+    })
+    .await??;
+    Ok(Json(info))
+}
+
+/// Register a new user
+pub async fn register(
+    db: Data<Db>,
+    tokens: Data<Tokens>,
+    mailer: Data<MailerHandle>,
+    settings: Data<Settings>,
+    register_info: Json<RegisterInfo>,
+) -> Result<Json<RegisterResponse>, ApiError> {
+    let register_info = register_info.into_inner().validate()?;
+    let block_settings = settings.clone();
+    let (info, confirmations) = web::block(move || {
+        if db.find_email(&register_info.email)?.is_some() {
+            return Err(ApiError::Conflict(
+                "Email is already registered".to_string(),
+            ));
+        }
+        let hash = hash_password(&register_info.password)?;
+        let user = db
+            .create_user(&register_info.username, &hash, &register_info.email)
+            .map_err(|e| match e {
+                StorageError::Conflict(_) => {
+                    ApiError::Conflict("Username is already taken".to_string())
+                }
+                e => e.into(),
+            })?;
+        info!("User {} registered", user.id);
+        let confirmations = db
+            .list_emails(user.id)?
+            .into_iter()
+            .map(|e| {
+                Ok(Confirmation {
+                    username: user.username.clone(),
+                    code: create_code(&db, &block_settings, e.id)?,
+                    address: e.email,
+                })
+            })
+            .collect::<Result<Vec<_>, ApiError>>()?;
+        Ok((
+            user_info(&db, user.id, tokens.issue(user.id)?)?,
+            confirmations,
+        ))
+    })
+    .await??;
+    for confirmation in confirmations {
+        send_confirmation(&mailer, &settings, confirmation);
+    }
+    Ok(Json(RegisterResponse {
+        result: "Registered".to_string(),
+        data: Some(info),
+    }))
+}
+
+/// Logout the current user, the token can no longer be used
+pub async fn logout(user: AuthenticatedUser, db: Data<Db>) -> Result<Json<ApiResult>, ApiError> {
+    web::block(move || db.revoke_token(&user.claims.jti, user.claims.exp)).await??;
+    Ok(Json(ApiResult::new("Logged out")))
+}
+
+/// Confirm an email address with the code sent to it
+pub async fn confirm_email(
+    db: Data<Db>,
+    query: web::Query<EmailCode>,
+) -> Result<Json<EmailConfirmationResult>, ApiError> {
+    let code = query.into_inner().code;
+    let email = web::block(move || {
+        let code = db.find_email_code(&code)?;
+        if code.expires_at < OffsetDateTime::now_utc().unix_timestamp() {
+            return Err(ApiError::Conflict(
+                "Confirmation code has expired, request a new one".to_string(),
+            ));
+        }
+        Ok(db.verify_email(code.email_id)?)
+    })
+    .await??;
+    Ok(Json(EmailConfirmationResult {
+        result: "Email confirmed".to_string(),
+        email: Some(email.email),
+    }))
+}
+
+/// Send a new confirmation code to an unverified email, at most once per
+/// `mail.resend_interval_minutes`
+pub async fn resend(
+    db: Data<Db>,
+    mailer: Data<MailerHandle>,
+    settings: Data<Settings>,
+    resend_info: Json<EmailResendInfo>,
+) -> Result<Json<ApiResult>, ApiError> {
+    let resend_info = resend_info.into_inner();
+    let block_settings = settings.clone();
+    let confirmation = web::block(move || {
+        let email = db
+            .find_email(resend_info.email.trim())?
+            .filter(|e| e.user_id == resend_info.user_id)
+            .ok_or(ApiError::NotFound)?;
+        if email.verified {
+            return Err(ApiError::Conflict("Email is already confirmed".to_string()));
+        }
+        let interval = block_settings.mail.resend_interval_minutes * 60;
+        if let Some(latest) = db.latest_email_code(email.id)? {
+            let wait = latest.created_at + interval - OffsetDateTime::now_utc().unix_timestamp();
+            if wait > 0 {
+                return Err(ApiError::Conflict(format!(
+                    "Confirmation email was sent recently, try again in {} minutes",
+                    (wait + 59) / 60
+                )));
+            }
+        }
+        Ok::<_, ApiError>(Confirmation {
+            username: db.get_user(email.user_id)?.username,
+            code: create_code(&db, &block_settings, email.id)?,
+            address: email.email,
+        })
+    })
+    .await??;
+    send_confirmation(&mailer, &settings, confirmation);
+    Ok(Json(ApiResult::new("Confirmation email sent")))
+}
+
+/// Routes of the `/users` scope
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/users")
+            .route("", web::get().to(current))
+            .route("", web::put().to(login))
+            .route("", web::post().to(register))
+            .route("", web::patch().to(logout))
+            .route("/email", web::get().to(confirm_email))
+            .route("/email", web::patch().to(resend)),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::testing;
+    use crate::types::ErrorInfo;
+    use actix_web::{http, test};
+
+    fn register_info() -> RegisterInfo {
+        RegisterInfo {
+            username: "reporter".to_string(),
+            password: "correct horse".to_string(),
+            email: "reporter@example.com".to_string(),
+        }
+    }
+
+    #[actix_web::test]
+    async fn register_login_logout() {
+        let app = testing::init(db::test_db().into_handle(), configure).await;
+
+        let req = test::TestRequest::post()
+            .uri("/users")
+            .set_json(register_info())
+            .to_request();
+        let registered: RegisterResponse = test::call_and_read_body_json(&app, req).await;
+        let user = registered.data.unwrap();
+        assert_eq!(user.username, "reporter");
+        assert_eq!(user.emails.len(), 1);
+        assert!(!user.emails[0].verified);
+
+        let req = test::TestRequest::put()
+            .uri("/users")
+            .set_json(LoginInfo {
+                username: "reporter".to_string(),
+                password: "correct horse".to_string(),
+            })
+            .to_request();
+        let logged_in: UserInfo = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(logged_in.id, user.id);
+
+        let req = test::TestRequest::get()
+            .uri("/users")
+            .insert_header((
+                http::header::AUTHORIZATION,
+                format!("Bearer {}", logged_in.token),
+            ))
+            .to_request();
+        let current: UserInfo = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(current.token, logged_in.token);
+
+        let req = test::TestRequest::patch()
+            .uri("/users")
+            .insert_header((
+                http::header::AUTHORIZATION,
+                format!("Bearer {}", logged_in.token),
+            ))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let req = test::TestRequest::get()
+            .uri("/users")
+            .insert_header((
+                http::header::AUTHORIZATION,
+                format!("Bearer {}", logged_in.token),
+            ))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+        let result: ApiResult = test::read_body_json(resp).await;
+        assert_eq!(result.result, "Token revoked");
+    }
+
+    #[actix_web::test]
+    async fn wrong_password() {
+        let app = testing::init(db::test_db().into_handle(), configure).await;
+        let req = test::TestRequest::post()
+            .uri("/users")
+            .set_json(register_info())
+            .to_request();
+        test::call_service(&app, req).await;
+
+        let req = test::TestRequest::put()
+            .uri("/users")
+            .set_json(LoginInfo {
+                username: "reporter".to_string(),
+                password: "battery staple".to_string(),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn register_conflict_and_invalid() {
+        let app = testing::init(db::test_db().into_handle(), configure).await;
+        let req = test::TestRequest::post()
+            .uri("/users")
+            .set_json(register_info())
+            .to_request();
+        test::call_service(&app, req).await;
+
+        let mut info = register_info();
+        info.email = "other@example.com".to_string();
+        let req = test::TestRequest::post()
+            .uri("/users")
+            .set_json(info)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CONFLICT);
+
+        let mut info = register_info();
+        info.password = "short".to_string();
+        let req = test::TestRequest::post()
+            .uri("/users")
+            .set_json(info)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNPROCESSABLE_ENTITY);
+        let errors: ErrorInfo = test::read_body_json(resp).await;
+        assert!(errors.errors.contains_key("password"));
+    }
+
+    #[actix_web::test]
+    async fn confirm_email_code() {
+        let db = db::test_db().into_handle();
+        let app = testing::init(db.clone(), configure).await;
+        let req = test::TestRequest::post()
+            .uri("/users")
+            .set_json(register_info())
+            .to_request();
+        test::call_service(&app, req).await;
+
+        let email = db.find_email("reporter@example.com").unwrap().unwrap();
+        db.create_email_code(email.id, "known", i64::MAX).unwrap();
+        db.create_email_code(email.id, "expired", 0).unwrap();
+
+        let req = test::TestRequest::get()
+            .uri("/users/email?code=unknown")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::get()
+            .uri("/users/email?code=expired")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CONFLICT);
+        let result: ApiResult = test::read_body_json(resp).await;
+        assert!(result.result.contains("expired"));
+
+        let req = test::TestRequest::get()
+            .uri("/users/email?code=known")
+            .to_request();
+        let result: EmailConfirmationResult = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(result.email, Some("reporter@example.com".to_string()));
+
+        let req = test::TestRequest::patch()
+            .uri("/users/email")
+            .set_json(EmailResendInfo {
+                user_id: email.user_id,
+                email: email.email,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CONFLICT);
+    }
+
+    #[actix_web::test]
+    async fn resend_throttled() {
+        let db = db::test_db().into_handle();
+        let mut settings = testing::settings();
+        settings.mail.resend_interval_minutes = 0;
+        let mailer = testing::mailer(&settings);
+        let app = testing::init_with(db.clone(), settings.clone(), mailer.clone(), configure).await;
+        let req = test::TestRequest::post()
+            .uri("/users")
+            .set_json(register_info())
+            .to_request();
+        let registered: RegisterResponse = test::call_and_read_body_json(&app, req).await;
+        let user_id = registered.data.unwrap().id;
+        let resend = || {
+            test::TestRequest::patch()
+                .uri("/users/email")
+                .set_json(EmailResendInfo {
+                    user_id,
+                    email: "reporter@example.com".to_string(),
+                })
+                .to_request()
+        };
+
+        let resp = test::call_service(&app, resend()).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        // Emails are sent in the background
+        let mut sent = 0;
+        for _ in 0..50 {
+            sent = std::fs::read_dir(&settings.mail.directory)
+                .map(|d| d.count())
+                .unwrap_or(0);
+            if sent == 2 {
+                break;
+            }
+            actix_rt::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert_eq!(sent, 2);
+
+        let req = test::TestRequest::patch()
+            .uri("/users/email")
+            .set_json(EmailResendInfo {
+                user_id: user_id + 1,
+                email: "reporter@example.com".to_string(),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        settings.mail.resend_interval_minutes = 5;
+        let directory = settings.mail.directory.clone();
+        let app = testing::init_with(db, settings, mailer, configure).await;
+        let resp = test::call_service(&app, resend()).await;
+        assert_eq!(resp.status(), http::StatusCode::CONFLICT);
+        let result: ApiResult = test::read_body_json(resp).await;
+        assert!(result.result.contains("5 minutes"));
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,74 @@
+use super::{Mailer, MailerError};
+use async_trait::async_trait;
+use lettre::message::Mailbox;
+use lettre::{AsyncFileTransport, AsyncTransport, Message, Tokio1Executor};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Writes every message as an `.eml` file into a directory
+#[derive(Clone)]
+pub struct FileMailer {
+    transport: AsyncFileTransport<Tokio1Executor>,
+    directory: PathBuf,
+    from: Mailbox,
+}
+
+impl FileMailer {
+    /// Create the directory if needed
+    pub fn new(directory: &Path, from: Mailbox) -> Result<Self, MailerError> {
+        std::fs::create_dir_all(directory)?;
+        Ok(Self {
+            transport: AsyncFileTransport::new(directory),
+            directory: directory.to_path_buf(),
+            from,
+        })
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    fn from(&self) -> &Mailbox {
+        &self.from
+    }
+
+    async fn deliver(&self, message: Message) -> Result<(), MailerError> {
+        let id = self.transport.send(message).await?;
+        info!("Wrote email {}/{id}.eml", self.directory.display());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mailer::Content;
+
+    #[actix_web::test]
+    async fn writes_eml() {
+        let directory = std::env::temp_dir().join(format!("mail-{}", uuid::Uuid::new_v4()));
+        let mailer = FileMailer::new(&directory, "noreply@example.com".parse().unwrap()).unwrap();
+        mailer
+            .send(
+                "reporter@example.com".parse().unwrap(),
+                Content {
+                    subject: "Hello".to_string(),
+                    text: "plain body".to_string(),
+                    html: "<p>html body</p>".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+
+        let files = std::fs::read_dir(&directory)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .collect::<Vec<_>>();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].extension().unwrap(), "eml");
+        let eml = std::fs::read_to_string(&files[0]).unwrap();
+        assert!(eml.contains("Subject: Hello"));
+        assert!(eml.contains("plain body"));
+        assert!(eml.contains("text/html"));
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
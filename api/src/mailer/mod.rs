@@ -0,0 +1,91 @@
+//! Outgoing email.
+//!
+//! Handlers send mail through the [`Mailer`] trait; the transport is picked
+//! from the `[mail]` settings: [`SmtpMailer`] for production and [`FileMailer`]
+//! writing `.eml` files for development and tests.
+
+mod file;
+mod smtp;
+pub mod templates;
+
+pub use file::FileMailer;
+pub use smtp::SmtpMailer;
+
+use crate::settings::{self, MailTransport};
+use async_trait::async_trait;
+use lettre::message::{Mailbox, MultiPart};
+use lettre::Message;
+use std::sync::Arc;
+use thiserror::Error as ThisError;
+
+#[derive(ThisError, Debug)]
+pub enum MailerError {
+    #[error("Invalid sender address: {0}")]
+    Address(#[from] lettre::address::AddressError),
+
+    #[error("SMTP error: {0}")]
+    Smtp(#[from] lettre::transport::smtp::Error),
+
+    #[error("File transport error: {0}")]
+    File(#[from] lettre::transport::file::Error),
+
+    #[error("Failed to build message: {0}")]
+    Message(#[from] lettre::error::Error),
+
+    #[error("Mail settings error: {0}")]
+    Settings(String),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Rendered email ready to be addressed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Content {
+    pub subject: String,
+    pub text: String,
+    pub html: String,
+}
+
+/// Something that can deliver email
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    /// Sender of all messages
+    fn from(&self) -> &Mailbox;
+
+    /// Deliver a complete message
+    async fn deliver(&self, message: Message) -> Result<(), MailerError>;
+
+    /// Send content as a plain text and HTML alternative
+    async fn send(&self, to: Mailbox, content: Content) -> Result<(), MailerError> {
+        let message = Message::builder()
+            .from(self.from().clone())
+            .to(to)
+            .subject(content.subject)
+            .multipart(MultiPart::alternative_plain_html(
+                content.text,
+                content.html,
+            ))?;
+        self.deliver(message).await
+    }
+}
+
+/// Shared handle to the configured mailer
+pub type MailerHandle = Arc<dyn Mailer>;
+
+/// Build the mailer selected by the settings
+pub fn from_settings(
+    mail: &settings::Mail,
+    smtp: Option<&settings::Smtp>,
+) -> Result<MailerHandle, MailerError> {
+    let from = mail.from.parse::<Mailbox>()?;
+    Ok(match mail.transport {
+        MailTransport::Smtp => {
+            let smtp = smtp.ok_or_else(|| {
+                MailerError::Settings("the smtp transport needs an [smtp] section".to_string())
+            })?;
+            Arc::new(SmtpMailer::new(smtp, from)?)
+        }
+        MailTransport::File => Arc::new(FileMailer::new(&mail.directory, from)?),
+    })
+}
@@ -0,0 +1,45 @@
+use super::{Mailer, MailerError};
+use crate::settings;
+use async_trait::async_trait;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+/// Sends email through an SMTP relay
+#[derive(Clone)]
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn new(settings: &settings::Smtp, from: Mailbox) -> Result<Self, MailerError> {
+        let mut builder = if settings.tls {
+            AsyncSmtpTransport::<Tokio1Executor>::relay(&settings.host)?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&settings.host)
+        };
+        if let Some(port) = settings.port {
+            builder = builder.port(port);
+        }
+        if let (Some(username), Some(password)) = (&settings.username, &settings.password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+        Ok(Self {
+            transport: builder.build(),
+            from,
+        })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    fn from(&self) -> &Mailbox {
+        &self.from
+    }
+
+    async fn deliver(&self, message: Message) -> Result<(), MailerError> {
+        self.transport.send(message).await?;
+        Ok(())
+    }
+}
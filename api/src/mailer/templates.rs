@@ -0,0 +1,90 @@
+//! Email templates, embedded at build time from `api/templates`.
+//!
+//! Placeholders are written as `{{ name }}`; values are HTML escaped in the
+//! HTML part.
+
+use super::Content;
+use crate::types::report::Report;
+
+const CONFIRM_EMAIL_TEXT: &str = include_str!("../../templates/confirm_email.txt");
+const CONFIRM_EMAIL_HTML: &str = include_str!("../../templates/confirm_email.html");
+const FORWARD_REPORT_TEXT: &str = include_str!("../../templates/forward_report.txt");
+const FORWARD_REPORT_HTML: &str = include_str!("../../templates/forward_report.html");
+
+fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Replace the placeholders of a template
+pub fn render(template: &str, values: &[(&str, &str)], html: bool) -> String {
+    values
+        .iter()
+        .fold(template.to_string(), |text, (key, value)| {
+            let value = if html {
+                escape_html(value)
+            } else {
+                value.to_string()
+            };
+            text.replace(&format!("{{{{ {key} }}}}"), &value)
+        })
+}
+
+/// Email asking the user to confirm an address
+pub fn confirm_email(username: &str, email: &str, link: &str, hours: i64) -> Content {
+    let hours = hours.to_string();
+    let values = [
+        ("username", username),
+        ("email", email),
+        ("link", link),
+        ("hours", hours.as_str()),
+    ];
+    Content {
+        subject: "Confirm your email address".to_string(),
+        text: render(CONFIRM_EMAIL_TEXT, &values, false),
+        html: render(CONFIRM_EMAIL_HTML, &values, true),
+    }
+}
+
+/// Email to an authority carrying a report, in Czech like the complaint
+pub fn forward_report(report: &Report) -> Content {
+    let id = report.id.to_string();
+    let values = [("report_id", id.as_str()), ("plate", report.plate.as_str())];
+    Content {
+        subject: format!("Oznámení přestupku č. {id}, {}", report.plate),
+        text: render(FORWARD_REPORT_TEXT, &values, false),
+        html: render(FORWARD_REPORT_HTML, &values, true),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn confirm_email_rendered() {
+        let content = confirm_email(
+            "<reporter>",
+            "r@example.com",
+            "http://localhost/email?code=abc&x=1",
+            48,
+        );
+        assert!(content.text.contains("Hello <reporter>,"));
+        assert!(content.text.contains("http://localhost/email?code=abc&x=1"));
+        assert!(content.text.contains("48 hours"));
+        assert!(content.html.contains("Hello &lt;reporter&gt;,"));
+        assert!(content.html.contains("code=abc&amp;x=1"));
+        assert!(!content.text.contains("{{"));
+        assert!(!content.html.contains("{{"));
+    }
+}
@@ -1,21 +1,208 @@
-#[actix_rt::main]
-async fn main() -> io::Result<()> {
-    let port = "8080".to_string();
-    let addr = "127.0.0.1".to_string();
+mod auth;
+mod db;
+mod document;
+mod duplicates;
+mod error;
+mod forward;
+mod geocode;
+mod handlers;
+mod mailer;
+mod permissions;
+mod phash;
+mod redact;
+mod seed;
+mod settings;
+#[cfg(test)]
+mod testing;
+mod types;
+mod uploads;
+
+use actix_web::middleware::{self, ErrorHandlers};
+use actix_web::web::{self, Data};
+use actix_web::{http, App, HttpServer};
+use clap::Parser;
+use settings::Settings;
+use std::io;
+use thiserror::Error as ThisError;
+use tracing::{error, info, warn};
+
+/// Command line arguments
+#[derive(Parser, Debug)]
+#[command(version, about)]
+struct Args {
+    /// Configuration file
+    #[arg(long, env = settings::CONFIG_ENV, default_value = "config.toml")]
+    config: String,
+
+    /// Apply pending database migrations and exit
+    #[arg(long)]
+    migrate_only: bool,
+}
+
+/// Reasons the server can fail to come up
+#[derive(ThisError, Debug)]
+enum StartupError {
+    #[error(transparent)]
+    Settings(#[from] settings::SettingsError),
+
+    #[error("Failed to open database: {0}")]
+    Database(#[from] r2d2::Error),
+
+    #[error("Failed to migrate database: {0}")]
+    Migration(#[from] db::StorageError),
+
+    #[error("Failed to seed database: {0}")]
+    Seed(#[from] seed::SeedError),
+
+    #[error("Failed to set up mailer: {0}")]
+    Mailer(#[from] mailer::MailerError),
+
+    #[error(transparent)]
+    Geocoding(#[from] geocode::DatasetError),
+
+    #[error(transparent)]
+    Documents(#[from] document::DocumentError),
+
+    #[error("Failed to create upload directory {0}: {1}")]
+    Uploads(String, io::Error),
+
+    #[error("Failed to bind {0}: {1}")]
+    Bind(String, io::Error),
+
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+fn cors(origins: &[String]) -> actix_cors::Cors {
+    let cors = if origins.iter().any(|o| o == "*") {
+        actix_cors::Cors::default().allow_any_origin()
+    } else {
+        origins
+            .iter()
+            .fold(actix_cors::Cors::default(), |cors, origin| {
+                cors.allowed_origin(origin)
+            })
+    };
+    cors.allowed_methods(vec![
+        http::Method::GET,
+        http::Method::POST,
+        http::Method::PUT,
+        http::Method::PATCH,
+        http::Method::DELETE,
+    ])
+    .allowed_headers(vec![
+        http::header::AUTHORIZATION,
+        http::header::ACCEPT,
+        http::header::CONTENT_TYPE,
+    ])
+}
+
+async fn run(args: Args) -> Result<(), StartupError> {
+    let settings = Settings::load(&args.config)?;
+    let storage = db::Sqlite::new(db::connect(&settings.database)?);
+    let applied = storage.migrate()?;
+    info!("Applied {applied} database migrations");
+    if args.migrate_only {
+        return Ok(());
+    }
+    let db = storage.into_handle();
+    seed::run(&db, settings.admin.as_ref())?;
+    let tokens = auth::Tokens::new(&settings.auth);
+    let mailer = mailer::from_settings(&settings.mail, settings.smtp.as_ref())?;
+    std::fs::create_dir_all(&settings.uploads.directory)
+        .map_err(|e| StartupError::Uploads(settings.uploads.directory.display().to_string(), e))?;
+    {
+        // Pictures uploaded before hashing existed, in the background as
+        // decoding them all takes a while
+        let db = db.clone();
+        let store = uploads::PictureStore::new(&settings.uploads.directory);
+        std::thread::spawn(
+            move || match handlers::pictures::hash_pictures(&db, &store) {
+                Ok(0) => {}
+                Ok(hashed) => info!("Hashed {hashed} pictures uploaded before hashing"),
+                Err(e) => warn!("Failed to hash pictures uploaded before hashing: {e}"),
+            },
+        );
+    }
+
+    let geocoder = match &settings.geocoding.dataset {
+        Some(path) => {
+            let geocoder = geocode::Geocoder::load(path, settings.geocoding.max_distance_meters)?;
+            info!(
+                "Loaded {} address points from {}",
+                geocoder.len(),
+                path.display()
+            );
+            geocoder
+        }
+        None => {
+            warn!("No geocoding dataset configured, report addresses won't be resolved");
+            geocode::Geocoder::default()
+        }
+    };
+    let geocoder = Data::new(geocoder);
+
+    let templates = match &settings.documents.templates {
+        Some(path) => {
+            let templates = document::Templates::load(path)?;
+            info!(
+                "Loaded complaint templates for {} municipalities from {}",
+                templates.len(),
+                path.display()
+            );
+            templates
+        }
+        None => document::Templates::default(),
+    };
+    let templates = Data::new(templates);
+    if settings.forwarding.authorities.is_empty() {
+        warn!("No authorities configured, reports can't be forwarded");
+    }
+
+    let addr = format!("{}:{}", settings.server.address, settings.server.port);
+    info!("Listening on {addr}");
+    let origins = settings.cors.origins.clone();
     HttpServer::new(move || {
-        let cors = actix_cors::Cors::default().allow_any_origin().allowed_methods(vec![
-            http::Method::GET,
-            http::Method::POST,
-            http::Method::PUT,
-            http::Method::PATCH,
-            http::Method::DELETE,
-        ]).allowed_headers(vec![
-            http::header::AUTHORIZATION,
-            http::header::ACCEPT,
-            http::header::CONTENT_TYPE,
-        ]);
-        App::new().app_data(Data::new(db.clone())).app_data(Data::new(mailer.clone())).wrap(middleware::NormalizePath::trim()).wrap(cors).wrap(
-            ErrorHandlers::new().handler(http::StatusCode::BAD_REQUEST, handlers::handle_bad_request),
-        ).default_service(web::get().to(handlers::default)).service(web::scope("/").route("", web::get().to(handlers::root)))
-    }).bind(format!("{addr}:{port}"))?.run().await
-}
\ No newline at end of file
+        App::new()
+            .app_data(Data::new(db.clone()))
+            .app_data(Data::new(mailer.clone()))
+            .app_data(Data::new(tokens.clone()))
+            .app_data(Data::new(settings.clone()))
+            .app_data(geocoder.clone())
+            .app_data(templates.clone())
+            .wrap(middleware::NormalizePath::trim())
+            .wrap(cors(&origins))
+            .wrap(
+                ErrorHandlers::new()
+                    .handler(http::StatusCode::BAD_REQUEST, handlers::handle_bad_request),
+            )
+            .default_service(web::get().to(handlers::default))
+            .configure(handlers::reports::configure)
+            .configure(handlers::moderation::configure)
+            .configure(handlers::users::configure)
+            .configure(handlers::admin::configure)
+            .configure(handlers::pictures::configure)
+            .configure(handlers::geocode::configure)
+            .configure(handlers::mail::configure)
+            .service(web::scope("/").route("", web::get().to(handlers::root)))
+    })
+    .bind(&addr)
+    .map_err(|e| StartupError::Bind(addr.clone(), e))?
+    .run()
+    .await?;
+    Ok(())
+}
+
+#[actix_rt::main]
+async fn main() {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+        )
+        .init();
+    if let Err(e) = run(Args::parse()).await {
+        error!("{e}");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,54 @@
+//! Named permissions checked by the handlers.
+//!
+//! Permissions are granted to users through roles. Every permission listed
+//! in [`ALL`] is inserted into the database on startup so it can be assigned
+//! to roles from the admin API. A user holding [`SUPER_ADMIN`] passes every
+//! check.
+
+pub use common::permissions::{
+    granted, FORWARD_REPORTS, MANAGE_ROLES, MANAGE_USERS, REVIEW_REPORTS, SUPER_ADMIN,
+    VIEW_ORIGINAL_PICTURES, VIEW_PICTURES,
+};
+
+/// Every permission known to the api with its description
+pub const ALL: &[(&str, &str)] = &[
+    (SUPER_ADMIN, "Every permission, including ones added later"),
+    (MANAGE_USERS, "List users and assign their roles"),
+    (MANAGE_ROLES, "Create, edit and delete roles"),
+    (VIEW_PICTURES, "View pictures uploaded by other users"),
+    (
+        VIEW_ORIGINAL_PICTURES,
+        "View pictures of other users without blurred faces and plates",
+    ),
+    (
+        REVIEW_REPORTS,
+        "Work through the moderation queue, take, return and reject reports",
+    ),
+    (
+        FORWARD_REPORTS,
+        "Forward reviewed reports to the authorities and record their outcome",
+    ),
+];
+
+/// Permission required by an [`Authorized`](crate::auth::Authorized) extractor
+pub trait Required {
+    const NAME: &'static str;
+}
+
+pub struct ManageUsers;
+
+impl Required for ManageUsers {
+    const NAME: &'static str = MANAGE_USERS;
+}
+
+pub struct ManageRoles;
+
+impl Required for ManageRoles {
+    const NAME: &'static str = MANAGE_ROLES;
+}
+
+pub struct ReviewReports;
+
+impl Required for ReviewReports {
+    const NAME: &'static str = REVIEW_REPORTS;
+}
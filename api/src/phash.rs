@@ -0,0 +1,145 @@
+//! Perceptual hashes telling whether two pictures show the same photo.
+//!
+//! Unlike the SHA-256 naming the pictures, the hashes survive recompression,
+//! scaling and small edits, so a photo reused for another report is found
+//! even when it was saved again. Every picture gets a difference hash (dHash,
+//! brightness gradients of a 9×8 thumbnail) and a DCT hash (pHash, the lowest
+//! frequencies of a 32×32 thumbnail); two pictures are similar when both
+//! differ in at most [`MAX_DISTANCE`] of their 64 bits.
+
+use image::imageops::{self, FilterType};
+use image::{GrayImage, RgbImage};
+
+/// Most differing bits of both hashes of similar pictures
+pub const MAX_DISTANCE: u32 = 10;
+/// Side of the thumbnail the DCT hash is computed from
+const DCT_SIZE: usize = 32;
+/// Side of the block of lowest frequencies kept by the DCT hash
+const DCT_KEPT: usize = 8;
+
+/// Perceptual hashes of an upright picture
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PictureHash {
+    pub difference: u64,
+    pub dct: u64,
+}
+
+impl PictureHash {
+    /// Differing bits of the hash that differs more
+    pub fn distance(&self, other: &Self) -> u32 {
+        let difference = (self.difference ^ other.difference).count_ones();
+        let dct = (self.dct ^ other.dct).count_ones();
+        difference.max(dct)
+    }
+}
+
+fn thumbnail(image: &GrayImage, width: u32, height: u32) -> GrayImage {
+    imageops::resize(image, width, height, FilterType::Triangle)
+}
+
+/// Whether each pixel of a 9×8 thumbnail is brighter than its right neighbour
+fn difference_hash(gray: &GrayImage) -> u64 {
+    let small = thumbnail(gray, 9, 8);
+    let mut hash = 0;
+    for y in 0..8 {
+        for x in 0..8 {
+            let bit = small.get_pixel(x, y)[0] > small.get_pixel(x + 1, y)[0];
+            hash = hash << 1 | u64::from(bit);
+        }
+    }
+    hash
+}
+
+/// Whether each of the 8×8 lowest frequencies of a 32×32 thumbnail is above
+/// their median, the constant term left out of the median
+fn dct_hash(gray: &GrayImage) -> u64 {
+    let small = thumbnail(gray, DCT_SIZE as u32, DCT_SIZE as u32);
+    let pixels = |x: usize, y: usize| f64::from(small.get_pixel(x as u32, y as u32)[0]);
+    let n = DCT_SIZE as f64;
+    let cosines = (0..DCT_KEPT)
+        .map(|u| {
+            (0..DCT_SIZE)
+                .map(|x| ((2 * x + 1) as f64 * u as f64 * std::f64::consts::PI / (2.0 * n)).cos())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    // Rows first, only the kept frequencies are needed
+    let rows = (0..DCT_SIZE)
+        .map(|y| {
+            (0..DCT_KEPT)
+                .map(|u| (0..DCT_SIZE).map(|x| pixels(x, y) * cosines[u][x]).sum())
+                .collect::<Vec<f64>>()
+        })
+        .collect::<Vec<_>>();
+    let mut coefficients = Vec::with_capacity(DCT_KEPT * DCT_KEPT);
+    for column in &cosines {
+        for u in 0..DCT_KEPT {
+            coefficients.push(
+                rows.iter()
+                    .zip(column)
+                    .map(|(row, cosine)| row[u] * cosine)
+                    .sum::<f64>(),
+            );
+        }
+    }
+    let mut sorted = coefficients[1..].to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let median = sorted[sorted.len() / 2];
+    coefficients
+        .iter()
+        .fold(0, |hash, c| hash << 1 | u64::from(*c > median))
+}
+
+/// Hashes of a decoded, upright picture
+pub fn hash(image: &RgbImage) -> PictureHash {
+    let gray = imageops::grayscale(image);
+    PictureHash {
+        difference: difference_hash(&gray),
+        dct: dct_hash(&gray),
+    }
+}
+
+/// Photo stand-in with large shapes as a JPEG, `variant` picks another
+/// scene and `quality` how it is compressed
+#[cfg(test)]
+pub fn test_photo(variant: u32, quality: u8) -> Vec<u8> {
+    let (width, height) = (400, 300);
+    let scene = RgbImage::from_fn(width, height, |x, y| {
+        let (fx, fy) = (x as f64 / width as f64, y as f64 / height as f64);
+        let value = match variant {
+            0 => (fx * 6.0).sin() * (fy * 4.0).cos(),
+            _ => ((fx - 0.3).powi(2) + (fy - 0.6).powi(2)).sqrt() * 5.0 - 1.0,
+        };
+        let level = ((value + 1.0) * 127.0).clamp(0.0, 255.0) as u8;
+        image::Rgb([level, level / 2, 255 - level])
+    });
+    let mut bytes = std::io::Cursor::new(Vec::new());
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut bytes, quality)
+        .encode_image(&scene)
+        .unwrap();
+    bytes.into_inner()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::redact::decode;
+
+    #[test]
+    fn same_photo_saved_again() {
+        let original = decode(&test_photo(0, 90)).unwrap();
+        let hashed = hash(&original);
+        assert_eq!(hashed.distance(&hashed), 0);
+        let recompressed = decode(&test_photo(0, 40)).unwrap();
+        assert!(hashed.distance(&hash(&recompressed)) <= MAX_DISTANCE);
+        let scaled = imageops::resize(&original, 160, 120, FilterType::Triangle);
+        assert!(hashed.distance(&hash(&scaled)) <= MAX_DISTANCE);
+    }
+
+    #[test]
+    fn other_photo() {
+        let first = hash(&decode(&test_photo(0, 90)).unwrap());
+        let second = hash(&decode(&test_photo(1, 90)).unwrap());
+        assert!(first.distance(&second) > MAX_DISTANCE);
+    }
+}
@@ -0,0 +1,402 @@
+//! Anonymization of pictures before they are published or forwarded.
+//!
+//! Faces and plates of vehicles other than the reported one are found with
+//! classical detectors on the CPU: faces as skin coloured blobs with holes
+//! for eyes and mouth, plates as light boxes dense in vertical character
+//! strokes. Both rather blur too much than too little, reporters can add
+//! rectangles for anything they miss. The original is kept unchanged and
+//! only shown to its uploader and users allowed to view originals.
+
+use common::types::picture::{BlurRegion, RegionKind};
+use image::codecs::jpeg::{JpegDecoder, JpegEncoder};
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, ImageDecoder, ImageResult, RgbImage};
+use std::io::Cursor;
+
+/// Pictures are scanned at most this large, detection doesn't need details
+const DETECTION_EDGE: u32 = 640;
+/// Brightness step between neighbours of a character stroke
+const STROKE_CONTRAST: i16 = 60;
+/// Quality of the published copy
+const QUALITY: u8 = 85;
+
+/// Bounding box of a connected set of pixels
+#[derive(Clone, Copy, Debug)]
+struct Component {
+    left: u32,
+    top: u32,
+    right: u32,
+    bottom: u32,
+    pixels: u32,
+}
+
+impl Component {
+    fn width(&self) -> u32 {
+        self.right - self.left + 1
+    }
+
+    fn height(&self) -> u32 {
+        self.bottom - self.top + 1
+    }
+
+    /// Share of the box covered by the component
+    fn fill(&self) -> f64 {
+        self.pixels as f64 / (self.width() * self.height()) as f64
+    }
+
+    /// The box grown by `margin` of its size on every side, in fractions of the picture
+    fn region(&self, kind: RegionKind, width: u32, height: u32, margin: f64) -> BlurRegion {
+        let (w, h) = (self.width() as f64, self.height() as f64);
+        BlurRegion {
+            kind,
+            x: (self.left as f64 - w * margin) / width as f64,
+            y: (self.top as f64 - h * margin) / height as f64,
+            width: w * (1.0 + 2.0 * margin) / width as f64,
+            height: h * (1.0 + 2.0 * margin) / height as f64,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Mask {
+    width: u32,
+    height: u32,
+    bits: Vec<bool>,
+}
+
+impl Mask {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            bits: vec![false; (width * height) as usize],
+        }
+    }
+
+    fn get(&self, x: u32, y: u32) -> bool {
+        self.bits[(y * self.width + x) as usize]
+    }
+
+    fn set(&mut self, x: u32, y: u32) {
+        self.bits[(y * self.width + x) as usize] = true;
+    }
+
+    /// Set pixels inside a box
+    fn count(&self, c: &Component) -> u32 {
+        (c.top..=c.bottom)
+            .flat_map(|y| (c.left..=c.right).map(move |x| (x, y)))
+            .filter(|(x, y)| self.get(*x, *y))
+            .count() as u32
+    }
+
+    /// 4-connected components of set pixels
+    fn components(&self) -> Vec<Component> {
+        let mut seen = vec![false; self.bits.len()];
+        let mut components = Vec::new();
+        let mut stack = Vec::new();
+        for start in 0..self.bits.len() {
+            if !self.bits[start] || seen[start] {
+                continue;
+            }
+            let (x, y) = (start as u32 % self.width, start as u32 / self.width);
+            let mut c = Component {
+                left: x,
+                top: y,
+                right: x,
+                bottom: y,
+                pixels: 0,
+            };
+            seen[start] = true;
+            stack.push((x, y));
+            while let Some((x, y)) = stack.pop() {
+                c.pixels += 1;
+                c.left = c.left.min(x);
+                c.right = c.right.max(x);
+                c.top = c.top.min(y);
+                c.bottom = c.bottom.max(y);
+                let neighbours = [
+                    (x.wrapping_sub(1), y),
+                    (x + 1, y),
+                    (x, y.wrapping_sub(1)),
+                    (x, y + 1),
+                ];
+                for (nx, ny) in neighbours {
+                    if nx < self.width && ny < self.height {
+                        let i = (ny * self.width + nx) as usize;
+                        if self.bits[i] && !seen[i] {
+                            seen[i] = true;
+                            stack.push((nx, ny));
+                        }
+                    }
+                }
+            }
+            components.push(c);
+        }
+        components
+    }
+}
+
+/// Skin tone in YCbCr, thresholds after Chai and Ngan
+fn is_skin([r, g, b]: [u8; 3]) -> bool {
+    let (r, g, b) = (r as f64, g as f64, b as f64);
+    let luma = 0.299 * r + 0.587 * g + 0.114 * b;
+    let cb = 128.0 - 0.168736 * r - 0.331264 * g + 0.5 * b;
+    let cr = 128.0 + 0.5 * r - 0.418688 * g - 0.081312 * b;
+    luma > 60.0 && (77.0..=127.0).contains(&cb) && (133.0..=173.0).contains(&cr)
+}
+
+/// Skin coloured blobs shaped like a face
+pub fn faces(image: &RgbImage) -> Vec<BlurRegion> {
+    let (width, height) = image.dimensions();
+    let mut skin = Mask::new(width, height);
+    for (x, y, pixel) in image.enumerate_pixels() {
+        if is_skin(pixel.0) {
+            skin.set(x, y);
+        }
+    }
+    let total = (width * height) as f64;
+    skin.components()
+        .into_iter()
+        .filter(|c| {
+            let share = (c.width() * c.height()) as f64 / total;
+            let ratio = c.height() as f64 / c.width() as f64;
+            // Eyes and mouth leave holes, a patch of wall or paint doesn't
+            c.width() >= 8
+                && (0.002..=0.25).contains(&share)
+                && (0.9..=2.0).contains(&ratio)
+                && (0.45..=0.9).contains(&c.fill())
+        })
+        .map(|c| c.region(RegionKind::Face, width, height, 0.2))
+        .collect()
+}
+
+/// Light boxes full of vertical character strokes
+pub fn plates(image: &RgbImage) -> Vec<BlurRegion> {
+    let gray = imageops::grayscale(image);
+    let (width, height) = gray.dimensions();
+    if width < 3 {
+        return Vec::new();
+    }
+    let mut strokes = Mask::new(width, height);
+    for y in 0..height {
+        for x in 1..width - 1 {
+            let step = gray.get_pixel(x + 1, y)[0] as i16 - gray.get_pixel(x - 1, y)[0] as i16;
+            if step.abs() > STROKE_CONTRAST {
+                strokes.set(x, y);
+            }
+        }
+    }
+    // Join the strokes of one plate into a solid block
+    let gap = (width / 60).max(3);
+    let mut joined = strokes.clone();
+    for y in 0..height {
+        let mut last = None;
+        for x in 0..width {
+            if strokes.get(x, y) {
+                if let Some(last) = last.filter(|l| x - l <= gap) {
+                    (last..x).for_each(|i| joined.set(i, y));
+                }
+                last = Some(x);
+            }
+        }
+    }
+    joined
+        .components()
+        .into_iter()
+        .filter(|c| {
+            let ratio = c.width() as f64 / c.height() as f64;
+            let share = c.width() as f64 / width as f64;
+            let area = (c.width() * c.height()) as f64;
+            let brightness = (c.top..=c.bottom)
+                .flat_map(|y| (c.left..=c.right).map(move |x| (x, y)))
+                .map(|(x, y)| gray.get_pixel(x, y)[0] as f64)
+                .sum::<f64>()
+                / area;
+            c.height() >= 6
+                && (2.0..=7.0).contains(&ratio)
+                && (0.03..=0.5).contains(&share)
+                && c.fill() >= 0.5
+                && strokes.count(c) as f64 / area >= 0.15
+                && brightness >= 90.0
+        })
+        .map(|c| c.region(RegionKind::Plate, width, height, 0.15))
+        .collect()
+}
+
+/// The reported vehicle is assumed to be the subject of the photo, so the
+/// largest plate stays readable and the others are blurred
+fn bystanders(mut plates: Vec<BlurRegion>) -> Vec<BlurRegion> {
+    let largest = plates
+        .iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| (a.width * a.height).total_cmp(&(b.width * b.height)))
+        .map(|(i, _)| i);
+    if let Some(i) = largest {
+        plates.remove(i);
+    }
+    plates
+}
+
+/// Regions to blur in an upright picture
+pub fn detect(image: &RgbImage) -> Vec<BlurRegion> {
+    let scaled = if image.width().max(image.height()) > DETECTION_EDGE {
+        DynamicImage::ImageRgb8(image.clone())
+            .resize(DETECTION_EDGE, DETECTION_EDGE, FilterType::Triangle)
+            .to_rgb8()
+    } else {
+        image.clone()
+    };
+    let mut regions = faces(&scaled);
+    regions.extend(bystanders(plates(&scaled)));
+    regions
+}
+
+/// Decode a JPEG and turn it upright according to its EXIF orientation
+pub fn decode(bytes: &[u8]) -> ImageResult<RgbImage> {
+    let mut decoder = JpegDecoder::new(Cursor::new(bytes))?;
+    let orientation = decoder.orientation()?;
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+    Ok(image.to_rgb8())
+}
+
+/// Blur the regions beyond recognition
+pub fn blur(image: &mut RgbImage, regions: &[BlurRegion]) {
+    let (width, height) = image.dimensions();
+    for (x, y, w, h) in regions.iter().filter_map(|r| r.to_pixels(width, height)) {
+        let area = imageops::crop_imm(image, x, y, w, h).to_image();
+        // Pixelate first, a blur on its own can partly be reverted
+        let block = (w.min(h) / 8).max(1);
+        let small = imageops::resize(
+            &area,
+            (w / block).max(1),
+            (h / block).max(1),
+            FilterType::Triangle,
+        );
+        let pixelated = imageops::resize(&small, w, h, FilterType::Nearest);
+        let blurred = imageops::fast_blur(&pixelated, block as f32);
+        imageops::replace(image, &blurred, x as i64, y as i64);
+    }
+}
+
+/// JPEG without any metadata with the regions blurred
+pub fn redact(image: &RgbImage, regions: &[BlurRegion]) -> ImageResult<Vec<u8>> {
+    let mut image = image.clone();
+    blur(&mut image, regions);
+    let mut bytes = Vec::new();
+    JpegEncoder::new_with_quality(&mut bytes, QUALITY).encode_image(&image)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    const BACKGROUND: Rgb<u8> = Rgb([70, 90, 110]);
+
+    fn fill(image: &mut RgbImage, x: u32, y: u32, w: u32, h: u32, color: Rgb<u8>) {
+        for py in y..y + h {
+            for px in x..x + w {
+                image.put_pixel(px, py, color);
+            }
+        }
+    }
+
+    /// Skin coloured ellipse with dark eyes and mouth
+    fn draw_face(image: &mut RgbImage, cx: u32, cy: u32, rx: u32, ry: u32) {
+        for y in cy - ry..=cy + ry {
+            for x in cx - rx..=cx + rx {
+                let (dx, dy) = (
+                    (x as f64 - cx as f64) / rx as f64,
+                    (y as f64 - cy as f64) / ry as f64,
+                );
+                if dx * dx + dy * dy <= 1.0 {
+                    image.put_pixel(x, y, Rgb([224, 172, 140]));
+                }
+            }
+        }
+        let dark = Rgb([40, 30, 30]);
+        fill(image, cx - rx / 2, cy - ry / 3, rx / 4, ry / 6, dark);
+        fill(image, cx + rx / 4, cy - ry / 3, rx / 4, ry / 6, dark);
+        fill(image, cx - rx / 3, cy + ry / 3, rx * 2 / 3, ry / 8, dark);
+    }
+
+    /// White plate with black character strokes
+    fn draw_plate(image: &mut RgbImage, x: u32, y: u32, w: u32) {
+        let h = w * 2 / 9;
+        fill(image, x, y, w, h, Rgb([235, 235, 235]));
+        let stroke = (w / 40).max(1);
+        let mut sx = x + w / 12;
+        while sx + stroke < x + w - w / 12 {
+            fill(image, sx, y + h / 6, stroke, h * 2 / 3, Rgb([20, 20, 20]));
+            sx += stroke * 3;
+        }
+    }
+
+    fn contains(region: &BlurRegion, x: f64, y: f64) -> bool {
+        (region.x..=region.x + region.width).contains(&x)
+            && (region.y..=region.y + region.height).contains(&y)
+    }
+
+    #[test]
+    fn finds_faces() {
+        let mut image = RgbImage::from_pixel(400, 300, BACKGROUND);
+        draw_face(&mut image, 100, 120, 30, 40);
+        // A skin coloured wall without holes is not a face
+        fill(&mut image, 250, 50, 100, 100, Rgb([224, 172, 140]));
+        let faces = faces(&image);
+        assert_eq!(faces.len(), 1, "{faces:?}");
+        assert_eq!(faces[0].kind, RegionKind::Face);
+        assert!(contains(&faces[0], 0.25, 0.4));
+        assert!(!contains(&faces[0], 0.75, 0.3));
+    }
+
+    #[test]
+    fn blurs_bystander_plates() {
+        let mut image = RgbImage::from_pixel(600, 400, BACKGROUND);
+        draw_plate(&mut image, 200, 250, 180);
+        draw_plate(&mut image, 40, 60, 90);
+        assert_eq!(plates(&image).len(), 2);
+        let regions = detect(&image);
+        assert_eq!(regions.len(), 1, "{regions:?}");
+        assert_eq!(regions[0].kind, RegionKind::Plate);
+        // The small plate is blurred, the reported one stays readable
+        assert!(contains(&regions[0], 85.0 / 600.0, 70.0 / 400.0));
+        assert!(!contains(&regions[0], 290.0 / 600.0, 270.0 / 400.0));
+    }
+
+    #[test]
+    fn plain_picture_has_nothing_to_blur() {
+        let image = RgbImage::from_fn(200, 150, |x, y| Rgb([(x / 2) as u8, (y / 2) as u8, 90]));
+        assert!(detect(&image).is_empty());
+    }
+
+    #[test]
+    fn blur_only_touches_regions() {
+        let mut image = RgbImage::new(100, 100);
+        draw_plate(&mut image, 10, 10, 80);
+        let original = image.clone();
+        let region = BlurRegion {
+            kind: RegionKind::Manual,
+            x: 0.0,
+            y: 0.0,
+            width: 1.0,
+            height: 0.5,
+        };
+        blur(&mut image, &[region]);
+        let variance = |image: &RgbImage| {
+            let values = (10..90)
+                .map(|x| image.get_pixel(x, 20)[0] as f64)
+                .collect::<Vec<_>>();
+            let mean = values.iter().sum::<f64>() / values.len() as f64;
+            values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64
+        };
+        assert!(variance(&image) < variance(&original) / 10.0);
+        assert_eq!(image.get_pixel(50, 80), original.get_pixel(50, 80));
+
+        let jpeg = redact(&original, &[region]).unwrap();
+        assert_eq!(decode(&jpeg).unwrap().dimensions(), (100, 100));
+        assert_eq!(common::photo::metadata(&jpeg), Default::default());
+    }
+}
@@ -0,0 +1,77 @@
+//! Data every installation needs: the known permissions, the `admin` role
+//! and optionally a first administrator from the `[admin]` settings.
+
+use crate::auth::hash_password;
+use crate::db::{Db, StorageError};
+use crate::permissions;
+use crate::settings;
+use thiserror::Error as ThisError;
+use tracing::info;
+
+/// Role granting every permission
+pub const ADMIN_ROLE: &str = "admin";
+
+#[derive(ThisError, Debug)]
+pub enum SeedError {
+    #[error("{0}")]
+    Storage(#[from] StorageError),
+
+    #[error("Failed to hash the admin password: {0}")]
+    Password(String),
+}
+
+/// Insert missing seed data, existing rows are left untouched
+pub fn run(db: &Db, admin: Option<&settings::Admin>) -> Result<(), SeedError> {
+    for (name, description) in permissions::ALL {
+        db.ensure_permission(name, description)?;
+    }
+    let role = match db.find_role_by_name(ADMIN_ROLE)? {
+        Some(role) => role,
+        None => {
+            info!("Creating the {ADMIN_ROLE} role");
+            db.create_role(
+                ADMIN_ROLE,
+                "Full access",
+                &[permissions::SUPER_ADMIN.to_string()],
+            )?
+        }
+    };
+    let Some(admin) = admin else {
+        return Ok(());
+    };
+    if db.find_user_by_username(&admin.username)?.is_some() {
+        return Ok(());
+    }
+    let hash = hash_password(&admin.password).map_err(|e| SeedError::Password(e.to_string()))?;
+    let user = db.create_user(&admin.username, &hash, &admin.email)?;
+    for email in db.list_emails(user.id)? {
+        db.verify_email(email.id)?;
+    }
+    db.set_user_roles(user.id, &[role.id])?;
+    info!("Created administrator {}", user.username);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    #[test]
+    fn seed_admin() {
+        let db = test_db().into_handle();
+        let admin = settings::Admin {
+            username: "admin".to_string(),
+            email: "admin@example.com".to_string(),
+            password: "long enough".to_string(),
+        };
+        run(&db, Some(&admin)).unwrap();
+        run(&db, Some(&admin)).unwrap();
+
+        assert_eq!(db.list_permissions().unwrap().len(), permissions::ALL.len());
+        let user = db.find_user_by_username("admin").unwrap().unwrap();
+        assert_eq!(db.user_permissions(user.id).unwrap(), vec!["*"]);
+        assert!(db.list_emails(user.id).unwrap()[0].verified);
+        assert_eq!(db.list_users().unwrap().len(), 1);
+    }
+}
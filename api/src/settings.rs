@@ -0,0 +1,554 @@
+//! Server configuration loaded from a TOML file with environment overrides.
+//!
+//! Values are read from the file given by `--config` or `CARREPORTER_CONFIG`
+//! (defaults to `config.toml`) and can be overridden by `CARREPORTER_<SECTION>__<KEY>`
+//! environment variables, e.g. `CARREPORTER_DATABASE__URL`.
+
+use crate::types::auth::RegisterInfo;
+use crate::types::Validate;
+use config::{Config, Environment, File, FileFormat};
+use serde::Deserialize;
+use std::path::PathBuf;
+use thiserror::Error as ThisError;
+
+pub const CONFIG_ENV: &str = "CARREPORTER_CONFIG";
+const ENV_PREFIX: &str = "CARREPORTER";
+
+/// Errors that prevent the configuration from being used
+#[derive(ThisError, Debug)]
+pub enum SettingsError {
+    #[error("Failed to load configuration: {0}")]
+    Load(#[from] config::ConfigError),
+
+    #[error("Invalid configuration value `{0}`: {1}")]
+    Invalid(&'static str, String),
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Settings {
+    pub server: Server,
+    pub database: Database,
+    pub mail: Mail,
+    pub smtp: Option<Smtp>,
+    pub uploads: Uploads,
+    pub geocoding: Geocoding,
+    pub moderation: Moderation,
+    pub duplicates: Duplicates,
+    #[serde(default)]
+    pub documents: Documents,
+    #[serde(default)]
+    pub forwarding: Forwarding,
+    pub cors: Cors,
+    pub auth: Auth,
+    pub admin: Option<Admin>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Server {
+    pub address: String,
+    pub port: u16,
+    /// Address of the frontend, used for links in emails
+    pub public_url: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Database {
+    pub url: String,
+    pub pool_size: u32,
+}
+
+/// How outgoing email is delivered
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MailTransport {
+    /// Through the server in the `[smtp]` section
+    Smtp,
+    /// Written as `.eml` files into `mail.directory`, for development and tests
+    File,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Mail {
+    pub transport: MailTransport,
+    pub from: String,
+    pub directory: PathBuf,
+    /// How long an email confirmation code stays valid
+    pub code_lifetime_hours: i64,
+    /// Minimal delay between two confirmation emails to the same address
+    pub resend_interval_minutes: i64,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Smtp {
+    pub host: String,
+    pub port: Option<u16>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    #[serde(default = "default_tls")]
+    pub tls: bool,
+}
+
+const fn default_tls() -> bool {
+    true
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Uploads {
+    pub directory: PathBuf,
+    /// Largest accepted picture in bytes
+    pub max_file_size: usize,
+    /// Most pictures accepted by one upload request
+    pub max_files: usize,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Geocoding {
+    /// Address points loaded at startup, geocoding finds nothing without them
+    pub dataset: Option<PathBuf>,
+    /// Farthest address point a position is resolved to, in meters
+    pub max_distance_meters: f64,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Moderation {
+    /// Reasons a moderator picks from when rejecting a report
+    pub rejection_reasons: Vec<String>,
+}
+
+/// When a new report is taken for another one of the same car
+#[derive(Deserialize, Clone, Debug)]
+pub struct Duplicates {
+    /// Farthest apart two reports of a plate are at the same spot
+    pub distance_meters: f64,
+    /// Longest time between two observations of the same offence
+    pub window_minutes: i64,
+    /// How far back moderators see the reports of a plate
+    pub history_days: i64,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct Documents {
+    /// Complaint templates named after the municipality, the built-in one is
+    /// used without them
+    pub templates: Option<PathBuf>,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct Forwarding {
+    /// Where reports are emailed to, picked by the position of the report
+    #[serde(default)]
+    pub authorities: Vec<Authority>,
+    /// Mailbox receiving the replies, piped from there to `POST /mail/inbound`
+    pub reply_to: Option<String>,
+    /// Bearer token of `POST /mail/inbound`, inbound mail is refused without it
+    pub inbound_secret: Option<String>,
+}
+
+/// Authority in charge of an area
+#[derive(Deserialize, Clone, Debug)]
+pub struct Authority {
+    pub name: String,
+    pub email: String,
+    /// Cities or city districts such as `Praha 2`, matched against the
+    /// resolved address when no polygon contains the report
+    #[serde(default)]
+    pub districts: Vec<String>,
+    /// Area as `[latitude, longitude]` corners
+    #[serde(default)]
+    pub polygon: Vec<[f64; 2]>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Cors {
+    pub origins: Vec<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Auth {
+    /// Secret used to sign JWT tokens
+    pub jwt_secret: String,
+    pub token_lifetime_hours: i64,
+}
+
+/// First administrator, created on startup when no user has this name yet
+#[derive(Deserialize, Clone, Debug)]
+pub struct Admin {
+    pub username: String,
+    pub email: String,
+    pub password: String,
+}
+
+impl Settings {
+    /// Load the configuration from a file, missing files are allowed, and the process environment
+    pub fn load(path: &str) -> Result<Self, SettingsError> {
+        let builder = Self::defaults()?
+            .add_source(
+                File::with_name(path)
+                    .format(FileFormat::Toml)
+                    .required(false),
+            )
+            .add_source(Self::environment());
+        Self::build(builder)
+    }
+
+    /// Parse the configuration from a TOML string
+    #[cfg(test)]
+    pub fn from_toml(toml: &str) -> Result<Self, SettingsError> {
+        let builder = Self::defaults()?.add_source(File::from_str(toml, FileFormat::Toml));
+        Self::build(builder)
+    }
+
+    fn defaults() -> Result<config::ConfigBuilder<config::builder::DefaultState>, SettingsError> {
+        Ok(Config::builder()
+            .set_default("server.address", "127.0.0.1")?
+            .set_default("server.port", 8081)?
+            .set_default("server.public_url", "http://localhost:8080")?
+            .set_default("database.pool_size", 8)?
+            .set_default("mail.transport", "smtp")?
+            .set_default("mail.directory", "mail")?
+            .set_default("mail.code_lifetime_hours", 48)?
+            .set_default("mail.resend_interval_minutes", 5)?
+            .set_default("uploads.directory", "uploads")?
+            .set_default("uploads.max_file_size", 10 * 1024 * 1024)?
+            .set_default("uploads.max_files", 10)?
+            .set_default("geocoding.max_distance_meters", 100.0)?
+            .set_default(
+                "moderation.rejection_reasons",
+                vec![
+                    "Insufficient evidence",
+                    "Plate not readable",
+                    "Not a traffic offence",
+                    "Duplicate report",
+                ],
+            )?
+            .set_default("duplicates.distance_meters", 50.0)?
+            .set_default("duplicates.window_minutes", 60)?
+            .set_default("duplicates.history_days", 30)?
+            .set_default("cors.origins", vec!["*"])?
+            .set_default("auth.token_lifetime_hours", 24 * 7)?)
+    }
+
+    fn environment() -> Environment {
+        Environment::with_prefix(ENV_PREFIX)
+            .prefix_separator("_")
+            .separator("__")
+            .try_parsing(true)
+            .list_separator(",")
+            .with_list_parse_key("cors.origins")
+            .with_list_parse_key("moderation.rejection_reasons")
+    }
+
+    fn build(
+        builder: config::ConfigBuilder<config::builder::DefaultState>,
+    ) -> Result<Self, SettingsError> {
+        let settings: Self = builder.build()?.try_deserialize()?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    fn validate(&self) -> Result<(), SettingsError> {
+        if self.server.address.trim().is_empty() {
+            return Err(SettingsError::Invalid(
+                "server.address",
+                "must not be empty".to_string(),
+            ));
+        }
+        if self.database.url.trim().is_empty() {
+            return Err(SettingsError::Invalid(
+                "database.url",
+                "must not be empty".to_string(),
+            ));
+        }
+        if self.database.pool_size == 0 {
+            return Err(SettingsError::Invalid(
+                "database.pool_size",
+                "must be at least 1".to_string(),
+            ));
+        }
+        if self.uploads.max_file_size == 0 || self.uploads.max_files == 0 {
+            return Err(SettingsError::Invalid(
+                "uploads",
+                "max_file_size and max_files must be positive".to_string(),
+            ));
+        }
+        let max_distance = self.geocoding.max_distance_meters;
+        if !max_distance.is_finite() || max_distance <= 0.0 {
+            return Err(SettingsError::Invalid(
+                "geocoding.max_distance_meters",
+                "must be positive".to_string(),
+            ));
+        }
+        let reasons = &self.moderation.rejection_reasons;
+        if reasons.is_empty() || reasons.iter().any(|r| r.trim().is_empty()) {
+            return Err(SettingsError::Invalid(
+                "moderation.rejection_reasons",
+                "must list at least one reason and no empty ones".to_string(),
+            ));
+        }
+        let duplicates = &self.duplicates;
+        if !duplicates.distance_meters.is_finite() || duplicates.distance_meters <= 0.0 {
+            return Err(SettingsError::Invalid(
+                "duplicates.distance_meters",
+                "must be positive".to_string(),
+            ));
+        }
+        if duplicates.window_minutes <= 0 || duplicates.history_days <= 0 {
+            return Err(SettingsError::Invalid(
+                "duplicates",
+                "window_minutes and history_days must be positive".to_string(),
+            ));
+        }
+        if self.mail.from.parse::<lettre::message::Mailbox>().is_err() {
+            return Err(SettingsError::Invalid(
+                "mail.from",
+                format!("`{}` is not a valid mailbox", self.mail.from),
+            ));
+        }
+        if self.mail.code_lifetime_hours <= 0 {
+            return Err(SettingsError::Invalid(
+                "mail.code_lifetime_hours",
+                "must be positive".to_string(),
+            ));
+        }
+        if self.mail.resend_interval_minutes < 0 {
+            return Err(SettingsError::Invalid(
+                "mail.resend_interval_minutes",
+                "must not be negative".to_string(),
+            ));
+        }
+        if self.mail.transport == MailTransport::Smtp {
+            let Some(smtp) = &self.smtp else {
+                return Err(SettingsError::Invalid(
+                    "smtp",
+                    "section is required for the smtp mail transport".to_string(),
+                ));
+            };
+            if smtp.host.trim().is_empty() {
+                return Err(SettingsError::Invalid(
+                    "smtp.host",
+                    "must not be empty".to_string(),
+                ));
+            }
+            if smtp.username.is_some() != smtp.password.is_some() {
+                return Err(SettingsError::Invalid(
+                    "smtp.username",
+                    "username and password must be set together".to_string(),
+                ));
+            }
+        }
+        for authority in &self.forwarding.authorities {
+            if authority.email.parse::<lettre::message::Mailbox>().is_err() {
+                return Err(SettingsError::Invalid(
+                    "forwarding.authorities",
+                    format!("`{}` is not a valid mailbox", authority.email),
+                ));
+            }
+            if authority.districts.is_empty() && authority.polygon.is_empty() {
+                return Err(SettingsError::Invalid(
+                    "forwarding.authorities",
+                    format!("`{}` needs districts or a polygon", authority.name),
+                ));
+            }
+            if !authority.polygon.is_empty() && authority.polygon.len() < 3 {
+                return Err(SettingsError::Invalid(
+                    "forwarding.authorities",
+                    format!(
+                        "the polygon of `{}` needs at least 3 corners",
+                        authority.name
+                    ),
+                ));
+            }
+        }
+        if let Some(reply_to) = &self.forwarding.reply_to {
+            if reply_to.parse::<lettre::message::Mailbox>().is_err() {
+                return Err(SettingsError::Invalid(
+                    "forwarding.reply_to",
+                    format!("`{reply_to}` is not a valid mailbox"),
+                ));
+            }
+        }
+        if self
+            .forwarding
+            .inbound_secret
+            .as_ref()
+            .is_some_and(|s| s.len() < 32)
+        {
+            return Err(SettingsError::Invalid(
+                "forwarding.inbound_secret",
+                "must be at least 32 characters long".to_string(),
+            ));
+        }
+        if self.auth.jwt_secret.len() < 32 {
+            return Err(SettingsError::Invalid(
+                "auth.jwt_secret",
+                "must be at least 32 characters long".to_string(),
+            ));
+        }
+        if self.auth.token_lifetime_hours <= 0 {
+            return Err(SettingsError::Invalid(
+                "auth.token_lifetime_hours",
+                "must be positive".to_string(),
+            ));
+        }
+        if let Some(admin) = &self.admin {
+            let info = RegisterInfo {
+                username: admin.username.clone(),
+                password: admin.password.clone(),
+                email: admin.email.clone(),
+            };
+            if let Err(errors) = info.validate() {
+                let mut messages = errors.errors.into_values().flatten().collect::<Vec<_>>();
+                messages.sort();
+                return Err(SettingsError::Invalid("admin", messages.join(", ")));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINIMAL: &str = r#"
+        [database]
+        url = "carreporter.db"
+
+        [mail]
+        from = "Car Reporter <noreply@example.com>"
+
+        [smtp]
+        host = "localhost"
+
+        [auth]
+        jwt_secret = "0123456789abcdef0123456789abcdef"
+    "#;
+
+    #[test]
+    fn defaults_applied() {
+        let settings = Settings::from_toml(MINIMAL).unwrap();
+        assert_eq!(settings.server.address, "127.0.0.1");
+        assert_eq!(settings.server.port, 8081);
+        assert_eq!(settings.database.pool_size, 8);
+        assert_eq!(settings.uploads.directory, PathBuf::from("uploads"));
+        assert_eq!(settings.geocoding.dataset, None);
+        assert_eq!(settings.geocoding.max_distance_meters, 100.0);
+        assert_eq!(
+            settings.moderation.rejection_reasons[0],
+            "Insufficient evidence"
+        );
+        assert_eq!(settings.duplicates.distance_meters, 50.0);
+        assert_eq!(settings.duplicates.window_minutes, 60);
+        assert_eq!(settings.cors.origins, vec!["*".to_string()]);
+    }
+
+    #[test]
+    fn missing_database() {
+        let toml = MINIMAL.replace("[database]\n        url = \"carreporter.db\"", "");
+        let error = Settings::from_toml(&toml).unwrap_err();
+        assert!(format!("{error}").contains("database"))
+    }
+
+    #[test]
+    fn invalid_from() {
+        let toml = MINIMAL.replace("Car Reporter <noreply@example.com>", "nobody");
+        let error = Settings::from_toml(&toml).unwrap_err();
+        assert!(matches!(error, SettingsError::Invalid("mail.from", _)))
+    }
+
+    #[test]
+    fn smtp_required() {
+        let toml = MINIMAL.replace("[smtp]\n        host = \"localhost\"", "");
+        let error = Settings::from_toml(&toml).unwrap_err();
+        assert!(matches!(error, SettingsError::Invalid("smtp", _)));
+
+        let toml = toml.replace("[mail]", "[mail]\ntransport = \"file\"");
+        let settings = Settings::from_toml(&toml).unwrap();
+        assert_eq!(settings.mail.transport, MailTransport::File);
+        assert!(settings.smtp.is_none());
+    }
+
+    #[test]
+    fn short_secret() {
+        let toml = MINIMAL.replace("0123456789abcdef0123456789abcdef", "short");
+        let error = Settings::from_toml(&toml).unwrap_err();
+        assert!(matches!(
+            error,
+            SettingsError::Invalid("auth.jwt_secret", _)
+        ))
+    }
+
+    #[test]
+    fn invalid_admin() {
+        let toml = format!(
+            "{MINIMAL}\n[admin]\nusername = \"admin\"\nemail = \"admin@example.com\"\npassword = \"short\""
+        );
+        let error = Settings::from_toml(&toml).unwrap_err();
+        assert!(matches!(error, SettingsError::Invalid("admin", _)));
+
+        let toml = toml.replace("\"short\"", "\"long enough\"");
+        let settings = Settings::from_toml(&toml).unwrap();
+        assert_eq!(settings.admin.unwrap().username, "admin");
+    }
+
+    #[test]
+    fn authorities() {
+        let toml = format!(
+            r#"{MINIMAL}
+            [forwarding]
+            reply_to = "reports@example.com"
+
+            [[forwarding.authorities]]
+            name = "Městská policie Praha"
+            email = "podatelna@mppraha.cz"
+            districts = ["Praha 2", "Praha 3"]
+
+            [[forwarding.authorities]]
+            name = "Městská policie Brno"
+            email = "mpb@mpb.cz"
+            polygon = [[49.1, 16.5], [49.3, 16.5], [49.3, 16.7]]
+            "#
+        );
+        let settings = Settings::from_toml(&toml).unwrap();
+        let authorities = &settings.forwarding.authorities;
+        assert_eq!(authorities[0].districts, vec!["Praha 2", "Praha 3"]);
+        assert_eq!(authorities[1].polygon[1], [49.3, 16.5]);
+        assert!(Settings::from_toml(MINIMAL)
+            .unwrap()
+            .forwarding
+            .authorities
+            .is_empty());
+
+        let error = Settings::from_toml(&toml.replace(", [49.3, 16.7]", "")).unwrap_err();
+        assert!(matches!(
+            error,
+            SettingsError::Invalid("forwarding.authorities", _)
+        ));
+        let error = Settings::from_toml(&toml.replace("mpb@mpb.cz", "mpb")).unwrap_err();
+        assert!(matches!(
+            error,
+            SettingsError::Invalid("forwarding.authorities", _)
+        ));
+    }
+
+    #[test]
+    fn environment_override() {
+        let env = std::collections::HashMap::from([
+            ("CARREPORTER_SERVER__PORT".to_string(), "9000".to_string()),
+            (
+                "CARREPORTER_CORS__ORIGINS".to_string(),
+                "http://a.test,http://b.test".to_string(),
+            ),
+        ]);
+        let builder = Settings::defaults()
+            .unwrap()
+            .add_source(File::from_str(MINIMAL, FileFormat::Toml))
+            .add_source(Settings::environment().source(Some(env)));
+        let settings = Settings::build(builder).unwrap();
+        assert_eq!(settings.server.port, 9000);
+        assert_eq!(
+            settings.cors.origins,
+            vec!["http://a.test".to_string(), "http://b.test".to_string()]
+        );
+    }
+}
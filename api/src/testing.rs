@@ -0,0 +1,212 @@
+//! Helpers shared by handler tests.
+
+use crate::auth::Tokens;
+use crate::db::Db;
+use crate::document::Templates;
+use crate::geocode;
+use crate::mailer::{self, MailerHandle, SmtpMailer};
+use crate::settings::{self, Settings};
+use actix_web::dev::{Service, ServiceResponse};
+use actix_web::web::{Data, ServiceConfig};
+use actix_web::{test, App};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+
+const CONFIG: &str = r#"
+    [database]
+    url = ":memory:"
+
+    [mail]
+    transport = "file"
+    from = "noreply@example.com"
+
+    [auth]
+    jwt_secret = "0123456789abcdef0123456789abcdef"
+"#;
+
+/// Test settings owning the temporary directory mail and uploads are written
+/// into, removed once the last clone is dropped
+#[derive(Clone)]
+pub struct TestSettings {
+    settings: Settings,
+    directory: Arc<TempDir>,
+}
+
+impl Deref for TestSettings {
+    type Target = Settings;
+
+    fn deref(&self) -> &Settings {
+        &self.settings
+    }
+}
+
+impl DerefMut for TestSettings {
+    fn deref_mut(&mut self) -> &mut Settings {
+        &mut self.settings
+    }
+}
+
+/// Test settings, mail and uploads go into a fresh temporary directory
+pub fn settings() -> TestSettings {
+    let directory = tempfile::Builder::new()
+        .prefix("carreporter-")
+        .tempdir()
+        .unwrap();
+    let mut settings = Settings::from_toml(CONFIG).unwrap();
+    settings.mail.directory = directory.path().join("mail");
+    settings.uploads.directory = directory.path().join("uploads");
+    TestSettings {
+        settings,
+        directory: Arc::new(directory),
+    }
+}
+
+pub fn mailer(settings: &Settings) -> MailerHandle {
+    mailer::from_settings(&settings.mail, settings.smtp.as_ref()).unwrap()
+}
+
+/// Local SMTP server standing in for a real relay, so tests send through
+/// [`SmtpMailer`] and see the raw messages it accepted
+pub struct SmtpStandIn {
+    port: u16,
+    messages: Arc<Mutex<Vec<String>>>,
+}
+
+impl SmtpStandIn {
+    /// Accept every message
+    pub fn start() -> Self {
+        Self::listen(false)
+    }
+
+    /// Refuse every recipient like a relay that does not know them
+    pub fn refusing() -> Self {
+        Self::listen(true)
+    }
+
+    fn listen(refuse: bool) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let captured = Arc::clone(&messages);
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let captured = Arc::clone(&captured);
+                std::thread::spawn(move || serve_smtp(stream, &captured, refuse));
+            }
+        });
+        Self { port, messages }
+    }
+
+    /// Mailer delivering to the stand-in
+    pub fn mailer(&self) -> MailerHandle {
+        let smtp = settings::Smtp {
+            host: "127.0.0.1".to_string(),
+            port: Some(self.port),
+            username: None,
+            password: None,
+            tls: false,
+        };
+        Arc::new(SmtpMailer::new(&smtp, "noreply@example.com".parse().unwrap()).unwrap())
+    }
+
+    /// Raw messages accepted so far
+    pub fn messages(&self) -> Vec<String> {
+        self.messages.lock().unwrap().clone()
+    }
+}
+
+/// Just enough of RFC 5321 for lettre
+fn serve_smtp(stream: TcpStream, captured: &Mutex<Vec<String>>, refuse: bool) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+    writer.write_all(b"220 localhost ESMTP stand-in\r\n")?;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        let command = line.trim_end().to_ascii_uppercase();
+        let reply: &[u8] = if command.starts_with("EHLO") {
+            b"250-localhost\r\n250 8BITMIME\r\n"
+        } else if command.starts_with("RCPT") && refuse {
+            b"550 5.1.1 No such user\r\n"
+        } else if command == "DATA" {
+            writer.write_all(b"354 End data with <CR><LF>.<CR><LF>\r\n")?;
+            let mut data = String::new();
+            loop {
+                line.clear();
+                if reader.read_line(&mut line)? == 0 || line == ".\r\n" {
+                    break;
+                }
+                // Undo the dot stuffing
+                data.push_str(line.strip_prefix('.').unwrap_or(&line));
+            }
+            captured.lock().unwrap().push(data);
+            b"250 2.0.0 Queued\r\n"
+        } else if command == "QUIT" {
+            writer.write_all(b"221 Bye\r\n")?;
+            return Ok(());
+        } else {
+            b"250 OK\r\n"
+        };
+        writer.write_all(reply)?;
+    }
+}
+
+/// Create a user granted `permissions` through a role of its own, returns
+/// the user id and a bearer token
+pub fn user_with_permissions(db: &Db, username: &str, permissions: &[&str]) -> (i64, String) {
+    let user = db
+        .create_user(username, "hash", &format!("{username}@example.com"))
+        .unwrap();
+    let names = permissions
+        .iter()
+        .map(|p| db.ensure_permission(p, "").unwrap().name)
+        .collect::<Vec<_>>();
+    let role = db
+        .create_role(&format!("{username}-role"), "", &names)
+        .unwrap();
+    db.set_user_roles(user.id, &[role.id]).unwrap();
+    let token = Tokens::new(&settings().auth).issue(user.id).unwrap();
+    (user.id, token)
+}
+
+/// Application with the same app data as the server, configured by `configure`
+pub async fn init(
+    db: Db,
+    configure: fn(&mut ServiceConfig),
+) -> impl Service<actix_http::Request, Response = ServiceResponse, Error = actix_web::Error> {
+    let settings = settings();
+    let mailer = mailer(&settings);
+    init_with(db, settings, mailer, configure).await
+}
+
+/// Like [`init`] with explicit settings and mailer
+pub async fn init_with(
+    db: Db,
+    settings: TestSettings,
+    mailer: MailerHandle,
+    configure: fn(&mut ServiceConfig),
+) -> impl Service<actix_http::Request, Response = ServiceResponse, Error = actix_web::Error> {
+    let TestSettings {
+        settings,
+        directory,
+    } = settings;
+    test::init_service(
+        App::new()
+            // Keeps the temporary directory as long as the application
+            .app_data(directory)
+            .app_data(Data::new(db))
+            .app_data(Data::new(Tokens::new(&settings.auth)))
+            .app_data(Data::new(mailer))
+            .app_data(Data::new(settings))
+            .app_data(Data::new(geocode::test_geocoder()))
+            .app_data(Data::new(Templates::default()))
+            .configure(configure),
+    )
+    .await
+}
@@ -0,0 +1,60 @@
+use crate::db;
+use crate::types::{ErrorInfo, Validate};
+
+pub use common::types::admin::*;
+
+impl From<db::Permission> for Permission {
+    fn from(p: db::Permission) -> Self {
+        Self {
+            id: p.id,
+            name: p.name,
+            description: p.description,
+        }
+    }
+}
+
+impl From<db::Role> for Role {
+    fn from(r: db::Role) -> Self {
+        Self {
+            id: r.id,
+            name: r.name,
+            description: r.description,
+            permissions: Some(r.permissions.into_iter().map(Permission::from).collect()),
+        }
+    }
+}
+
+impl Validate for RoleInfo {
+    fn validate(mut self) -> Result<Self, ErrorInfo> {
+        let mut errors = ErrorInfo::default();
+        self.name = self.name.trim().to_string();
+        if self.name.is_empty() {
+            errors.add("name", "Name is required");
+        }
+        self.description = self.description.trim().to_string();
+        errors.into_result().map(|()| self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn role_info_validate() {
+        let info = RoleInfo {
+            id: None,
+            name: " moderator ".to_string(),
+            description: String::new(),
+            permissions: HashSet::from(["b".to_string(), "a".to_string()]),
+        }
+        .validate()
+        .unwrap();
+        assert_eq!(info.name, "moderator");
+        assert_eq!(info.permission_list(), vec!["a", "b"]);
+
+        let errors = RoleInfo::default().validate().unwrap_err();
+        assert!(errors.errors.contains_key("name"));
+    }
+}
@@ -0,0 +1,68 @@
+use crate::types::{ErrorInfo, Validate};
+
+pub use common::types::auth::*;
+
+impl Validate for RegisterInfo {
+    fn validate(mut self) -> Result<Self, ErrorInfo> {
+        let mut errors = ErrorInfo::default();
+
+        self.username = self.username.trim().to_string();
+        let length = self.username.chars().count();
+        if !(3..=32).contains(&length) {
+            errors.add("username", "Username must be 3 to 32 characters long");
+        }
+        if !self
+            .username
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '.' | '_' | '-'))
+        {
+            errors.add(
+                "username",
+                "Username can only contain letters, numbers, dots, dashes and underscores",
+            );
+        }
+
+        if self.password.chars().count() < 8 {
+            errors.add("password", "Password must be at least 8 characters long");
+        }
+
+        self.email = self.email.trim().to_string();
+        if self.email.parse::<lettre::Address>().is_err() {
+            errors.add("email", "Email address is not valid");
+        }
+
+        errors.into_result().map(|()| self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_valid() {
+        let info = RegisterInfo {
+            username: " reporter ".to_string(),
+            password: "long enough".to_string(),
+            email: "reporter@example.com ".to_string(),
+        }
+        .validate()
+        .unwrap();
+        assert_eq!(info.username, "reporter");
+        assert_eq!(info.email, "reporter@example.com");
+    }
+
+    #[test]
+    fn register_invalid() {
+        let errors = RegisterInfo {
+            username: "a b".to_string(),
+            password: "short".to_string(),
+            email: "nope".to_string(),
+        }
+        .validate()
+        .unwrap_err();
+        assert_eq!(errors.errors["username"].len(), 1);
+        assert!(errors.errors.contains_key("password"));
+        assert!(errors.errors.contains_key("email"));
+    }
+}
@@ -0,0 +1,13 @@
+//! Wire types shared with the frontend through the `common` crate, together
+//! with the validation the api applies to submitted values.
+
+pub mod admin;
+pub mod auth;
+pub mod report;
+
+pub use common::types::{duplicate, forward, geocode, picture, ErrorInfo};
+
+/// Check submitted values and normalize them for storage
+pub trait Validate: Sized {
+    fn validate(self) -> Result<Self, ErrorInfo>;
+}
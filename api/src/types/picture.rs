@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+/// Result of one uploaded file, either the stored name or why it was rejected
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct PictureUpload {
+    pub error: Option<String>,
+    pub path: Option<String>,
+    /// Capture time read from the EXIF tags, `YYYY-MM-DDTHH:MM:SS`
+    #[serde(default)]
+    pub taken_at: Option<String>,
+    #[serde(default)]
+    pub latitude: Option<f64>,
+    #[serde(default)]
+    pub longitude: Option<f64>,
+}
@@ -0,0 +1,209 @@
+use crate::types::{ErrorInfo, Validate};
+use time::format_description::FormatItem;
+use time::macros::format_description;
+use time::{OffsetDateTime, PrimitiveDateTime};
+
+pub use common::types::report::{
+    NewReport, Report, ReportDetail, ReportPage, ReportQuery, ReportStatus, StatusChange,
+    StatusUpdate, Withdrawal, TRANSITIONS,
+};
+
+/// Format produced by a `datetime-local` input, seconds are optional
+const INPUT_FORMAT: &[FormatItem<'static>] =
+    format_description!("[year]-[month]-[day]T[hour]:[minute][optional [:[second]]]");
+/// Longest accepted picture caption, in characters
+const MAX_CAPTION_LENGTH: usize = 200;
+/// Format of the days filtering report lists
+const DAY_FORMAT: &[FormatItem<'static>] = format_description!("[year]-[month]-[day]");
+/// Format reports are stored and returned in
+pub const DATETIME_FORMAT: &[FormatItem<'static>] =
+    format_description!("[year]-[month]-[day]T[hour]:[minute]:[second]");
+
+impl Validate for NewReport {
+    fn validate(mut self) -> Result<Self, ErrorInfo> {
+        let mut errors = ErrorInfo::default();
+
+        match self.parse_plate() {
+            Ok(parsed) => {
+                self.plate = parsed.to_string();
+                self.country = parsed.country;
+            }
+            Err(e) => errors.add("plate", &e.to_string()),
+        }
+
+        self.address = self
+            .address
+            .map(|a| a.trim().to_string())
+            .filter(|a| !a.is_empty());
+        match (self.latitude, self.longitude) {
+            (Some(lat), Some(lon)) => {
+                if !(-90.0..=90.0).contains(&lat) {
+                    errors.add("latitude", "Latitude must be between -90 and 90");
+                }
+                if !(-180.0..=180.0).contains(&lon) {
+                    errors.add("longitude", "Longitude must be between -180 and 180");
+                }
+            }
+            (None, None) => {
+                if self.address.is_none() {
+                    errors.add("location", "Either an address or GPS position is required");
+                }
+            }
+            _ => errors.add("location", "Both latitude and longitude are required"),
+        }
+
+        match PrimitiveDateTime::parse(self.observed_at.trim(), INPUT_FORMAT) {
+            Ok(observed_at) => {
+                let now = OffsetDateTime::now_utc();
+                // The input carries no zone, allow a day of slack for local time ahead of UTC
+                if observed_at.assume_utc() > now + time::Duration::days(1) {
+                    errors.add("observed_at", "Date cannot be in the future");
+                }
+                if let Ok(formatted) = observed_at.format(DATETIME_FORMAT) {
+                    self.observed_at = formatted;
+                }
+            }
+            Err(_) => errors.add("observed_at", "Date is required"),
+        }
+
+        self.description = self
+            .description
+            .map(|d| d.trim().to_string())
+            .filter(|d| !d.is_empty());
+
+        for (position, (_, caption)) in self.pictures.iter_mut().enumerate() {
+            *caption = caption
+                .take()
+                .map(|c| c.trim().to_string())
+                .filter(|c| !c.is_empty());
+            if caption
+                .as_ref()
+                .is_some_and(|c| c.chars().count() > MAX_CAPTION_LENGTH)
+            {
+                errors.add(
+                    "pictures",
+                    &format!(
+                        "Caption of picture {} is longer than {MAX_CAPTION_LENGTH} characters",
+                        position + 1
+                    ),
+                );
+            }
+        }
+
+        errors.into_result().map(|()| self)
+    }
+}
+
+impl Validate for ReportQuery {
+    fn validate(mut self) -> Result<Self, ErrorInfo> {
+        let mut errors = ErrorInfo::default();
+        for (field, day) in [("from", &mut self.from), ("to", &mut self.to)] {
+            *day = day.take().filter(|d| !d.trim().is_empty());
+            if let Some(value) = day {
+                if time::Date::parse(value.trim(), DAY_FORMAT).is_err() {
+                    errors.add(field, "Date must be in the YYYY-MM-DD format");
+                }
+                *value = value.trim().to_string();
+            }
+        }
+        self.plate = self
+            .plate
+            .map(|p| p.trim().to_string())
+            .filter(|p| !p.is_empty());
+        errors.into_result()?;
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn valid() -> NewReport {
+        NewReport {
+            plate: " 1ab-2345 ".to_string(),
+            country: String::new(),
+            latitude: Some(50.0755),
+            longitude: Some(14.4378),
+            address: None,
+            observed_at: "2023-06-01T12:30".to_string(),
+            description: Some("  ".to_string()),
+            pictures: vec![
+                ("a.jpg".to_string(), Some(" Front ".to_string())),
+                ("b.jpg".to_string(), Some(" ".to_string())),
+            ],
+        }
+    }
+
+    #[test]
+    fn normalizes() {
+        let report = valid().validate().unwrap();
+        assert_eq!(report.plate, "1AB 2345");
+        assert_eq!(report.country, "CZ");
+        assert_eq!(report.observed_at, "2023-06-01T12:30:00");
+        assert_eq!(report.description, None);
+        assert_eq!(
+            report.pictures,
+            vec![
+                ("a.jpg".to_string(), Some("Front".to_string())),
+                ("b.jpg".to_string(), None)
+            ]
+        );
+    }
+
+    #[test]
+    fn long_caption() {
+        let mut report = valid();
+        report.pictures[1].1 = Some("x".repeat(201));
+        let errors = report.validate().unwrap_err();
+        assert_eq!(
+            errors.errors["pictures"],
+            vec!["Caption of picture 2 is longer than 200 characters"]
+        );
+    }
+
+    #[test]
+    fn address_instead_of_gps() {
+        let mut report = valid();
+        report.latitude = None;
+        report.longitude = None;
+        report.address = Some("Vinohradská 12".to_string());
+        assert!(report.validate().is_ok())
+    }
+
+    #[test]
+    fn missing_fields() {
+        let errors = NewReport::default().validate().unwrap_err();
+        assert!(errors.errors.contains_key("plate"));
+        assert!(errors.errors.contains_key("location"));
+        assert!(errors.errors.contains_key("observed_at"));
+    }
+
+    #[test]
+    fn invalid_plate() {
+        let mut report = valid();
+        report.plate = "1XB 2345".to_string();
+        let errors = report.validate().unwrap_err();
+        assert_eq!(
+            errors.errors["plate"],
+            vec!["X is not a Czech region letter"]
+        );
+
+        let mut report = valid();
+        report.plate = "m-ab 123".to_string();
+        report.country = "d".to_string();
+        let report = report.validate().unwrap();
+        assert_eq!(
+            (report.plate.as_str(), report.country.as_str()),
+            ("MAB123", "D")
+        );
+    }
+
+    #[test]
+    fn future_date() {
+        let mut report = valid();
+        report.observed_at = "2999-01-01T00:00".to_string();
+        let errors = report.validate().unwrap_err();
+        assert!(errors.errors.contains_key("observed_at"))
+    }
+}
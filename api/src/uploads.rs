@@ -0,0 +1,159 @@
+//! Content addressed storage of uploaded pictures.
+//!
+//! A picture is named by the SHA-256 of its bytes (`<hex>.jpg`) and stored
+//! under a directory named by the first two hex digits, so uploading the same
+//! file twice stores it once.
+
+use sha2::{Digest, Sha256};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error as ThisError;
+
+/// Why an uploaded file was rejected
+#[derive(ThisError, Debug, Clone, PartialEq, Eq)]
+pub enum PictureError {
+    #[error("Only JPEG pictures are accepted")]
+    NotJpeg,
+
+    #[error("Picture could not be decoded: {0}")]
+    Corrupt(String),
+}
+
+const JPEG_MAGIC: [u8; 3] = [0xFF, 0xD8, 0xFF];
+
+/// Check the magic bytes and that the whole picture decodes
+pub fn validate_jpeg(bytes: &[u8]) -> Result<(), PictureError> {
+    if !bytes.starts_with(&JPEG_MAGIC) {
+        return Err(PictureError::NotJpeg);
+    }
+    image::load_from_memory_with_format(bytes, image::ImageFormat::Jpeg)
+        .map(|_| ())
+        .map_err(|e| PictureError::Corrupt(e.to_string()))
+}
+
+/// Pictures on disk below the uploads directory
+#[derive(Clone, Debug)]
+pub struct PictureStore {
+    directory: PathBuf,
+}
+
+impl PictureStore {
+    pub fn new(directory: &Path) -> Self {
+        Self {
+            directory: directory.to_path_buf(),
+        }
+    }
+
+    /// Location of a stored picture, `None` for names the store never creates
+    pub fn path(&self, name: &str) -> Option<PathBuf> {
+        let hash = name.strip_suffix(".jpg")?;
+        if hash.len() != 64
+            || !hash
+                .bytes()
+                .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+        {
+            return None;
+        }
+        Some(self.directory.join(&hash[..2]).join(name))
+    }
+
+    /// Write the picture unless it is already stored, returns its name
+    pub fn store(&self, bytes: &[u8]) -> io::Result<String> {
+        let name = format!("{}.jpg", hex::encode(Sha256::digest(bytes)));
+        let path = self.path(&name).expect("generated names are valid");
+        if path.exists() {
+            return Ok(name);
+        }
+        let directory = path.parent().expect("pictures are stored in a directory");
+        std::fs::create_dir_all(directory)?;
+        // Write next to the target and rename so readers never see partial files
+        let temporary = directory.join(format!(".{}.tmp", uuid::Uuid::new_v4()));
+        let mut file = std::fs::File::create(&temporary)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        std::fs::rename(&temporary, &path)?;
+        Ok(name)
+    }
+
+    /// Delete a stored picture, names the store never creates are ignored
+    pub fn remove(&self, name: &str) -> io::Result<()> {
+        match self.path(name) {
+            Some(path) => std::fs::remove_file(path),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+pub fn test_jpeg(seed: u8) -> Vec<u8> {
+    let picture = image::RgbImage::from_fn(16, 16, |x, y| {
+        image::Rgb([x as u8 * 16, y as u8 * 16, seed])
+    });
+    let mut bytes = io::Cursor::new(Vec::new());
+    picture
+        .write_to(&mut bytes, image::ImageFormat::Jpeg)
+        .unwrap();
+    bytes.into_inner()
+}
+
+/// Add an EXIF segment with capture time and position to a JPEG
+#[cfg(test)]
+pub fn with_exif(jpeg: &[u8], metadata: &common::photo::PhotoMetadata) -> Vec<u8> {
+    let tiff = common::photo::exif(metadata).unwrap();
+    let mut out = jpeg[..2].to_vec();
+    out.extend_from_slice(&[0xFF, 0xE1]);
+    out.extend_from_slice(&((tiff.len() + 8) as u16).to_be_bytes());
+    out.extend_from_slice(b"Exif\0\0");
+    out.extend_from_slice(&tiff);
+    out.extend_from_slice(&jpeg[2..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jpeg_validation() {
+        let jpeg = test_jpeg(0);
+        assert_eq!(validate_jpeg(&jpeg), Ok(()));
+        let photo = common::photo::PhotoMetadata {
+            taken_at: Some("2023-05-01T12:34:56".to_string()),
+            position: Some((50.0875, -14.4213)),
+        };
+        let tagged = with_exif(&jpeg, &photo);
+        assert_eq!(validate_jpeg(&tagged), Ok(()));
+        assert_eq!(common::photo::metadata(&tagged).taken_at, photo.taken_at);
+        assert_eq!(validate_jpeg(b"GIF89a"), Err(PictureError::NotJpeg));
+        assert!(matches!(
+            validate_jpeg(&jpeg[..jpeg.len() / 3]),
+            Err(PictureError::Corrupt(_))
+        ));
+    }
+
+    #[test]
+    fn store_deduplicates() {
+        let directory = std::env::temp_dir().join(format!("uploads-{}", uuid::Uuid::new_v4()));
+        let store = PictureStore::new(&directory);
+        let jpeg = test_jpeg(1);
+        let name = store.store(&jpeg).unwrap();
+        assert_eq!(store.store(&jpeg).unwrap(), name);
+        let path = store.path(&name).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), jpeg);
+        assert_eq!(
+            std::fs::read_dir(path.parent().unwrap()).unwrap().count(),
+            1
+        );
+        store.remove(&name).unwrap();
+        assert!(!path.exists());
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn rejects_foreign_names() {
+        let store = PictureStore::new(Path::new("uploads"));
+        assert!(store.path("../config.toml").is_none());
+        assert!(store.path(&format!("{}.jpg", "A".repeat(64))).is_none());
+        assert!(store.path(&format!("{}.jpg", "a".repeat(64))).is_some());
+    }
+}
@@ -0,0 +1,9 @@
+//! Code shared by the frontend and the api, it must compile for wasm too.
+//!
+//! [`types`] holds everything sent over the wire, so a field renamed on one
+//! side cannot silently break the other.
+
+pub mod permissions;
+pub mod photo;
+pub mod plate;
+pub mod types;
@@ -0,0 +1,30 @@
+//! Names of the permissions checked by the api and the frontend.
+
+/// Wildcard permission granting everything
+pub const SUPER_ADMIN: &str = "*";
+pub const MANAGE_USERS: &str = "users.manage";
+pub const MANAGE_ROLES: &str = "roles.manage";
+pub const VIEW_PICTURES: &str = "pictures.view";
+pub const VIEW_ORIGINAL_PICTURES: &str = "pictures.view_original";
+pub const REVIEW_REPORTS: &str = "reports.review";
+pub const FORWARD_REPORTS: &str = "reports.forward";
+
+/// Check whether the granted permissions contain the named one
+pub fn granted<S: AsRef<str>>(permissions: &[S], name: &str) -> bool {
+    permissions
+        .iter()
+        .any(|p| p.as_ref() == SUPER_ADMIN || p.as_ref() == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn granted_by_name_or_wildcard() {
+        assert!(granted(&["users.manage"], MANAGE_USERS));
+        assert!(!granted(&["users.manage"], MANAGE_ROLES));
+        assert!(granted(&[SUPER_ADMIN], MANAGE_ROLES));
+        assert!(!granted::<&str>(&[], MANAGE_ROLES));
+    }
+}
@@ -0,0 +1,160 @@
+//! Capture time and position recorded by the camera in EXIF tags.
+//!
+//! The api stores them with an uploaded picture and the frontend keeps them
+//! in the copy it shrinks before upload, so both read and write them here.
+
+use exif::{Field, In, Reader, Tag, Value};
+use std::io::Cursor;
+
+/// When and where a photo was taken according to its EXIF tags
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PhotoMetadata {
+    /// Local time of the camera as `YYYY-MM-DDTHH:MM:SS`
+    pub taken_at: Option<String>,
+    /// Latitude and longitude, only when the photo has both
+    pub position: Option<(f64, f64)>,
+}
+
+impl PhotoMetadata {
+    /// Capture time in the format of a `datetime-local` input
+    pub fn input_time(&self) -> Option<String> {
+        self.taken_at.as_ref().map(|t| t.chars().take(16).collect())
+    }
+}
+
+/// Degrees, minutes and seconds with a hemisphere reference, negative for `S`/`W`
+fn coordinate(exif: &exif::Exif, value: Tag, reference: Tag, limit: f64) -> Option<f64> {
+    let Value::Rational(parts) = &exif.get_field(value, In::PRIMARY)?.value else {
+        return None;
+    };
+    if parts.len() < 3 || parts.iter().any(|p| p.denom == 0) {
+        return None;
+    }
+    let degrees = parts[0].to_f64() + parts[1].to_f64() / 60.0 + parts[2].to_f64() / 3600.0;
+    let negative = matches!(
+        &exif.get_field(reference, In::PRIMARY)?.value,
+        Value::Ascii(v) if v.first().is_some_and(|r| r.starts_with(b"S") || r.starts_with(b"W"))
+    );
+    (degrees <= limit).then_some(if negative { -degrees } else { degrees })
+}
+
+fn taken_at(exif: &exif::Exif) -> Option<String> {
+    let field = exif
+        .get_field(Tag::DateTimeOriginal, In::PRIMARY)
+        .or_else(|| exif.get_field(Tag::DateTime, In::PRIMARY))?;
+    let Value::Ascii(values) = &field.value else {
+        return None;
+    };
+    let time = exif::DateTime::from_ascii(values.first()?).ok()?;
+    Some(format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        time.year, time.month, time.day, time.hour, time.minute, time.second
+    ))
+}
+
+/// Read the metadata of a JPEG, missing or broken tags are left out
+pub fn metadata(bytes: &[u8]) -> PhotoMetadata {
+    let Ok(exif) = Reader::new().read_from_container(&mut Cursor::new(bytes)) else {
+        return PhotoMetadata::default();
+    };
+    let latitude = coordinate(&exif, Tag::GPSLatitude, Tag::GPSLatitudeRef, 90.0);
+    let longitude = coordinate(&exif, Tag::GPSLongitude, Tag::GPSLongitudeRef, 180.0);
+    let position = latitude.zip(longitude);
+    PhotoMetadata {
+        taken_at: taken_at(&exif),
+        position,
+    }
+}
+
+/// EXIF fields for the capture time and position
+pub fn exif_fields(metadata: &PhotoMetadata) -> Vec<Field> {
+    fn dms(value: f64) -> Value {
+        let value = value.abs();
+        let degrees = value.trunc();
+        let minutes = ((value - degrees) * 60.0).trunc();
+        let seconds = ((value - degrees) * 60.0 - minutes) * 60.0;
+        Value::Rational(vec![
+            (degrees as u32, 1).into(),
+            (minutes as u32, 1).into(),
+            ((seconds * 1000.0).round() as u32, 1000).into(),
+        ])
+    }
+    let ascii = |s: &str| Value::Ascii(vec![s.as_bytes().to_vec()]);
+    let field = |tag, value| Field {
+        tag,
+        ifd_num: In::PRIMARY,
+        value,
+    };
+    let hemisphere = |value: f64, negative, positive| if value < 0.0 { negative } else { positive };
+
+    let mut fields = Vec::new();
+    if let Some(taken_at) = &metadata.taken_at {
+        // EXIF writes dates as `YYYY:MM:DD HH:MM:SS`
+        let taken_at = taken_at.replacen('-', ":", 2).replace('T', " ");
+        fields.push(field(Tag::DateTimeOriginal, ascii(&taken_at)));
+    }
+    if let Some((latitude, longitude)) = metadata.position {
+        fields.push(field(
+            Tag::GPSLatitudeRef,
+            ascii(hemisphere(latitude, "S", "N")),
+        ));
+        fields.push(field(Tag::GPSLatitude, dms(latitude)));
+        fields.push(field(
+            Tag::GPSLongitudeRef,
+            ascii(hemisphere(longitude, "W", "E")),
+        ));
+        fields.push(field(Tag::GPSLongitude, dms(longitude)));
+    }
+    fields
+}
+
+/// EXIF block carrying only the capture time and position, `None` when
+/// there is nothing to write
+pub fn exif(metadata: &PhotoMetadata) -> Option<Vec<u8>> {
+    let fields = exif_fields(metadata);
+    if fields.is_empty() {
+        return None;
+    }
+    let mut writer = exif::experimental::Writer::new();
+    for f in &fields {
+        writer.push_field(f);
+    }
+    let mut tiff = Cursor::new(Vec::new());
+    writer.write(&mut tiff, false).ok()?;
+    Some(tiff.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bare JPEG container holding only an EXIF segment
+    fn jpeg(tiff: &[u8]) -> Vec<u8> {
+        let mut out = vec![0xFF, 0xD8, 0xFF, 0xE1];
+        out.extend_from_slice(&((tiff.len() + 8) as u16).to_be_bytes());
+        out.extend_from_slice(b"Exif\0\0");
+        out.extend_from_slice(tiff);
+        out.extend_from_slice(&[0xFF, 0xD9]);
+        out
+    }
+
+    #[test]
+    fn time_and_position() {
+        let photo = PhotoMetadata {
+            taken_at: Some("2023-05-01T12:34:56".to_string()),
+            position: Some((50.0875, -14.4213)),
+        };
+        let read = metadata(&jpeg(&exif(&photo).unwrap()));
+        assert_eq!(read.taken_at, photo.taken_at);
+        let (lat, lon) = read.position.unwrap();
+        assert!((lat - 50.0875).abs() < 1e-6 && (lon + 14.4213).abs() < 1e-6);
+        assert_eq!(read.input_time().as_deref(), Some("2023-05-01T12:34"));
+    }
+
+    #[test]
+    fn without_exif() {
+        assert_eq!(exif(&PhotoMetadata::default()), None);
+        assert_eq!(metadata(&jpeg(&[])), PhotoMetadata::default());
+        assert_eq!(metadata(b"garbage"), PhotoMetadata::default());
+    }
+}
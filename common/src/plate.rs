@@ -0,0 +1,385 @@
+//! Licence plates (SPZ) of Czech and foreign vehicles.
+//!
+//! User input is normalized by dropping spaces and dashes, upper casing and
+//! replacing characters that are easy to mistake on a photo: Czech plates
+//! never use the letter O, so it is always read as 0, and I is read as 1
+//! where the format expects a digit.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use thiserror::Error as ThisError;
+
+/// Country code of Czech plates
+pub const CZECH: &str = "CZ";
+
+/// Countries offered for foreign plates, by international vehicle registration code
+pub const COUNTRIES: &[(&str, &str)] = &[
+    ("CZ", "Czechia"),
+    ("SK", "Slovakia"),
+    ("D", "Germany"),
+    ("A", "Austria"),
+    ("PL", "Poland"),
+    ("H", "Hungary"),
+    ("UA", "Ukraine"),
+    ("SLO", "Slovenia"),
+    ("HR", "Croatia"),
+    ("RO", "Romania"),
+    ("BG", "Bulgaria"),
+    ("LT", "Lithuania"),
+    ("LV", "Latvia"),
+    ("EST", "Estonia"),
+    ("I", "Italy"),
+    ("F", "France"),
+    ("E", "Spain"),
+    ("P", "Portugal"),
+    ("NL", "Netherlands"),
+    ("B", "Belgium"),
+    ("L", "Luxembourg"),
+    ("CH", "Switzerland"),
+    ("GB", "United Kingdom"),
+    ("IRL", "Ireland"),
+    ("DK", "Denmark"),
+    ("S", "Sweden"),
+    ("N", "Norway"),
+    ("FIN", "Finland"),
+    ("GR", "Greece"),
+    ("SRB", "Serbia"),
+    ("TR", "Turkey"),
+    ("USA", "United States"),
+];
+
+/// Region letters in the second position of regular plates
+pub const REGIONS: &[(char, &str)] = &[
+    ('A', "Hlavní město Praha"),
+    ('S', "Středočeský kraj"),
+    ('C', "Jihočeský kraj"),
+    ('P', "Plzeňský kraj"),
+    ('K', "Karlovarský kraj"),
+    ('U', "Ústecký kraj"),
+    ('L', "Liberecký kraj"),
+    ('H', "Královéhradecký kraj"),
+    ('E', "Pardubický kraj"),
+    ('J', "Kraj Vysočina"),
+    ('B', "Jihomoravský kraj"),
+    ('M', "Olomoucký kraj"),
+    ('T', "Moravskoslezský kraj"),
+    ('Z', "Zlínský kraj"),
+];
+
+/// Letters never issued on Czech plates
+const UNUSED_LETTERS: [char; 4] = ['G', 'O', 'Q', 'W'];
+
+/// Prefixes of plates issued to diplomats and embassy staff
+const DIPLOMATIC_PREFIXES: [&str; 3] = ["DD", "XX", "XS"];
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlateKind {
+    /// `1AB 2345`, digit, region letter, letter or digit and four digits
+    Regular,
+    /// `12V 3456` issued to veteran vehicles
+    Historic,
+    /// `DD 123 45`
+    Diplomatic,
+    /// Five to eight letters and digits chosen by the owner
+    Personalized,
+    /// Any plate from another country, only loosely checked
+    Foreign,
+}
+
+#[derive(ThisError, Clone, Debug, PartialEq, Eq)]
+pub enum PlateError {
+    #[error("Licence plate is required")]
+    Empty,
+
+    #[error("Unknown country {0}")]
+    UnknownCountry(String),
+
+    #[error("Character {0} is not allowed on licence plates")]
+    InvalidCharacter(char),
+
+    #[error("Letter {0} is not used on Czech licence plates")]
+    UnusedLetter(char),
+
+    #[error("{0} is not a Czech region letter")]
+    UnknownRegion(char),
+
+    #[error("Licence plate is too short")]
+    TooShort,
+
+    #[error("Licence plate is too long")]
+    TooLong,
+
+    #[error("Licence plate does not match any Czech format")]
+    InvalidFormat,
+}
+
+/// Parsed and normalized licence plate
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Plate {
+    /// Country code from [`COUNTRIES`]
+    pub country: String,
+    pub kind: PlateKind,
+    /// Normalized characters without separators
+    pub text: String,
+}
+
+/// Upper case the input and drop separators
+fn compact(input: &str, czech: bool) -> Result<Vec<char>, PlateError> {
+    let mut chars = Vec::new();
+    for c in input.chars().flat_map(char::to_uppercase) {
+        if c.is_whitespace() || matches!(c, '-' | '·' | '.') {
+            continue;
+        }
+        let allowed = if czech {
+            c.is_ascii_alphanumeric()
+        } else {
+            c.is_alphanumeric()
+        };
+        if !allowed {
+            return Err(PlateError::InvalidCharacter(c));
+        }
+        chars.push(c);
+    }
+    if chars.is_empty() {
+        return Err(PlateError::Empty);
+    }
+    Ok(chars)
+}
+
+/// Read a character in a position where only digits are valid
+fn digit(c: char) -> Option<char> {
+    match c {
+        'O' => Some('0'),
+        'I' => Some('1'),
+        c if c.is_ascii_digit() => Some(c),
+        _ => None,
+    }
+}
+
+fn digits(chars: &[char]) -> Option<String> {
+    chars.iter().map(|c| digit(*c)).collect()
+}
+
+fn is_region(c: char) -> bool {
+    REGIONS.iter().any(|(r, _)| *r == c)
+}
+
+fn diplomatic(chars: &[char]) -> Option<String> {
+    let prefix = chars.get(..2)?.iter().collect::<String>();
+    if chars.len() != 7 || !DIPLOMATIC_PREFIXES.contains(&prefix.as_str()) {
+        return None;
+    }
+    Some(prefix + &digits(&chars[2..])?)
+}
+
+fn historic(chars: &[char]) -> Option<String> {
+    if chars.len() != 7 || chars[2] != 'V' {
+        return None;
+    }
+    Some(format!("{}V{}", digits(&chars[..2])?, digits(&chars[3..])?))
+}
+
+fn regular(chars: &[char]) -> Option<String> {
+    if chars.len() != 7 || !is_region(chars[1]) {
+        return None;
+    }
+    let third = match chars[2] {
+        'O' => '0',
+        c if c.is_ascii_digit() || (c.is_ascii_uppercase() && !UNUSED_LETTERS.contains(&c)) => c,
+        _ => return None,
+    };
+    Some(format!(
+        "{}{}{third}{}",
+        digit(chars[0])?,
+        chars[1],
+        digits(&chars[3..])?
+    ))
+}
+
+/// Shape of a regular plate, whatever the region letter
+fn regular_shape(chars: &[char]) -> bool {
+    chars.len() == 7
+        && digit(chars[0]).is_some()
+        && chars[1].is_ascii_uppercase()
+        && digits(&chars[3..]).is_some()
+}
+
+/// Personalized plates must not be mistaken for regular ones
+fn personalized(chars: &[char]) -> Option<String> {
+    if regular_shape(chars) {
+        return None;
+    }
+    let text = chars
+        .iter()
+        .map(|c| if *c == 'O' { '0' } else { *c })
+        .collect::<String>();
+    let valid = (5..=8).contains(&chars.len())
+        && text.chars().any(|c| c.is_ascii_digit())
+        && text.chars().any(|c| c.is_ascii_uppercase())
+        && !text.chars().any(|c| UNUSED_LETTERS.contains(&c));
+    valid.then_some(text)
+}
+
+/// Most helpful explanation of why no Czech format matched
+fn czech_error(chars: &[char]) -> PlateError {
+    if chars.len() > 8 {
+        return PlateError::TooLong;
+    }
+    if chars.len() < 5 {
+        return PlateError::TooShort;
+    }
+    if let Some(c) = chars
+        .iter()
+        .find(|c| **c != 'O' && UNUSED_LETTERS.contains(c))
+    {
+        return PlateError::UnusedLetter(*c);
+    }
+    if regular_shape(chars) && !is_region(chars[1]) {
+        return PlateError::UnknownRegion(chars[1]);
+    }
+    PlateError::InvalidFormat
+}
+
+impl Plate {
+    /// Parse user input for a plate registered in `country`
+    pub fn parse(input: &str, country: &str) -> Result<Self, PlateError> {
+        let country = country.trim().to_uppercase();
+        if !COUNTRIES.iter().any(|(code, _)| *code == country) {
+            return Err(PlateError::UnknownCountry(country));
+        }
+        if country != CZECH {
+            let chars = compact(input, false)?;
+            if chars.len() > 10 {
+                return Err(PlateError::TooLong);
+            }
+            return Ok(Self {
+                country,
+                kind: PlateKind::Foreign,
+                text: chars.into_iter().collect(),
+            });
+        }
+
+        let chars = compact(input, true)?;
+        let (kind, text) = [
+            (
+                PlateKind::Diplomatic,
+                diplomatic as fn(&[char]) -> Option<String>,
+            ),
+            (PlateKind::Historic, historic),
+            (PlateKind::Regular, regular),
+            (PlateKind::Personalized, personalized),
+        ]
+        .into_iter()
+        .find_map(|(kind, parse)| parse(&chars).map(|text| (kind, text)))
+        .ok_or_else(|| czech_error(&chars))?;
+        Ok(Self {
+            country,
+            kind,
+            text,
+        })
+    }
+
+    /// Region that issued a regular plate
+    pub fn region(&self) -> Option<&'static str> {
+        if self.kind != PlateKind::Regular {
+            return None;
+        }
+        let letter = self.text.chars().nth(1)?;
+        REGIONS
+            .iter()
+            .find(|(r, _)| *r == letter)
+            .map(|(_, name)| *name)
+    }
+}
+
+/// The plate as printed, e.g. `1AB 2345`
+impl fmt::Display for Plate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            PlateKind::Regular | PlateKind::Historic => {
+                write!(f, "{} {}", &self.text[..3], &self.text[3..])
+            }
+            PlateKind::Diplomatic => write!(
+                f,
+                "{} {} {}",
+                &self.text[..2],
+                &self.text[2..5],
+                &self.text[5..]
+            ),
+            PlateKind::Personalized | PlateKind::Foreign => f.write_str(&self.text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn czech(input: &str) -> Result<Plate, PlateError> {
+        Plate::parse(input, CZECH)
+    }
+
+    #[test]
+    fn regular() {
+        let plate = czech(" 1ab-2345 ").unwrap();
+        assert_eq!(plate.kind, PlateKind::Regular);
+        assert_eq!(plate.text, "1AB2345");
+        assert_eq!(plate.to_string(), "1AB 2345");
+        assert_eq!(plate.region(), Some("Hlavní město Praha"));
+        assert_eq!(
+            czech("5T7 1234").unwrap().region(),
+            Some("Moravskoslezský kraj")
+        );
+    }
+
+    #[test]
+    fn confusable_characters() {
+        assert_eq!(czech("IAO 234O").unwrap().to_string(), "1A0 2340");
+        assert_eq!(czech("2AI 1I11").unwrap().to_string(), "2AI 1111");
+    }
+
+    #[test]
+    fn historic_and_diplomatic() {
+        let plate = czech("12v 3456").unwrap();
+        assert_eq!(plate.kind, PlateKind::Historic);
+        assert_eq!(plate.to_string(), "12V 3456");
+        let plate = czech("dd12345").unwrap();
+        assert_eq!(plate.kind, PlateKind::Diplomatic);
+        assert_eq!(plate.to_string(), "DD 123 45");
+        assert_eq!(plate.region(), None);
+    }
+
+    #[test]
+    fn personalized() {
+        let plate = czech("AHOJ 1").unwrap();
+        assert_eq!(plate.kind, PlateKind::Personalized);
+        assert_eq!(plate.to_string(), "AH0J1");
+        assert_eq!(czech("PETR2024").unwrap().kind, PlateKind::Personalized);
+    }
+
+    #[test]
+    fn invalid_czech() {
+        assert_eq!(czech("  "), Err(PlateError::Empty));
+        assert_eq!(czech("1A_2345"), Err(PlateError::InvalidCharacter('_')));
+        assert_eq!(czech("1AB"), Err(PlateError::TooShort));
+        assert_eq!(czech("1AB2345678"), Err(PlateError::TooLong));
+        assert_eq!(czech("1XB 2345"), Err(PlateError::UnknownRegion('X')));
+        assert_eq!(czech("1AG 2345"), Err(PlateError::UnusedLetter('G')));
+        assert_eq!(czech("12345"), Err(PlateError::InvalidFormat));
+        assert_eq!(czech("ČAU12"), Err(PlateError::InvalidCharacter('Č')));
+    }
+
+    #[test]
+    fn foreign() {
+        let plate = Plate::parse("m-ab 123", "d").unwrap();
+        assert_eq!(plate.country, "D");
+        assert_eq!(plate.kind, PlateKind::Foreign);
+        assert_eq!(plate.to_string(), "MAB123");
+        assert_eq!(Plate::parse("BOB 1", "SK").unwrap().text, "BOB1");
+        assert_eq!(
+            Plate::parse("AB 123", "XYZ"),
+            Err(PlateError::UnknownCountry("XYZ".to_string()))
+        );
+        assert_eq!(Plate::parse("ABCDEFGHIJK", "A"), Err(PlateError::TooLong));
+    }
+}
@@ -0,0 +1,166 @@
+use crate::types::auth::EmailDetail;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+/// Row of the user list
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, PartialOrd, Ord, Default)]
+pub struct User {
+    pub id: i64,
+    /// Primary email
+    pub email: String,
+    pub username: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, PartialOrd, Ord, Default)]
+pub struct UserDetail {
+    pub id: i64,
+    pub emails: Vec<EmailDetail>,
+    pub username: String,
+    /// Role names
+    pub roles: Vec<String>,
+}
+
+/// Replaces the roles of a user
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, Default)]
+pub struct UserRoles {
+    pub id: i64,
+    /// Role names
+    pub roles: HashSet<String>,
+}
+
+/// Ordered by name
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, Default)]
+pub struct Permission {
+    pub id: i64,
+    pub name: String,
+    pub description: String,
+}
+
+impl Ord for Permission {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.name.cmp(&other.name)
+    }
+}
+
+impl PartialOrd for Permission {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Ordered by name
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, Default)]
+pub struct Role {
+    pub id: i64,
+    pub name: String,
+    pub description: String,
+    pub permissions: Option<Vec<Permission>>,
+}
+
+impl Ord for Role {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.name.cmp(&other.name)
+    }
+}
+
+impl PartialOrd for Role {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// New or edited role, `id` is required for edits
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct RoleInfo {
+    pub id: Option<i64>,
+    pub name: String,
+    pub description: String,
+    /// Permission names
+    pub permissions: HashSet<String>,
+}
+
+impl RoleInfo {
+    /// Permission names in a stable order
+    pub fn permission_list(&self) -> Vec<String> {
+        let mut permissions = self.permissions.iter().cloned().collect::<Vec<_>>();
+        permissions.sort();
+        permissions
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Id {
+    pub id: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::roundtrip;
+    use serde_json::json;
+
+    #[test]
+    fn role_wire() {
+        let role = Role {
+            id: 1,
+            name: "moderator".to_string(),
+            description: "Reviews reports".to_string(),
+            permissions: Some(vec![Permission {
+                id: 2,
+                name: "users.manage".to_string(),
+                description: "List users".to_string(),
+            }]),
+        };
+        assert_eq!(
+            roundtrip(&role),
+            json!({
+                "id": 1,
+                "name": "moderator",
+                "description": "Reviews reports",
+                "permissions": [{"id": 2, "name": "users.manage", "description": "List users"}],
+            })
+        );
+        let info = RoleInfo {
+            id: Some(1),
+            name: "moderator".to_string(),
+            description: String::new(),
+            permissions: HashSet::from(["b".to_string(), "a".to_string()]),
+        };
+        roundtrip(&info);
+        assert_eq!(info.permission_list(), vec!["a", "b"]);
+        assert_eq!(roundtrip(&Id { id: 4 }), json!({"id": 4}));
+    }
+
+    #[test]
+    fn user_wire() {
+        roundtrip(&User {
+            id: 1,
+            email: "r@example.com".to_string(),
+            username: "reporter".to_string(),
+        });
+        roundtrip(&UserDetail {
+            id: 1,
+            emails: vec![EmailDetail::default()],
+            username: "reporter".to_string(),
+            roles: vec!["admin".to_string()],
+        });
+        let json = roundtrip(&UserRoles {
+            id: 1,
+            roles: HashSet::from(["admin".to_string()]),
+        });
+        assert_eq!(json, json!({"id": 1, "roles": ["admin"]}));
+    }
+
+    #[test]
+    fn ordered_by_name() {
+        let role = |name: &str, id| Role {
+            id,
+            name: name.to_string(),
+            ..Role::default()
+        };
+        let mut roles = [role("b", 1), role("a", 2)];
+        roles.sort();
+        assert_eq!(roles[0].id, 2);
+    }
+}
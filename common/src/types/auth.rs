@@ -0,0 +1,180 @@
+use crate::permissions;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginInfo {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq)]
+pub struct RegisterInfo {
+    pub username: String,
+    pub password: String,
+    pub email: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq)]
+pub struct EmailResendInfo {
+    pub user_id: i64,
+    pub email: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq)]
+pub struct ApiResult {
+    pub result: String,
+}
+
+impl ApiResult {
+    pub fn new(result: &str) -> Self {
+        Self {
+            result: result.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq)]
+pub struct EmailConfirmationResult {
+    pub result: String,
+    pub email: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, Default)]
+pub struct RegisterResponse {
+    pub result: String,
+    pub data: Option<UserInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, PartialOrd, Ord, Default)]
+pub struct EmailDetail {
+    pub email: String,
+    pub verified: bool,
+    pub primary: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, Default)]
+pub struct UserInfo {
+    pub id: i64,
+    pub token: String,
+    pub username: String,
+    pub emails: Vec<EmailDetail>,
+    pub permissions: Vec<String>,
+}
+
+impl UserInfo {
+    pub fn is_authenticated(&self) -> bool {
+        !self.token.is_empty()
+    }
+
+    /// Whether the user holds the permission or the `*` wildcard
+    pub fn has_permission(&self, permission: &str) -> bool {
+        permissions::granted(&self.permissions, permission)
+    }
+
+    pub fn primary_email(&self) -> Option<String> {
+        self.emails
+            .iter()
+            .find(|e| e.primary)
+            .map(|e| e.email.clone())
+    }
+
+    /// Unverified addresses, the primary one last
+    pub fn non_validated_emails(&self) -> Vec<String> {
+        let mut emails = self.emails.clone();
+        emails.sort_by_key(|e| e.primary);
+        emails
+            .iter()
+            .filter(|e| !e.verified)
+            .map(|e| e.email.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::permissions::{MANAGE_ROLES, MANAGE_USERS, SUPER_ADMIN};
+    use crate::types::roundtrip;
+    use serde_json::json;
+
+    fn user() -> UserInfo {
+        UserInfo {
+            id: 3,
+            token: "token".to_string(),
+            username: "reporter".to_string(),
+            emails: vec![
+                EmailDetail {
+                    email: "test@example.com".to_string(),
+                    verified: false,
+                    primary: false,
+                },
+                EmailDetail {
+                    email: "primary@example.com".to_string(),
+                    verified: true,
+                    primary: true,
+                },
+            ],
+            permissions: vec![MANAGE_USERS.to_string()],
+        }
+    }
+
+    #[test]
+    fn authenticated() {
+        assert!(!UserInfo::default().is_authenticated());
+        assert!(user().is_authenticated());
+    }
+
+    #[test]
+    fn has_permission() {
+        let mut user = user();
+        assert!(user.has_permission(MANAGE_USERS));
+        assert!(!user.has_permission(MANAGE_ROLES));
+        user.permissions.push(SUPER_ADMIN.to_string());
+        assert!(user.has_permission(MANAGE_ROLES));
+    }
+
+    #[test]
+    fn emails() {
+        assert_eq!(UserInfo::default().primary_email(), None);
+        assert_eq!(
+            user().primary_email().as_deref(),
+            Some("primary@example.com")
+        );
+        assert_eq!(user().non_validated_emails(), vec!["test@example.com"]);
+    }
+
+    #[test]
+    fn user_info_wire() {
+        let json = roundtrip(&RegisterResponse {
+            result: "ok".to_string(),
+            data: Some(user()),
+        });
+        assert_eq!(json["data"]["emails"][1]["primary"], json!(true));
+        assert_eq!(json["data"]["permissions"], json!(["users.manage"]));
+    }
+
+    #[test]
+    fn requests_wire() {
+        assert_eq!(
+            roundtrip(&LoginInfo {
+                username: "reporter".to_string(),
+                password: "secret".to_string(),
+            }),
+            json!({"username": "reporter", "password": "secret"})
+        );
+        assert_eq!(
+            roundtrip(&EmailResendInfo {
+                user_id: 3,
+                email: "r@example.com".to_string(),
+            }),
+            json!({"user_id": 3, "email": "r@example.com"})
+        );
+        roundtrip(&RegisterInfo::default());
+        roundtrip(&EmailConfirmationResult {
+            result: "ok".to_string(),
+            email: Some("r@example.com".to_string()),
+        });
+        assert_eq!(roundtrip(&ApiResult::new("ok")), json!({"result": "ok"}));
+    }
+}
@@ -0,0 +1,81 @@
+use crate::types::report::ReportStatus;
+use serde::{Deserialize, Serialize};
+
+/// Report in moderation of the same car observed close by shortly before or
+/// after, pictures of a new report can be added to it instead
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Duplicate {
+    pub id: i64,
+    pub observed_at: String,
+    /// How far from the new report it was observed, none when matched by the
+    /// address as one of them has no position
+    pub distance_meters: Option<f64>,
+    /// Number of pictures it already has
+    pub pictures: usize,
+    pub status: ReportStatus,
+}
+
+/// Report of a car as listed in its plate history
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PlateReport {
+    pub id: i64,
+    pub observed_at: String,
+    pub status: ReportStatus,
+}
+
+/// Place where a car was reported, possibly several times
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct PlateSpot {
+    /// Position of the first report there
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Resolved or typed address of the first report there
+    pub address: Option<String>,
+    /// Oldest first
+    pub reports: Vec<PlateReport>,
+}
+
+/// Reports of one plate over the last days, grouped by spot
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct PlateHistory {
+    pub plate: String,
+    pub country: String,
+    /// How many days back the history reaches
+    pub days: i64,
+    /// Reports on all spots
+    pub total: usize,
+    /// Most reported spot first
+    pub spots: Vec<PlateSpot>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::roundtrip;
+    use serde_json::json;
+
+    #[test]
+    fn history_wire() {
+        let history = PlateHistory {
+            plate: "1AB 2345".to_string(),
+            country: "CZ".to_string(),
+            days: 30,
+            total: 1,
+            spots: vec![PlateSpot {
+                latitude: Some(50.0755),
+                longitude: Some(14.4378),
+                address: None,
+                reports: vec![PlateReport {
+                    id: 3,
+                    observed_at: "2023-06-01T12:30:00".to_string(),
+                    status: ReportStatus::UnderReview,
+                }],
+            }],
+        };
+        let json = roundtrip(&history);
+        assert_eq!(
+            json["spots"][0]["reports"][0]["status"],
+            json!("under_review")
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+
+/// Delivery state of a report emailed to an authority
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ForwardStatus {
+    /// Accepted by the mail server
+    #[default]
+    Sent,
+    /// The mail server refused it, nothing was delivered
+    Failed,
+    /// Came back undeliverable
+    Bounced,
+    /// The authority answered
+    Replied,
+}
+
+impl ForwardStatus {
+    pub const ALL: &'static [Self] = &[Self::Sent, Self::Failed, Self::Bounced, Self::Replied];
+
+    /// Name used on the wire and in the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sent => "sent",
+            Self::Failed => "failed",
+            Self::Bounced => "bounced",
+            Self::Replied => "replied",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|s| s.as_str() == name)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Sent => "Sent",
+            Self::Failed => "Failed",
+            Self::Bounced => "Bounced",
+            Self::Replied => "Replied",
+        }
+    }
+}
+
+/// What an email threaded back onto a forwarded report is
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageKind {
+    /// Answer of the authority
+    #[default]
+    Reply,
+    /// Delivery failure report of a mail server
+    Bounce,
+}
+
+impl MessageKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Reply => "reply",
+            Self::Bounce => "bounce",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Self::Reply, Self::Bounce]
+            .into_iter()
+            .find(|k| k.as_str() == name)
+    }
+}
+
+/// Email received in reply to a forwarded report
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct InboundMessage {
+    pub id: i64,
+    pub kind: MessageKind,
+    pub sender: Option<String>,
+    pub subject: Option<String>,
+    /// Plain text body
+    pub body: String,
+    pub received_at: String,
+}
+
+/// Email sending a report to the authority in charge of the place
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Forward {
+    pub id: i64,
+    pub report_id: i64,
+    /// Name of the authority as configured
+    pub authority: String,
+    pub recipient: String,
+    /// `Message-ID` of the outgoing email without the angle brackets
+    pub message_id: String,
+    pub status: ForwardStatus,
+    /// Why the mail server refused the email
+    pub error: Option<String>,
+    pub sent_by: Option<i64>,
+    pub sent_at: String,
+    /// Replies and bounces, oldest first
+    pub messages: Vec<InboundMessage>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::roundtrip;
+    use serde_json::json;
+
+    #[test]
+    fn forward_wire() {
+        let forward = Forward {
+            id: 1,
+            report_id: 2,
+            authority: "Městská policie Praha".to_string(),
+            recipient: "podatelna@mppraha.cz".to_string(),
+            message_id: "report-2.abc@example.com".to_string(),
+            status: ForwardStatus::Replied,
+            messages: vec![InboundMessage {
+                id: 3,
+                kind: MessageKind::Reply,
+                body: "Thanks".to_string(),
+                ..InboundMessage::default()
+            }],
+            ..Forward::default()
+        };
+        let json = roundtrip(&forward);
+        assert_eq!(json["status"], json!("replied"));
+        assert_eq!(json["messages"][0]["kind"], json!("reply"));
+        for status in ForwardStatus::ALL {
+            assert_eq!(ForwardStatus::from_name(status.as_str()), Some(*status));
+        }
+        assert_eq!(MessageKind::from_name("bounce"), Some(MessageKind::Bounce));
+    }
+}
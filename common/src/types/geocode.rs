@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Postal address of an address point
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Address {
+    /// Missing in villages without street names
+    pub street: Option<String>,
+    /// Descriptive and orientation number as written on the house, e.g. `1234/12`
+    pub house_number: String,
+    pub city: String,
+    /// City district such as `Praha 2`
+    pub district: Option<String>,
+    pub postcode: Option<String>,
+}
+
+impl fmt::Display for Address {
+    /// `Vinohradská 12, Praha 2`, villages without streets use the city name
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let street = self.street.as_deref().unwrap_or(&self.city);
+        write!(f, "{street} {}", self.house_number)?;
+        let area = self.district.as_deref().unwrap_or(&self.city);
+        if self.street.is_some() || area != self.city {
+            write!(f, ", {area}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Address point found by `GET /geocode/reverse` or `GET /geocode/search`
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Place {
+    pub address: Address,
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Meters from the requested position, when one was given
+    #[serde(default)]
+    pub distance: Option<f64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::roundtrip;
+
+    #[test]
+    fn address_display() {
+        let mut address = Address {
+            street: Some("Vinohradská".to_string()),
+            house_number: "1511/12".to_string(),
+            city: "Praha".to_string(),
+            district: Some("Praha 2".to_string()),
+            postcode: Some("12000".to_string()),
+        };
+        assert_eq!(address.to_string(), "Vinohradská 1511/12, Praha 2");
+        roundtrip(&Place {
+            address: address.clone(),
+            latitude: 50.0755,
+            longitude: 14.4378,
+            distance: Some(3.5),
+        });
+        address.street = None;
+        address.district = None;
+        address.city = "Lhota".to_string();
+        assert_eq!(address.to_string(), "Lhota 1511/12");
+    }
+}
@@ -0,0 +1,91 @@
+//! Request and response bodies of the api.
+
+pub mod admin;
+pub mod auth;
+pub mod duplicate;
+pub mod forward;
+pub mod geocode;
+pub mod picture;
+pub mod report;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Validation errors keyed by field name, rendered next to each form input
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq)]
+pub struct ErrorInfo {
+    pub errors: HashMap<String, Vec<String>>,
+}
+
+impl ErrorInfo {
+    pub fn add(&mut self, field: &str, message: &str) {
+        self.errors
+            .entry(field.to_string())
+            .or_default()
+            .push(message.to_string());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Turn collected errors into a result
+    pub fn into_result(self) -> Result<(), Self> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+
+    /// All messages reported for any of the given fields
+    pub fn messages(&self, fields: &[&str]) -> Vec<String> {
+        fields
+            .iter()
+            .filter_map(|f| self.errors.get(*f))
+            .flatten()
+            .cloned()
+            .collect()
+    }
+}
+
+/// Serialize and parse back a value, checking it survives unchanged
+#[cfg(test)]
+pub(crate) fn roundtrip<T>(value: &T) -> serde_json::Value
+where
+    T: Serialize + serde::de::DeserializeOwned + PartialEq + std::fmt::Debug,
+{
+    let json = serde_json::to_value(value).unwrap();
+    let parsed: T = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(&parsed, value);
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn messages() {
+        let mut info = ErrorInfo::default();
+        assert_eq!(info.clone().into_result(), Ok(()));
+        info.add("plate", "Licence plate is required");
+        info.add("latitude", "Latitude out of range");
+        assert_eq!(
+            info.messages(&["location", "latitude"]),
+            vec!["Latitude out of range".to_string()]
+        );
+        assert!(info.into_result().is_err());
+    }
+
+    #[test]
+    fn error_info_wire() {
+        let mut info = ErrorInfo::default();
+        info.add("plate", "Licence plate is required");
+        assert_eq!(
+            roundtrip(&info),
+            json!({"errors": {"plate": ["Licence plate is required"]}})
+        );
+    }
+}
@@ -0,0 +1,165 @@
+use crate::types::report::ReportStatus;
+use serde::{Deserialize, Serialize};
+
+/// Result of one uploaded file, either the stored name or why it was rejected
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct PictureUpload {
+    pub error: Option<String>,
+    pub path: Option<String>,
+    /// Capture time read from the EXIF tags, `YYYY-MM-DDTHH:MM:SS`
+    #[serde(default)]
+    pub taken_at: Option<String>,
+    #[serde(default)]
+    pub latitude: Option<f64>,
+    #[serde(default)]
+    pub longitude: Option<f64>,
+    /// Areas blurred in the published copy
+    #[serde(default)]
+    pub regions: Vec<BlurRegion>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RegionKind {
+    /// Detected face
+    Face,
+    /// Detected plate of a vehicle other than the reported one
+    Plate,
+    /// Drawn by the reporter for something the detectors missed
+    Manual,
+}
+
+impl RegionKind {
+    /// Name used on the wire and in the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Face => "face",
+            Self::Plate => "plate",
+            Self::Manual => "manual",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Self::Face, Self::Plate, Self::Manual]
+            .into_iter()
+            .find(|kind| kind.as_str() == name)
+    }
+}
+
+/// Rectangle blurred in the published copy of a picture, in fractions of
+/// the picture width and height so it doesn't depend on the resolution
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct BlurRegion {
+    pub kind: RegionKind,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl BlurRegion {
+    /// Region in pixels of a `width`x`height` picture, cut to its bounds
+    pub fn to_pixels(&self, width: u32, height: u32) -> Option<(u32, u32, u32, u32)> {
+        let clamp = |v: f64| v.clamp(0.0, 1.0);
+        let (x1, y1) = (clamp(self.x), clamp(self.y));
+        let (x2, y2) = (clamp(self.x + self.width), clamp(self.y + self.height));
+        let left = (x1 * width as f64).floor() as u32;
+        let top = (y1 * height as f64).floor() as u32;
+        let right = (x2 * width as f64).ceil() as u32;
+        let bottom = (y2 * height as f64).ceil() as u32;
+        (right > left && bottom > top).then_some((left, top, right - left, bottom - top))
+    }
+
+    /// The same area after turning the picture clockwise by quarter turns
+    pub fn rotated(&self, quarter_turns: u8) -> Self {
+        (0..quarter_turns % 4).fold(*self, |r, _| Self {
+            x: 1.0 - r.y - r.height,
+            y: r.x,
+            width: r.height,
+            height: r.width,
+            ..r
+        })
+    }
+}
+
+/// Picture of another report looking like a picture of the report under
+/// review, the same photo reused or saved again
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SimilarPicture {
+    /// Picture of the report under review
+    pub picture: String,
+    /// The look-alike
+    pub similar: String,
+    /// Differing bits of the perceptual hashes, 0 for the same photo
+    pub distance: u32,
+    pub report_id: i64,
+    pub plate: String,
+    pub observed_at: String,
+    pub status: ReportStatus,
+    /// Both reports were submitted by the same signed in user
+    pub same_reporter: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::roundtrip;
+    use serde_json::json;
+
+    #[test]
+    fn upload_wire() {
+        let json = roundtrip(&PictureUpload {
+            error: None,
+            path: Some("ab.jpg".to_string()),
+            taken_at: Some("2023-06-01T12:30:00".to_string()),
+            latitude: Some(50.0),
+            longitude: Some(14.0),
+            regions: vec![BlurRegion {
+                kind: RegionKind::Face,
+                x: 0.1,
+                y: 0.2,
+                width: 0.3,
+                height: 0.4,
+            }],
+        });
+        assert_eq!(json["regions"][0]["kind"], json!("face"));
+        assert_eq!(json["taken_at"], json!("2023-06-01T12:30:00"));
+        // Metadata is optional for clients that don't send it
+        let rejected: PictureUpload =
+            serde_json::from_value(json!({"error": "Not a JPEG", "path": null})).unwrap();
+        assert_eq!(rejected.error.as_deref(), Some("Not a JPEG"));
+    }
+
+    #[test]
+    fn region_geometry() {
+        let region = BlurRegion {
+            kind: RegionKind::Manual,
+            x: 0.5,
+            y: 0.0,
+            width: 0.75,
+            height: 0.25,
+        };
+        assert_eq!(region.to_pixels(200, 100), Some((100, 0, 100, 25)));
+        assert_eq!(BlurRegion { x: 1.5, ..region }.to_pixels(200, 100), None);
+        // Top right corner moves to the bottom right after a clockwise turn
+        let turned = BlurRegion {
+            width: 0.5,
+            ..region
+        }
+        .rotated(1);
+        assert_eq!(
+            (turned.x, turned.y, turned.width, turned.height),
+            (0.75, 0.5, 0.25, 0.5)
+        );
+        assert_eq!(region.rotated(4), region);
+    }
+
+    #[test]
+    fn region_kind_names() {
+        for kind in [RegionKind::Face, RegionKind::Plate, RegionKind::Manual] {
+            assert_eq!(roundtrip(&kind), json!(kind.as_str()));
+            assert_eq!(RegionKind::from_name(kind.as_str()), Some(kind));
+        }
+        assert_eq!(RegionKind::from_name("car"), None);
+    }
+}
@@ -0,0 +1,416 @@
+use crate::permissions::{granted, FORWARD_REPORTS, REVIEW_REPORTS};
+use crate::plate::{self, Plate, PlateError};
+use crate::types::geocode::Address;
+use serde::{Deserialize, Serialize};
+
+/// Report as submitted by the Report form
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct NewReport {
+    pub plate: String,
+    /// Country the plate was issued in, Czech when empty
+    #[serde(default)]
+    pub country: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub address: Option<String>,
+    pub observed_at: String,
+    pub description: Option<String>,
+    /// Picture paths with optional captions, the first one is the primary evidence
+    pub pictures: Vec<(String, Option<String>)>,
+}
+
+/// Stored report
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Report {
+    pub id: i64,
+    pub plate: String,
+    pub country: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub address: Option<String>,
+    /// Nearest address point to the GPS position, from the offline geocoder
+    #[serde(default)]
+    pub resolved_address: Option<Address>,
+    pub observed_at: String,
+    pub description: Option<String>,
+    pub pictures: Vec<(String, Option<String>)>,
+    pub reporter_id: Option<i64>,
+    pub created_at: String,
+    #[serde(default)]
+    pub status: ReportStatus,
+}
+
+/// Where a report is in its processing, moved along [`TRANSITIONS`]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportStatus {
+    /// Stored but not sent for review yet
+    Draft,
+    /// Waiting in the moderation queue
+    #[default]
+    Submitted,
+    /// Taken by a moderator
+    UnderReview,
+    /// Sent to the authority in charge
+    Forwarded,
+    /// The authority dealt with it
+    Resolved,
+    Rejected,
+    /// Taken back by the reporter
+    Withdrawn,
+}
+
+/// Who may make a transition
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Actor {
+    /// The user who submitted the report
+    Reporter,
+    /// Users holding the permission
+    Holder(&'static str),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transition {
+    pub from: ReportStatus,
+    pub to: ReportStatus,
+    pub by: Actor,
+}
+
+const fn transition(from: ReportStatus, to: ReportStatus, by: Actor) -> Transition {
+    Transition { from, to, by }
+}
+
+/// Every allowed status change, anything else is refused
+pub const TRANSITIONS: &[Transition] = {
+    use Actor::{Holder, Reporter};
+    use ReportStatus::*;
+    &[
+        transition(Draft, Submitted, Reporter),
+        transition(Draft, Withdrawn, Reporter),
+        transition(Submitted, Withdrawn, Reporter),
+        transition(Submitted, UnderReview, Holder(REVIEW_REPORTS)),
+        transition(UnderReview, Submitted, Holder(REVIEW_REPORTS)),
+        transition(UnderReview, Rejected, Holder(REVIEW_REPORTS)),
+        transition(UnderReview, Forwarded, Holder(FORWARD_REPORTS)),
+        transition(Forwarded, Resolved, Holder(FORWARD_REPORTS)),
+        transition(Forwarded, Rejected, Holder(FORWARD_REPORTS)),
+    ]
+};
+
+impl ReportStatus {
+    pub const ALL: &'static [Self] = &[
+        Self::Draft,
+        Self::Submitted,
+        Self::UnderReview,
+        Self::Forwarded,
+        Self::Resolved,
+        Self::Rejected,
+        Self::Withdrawn,
+    ];
+    /// Statuses of reports waiting for a moderator
+    pub const QUEUE: &'static [Self] = &[Self::Submitted, Self::UnderReview];
+
+    /// Name used on the wire and in the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Draft => "draft",
+            Self::Submitted => "submitted",
+            Self::UnderReview => "under_review",
+            Self::Forwarded => "forwarded",
+            Self::Resolved => "resolved",
+            Self::Rejected => "rejected",
+            Self::Withdrawn => "withdrawn",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|s| s.as_str() == name)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Draft => "Draft",
+            Self::Submitted => "Submitted",
+            Self::UnderReview => "Under review",
+            Self::Forwarded => "Forwarded",
+            Self::Resolved => "Resolved",
+            Self::Rejected => "Rejected",
+            Self::Withdrawn => "Withdrawn",
+        }
+    }
+
+    /// The reporter can still amend or withdraw the report
+    pub fn is_open(&self) -> bool {
+        matches!(self, Self::Draft | Self::Submitted)
+    }
+
+    /// Moving to this status needs one of the configured rejection reasons
+    pub fn needs_reason(&self) -> bool {
+        *self == Self::Rejected
+    }
+
+    /// Whether the reporter of the report (`is_reporter`) or a user holding
+    /// `permissions` may move it to `to`
+    pub fn allows<S: AsRef<str>>(&self, to: Self, is_reporter: bool, permissions: &[S]) -> bool {
+        TRANSITIONS
+            .iter()
+            .filter(|t| t.from == *self && t.to == to)
+            .any(|t| match t.by {
+                Actor::Reporter => is_reporter,
+                Actor::Holder(permission) => granted(permissions, permission),
+            })
+    }
+
+    /// Statuses the user may move the report to, in the order of [`TRANSITIONS`]
+    pub fn next<S: AsRef<str>>(&self, is_reporter: bool, permissions: &[S]) -> Vec<Self> {
+        TRANSITIONS
+            .iter()
+            .filter(|t| t.from == *self && self.allows(t.to, is_reporter, permissions))
+            .map(|t| t.to)
+            .collect()
+    }
+}
+
+/// Entry of the status history of a report, amendments keep the status
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct StatusChange {
+    pub status: ReportStatus,
+    pub note: Option<String>,
+    /// Why the report was rejected, one of the configured reasons
+    #[serde(default)]
+    pub reason: Option<String>,
+    pub changed_by: Option<i64>,
+    pub changed_at: String,
+}
+
+/// Reporter taking back a report
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Withdrawal {
+    pub reason: Option<String>,
+}
+
+/// Moderator moving a report to another status
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct StatusUpdate {
+    pub status: ReportStatus,
+    /// Reviewer note kept in the history
+    pub note: Option<String>,
+    /// Required when rejecting
+    pub reason: Option<String>,
+}
+
+/// Report with everything that happened to it, oldest change first
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ReportDetail {
+    pub report: Report,
+    pub history: Vec<StatusChange>,
+}
+
+/// Filter and page of a report list
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReportQuery {
+    /// Starting at 1
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+    pub status: Option<ReportStatus>,
+    /// Part of the plate, spaces are ignored
+    pub plate: Option<String>,
+    /// Observed on or after this day, `YYYY-MM-DD`
+    pub from: Option<String>,
+    /// Observed on or before this day, `YYYY-MM-DD`
+    pub to: Option<String>,
+}
+
+impl ReportQuery {
+    /// Query string with the fields that are set, without the leading `?`
+    pub fn to_query_string(&self) -> String {
+        let encode = |value: &str| {
+            value
+                .bytes()
+                .map(|b| match b {
+                    b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                        (b as char).to_string()
+                    }
+                    b => format!("%{b:02X}"),
+                })
+                .collect::<String>()
+        };
+        [
+            ("page", self.page.map(|p| p.to_string())),
+            ("per_page", self.per_page.map(|p| p.to_string())),
+            ("status", self.status.map(|s| s.as_str().to_string())),
+            ("plate", self.plate.clone()),
+            ("from", self.from.clone()),
+            ("to", self.to.clone()),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| {
+            value
+                .filter(|v| !v.is_empty())
+                .map(|v| format!("{key}={}", encode(&v)))
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+    }
+}
+
+/// One page of a report list
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ReportPage {
+    pub reports: Vec<Report>,
+    /// Reports matching the filter on all pages
+    pub total: u64,
+    pub page: u32,
+    pub per_page: u32,
+}
+
+impl ReportPage {
+    pub fn pages(&self) -> u32 {
+        (self.total.div_ceil(u64::from(self.per_page.max(1))) as u32).max(1)
+    }
+}
+
+impl NewReport {
+    pub fn country(&self) -> &str {
+        if self.country.trim().is_empty() {
+            plate::CZECH
+        } else {
+            &self.country
+        }
+    }
+
+    /// Parse the plate in the selected country
+    pub fn parse_plate(&self) -> Result<Plate, PlateError> {
+        Plate::parse(&self.plate, self.country())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::permissions::SUPER_ADMIN;
+    use crate::types::roundtrip;
+    use serde_json::json;
+
+    #[test]
+    fn plate_country() {
+        let mut report = NewReport {
+            plate: "1ab2345".to_string(),
+            ..NewReport::default()
+        };
+        assert_eq!(report.parse_plate().unwrap().to_string(), "1AB 2345");
+        report.plate = "XY 12".to_string();
+        assert!(report.parse_plate().is_err());
+        report.country = "SK".to_string();
+        assert_eq!(report.parse_plate().unwrap().to_string(), "XY12");
+    }
+
+    #[test]
+    fn report_wire() {
+        let report = NewReport {
+            plate: "1AB 2345".to_string(),
+            country: "CZ".to_string(),
+            latitude: Some(50.0755),
+            longitude: Some(14.4378),
+            address: Some("Vinohradská 12".to_string()),
+            observed_at: "2023-06-01T12:30".to_string(),
+            description: Some("Blocking the crossing".to_string()),
+            pictures: vec![
+                ("ab.jpg".to_string(), Some("Front".to_string())),
+                ("cd.jpg".to_string(), None),
+            ],
+        };
+        let json = roundtrip(&report);
+        assert_eq!(json["observed_at"], json!("2023-06-01T12:30"));
+        assert_eq!(
+            json["pictures"],
+            json!([["ab.jpg", "Front"], ["cd.jpg", null]])
+        );
+        // Older clients don't send a country
+        let mut old = json.clone();
+        old.as_object_mut().unwrap().remove("country");
+        let parsed: NewReport = serde_json::from_value(old).unwrap();
+        assert_eq!(parsed.country(), "CZ");
+
+        let stored = Report {
+            id: 1,
+            plate: report.plate,
+            country: report.country,
+            reporter_id: Some(2),
+            created_at: "2023-06-01 12:31:00".to_string(),
+            status: ReportStatus::Withdrawn,
+            ..Report::default()
+        };
+        assert_eq!(roundtrip(&stored)["status"], json!("withdrawn"));
+        roundtrip(&ReportDetail {
+            report: stored,
+            history: vec![StatusChange {
+                status: ReportStatus::Rejected,
+                note: Some("Plate not readable".to_string()),
+                reason: Some("Insufficient evidence".to_string()),
+                changed_by: Some(2),
+                changed_at: "2023-06-01T12:31:00".to_string(),
+            }],
+        });
+    }
+
+    #[test]
+    fn status_names() {
+        for status in ReportStatus::ALL {
+            assert_eq!(ReportStatus::from_name(status.as_str()), Some(*status));
+        }
+        assert_eq!(ReportStatus::from_name("unknown"), None);
+        assert!(ReportStatus::Submitted.is_open());
+        assert!(!ReportStatus::Withdrawn.is_open());
+        assert_eq!(
+            serde_json::to_value(ReportStatus::UnderReview).unwrap(),
+            json!("under_review")
+        );
+    }
+
+    #[test]
+    fn transitions() {
+        use ReportStatus::*;
+        let none: &[&str] = &[];
+        assert!(Submitted.allows(Withdrawn, true, none));
+        assert!(!Submitted.allows(Withdrawn, false, &[SUPER_ADMIN]));
+        assert!(!UnderReview.allows(Withdrawn, true, none));
+        assert!(Submitted.allows(UnderReview, false, &[REVIEW_REPORTS]));
+        assert!(!UnderReview.allows(Forwarded, false, &[REVIEW_REPORTS]));
+        assert_eq!(
+            UnderReview.next(false, &[REVIEW_REPORTS]),
+            vec![Submitted, Rejected]
+        );
+        assert_eq!(
+            UnderReview.next(false, &[SUPER_ADMIN]),
+            vec![Submitted, Rejected, Forwarded]
+        );
+        assert_eq!(Draft.next(true, none), vec![Submitted, Withdrawn]);
+        for status in [Resolved, Rejected, Withdrawn] {
+            assert!(status.next(true, &[SUPER_ADMIN]).is_empty());
+        }
+    }
+
+    #[test]
+    fn query_string() {
+        assert_eq!(ReportQuery::default().to_query_string(), "");
+        let query = ReportQuery {
+            page: Some(2),
+            status: Some(ReportStatus::Submitted),
+            plate: Some("1AB 2/3".to_string()),
+            from: Some(String::new()),
+            ..ReportQuery::default()
+        };
+        assert_eq!(
+            query.to_query_string(),
+            "page=2&status=submitted&plate=1AB%202%2F3"
+        );
+        let page = ReportPage {
+            total: 41,
+            per_page: 20,
+            ..ReportPage::default()
+        };
+        assert_eq!(page.pages(), 3);
+        assert_eq!(ReportPage::default().pages(), 1);
+    }
+}
@@ -1,8 +1,18 @@
+use crate::components::outbox_provider::OutboxProvider;
+use crate::components::require_permission::RequirePermission;
+use crate::components::update_prompt::UpdatePrompt;
 use crate::components::user_context_provider::UserContextProvider;
+use crate::hooks::Routes;
+use crate::pages::admin::{AdminRoles, AdminUsers};
+use crate::pages::confirm_email::ConfirmEmail;
 use crate::pages::footer::Footer;
 use crate::pages::header::Header;
 use crate::pages::page_not_found::PageNotFound;
 use crate::pages::home::Home;
+use crate::pages::moderation::{ModerationQueue, ModerationReport};
+use crate::pages::my_reports::MyReports;
+use crate::pages::report_detail::ReportDetail;
+use crate::types::auth::{MANAGE_ROLES, MANAGE_USERS, REVIEW_REPORTS};
 use tracing::debug;
 use yew::html::Html;
 use yew::prelude::*;
@@ -12,24 +22,53 @@ use yew_router::prelude::*;
 pub enum Route {
     #[at("/")]
     Home,
+    #[at("/email")]
+    ConfirmEmail,
+    #[at("/reports")]
+    MyReports,
+    #[at("/reports/:id")]
+    ReportDetail { id: i64 },
+    #[at("/moderation")]
+    ModerationQueue,
+    #[at("/moderation/:id")]
+    ModerationReport { id: i64 },
+    #[at("/admin/users")]
+    AdminUsers,
+    #[at("/admin/roles")]
+    AdminRoles,
     #[not_found]
     #[at("/404")]
     NotFound,
 }
 
+impl Route {
+    /// Permission needed to open the route
+    pub fn permission(&self) -> Option<&'static str> {
+        match self {
+            Self::AdminUsers => Some(MANAGE_USERS),
+            Self::AdminRoles => Some(MANAGE_ROLES),
+            Self::ModerationQueue | Self::ModerationReport { .. } => Some(REVIEW_REPORTS),
+            _ => None,
+        }
+    }
+}
+
 #[function_component(App)]
 pub fn app() -> Html {
     html!(
         <UserContextProvider>
-            <BrowserRouter>
-                <Header />
-                <div class="d-flex p-2 flex-grow-1">
-                    <main class="container">
-                        <Switch<Route> render={switch} />
-                    </main>
-                </div>
-                <Footer />
-            </BrowserRouter>
+            <OutboxProvider>
+                <BrowserRouter>
+                    <Header />
+                    <div class="d-flex p-2 flex-grow-1">
+                        <main class="container">
+                            <Switch<Route> render={switch} />
+                        </main>
+                    </div>
+                    <Footer />
+                    <UpdatePrompt />
+                </BrowserRouter>
+            </OutboxProvider>
         </UserContextProvider>
     )
 }
@@ -38,8 +77,26 @@ pub fn app() -> Html {
 #[allow(clippy::needless_pass_by_value)]
 fn switch(routes: Route) -> Html {
     debug!("Routing to {:?}", routes);
-    match routes {
+    match Routes::from(routes) {
+        Routes::Admin(route) => html!(
+            <RequirePermission permission={route.permission().unwrap_or_default()} redirect={true}>
+                { render(route) }
+            </RequirePermission>
+        ),
+        Routes::Default(route) => render(route),
+    }
+}
+
+fn render(route: Route) -> Html {
+    match route {
         Route::Home => html!( <Home /> ),
+        Route::ConfirmEmail => html!( <ConfirmEmail /> ),
+        Route::MyReports => html!( <MyReports /> ),
+        Route::ReportDetail { id } => html!( <ReportDetail {id} /> ),
+        Route::ModerationQueue => html!( <ModerationQueue /> ),
+        Route::ModerationReport { id } => html!( <ModerationReport {id} /> ),
+        Route::AdminUsers => html!( <AdminUsers /> ),
+        Route::AdminRoles => html!( <AdminRoles /> ),
         Route::NotFound => html!( <PageNotFound /> ),
     }
 }
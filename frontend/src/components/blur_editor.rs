@@ -0,0 +1,165 @@
+use crate::types::picture::{drawn_region, BlurRegion, SelectedPicture};
+use web_sys::HtmlElement;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct BlurEditorProps {
+    pub picture: SelectedPicture,
+    pub onsave: Callback<Vec<BlurRegion>>,
+    pub onclose: Callback<()>,
+}
+
+/// Dialog for drawing rectangles the server blurs in addition to the faces
+/// and plates it finds on its own
+///
+/// The picture is shown with its pending rotation, but regions are kept in
+/// the frame of the unrotated picture: mouse offsets are reported in the
+/// local coordinates of the rotated element.
+#[function_component(BlurEditor)]
+pub fn blur_editor(props: &BlurEditorProps) -> Html {
+    let regions = use_state(|| props.picture.blur.clone());
+    // Start and current point of the rectangle being drawn
+    let drawing = use_state(|| None::<((f64, f64), (f64, f64))>);
+    let area = use_node_ref();
+
+    let position = {
+        let area = area.clone();
+        move |e: &MouseEvent| {
+            let area = area.cast::<HtmlElement>()?;
+            // Offsets over a remove button are relative to the button
+            if e.target_dyn_into::<HtmlElement>()? != area {
+                return None;
+            }
+            let (width, height) = (area.client_width(), area.client_height());
+            (width > 0 && height > 0).then(|| {
+                (
+                    e.offset_x() as f64 / width as f64,
+                    e.offset_y() as f64 / height as f64,
+                )
+            })
+        }
+    };
+    let on_mouse_down = {
+        let drawing = drawing.clone();
+        let position = position.clone();
+        Callback::from(move |e: MouseEvent| {
+            e.prevent_default();
+            drawing.set(position(&e).map(|p| (p, p)));
+        })
+    };
+    let on_mouse_move = {
+        let drawing = drawing.clone();
+        Callback::from(move |e: MouseEvent| {
+            if let (Some((start, _)), Some(current)) = (*drawing, position(&e)) {
+                drawing.set(Some((start, current)));
+            }
+        })
+    };
+    let on_mouse_up = {
+        let drawing = drawing.clone();
+        let regions = regions.clone();
+        Callback::from(move |_: MouseEvent| {
+            if let Some(region) = drawing.and_then(|(start, end)| drawn_region(start, end)) {
+                let mut updated = (*regions).clone();
+                updated.push(region);
+                regions.set(updated);
+            }
+            drawing.set(None);
+        })
+    };
+    let on_save = {
+        let regions = regions.clone();
+        let onsave = props.onsave.clone();
+        Callback::from(move |_: MouseEvent| onsave.emit((*regions).clone()))
+    };
+    let on_close = props.onclose.reform(|_: MouseEvent| ());
+    let on_clear = {
+        let regions = regions.clone();
+        Callback::from(move |_: MouseEvent| regions.set(Vec::new()))
+    };
+
+    let rectangle = |region: &BlurRegion| {
+        format!(
+            "left: {}%; top: {}%; width: {}%; height: {}%;",
+            region.x * 100.0,
+            region.y * 100.0,
+            region.width * 100.0,
+            region.height * 100.0
+        )
+    };
+    let drawn = regions.iter().enumerate().map(|(index, region)| {
+        let style = rectangle(region);
+        let on_remove = {
+            let regions = regions.clone();
+            Callback::from(move |e: MouseEvent| {
+                e.stop_propagation();
+                let mut updated = (*regions).clone();
+                updated.remove(index);
+                regions.set(updated);
+            })
+        };
+        html!(
+            <div class="position-absolute border border-2 border-danger"
+                style={format!("{style} pointer-events: none; background: rgba(220, 53, 69, 0.25);")}>
+                <button type="button" class="btn-close btn-close-white position-absolute top-0 end-0"
+                    style="pointer-events: auto; font-size: 0.6rem;"
+                    title="Remove"
+                    onmousedown={|e: MouseEvent| e.stop_propagation()}
+                    onclick={on_remove}></button>
+            </div>
+        )
+    });
+    let pending = drawing
+        .and_then(|(start, end)| drawn_region(start, end))
+        .map(|region| {
+            html!(
+                <div class="position-absolute border border-2 border-warning"
+                    style={format!("{} pointer-events: none;", rectangle(&region))}></div>
+            )
+        });
+    let turns = props.picture.quarter_turns;
+    // A sideways picture has to fit the dialog with its width as height
+    let size = if turns % 2 == 1 {
+        "max-width: 60vh; max-height: 90vw;"
+    } else {
+        "max-width: 100%; max-height: 60vh;"
+    };
+
+    html!(
+        <div class="modal d-block" tabindex="-1" style="background: rgba(0, 0, 0, 0.5);">
+            <div class="modal-dialog modal-lg modal-dialog-centered">
+                <div class="modal-content">
+                    <div class="modal-header">
+                        <h5 class="modal-title">{"Blur parts of the picture"}</h5>
+                        <button type="button" class="btn-close" title="Close" onclick={on_close.clone()}></button>
+                    </div>
+                    <div class="modal-body text-center overflow-hidden">
+                        <p class="small text-muted">
+                            {"Faces and other vehicles' plates are blurred automatically. Drag over anything else that should not be published."}
+                        </p>
+                        <div ref={area}
+                            class="position-relative d-inline-block user-select-none"
+                            style={format!("cursor: crosshair; transform: rotate({}deg);", u16::from(turns) * 90)}
+                            onmousedown={on_mouse_down}
+                            onmousemove={on_mouse_move}
+                            onmouseup={on_mouse_up.clone()}
+                            onmouseleave={on_mouse_up}>
+                            <img src={props.picture.url.to_string()}
+                                class="d-block"
+                                style={format!("{size} pointer-events: none;")}
+                                draggable="false"
+                                alt={props.picture.name()} />
+                            { for drawn }
+                            { for pending }
+                        </div>
+                    </div>
+                    <div class="modal-footer">
+                        <button type="button" class="btn btn-outline-danger me-auto" disabled={regions.is_empty()} onclick={on_clear}>{"Remove all"}</button>
+                        <button type="button" class="btn btn-secondary" onclick={on_close}>{"Cancel"}</button>
+                        <button type="button" class="btn btn-primary" onclick={on_save}>{"Save"}</button>
+                    </div>
+                </div>
+            </div>
+        </div>
+    )
+}
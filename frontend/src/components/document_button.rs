@@ -0,0 +1,66 @@
+use crate::services::reports::report_document;
+use std::rc::Rc;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlAnchorElement;
+use yew::prelude::*;
+use yew_hooks::use_async;
+
+#[derive(Properties, Clone, PartialEq)]
+pub struct DocumentButtonProps {
+    /// Report the complaint is about
+    pub id: i64,
+}
+
+/// Let the browser save an object URL under `file_name`
+fn save(url: &str, file_name: &str) {
+    let Some(document) = web_sys::window().and_then(|w| w.document()) else {
+        return;
+    };
+    if let Some(link) = document
+        .create_element("a")
+        .ok()
+        .and_then(|e| e.dyn_into::<HtmlAnchorElement>().ok())
+    {
+        link.set_href(url);
+        link.set_download(file_name);
+        link.click();
+    }
+}
+
+/// Downloads the complaint PDF of a report, which needs the user's token
+#[function_component(DocumentButton)]
+pub fn document_button(props: &DocumentButtonProps) -> Html {
+    let id = props.id;
+    // Kept in the result so the object URL stays valid while the browser saves it
+    let document = use_async(async move { report_document(id).await.map(Rc::new) });
+    use_effect_with_deps(
+        move |url: &Option<String>| {
+            if let Some(url) = url {
+                save(url, &format!("report-{id}.pdf"));
+            }
+            || ()
+        },
+        document.data.as_ref().map(|url| url.to_string()),
+    );
+
+    let onclick = {
+        let document = document.clone();
+        Callback::from(move |_: MouseEvent| document.run())
+    };
+
+    html!(
+        <>
+            <button type="button" class="btn btn-outline-secondary" {onclick} disabled={document.loading}>
+                if document.loading {
+                    <span class="spinner-border spinner-border-sm me-1" role="status"></span>
+                } else {
+                    <i class="fa-solid fa-file-pdf me-1"></i>
+                }
+                { "Complaint PDF" }
+            </button>
+            if let Some(error) = &document.error {
+                <span class="text-danger small align-self-center">{ error.to_string() }</span>
+            }
+        </>
+    )
+}
@@ -0,0 +1,42 @@
+use crate::hooks::use_user_context;
+use crate::services::auth::resend;
+use yew::prelude::*;
+use yew_hooks::use_async;
+
+#[derive(Properties, Clone, PartialEq)]
+pub struct Props {
+    pub email: String,
+}
+
+/// Reminder to confirm an email address with a button to send the link again
+#[function_component(EmailBanner)]
+pub fn email_banner(props: &Props) -> Html {
+    let user_ctx = use_user_context();
+    let send = {
+        let email = props.email.clone();
+        let user_id = user_ctx.id;
+        use_async(async move { resend(user_id, email).await })
+    };
+
+    let onclick = {
+        let send = send.clone();
+        Callback::from(move |_| send.run())
+    };
+
+    html!(
+        <div class="alert alert-warning d-flex align-items-center m-2 mb-0" role="alert">
+            <span class="flex-grow-1">
+                { format!("Please confirm your email address {}.", props.email) }
+                if let Some(result) = &send.data {
+                    { format!(" {}.", result.result) }
+                }
+                if let Some(error) = &send.error {
+                    <span class="text-danger">{ format!(" {error}") }</span>
+                }
+            </span>
+            <button type="button" class="btn btn-sm btn-outline-secondary" {onclick} disabled={send.loading}>
+                { "Resend link" }
+            </button>
+        </div>
+    )
+}
@@ -0,0 +1,173 @@
+use crate::components::blur_editor::BlurEditor;
+use crate::types::picture::{move_item, BlurRegion, Progress, SelectedPicture};
+use std::collections::HashMap;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct GalleryProps {
+    /// The first picture is the primary evidence
+    pub pictures: Vec<SelectedPicture>,
+    /// Processing and upload state keyed by file name
+    pub progress: HashMap<String, Progress>,
+    pub onchange: Callback<Vec<SelectedPicture>>,
+}
+
+/// Thumbnails of the selected pictures with caption, rotate, blur, remove
+/// and drag to reorder
+#[function_component(Gallery)]
+pub fn gallery(props: &GalleryProps) -> Html {
+    let dragging = use_state(|| None::<usize>);
+    // Picture whose blur regions are being edited
+    let editing = use_state(|| None::<usize>);
+
+    // Callback applying an edit to a copy of the pictures
+    let edit = |index: usize, action: fn(&mut Vec<SelectedPicture>, usize)| {
+        let pictures = props.pictures.clone();
+        let onchange = props.onchange.clone();
+        Callback::from(move |_: MouseEvent| {
+            let mut pictures = pictures.clone();
+            action(&mut pictures, index);
+            onchange.emit(pictures);
+        })
+    };
+
+    let thumbnail = |(index, picture): (usize, &SelectedPicture)| {
+        let on_caption = {
+            let pictures = props.pictures.clone();
+            let onchange = props.onchange.clone();
+            Callback::from(move |e: InputEvent| {
+                let input: HtmlInputElement = e.target_unchecked_into();
+                let mut pictures = pictures.clone();
+                pictures[index].caption = input.value();
+                onchange.emit(pictures);
+            })
+        };
+        let on_drag_start = {
+            let dragging = dragging.clone();
+            Callback::from(move |e: DragEvent| {
+                if let Some(data) = e.data_transfer() {
+                    data.set_effect_allowed("move");
+                    // Firefox only starts dragging with some data set
+                    data.set_data("text/plain", &index.to_string()).ok();
+                }
+                dragging.set(Some(index));
+            })
+        };
+        let on_drop = {
+            let dragging = dragging.clone();
+            let pictures = props.pictures.clone();
+            let onchange = props.onchange.clone();
+            Callback::from(move |e: DragEvent| {
+                e.prevent_default();
+                if let Some(from) = *dragging {
+                    let mut pictures = pictures.clone();
+                    move_item(&mut pictures, from, index);
+                    onchange.emit(pictures);
+                }
+                dragging.set(None);
+            })
+        };
+        let on_drag_end = {
+            let dragging = dragging.clone();
+            Callback::from(move |_: DragEvent| dragging.set(None))
+        };
+        let on_blur = {
+            let editing = editing.clone();
+            Callback::from(move |_: MouseEvent| editing.set(Some(index)))
+        };
+        let progress = props.progress.get(&picture.name());
+        let primary = index == 0;
+
+        html!(
+            <div class="col" key={&*picture.url}
+                draggable="true"
+                ondragstart={on_drag_start}
+                ondragover={|e: DragEvent| e.prevent_default()}
+                ondrop={on_drop}
+                ondragend={on_drag_end}>
+                <div class={classes!("card", "h-100", primary.then_some("border-primary"), (*dragging == Some(index)).then_some("opacity-50"))}>
+                    <div class="ratio ratio-4x3 overflow-hidden bg-light">
+                        <img src={picture.url.to_string()}
+                            class="w-100 h-100"
+                            style={format!("object-fit: contain; transform: rotate({}deg);", u16::from(picture.quarter_turns) * 90)}
+                            alt={picture.caption().unwrap_or_else(|| picture.name())} />
+                    </div>
+                    if primary {
+                        <span class="badge bg-primary position-absolute top-0 start-0 m-1">{"Primary"}</span>
+                    }
+                    if !picture.blur.is_empty() {
+                        <span class="badge bg-danger position-absolute top-0 end-0 m-1" title="Areas blurred by hand">
+                            <i class="fa-solid fa-eye-slash"></i>{format!(" {}", picture.blur.len())}
+                        </span>
+                    }
+                    <div class="card-body p-1">
+                        <input class="form-control form-control-sm"
+                            type="text"
+                            placeholder="Caption"
+                            value={picture.caption.clone()}
+                            oninput={on_caption} />
+                        if let Some(progress) = progress {
+                            <div class="small text-muted text-truncate mt-1" title={picture.name()}>{ progress.label() }</div>
+                            <div class="progress" style="height: 4px;">
+                                <div
+                                    class={classes!("progress-bar", matches!(progress, Progress::Failed(_)).then_some("bg-warning"))}
+                                    role="progressbar"
+                                    style={format!("width: {}%;", progress.percent())}></div>
+                            </div>
+                        }
+                    </div>
+                    <div class="card-footer p-1 d-flex justify-content-between">
+                        <button type="button" class="btn btn-sm btn-outline-secondary" title="Rotate"
+                            onclick={edit(index, |p, i| p[i].rotate())}>
+                            <i class="fa-solid fa-rotate-right"></i>
+                        </button>
+                        <button type="button" class="btn btn-sm btn-outline-secondary" title="Blur parts of the picture"
+                            onclick={on_blur}>
+                            <i class="fa-solid fa-eye-slash"></i>
+                        </button>
+                        <button type="button" class="btn btn-sm btn-outline-primary" title="Mark as primary evidence"
+                            disabled={primary}
+                            onclick={edit(index, |p, i| move_item(p, i, 0))}>
+                            <i class="fa-solid fa-star"></i>
+                        </button>
+                        <button type="button" class="btn btn-sm btn-outline-danger" title="Remove"
+                            onclick={edit(index, |p, i| { p.remove(i); })}>
+                            <i class="fa-solid fa-trash"></i>
+                        </button>
+                    </div>
+                </div>
+            </div>
+        )
+    };
+
+    let editor = editing
+        .and_then(|index| props.pictures.get(index).map(|p| (index, p.clone())))
+        .map(|(index, picture)| {
+            let on_save = {
+                let editing = editing.clone();
+                let pictures = props.pictures.clone();
+                let onchange = props.onchange.clone();
+                Callback::from(move |blur: Vec<BlurRegion>| {
+                    let mut pictures = pictures.clone();
+                    pictures[index].blur = blur;
+                    onchange.emit(pictures);
+                    editing.set(None);
+                })
+            };
+            let on_close = {
+                let editing = editing.clone();
+                Callback::from(move |_| editing.set(None))
+            };
+            html!(<BlurEditor {picture} onsave={on_save} onclose={on_close} />)
+        });
+
+    html!(
+        <>
+            <div class="row row-cols-2 row-cols-md-4 g-2 mt-1">
+                { for props.pictures.iter().enumerate().map(thumbnail) }
+            </div>
+            { for editor }
+        </>
+    )
+}
@@ -1,9 +1,67 @@
+use crate::types::geolocation::{GeoSettings, GeoState, GeoStatus};
+use crate::types::map::parse_coordinate;
+use web_sys::HtmlInputElement;
 use yew::prelude::*;
-use yew_hooks::use_geolocation;
 
+#[derive(Properties, Clone, PartialEq)]
+pub struct GeoLocationProps {
+    pub value: Option<(f64, f64)>,
+    /// The user typed a position
+    pub onchange: Callback<(f64, f64)>,
+    /// Whether the fields can be edited instead of following the device
+    pub unlocked: bool,
+    pub ontoggle: Callback<()>,
+    #[prop_or_default]
+    pub invalid: bool,
+}
+
+fn coordinate_text(value: Option<f64>) -> String {
+    value.map(|v| format!("{v:.6}")).unwrap_or_default()
+}
+
+/// Latitude and longitude fields, read-only until manual editing is unlocked
 #[function_component(GeoLocation)]
-pub fn geo_location() -> Html {
-    let location = use_geolocation();
+pub fn geo_location(props: &GeoLocationProps) -> Html {
+    // Typed text, kept apart from the value so half typed numbers aren't replaced
+    let latitude = use_state(|| coordinate_text(props.value.map(|v| v.0)));
+    let longitude = use_state(|| coordinate_text(props.value.map(|v| v.1)));
+    {
+        let latitude = latitude.clone();
+        let longitude = longitude.clone();
+        use_effect_with_deps(
+            move |value| {
+                let typed = parse_coordinate(&latitude, 90.0).zip(parse_coordinate(&longitude, 180.0));
+                if *value != typed {
+                    latitude.set(coordinate_text(value.map(|v| v.0)));
+                    longitude.set(coordinate_text(value.map(|v| v.1)));
+                }
+                || ()
+            },
+            props.value,
+        );
+    }
+    let oninput = |is_latitude: bool| {
+        let latitude = latitude.clone();
+        let longitude = longitude.clone();
+        let onchange = props.onchange.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let text = input.value();
+            let (typed_latitude, typed_longitude) = if is_latitude {
+                latitude.set(text.clone());
+                (text, (*longitude).clone())
+            } else {
+                longitude.set(text.clone());
+                ((*latitude).clone(), text)
+            };
+            if let Some(position) = parse_coordinate(&typed_latitude, 90.0).zip(parse_coordinate(&typed_longitude, 180.0)) {
+                onchange.emit(position);
+            }
+        })
+    };
+    let invalid_class = props.invalid.then_some("is-invalid");
+    let readonly = !props.unlocked;
+    let on_toggle = props.ontoggle.reform(|_: MouseEvent| ());
     html!(
         <>
             <span class="input-group-text" title={"Latitude"}>
@@ -11,10 +69,14 @@ pub fn geo_location() -> Html {
             </span>
             <div class="form-floating">
                 <input
-                    class="form-control"
+                    class={classes!("form-control", invalid_class)}
                     type="text"
+                    inputmode="decimal"
                     id="latitudeGroup"
-                    value={location.latitude.to_string()}
+                    placeholder="Latitude"
+                    {readonly}
+                    value={(*latitude).clone()}
+                    oninput={oninput(true)}
                     />
                 <label for="latitudeGroup">{"Latitude"}</label>
             </div>
@@ -23,19 +85,107 @@ pub fn geo_location() -> Html {
             </span>
             <div class="form-floating">
                 <input
-                    class="form-control"
+                    class={classes!("form-control", invalid_class)}
                     type="text"
+                    inputmode="decimal"
                     id="longitudeGroup"
-                    value={location.longitude.to_string()}
+                    placeholder="Longitude"
+                    {readonly}
+                    value={(*longitude).clone()}
+                    oninput={oninput(false)}
                     />
                 <label for="longitudeGroup">{"Longitude"}</label>
             </div>
+            <button type="button"
+                class={classes!("btn", "btn-lg", if props.unlocked { "btn-warning" } else { "btn-outline-secondary" })}
+                title={if props.unlocked { "Follow the device position" } else { "Edit the position manually" }}
+                onclick={on_toggle}>
+                if props.unlocked {
+                    <i class="fa-solid fa-lock-open"></i>
+                } else {
+                    <i class="fa-solid fa-lock"></i>
+                }
+            </button>
         </>
     )
 }
 
+#[derive(Properties, Clone, PartialEq)]
+pub struct PositionStatusProps {
+    pub state: GeoState,
+    /// The position was entered manually, readings are only informative
+    pub unlocked: bool,
+    pub high_accuracy: bool,
+    pub onhighaccuracy: Callback<bool>,
+    pub onrefresh: Callback<()>,
+}
+
+/// State of the device position: progress, failures and the accuracy of
+/// the last reading with controls to improve it
+#[function_component(PositionStatus)]
+pub fn position_status(props: &PositionStatusProps) -> Html {
+    let max_accuracy = GeoSettings::default().max_accuracy;
+    let on_refresh = props.onrefresh.reform(|_: MouseEvent| ());
+    let on_high_accuracy = props.onhighaccuracy.reform(|e: Event| {
+        let input: HtmlInputElement = e.target_unchecked_into();
+        input.checked()
+    });
+    let status = match props.state.status {
+        GeoStatus::Locating => html!(
+            <span class="text-muted">
+                <span class="spinner-border spinner-border-sm me-1" role="status"></span>
+                {"Determining your position…"}
+            </span>
+        ),
+        status => status
+            .message()
+            .map(|message| html!(<span class="text-danger">{ message }</span>))
+            .unwrap_or_default(),
+    };
+    let accuracy = props.state.reading.map(|reading| {
+        let poor = !props.unlocked && reading.accuracy_error(max_accuracy).is_some();
+        html!(
+            <span class={classes!("badge", if poor { "text-bg-warning" } else { "text-bg-light" })}
+                title={format!("Positions less accurate than {max_accuracy:.0} m can't be submitted")}>
+                { format!("±{:.0} m", reading.accuracy) }
+            </span>
+        )
+    });
+    html!(
+        <div class="d-flex flex-wrap align-items-center gap-2 small mt-n1 mb-2">
+            { for accuracy }
+            { status }
+            <div class="form-check form-switch mb-0 ms-auto">
+                <input class="form-check-input" type="checkbox" role="switch" id="highAccuracy"
+                    checked={props.high_accuracy} onchange={on_high_accuracy} />
+                <label class="form-check-label" for="highAccuracy">{"High accuracy"}</label>
+            </div>
+            <button type="button" class="btn btn-sm btn-outline-secondary"
+                disabled={props.state.status == GeoStatus::Unsupported}
+                onclick={on_refresh}>
+                <i class="fa-solid fa-arrows-rotate me-1"></i>{"Refresh position"}
+            </button>
+        </div>
+    )
+}
+
+#[derive(Properties, Clone, PartialEq)]
+pub struct LocationProps {
+    pub value: String,
+    pub oninput: Callback<String>,
+    #[prop_or_default]
+    pub invalid: bool,
+}
+
 #[function_component(Location)]
-pub fn location() -> Html {
+pub fn location(props: &LocationProps) -> Html {
+    let oninput = {
+        let oninput = props.oninput.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            oninput.emit(input.value());
+        })
+    };
     html!(
         <>
             <span class="input-group-text">
@@ -43,13 +193,15 @@ pub fn location() -> Html {
             </span>
             <div class="form-floating">
                 <input
-                    class="form-control"
+                    class={classes!("form-control", props.invalid.then_some("is-invalid"))}
                     type="text"
                     id="LocationGroup"
                     placeholder="Location"
+                    value={props.value.clone()}
+                    {oninput}
                     />
                 <label for="LocationGroup">{"Location"}</label>
             </div>
         </>
     )
-}
\ No newline at end of file
+}
@@ -0,0 +1,300 @@
+//! Location map on top of Leaflet, loaded by `index.html` as the global `L`.
+
+use crate::types::map::MapSettings;
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen::JsValue;
+use web_sys::HtmlElement;
+use yew::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = L, js_name = Map)]
+    type LeafletMap;
+
+    #[wasm_bindgen(js_namespace = L, js_name = map)]
+    fn leaflet_map(element: &HtmlElement) -> LeafletMap;
+
+    #[wasm_bindgen(method, js_name = setView)]
+    fn set_view(this: &LeafletMap, center: &JsValue, zoom: u8) -> LeafletMap;
+
+    #[wasm_bindgen(method, js_name = panTo)]
+    fn pan_to(this: &LeafletMap, center: &JsValue) -> LeafletMap;
+
+    #[wasm_bindgen(method)]
+    fn on(this: &LeafletMap, event: &str, handler: &JsValue) -> LeafletMap;
+
+    #[wasm_bindgen(method)]
+    fn remove(this: &LeafletMap) -> LeafletMap;
+
+    #[wasm_bindgen(js_namespace = L, js_name = TileLayer)]
+    type TileLayer;
+
+    #[wasm_bindgen(js_namespace = L, js_name = tileLayer)]
+    fn tile_layer(url: &str, options: &JsValue) -> TileLayer;
+
+    #[wasm_bindgen(method, js_name = addTo)]
+    fn add_to(this: &TileLayer, map: &LeafletMap) -> TileLayer;
+
+    #[wasm_bindgen(js_namespace = L, js_name = Marker)]
+    type Marker;
+
+    #[wasm_bindgen(js_namespace = L, js_name = marker)]
+    fn marker(position: &JsValue, options: &JsValue) -> Marker;
+
+    #[wasm_bindgen(method, js_name = addTo)]
+    fn add_to(this: &Marker, map: &LeafletMap) -> Marker;
+
+    #[wasm_bindgen(method, js_name = setLatLng)]
+    fn set_lat_lng(this: &Marker, position: &JsValue) -> Marker;
+
+    #[wasm_bindgen(method, js_name = getLatLng)]
+    fn get_lat_lng(this: &Marker) -> JsValue;
+
+    #[wasm_bindgen(method, js_name = on)]
+    fn on(this: &Marker, event: &str, handler: &JsValue) -> Marker;
+
+    #[wasm_bindgen(method, getter)]
+    fn dragging(this: &Marker) -> Handler;
+
+    /// Interaction handler such as the dragging of a marker
+    #[wasm_bindgen(js_namespace = L, js_name = Handler)]
+    type Handler;
+
+    #[wasm_bindgen(method)]
+    fn enable(this: &Handler);
+
+    #[wasm_bindgen(method)]
+    fn disable(this: &Handler);
+
+    #[wasm_bindgen(js_namespace = L, js_name = Circle)]
+    type Circle;
+
+    #[wasm_bindgen(js_namespace = L, js_name = circle)]
+    fn circle(position: &JsValue, options: &JsValue) -> Circle;
+
+    #[wasm_bindgen(method, js_name = addTo)]
+    fn add_to(this: &Circle, map: &LeafletMap) -> Circle;
+
+    #[wasm_bindgen(method, js_name = setLatLng)]
+    fn set_lat_lng(this: &Circle, position: &JsValue) -> Circle;
+
+    #[wasm_bindgen(method, js_name = setRadius)]
+    fn set_radius(this: &Circle, radius: f64) -> Circle;
+
+    #[wasm_bindgen(method, js_name = remove)]
+    fn remove(this: &Circle) -> Circle;
+}
+
+fn lat_lng((latitude, longitude): (f64, f64)) -> JsValue {
+    js_sys::Array::of2(&latitude.into(), &longitude.into()).into()
+}
+
+/// Read a Leaflet `LatLng` object
+fn position(value: &JsValue) -> Option<(f64, f64)> {
+    let get = |key: &str| js_sys::Reflect::get(value, &JsValue::from_str(key)).ok()?.as_f64();
+    Some((get("lat")?, get("lng")?))
+}
+
+fn options(entries: &[(&str, JsValue)]) -> JsValue {
+    let object = js_sys::Object::new();
+    for (key, value) in entries {
+        js_sys::Reflect::set(&object, &JsValue::from_str(key), value).ok();
+    }
+    object.into()
+}
+
+/// Leaflet objects of a mounted map, the closures must live as long as the map
+struct Leaflet {
+    map: LeafletMap,
+    marker: Option<Marker>,
+    accuracy: Option<Circle>,
+    on_pick: Closure<dyn FnMut(JsValue)>,
+    /// Whether clicks and drags move the pin
+    editable: bool,
+    /// Last position reported by the map itself, not panned to again
+    picked: Option<(f64, f64)>,
+}
+
+#[derive(Properties, Clone, PartialEq)]
+pub struct MapPickerProps {
+    /// Position of the pin
+    pub position: Option<(f64, f64)>,
+    /// Device reading with its accuracy in meters, drawn as a circle
+    #[prop_or_default]
+    pub accuracy: Option<((f64, f64), f64)>,
+    /// The pin can be dragged and placed by clicking the map
+    #[prop_or(true)]
+    pub editable: bool,
+    /// The pin was dragged or the map clicked
+    pub onchange: Callback<(f64, f64)>,
+}
+
+/// Map with a draggable pin for the report location
+#[function_component(MapPicker)]
+pub fn map_picker(props: &MapPickerProps) -> Html {
+    let container = use_node_ref();
+    let leaflet = use_mut_ref(|| None::<Leaflet>);
+    // The Leaflet handlers outlive renders, so they read the latest callback from here
+    let onchange = use_mut_ref(|| props.onchange.clone());
+    *onchange.borrow_mut() = props.onchange.clone();
+
+    {
+        let container = container.clone();
+        let leaflet = leaflet.clone();
+        use_effect_with_deps(
+            move |_| {
+                if let Some(element) = container.cast::<HtmlElement>() {
+                    let settings = MapSettings::default();
+                    let map = leaflet_map(&element);
+                    map.set_view(&lat_lng(settings.center), settings.zoom);
+                    tile_layer(
+                        settings.tiles,
+                        &options(&[
+                            ("attribution", settings.attribution.into()),
+                            ("maxZoom", 19.into()),
+                        ]),
+                    )
+                    .add_to(&map);
+                    let on_pick = pick_handler(leaflet.clone(), onchange);
+                    map.on("click", on_pick.as_ref());
+                    *leaflet.borrow_mut() = Some(Leaflet {
+                        map,
+                        marker: None,
+                        accuracy: None,
+                        on_pick,
+                        editable: false,
+                        picked: None,
+                    });
+                }
+                move || {
+                    if let Some(leaflet) = leaflet.borrow_mut().take() {
+                        leaflet.map.remove();
+                    }
+                }
+            },
+            (),
+        );
+    }
+
+    {
+        let leaflet = leaflet.clone();
+        use_effect_with_deps(
+            move |editable| {
+                if let Some(leaflet) = leaflet.borrow_mut().as_mut() {
+                    leaflet.set_editable(*editable);
+                }
+                || ()
+            },
+            props.editable,
+        );
+    }
+
+    {
+        let leaflet = leaflet.clone();
+        use_effect_with_deps(
+            move |(position, accuracy)| {
+                if let Some(leaflet) = leaflet.borrow_mut().as_mut() {
+                    leaflet.show(*position, *accuracy);
+                }
+                || ()
+            },
+            (props.position, props.accuracy),
+        );
+    }
+
+    html!(
+        <div ref={container} class="w-100 rounded border mb-2" style="height: 300px;"></div>
+    )
+}
+
+/// Handler for map clicks and pin drags, both move the pin and report it
+fn pick_handler(
+    leaflet: Rc<RefCell<Option<Leaflet>>>,
+    onchange: Rc<RefCell<Callback<(f64, f64)>>>,
+) -> Closure<dyn FnMut(JsValue)> {
+    Closure::new(move |event: JsValue| {
+        let target = js_sys::Reflect::get(&event, &JsValue::from_str("target")).ok();
+        // Clicks carry the position, drags end with the pin already moved
+        let picked = js_sys::Reflect::get(&event, &JsValue::from_str("latlng"))
+            .ok()
+            .and_then(|p| position(&p))
+            .or_else(|| target.and_then(|t| position(&Marker::from(t).get_lat_lng())));
+        let Some(picked) = picked else {
+            return;
+        };
+        if let Some(leaflet) = leaflet.borrow_mut().as_mut() {
+            if !leaflet.editable {
+                return;
+            }
+            leaflet.picked = Some(picked);
+            leaflet.place_marker(picked);
+        }
+        onchange.borrow().emit(picked);
+    })
+}
+
+impl Leaflet {
+    fn place_marker(&mut self, position: (f64, f64)) {
+        match &self.marker {
+            Some(marker) => {
+                marker.set_lat_lng(&lat_lng(position));
+            }
+            None => {
+                let marker = marker(&lat_lng(position), &options(&[("draggable", true.into())]));
+                marker.on("dragend", self.on_pick.as_ref());
+                marker.add_to(&self.map);
+                if !self.editable {
+                    marker.dragging().disable();
+                }
+                self.marker = Some(marker);
+            }
+        }
+    }
+
+    fn set_editable(&mut self, editable: bool) {
+        self.editable = editable;
+        if let Some(marker) = &self.marker {
+            let dragging = marker.dragging();
+            if editable {
+                dragging.enable();
+            } else {
+                dragging.disable();
+            }
+        }
+    }
+
+    fn show(&mut self, position: Option<(f64, f64)>, accuracy: Option<((f64, f64), f64)>) {
+        if let Some(position) = position {
+            let first = self.marker.is_none();
+            self.place_marker(position);
+            if first {
+                self.map
+                    .set_view(&lat_lng(position), MapSettings::default().detail_zoom);
+            } else if self.picked != Some(position) {
+                self.map.pan_to(&lat_lng(position));
+            }
+        }
+        match (accuracy, &self.accuracy) {
+            (Some((center, radius)), Some(circle)) => {
+                circle.set_lat_lng(&lat_lng(center));
+                circle.set_radius(radius);
+            }
+            (Some((center, radius)), None) => {
+                let circle = circle(
+                    &lat_lng(center),
+                    &options(&[("radius", radius.into()), ("interactive", false.into())]),
+                );
+                circle.add_to(&self.map);
+                self.accuracy = Some(circle);
+            }
+            (None, Some(circle)) => {
+                circle.remove();
+                self.accuracy = None;
+            }
+            (None, None) => {}
+        }
+    }
+}
@@ -1,2 +1,12 @@
+pub mod blur_editor;
+pub mod document_button;
+pub mod email_banner;
+pub mod gallery;
 pub mod user_context_provider;
-pub mod location;
\ No newline at end of file
+pub mod location;
+pub mod map;
+pub mod outbox_provider;
+pub mod report_list;
+pub mod require_permission;
+pub mod stored_picture;
+pub mod update_prompt;
\ No newline at end of file
@@ -0,0 +1,46 @@
+//! Outbox context provider.
+use crate::hooks::OutboxHandle;
+use gloo::events::EventListener;
+use gloo::timers::callback::Interval;
+use yew::prelude::*;
+
+/// How often queued reports are checked for being due
+const CHECK_INTERVAL_MS: u32 = 5_000;
+
+#[derive(Properties, Clone, PartialEq)]
+pub struct Props {
+    pub children: Children,
+}
+
+/// Outbox context provider, sends queued reports in the background and as
+/// soon as the browser is back online.
+#[function_component(OutboxProvider)]
+pub fn outbox_provider(props: &Props) -> Html {
+    let outbox = OutboxHandle::new(use_state(Vec::new), use_state(|| None));
+
+    {
+        let outbox = outbox.clone();
+        use_effect_with_deps(
+            move |_| {
+                outbox.flush();
+                let interval = {
+                    let outbox = outbox.clone();
+                    Interval::new(CHECK_INTERVAL_MS, move || outbox.flush())
+                };
+                let online = web_sys::window()
+                    .map(|window| EventListener::new(&window, "online", move |_| outbox.flush()));
+                move || {
+                    drop(interval);
+                    drop(online);
+                }
+            },
+            (),
+        );
+    }
+
+    html! (
+        <ContextProvider<OutboxHandle> context={outbox}>
+            { for props.children.iter() }
+        </ContextProvider<OutboxHandle>>
+    )
+}
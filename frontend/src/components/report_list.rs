@@ -0,0 +1,145 @@
+use crate::app::Route;
+use crate::components::stored_picture::StoredPicture;
+use crate::types::report::{display_time, status_class, ReportPage, ReportQuery, ReportStatus};
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+/// Text of a filter input, `None` when emptied
+fn filter_value(value: String) -> Option<String> {
+    Some(value.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+#[derive(Properties, Clone, PartialEq)]
+pub struct ReportListProps {
+    pub query: ReportQuery,
+    pub page: Option<ReportPage>,
+    #[prop_or_default]
+    pub loading: bool,
+    /// The filter or page changed
+    pub onquery: Callback<ReportQuery>,
+    /// Page of a listed report
+    pub link: Callback<i64, Route>,
+    /// Label of the status option that doesn't filter
+    #[prop_or(AttrValue::Static("Any status"))]
+    pub any_status: AttrValue,
+}
+
+/// Reports with their first picture, filtered by plate, status and date and
+/// split into pages
+#[function_component(ReportList)]
+pub fn report_list(props: &ReportListProps) -> Html {
+    // Callback applying a change to the query, a new filter starts on the first page
+    let apply = |change: fn(&mut ReportQuery, Option<String>)| {
+        let query = props.query.clone();
+        let onquery = props.onquery.clone();
+        move |value: Option<String>| {
+            let mut query = query.clone();
+            query.page = None;
+            change(&mut query, value);
+            onquery.emit(query);
+        }
+    };
+    let on_input = |change: fn(&mut ReportQuery, Option<String>)| {
+        let apply = apply(change);
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            apply(filter_value(input.value()));
+        })
+    };
+    let on_plate = on_input(|q, v| q.plate = v);
+    let on_from = on_input(|q, v| q.from = v);
+    let on_to = on_input(|q, v| q.to = v);
+    let on_status = {
+        let apply = apply(|q, v| q.status = v.as_deref().and_then(ReportStatus::from_name));
+        Callback::from(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            apply(filter_value(select.value()));
+        })
+    };
+    let on_page = apply(|q, v| q.page = v.and_then(|p| p.parse().ok()));
+
+    let query = &props.query;
+    let page = props.page.as_ref();
+    let current = page.map_or(1, |p| p.page);
+    let pages = page.map_or(1, ReportPage::pages);
+    let page_link = |number: u32, label: Html, disabled: bool| {
+        let on_page = on_page.clone();
+        let onclick = Callback::from(move |e: MouseEvent| {
+            e.prevent_default();
+            on_page(Some(number.to_string()));
+        });
+        html!(
+            <li class={classes!("page-item", disabled.then_some("disabled"), (number == current).then_some("active"))}>
+                <a class="page-link" href="#" {onclick}>{ label }</a>
+            </li>
+        )
+    };
+
+    html!(
+        <>
+            <div class="row g-2 mb-3">
+                <div class="col-md">
+                    <input class="form-control" type="search" placeholder="Plate" aria-label="Plate"
+                        value={query.plate.clone().unwrap_or_default()} onchange={on_plate} />
+                </div>
+                <div class="col-md">
+                    <select class="form-select" aria-label="Status" onchange={on_status}>
+                        <option value="" selected={query.status.is_none()}>{ props.any_status.clone() }</option>
+                        { for ReportStatus::ALL.iter().map(|s| html!(
+                            <option value={s.as_str()} selected={query.status == Some(*s)}>{ s.label() }</option>
+                        )) }
+                    </select>
+                </div>
+                <div class="col-md">
+                    <input class="form-control" type="date" title="Observed from" aria-label="Observed from"
+                        value={query.from.clone().unwrap_or_default()} onchange={on_from} />
+                </div>
+                <div class="col-md">
+                    <input class="form-control" type="date" title="Observed until" aria-label="Observed until"
+                        value={query.to.clone().unwrap_or_default()} onchange={on_to} />
+                </div>
+            </div>
+            if props.loading {
+                <div class="text-center my-3"><span class="spinner-border" role="status"></span></div>
+            }
+            if page.is_some_and(|p| p.reports.is_empty()) {
+                <p class="text-muted">{ "No reports found." }</p>
+            }
+            <table class="table table-hover align-middle">
+                <thead>
+                    <tr><th></th><th>{ "Plate" }</th><th>{ "Observed" }</th><th>{ "Submitted" }</th><th>{ "Status" }</th></tr>
+                </thead>
+                <tbody>
+                    { for page.iter().flat_map(|p| p.reports.iter()).map(|r| html!(
+                        <tr key={r.id}>
+                            <td style="width: 5rem;">
+                                if let Some((picture, caption)) = r.pictures.first() {
+                                    <StoredPicture name={picture.clone()} alt={caption.clone().unwrap_or_default()}
+                                        class={classes!("w-100", "rounded")} />
+                                }
+                            </td>
+                            <td>
+                                <Link<Route> to={props.link.emit(r.id)}>
+                                    { format!("{} ({})", r.plate, r.country) }
+                                </Link<Route>>
+                            </td>
+                            <td>{ display_time(&r.observed_at) }</td>
+                            <td>{ display_time(&r.created_at) }</td>
+                            <td><span class={classes!("badge", status_class(r.status))}>{ r.status.label() }</span></td>
+                        </tr>
+                    )) }
+                </tbody>
+            </table>
+            if pages > 1 {
+                <nav aria-label="Report pages">
+                    <ul class="pagination justify-content-center">
+                        { page_link(current.saturating_sub(1).max(1), html!("«"), current == 1) }
+                        { for (1..=pages).map(|n| page_link(n, html!({ n }), false)) }
+                        { page_link((current + 1).min(pages), html!("»"), current == pages) }
+                    </ul>
+                </nav>
+            }
+        </>
+    )
+}
@@ -0,0 +1,31 @@
+//! Show content only to users holding a permission.
+use crate::app::Route;
+use crate::hooks::use_user_context;
+use crate::services::requests::get_token;
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+#[derive(Properties, Clone, PartialEq)]
+pub struct Props {
+    pub permission: AttrValue,
+    /// Redirect to the home page instead of rendering nothing
+    #[prop_or_default]
+    pub redirect: bool,
+    #[prop_or_default]
+    pub children: Children,
+}
+
+/// Renders its children only when the current user has the permission.
+#[function_component(RequirePermission)]
+pub fn require_permission(props: &Props) -> Html {
+    let user_ctx = use_user_context();
+
+    if user_ctx.check_permission(&props.permission) {
+        html!( <>{ for props.children.iter() }</> )
+    } else if !props.redirect || (get_token().is_some() && !user_ctx.is_authenticated()) {
+        // Nothing to show, or the user is still being loaded
+        html!()
+    } else {
+        html!( <Redirect<Route> to={Route::Home} /> )
+    }
+}
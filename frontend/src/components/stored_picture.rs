@@ -0,0 +1,49 @@
+use crate::services::pictures::fetch_picture;
+use std::rc::Rc;
+use yew::prelude::*;
+use yew_hooks::use_async;
+
+#[derive(Properties, Clone, PartialEq)]
+pub struct StoredPictureProps {
+    /// Name the api stored the picture under
+    pub name: AttrValue,
+    #[prop_or_default]
+    pub alt: AttrValue,
+    #[prop_or_default]
+    pub class: Classes,
+}
+
+/// Uploaded picture loaded with the user's token, a placeholder until it arrives
+#[function_component(StoredPicture)]
+pub fn stored_picture(props: &StoredPictureProps) -> Html {
+    let picture = {
+        let name = props.name.clone();
+        use_async(async move { fetch_picture(&name).await.map(Rc::new) })
+    };
+    {
+        let picture = picture.clone();
+        use_effect_with_deps(
+            move |_| {
+                picture.run();
+                || ()
+            },
+            props.name.clone(),
+        );
+    }
+
+    match &picture.data {
+        Some(url) => html!(
+            <img src={url.to_string()} alt={props.alt.clone()} class={props.class.clone()} />
+        ),
+        None => html!(
+            <div class={classes!(props.class.clone(), "d-flex", "align-items-center", "justify-content-center", "bg-light", "text-muted")}
+                title={props.alt.clone()}>
+                if picture.error.is_some() {
+                    <i class="fa-regular fa-image"></i>
+                } else {
+                    <span class="spinner-border spinner-border-sm" role="status"></span>
+                }
+            </div>
+        ),
+    }
+}
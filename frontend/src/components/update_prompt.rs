@@ -0,0 +1,40 @@
+use crate::services::pwa::{apply_update, register};
+use web_sys::ServiceWorker;
+use yew::prelude::*;
+
+/// Registers the service worker and offers to reload once a new version
+/// of the app was downloaded
+#[function_component(UpdatePrompt)]
+pub fn update_prompt() -> Html {
+    let update = use_state(|| None::<ServiceWorker>);
+
+    {
+        let update = update.clone();
+        use_effect_with_deps(
+            move |_| {
+                register(Callback::from(move |worker| update.set(Some(worker))));
+                || ()
+            },
+            (),
+        );
+    }
+
+    let Some(worker) = (*update).clone() else {
+        return html!();
+    };
+    let on_reload = Callback::from(move |_: MouseEvent| apply_update(&worker));
+    let on_dismiss = {
+        let update = update.clone();
+        Callback::from(move |_: MouseEvent| update.set(None))
+    };
+
+    html!(
+        <div class="toast show position-fixed bottom-0 end-0 m-3" role="status" aria-live="polite" style="z-index: 1080;">
+            <div class="toast-body d-flex align-items-center gap-2">
+                <span class="me-auto">{"A new version of the app is available."}</span>
+                <button type="button" class="btn btn-sm btn-primary" onclick={on_reload}>{"Reload"}</button>
+                <button type="button" class="btn-close" title="Later" onclick={on_dismiss}></button>
+            </div>
+        </div>
+    )
+}
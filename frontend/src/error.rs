@@ -4,6 +4,7 @@ use crate::types::ErrorInfo;
 use thiserror::Error as ThisError;
 
 /// Define all possible errors
+#[allow(clippy::enum_variant_names)]
 #[derive(ThisError, Clone, Debug, Eq, PartialEq)]
 pub enum Error {
     /// 400
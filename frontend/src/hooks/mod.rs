@@ -1,3 +1,7 @@
+mod use_outbox;
+mod use_position;
 mod use_user_context;
 
+pub use use_outbox::*;
+pub use use_position::*;
 pub use use_user_context::*;
@@ -0,0 +1,85 @@
+use crate::services::outbox;
+use crate::types::outbox::{OutboxItem, OutboxStatus};
+use tracing::warn;
+use yew::platform::spawn_local;
+use yew::prelude::*;
+
+/// State handle for the [`use_outbox`] hook, provided by
+/// [`OutboxProvider`](crate::components::outbox_provider::OutboxProvider).
+#[derive(Clone, PartialEq)]
+pub struct OutboxHandle {
+    items: UseStateHandle<Vec<OutboxItem>>,
+    sending: UseStateHandle<Option<String>>,
+}
+
+impl OutboxHandle {
+    pub fn new(items: UseStateHandle<Vec<OutboxItem>>, sending: UseStateHandle<Option<String>>) -> Self {
+        Self { items, sending }
+    }
+
+    pub fn items(&self) -> &[OutboxItem] {
+        &self.items
+    }
+
+    /// Id of the report being sent right now
+    pub fn sending(&self) -> Option<&str> {
+        self.sending.as_deref()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.items
+            .iter()
+            .filter(|i| matches!(i.status, OutboxStatus::Failed(_)))
+            .count()
+    }
+
+    /// Read the outbox again after it was changed elsewhere
+    pub fn refresh(&self) {
+        let items = self.items.clone();
+        spawn_local(async move {
+            match outbox::pending().await {
+                Ok(pending) => items.set(pending),
+                Err(e) => warn!("Failed to read the outbox: {e}"),
+            }
+        });
+    }
+
+    /// Send the reports that are due
+    pub fn flush(&self) {
+        let handle = self.clone();
+        spawn_local(async move {
+            let sending = handle.sending.clone();
+            if let Err(e) = outbox::flush(move |id| sending.set(id)).await {
+                warn!("Failed to send the outbox: {e}");
+                handle.sending.set(None);
+            }
+            handle.refresh();
+        });
+    }
+
+    pub fn retry(&self, id: String) {
+        let handle = self.clone();
+        spawn_local(async move {
+            if let Err(e) = outbox::retry(&id).await {
+                warn!("Failed to retry report: {e}");
+            }
+            handle.flush();
+        });
+    }
+
+    pub fn discard(&self, id: String) {
+        let handle = self.clone();
+        spawn_local(async move {
+            if let Err(e) = outbox::discard(&id).await {
+                warn!("Failed to discard report: {e}");
+            }
+            handle.refresh();
+        });
+    }
+}
+
+#[hook]
+/// This hook is used to show and send reports waiting in the outbox.
+pub fn use_outbox() -> OutboxHandle {
+    use_context::<OutboxHandle>().unwrap()
+}
@@ -0,0 +1,117 @@
+use crate::types::geolocation::{GeoEvent, GeoSettings, GeoState, GeoStatus, Reading};
+use std::rc::Rc;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::{Geolocation, Position, PositionError, PositionOptions};
+use yew::prelude::*;
+
+impl Reducible for GeoState {
+    type Action = GeoEvent;
+
+    fn reduce(self: Rc<Self>, event: GeoEvent) -> Rc<Self> {
+        Rc::new(self.apply(event))
+    }
+}
+
+/// State handle for the [`use_position`] hook
+#[derive(Clone, PartialEq)]
+pub struct PositionHandle {
+    state: UseReducerHandle<GeoState>,
+    generation: UseStateHandle<u32>,
+}
+
+impl PositionHandle {
+    pub fn state(&self) -> GeoState {
+        *self.state
+    }
+
+    /// Ask the device for a fresh reading
+    pub fn refresh(&self) {
+        self.generation.set(*self.generation + 1);
+    }
+}
+
+/// Watch that ends when dropped, the closures must live as long as it
+struct Watch {
+    geolocation: Geolocation,
+    id: i32,
+    _on_read: Closure<dyn Fn(Position)>,
+    _on_error: Closure<dyn Fn(PositionError)>,
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        self.geolocation.clear_watch(self.id);
+    }
+}
+
+fn watch(dispatcher: UseReducerDispatcher<GeoState>, high_accuracy: bool) -> Option<Watch> {
+    let Some(geolocation) = web_sys::window().and_then(|w| w.navigator().geolocation().ok()) else {
+        dispatcher.dispatch(GeoEvent::Failed(GeoStatus::Unsupported));
+        return None;
+    };
+    let on_read = {
+        let dispatcher = dispatcher.clone();
+        Closure::<dyn Fn(Position)>::new(move |position: Position| {
+            let coords = position.coords();
+            dispatcher.dispatch(GeoEvent::Read(Reading {
+                latitude: coords.latitude(),
+                longitude: coords.longitude(),
+                accuracy: coords.accuracy(),
+            }));
+        })
+    };
+    let on_error = {
+        let dispatcher = dispatcher.clone();
+        Closure::<dyn Fn(PositionError)>::new(move |error: PositionError| {
+            dispatcher.dispatch(GeoEvent::Failed(GeoStatus::from_error_code(error.code())));
+        })
+    };
+    let options = PositionOptions::new();
+    options.set_enable_high_accuracy(high_accuracy);
+    options.set_timeout(GeoSettings::default().timeout_ms);
+    // A refresh must not be answered from the cache
+    options.set_maximum_age(0);
+    match geolocation.watch_position_with_error_callback_and_options(
+        on_read.as_ref().unchecked_ref(),
+        Some(on_error.as_ref().unchecked_ref()),
+        &options,
+    ) {
+        Ok(id) => Some(Watch {
+            geolocation,
+            id,
+            _on_read: on_read,
+            _on_error: on_error,
+        }),
+        Err(_) => {
+            dispatcher.dispatch(GeoEvent::Failed(GeoStatus::Unsupported));
+            None
+        }
+    }
+}
+
+/// Position of the device while `enabled`, with the accuracy of each reading
+/// and the reason when there is none. Nothing is asked, so no permission
+/// prompt shows up, until it is enabled.
+#[hook]
+pub fn use_position(enabled: bool, high_accuracy: bool) -> PositionHandle {
+    let state = use_reducer(GeoState::default);
+    let generation = use_state(|| 0);
+    {
+        let dispatcher = state.dispatcher();
+        use_effect_with_deps(
+            move |(enabled, high_accuracy, _)| {
+                let watch = if *enabled {
+                    dispatcher.dispatch(GeoEvent::Start);
+                    watch(dispatcher, *high_accuracy)
+                } else {
+                    dispatcher.dispatch(GeoEvent::Reset);
+                    None
+                };
+                move || drop(watch)
+            },
+            (enabled, high_accuracy, *generation),
+        );
+    }
+    PositionHandle { state, generation }
+}
@@ -16,13 +16,24 @@ pub struct Handle {
     history: Navigator,
 }
 
-#[allow(dead_code)]
+/// Route split by whether it needs a permission, see [`Route::permission`]
 pub enum Routes {
     Admin(Route),
     Default(Route),
 }
 
+impl From<Route> for Routes {
+    fn from(route: Route) -> Self {
+        if route.permission().is_some() {
+            Self::Admin(route)
+        } else {
+            Self::Default(route)
+        }
+    }
+}
+
 impl Handle {
+    #[allow(dead_code)]
     pub fn login(&self, value: UserInfo) {
         // Set global token after logged in
         set_token(Some(value.token.clone()));
@@ -32,20 +43,19 @@ impl Handle {
     }
 
     pub fn check_permission(&self, permission: &str) -> bool {
-        // Check foR Admin
-        // if self.permissions.contains(&"*".to_string()) {
-        //     return true;
-        // };
-        // if self.permissions.contains(&permission.to_string()) {
-        //     return true;
-        // };
-        false
+        self.has_permission(permission)
     }
 
+    /// Navigate to the route, admin routes fall back to home without the permission
+    #[allow(dead_code)]
     pub fn navigate_to(&self, route: &Routes) {
         match route {
             Routes::Admin(r) => {
-                self.history.push(r);
+                if r.permission().is_some_and(|p| self.check_permission(p)) {
+                    self.history.push(r);
+                } else {
+                    self.history.push(&Route::Home);
+                }
             }
             Routes::Default(r) => {
                 self.history.push(r);
@@ -53,6 +63,7 @@ impl Handle {
         }
     }
 
+    #[allow(dead_code)]
     pub fn register(&self, value: RegisterResponse) {
         // Set global token after logged in
         if let Some(data) = value.data {
@@ -63,6 +74,7 @@ impl Handle {
         };
     }
 
+    #[allow(dead_code)]
     pub fn logout(&self) {
         // Clear global token after logged out
         self.inner.set(UserInfo::default());
@@ -142,11 +154,9 @@ pub fn use_refresh_user_context() -> UseStateHandle<UserInfo> {
                     user_ctx.set(user_info.clone());
                 }
 
-                if let Some(error) = &current_user.error {
-                    if let Error::Unauthorized(s) | Error::Forbidden(s) = error {
-                        warn!("Unauthorized {s}");
-                        set_token(None);
-                    }
+                if let Some(Error::Unauthorized(s) | Error::Forbidden(s)) = &current_user.error {
+                    warn!("Unauthorized {s}");
+                    set_token(None);
                 }
                 || ()
             },
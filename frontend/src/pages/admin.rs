@@ -0,0 +1,69 @@
+use crate::services::admin::{get_role_list, get_user_list};
+use yew::prelude::*;
+use yew_hooks::{use_async, use_mount};
+
+#[function_component(AdminUsers)]
+pub fn admin_users() -> Html {
+    let users = use_async(async move { get_user_list().await });
+
+    {
+        let users = users.clone();
+        use_mount(move || users.run());
+    }
+
+    html!(
+        <section>
+            <h1 class="h3">{ "Users" }</h1>
+            if let Some(error) = &users.error {
+                <div class="alert alert-danger" role="alert">{ error.to_string() }</div>
+            }
+            <table class="table table-striped">
+                <thead>
+                    <tr><th>{ "Id" }</th><th>{ "Username" }</th><th>{ "Email" }</th></tr>
+                </thead>
+                <tbody>
+                    { for users.data.iter().flatten().map(|u| html!(
+                        <tr key={u.id}><td>{ u.id }</td><td>{ &u.username }</td><td>{ &u.email }</td></tr>
+                    )) }
+                </tbody>
+            </table>
+        </section>
+    )
+}
+
+#[function_component(AdminRoles)]
+pub fn admin_roles() -> Html {
+    let roles = use_async(async move { get_role_list().await });
+
+    {
+        let roles = roles.clone();
+        use_mount(move || roles.run());
+    }
+
+    html!(
+        <section>
+            <h1 class="h3">{ "Roles" }</h1>
+            if let Some(error) = &roles.error {
+                <div class="alert alert-danger" role="alert">{ error.to_string() }</div>
+            }
+            <table class="table table-striped">
+                <thead>
+                    <tr><th>{ "Name" }</th><th>{ "Description" }</th><th>{ "Permissions" }</th></tr>
+                </thead>
+                <tbody>
+                    { for roles.data.iter().flatten().map(|r| html!(
+                        <tr key={r.id}>
+                            <td>{ &r.name }</td>
+                            <td>{ &r.description }</td>
+                            <td>
+                                { for r.permissions.iter().flatten().map(|p| html!(
+                                    <span class="badge text-bg-secondary me-1" title={p.description.clone()}>{ &p.name }</span>
+                                )) }
+                            </td>
+                        </tr>
+                    )) }
+                </tbody>
+            </table>
+        </section>
+    )
+}
@@ -0,0 +1,56 @@
+use crate::hooks::use_user_context;
+use crate::services::auth::confirm_email;
+use std::collections::HashMap;
+use yew::prelude::*;
+use yew_hooks::{use_async, use_mount};
+use yew_router::prelude::*;
+
+/// Landing page of the link sent in confirmation emails
+#[function_component(ConfirmEmail)]
+pub fn confirm_email_page() -> Html {
+    let user_ctx = use_user_context();
+    let code = use_location()
+        .and_then(|l| l.query::<HashMap<String, String>>().ok())
+        .and_then(|q| q.get("code").cloned())
+        .unwrap_or_default();
+
+    let confirm = use_async(async move { confirm_email(&code).await });
+
+    {
+        let confirm = confirm.clone();
+        use_mount(move || confirm.run());
+    }
+
+    {
+        use_effect_with_deps(
+            move |confirm| {
+                if let Some(email) = confirm.data.as_ref().and_then(|r| r.email.as_ref()) {
+                    user_ctx.validate_email(email, true);
+                }
+                || ()
+            },
+            confirm.clone(),
+        );
+    }
+
+    html!(
+        <section class="hero is-bold is-large">
+            <div class="hero-body">
+                <div class="container">
+                    <h1 class="title">{ "Email confirmation" }</h1>
+                    if confirm.loading {
+                        <span class="spinner-border spinner-border-sm me-2" role="status"></span>
+                    }
+                    if let Some(result) = &confirm.data {
+                        <div class="alert alert-success" role="alert">
+                            { format!("{} {}", result.email.clone().unwrap_or_default(), result.result) }
+                        </div>
+                    }
+                    if let Some(error) = &confirm.error {
+                        <div class="alert alert-danger" role="alert">{ error.to_string() }</div>
+                    }
+                </div>
+            </div>
+        </section>
+    )
+}
@@ -1,19 +1,88 @@
 use crate::app::Route;
-use crate::error::Error;
-use crate::hooks::use_user_context;
-use crate::types::auth::ApiResult;
-use std::collections::HashMap;
+use crate::components::email_banner::EmailBanner;
+use crate::components::require_permission::RequirePermission;
+use crate::hooks::{use_outbox, use_user_context};
+use crate::types::auth::{MANAGE_ROLES, MANAGE_USERS, REVIEW_REPORTS};
+use crate::types::outbox::{OutboxItem, OutboxStatus};
 use yew::prelude::*;
-use yew_hooks::{use_async, use_click_away, UseAsyncHandle};
 use yew_router::prelude::*;
 
+/// Reports waiting in the outbox with their state, shown while there are any
+#[function_component(PendingReports)]
+fn pending_reports() -> Html {
+    let outbox = use_outbox();
+    let open = use_state(|| false);
+    if outbox.items().is_empty() {
+        return html!();
+    }
+
+    let toggle = {
+        let open = open.clone();
+        Callback::from(move |_: MouseEvent| open.set(!*open))
+    };
+    let failed = outbox.failed();
+    let item = |item: &OutboxItem| {
+        let status = if outbox.sending() == Some(item.id.as_str()) {
+            html!(<span class="text-primary">{"Sending…"}</span>)
+        } else {
+            match &item.status {
+                OutboxStatus::Queued if item.attempts == 0 => html!(<span class="text-muted">{"Waiting for a connection"}</span>),
+                OutboxStatus::Queued => html!(
+                    <span class="text-muted">
+                        { format!("Retrying, {} failed attempts: {}", item.attempts, item.last_error.clone().unwrap_or_default()) }
+                    </span>
+                ),
+                OutboxStatus::Failed(error) => html!(<span class="text-danger">{ error }</span>),
+            }
+        };
+        let on_retry = {
+            let outbox = outbox.clone();
+            let id = item.id.clone();
+            Callback::from(move |_: MouseEvent| outbox.retry(id.clone()))
+        };
+        let on_discard = {
+            let outbox = outbox.clone();
+            let id = item.id.clone();
+            Callback::from(move |_: MouseEvent| outbox.discard(id.clone()))
+        };
+        html!(
+            <li key={item.id.as_str()} class="dropdown-item-text d-flex align-items-center gap-2">
+                <div class="me-auto">
+                    <div>{ item.label() }</div>
+                    <div class="small">{ status }</div>
+                </div>
+                <button type="button" class="btn btn-sm btn-outline-primary" title="Send now" onclick={on_retry}>
+                    <i class="fa-solid fa-rotate"></i>
+                </button>
+                <button type="button" class="btn btn-sm btn-outline-danger" title="Discard" onclick={on_discard}>
+                    <i class="fa-solid fa-trash"></i>
+                </button>
+            </li>
+        )
+    };
+
+    html!(
+        <li class="nav-item dropdown">
+            <a class="nav-link dropdown-toggle" href="#" role="button" aria-expanded={(*open).to_string()}
+                onclick={toggle.reform(|e: MouseEvent| { e.prevent_default(); e })}>
+                <i class="fa-solid fa-cloud-arrow-up"></i>
+                {" Pending reports "}
+                <span class="badge bg-secondary">{ outbox.items().len() }</span>
+                if failed > 0 {
+                    <span class="badge bg-danger ms-1" title="Failed">{ failed }</span>
+                }
+            </a>
+            <ul class={classes!("dropdown-menu", "dropdown-menu-end", (*open).then_some("show"))} style="min-width: 20rem;">
+                { for outbox.items().iter().map(item) }
+            </ul>
+        </li>
+    )
+}
+
 #[function_component(Header)]
 pub fn header() -> Html {
+    let user_ctx = use_user_context();
     let active = use_state(|| false);
-    let resent = use_state(|| true);
-    let dropdown = use_state(|| false);
-    let location = use_location().unwrap();
-    let route = Route::from_path(location.path(), &HashMap::new());
 
     let active_class = if *active {
         (Some("show"), None)
@@ -35,9 +104,35 @@ pub fn header() -> Html {
                       <span class="navbar-toggler-icon"></span>
                     </button>
                     <div class={classes!("collapse","navbar-collapse", active_class.0)} id="navbarSupportedContent">
+                        <ul class="navbar-nav ms-auto">
+                            <PendingReports />
+                            if user_ctx.is_authenticated() {
+                                <li class="nav-item">
+                                    <Link<Route> to={Route::MyReports} classes="nav-link">{ "My reports" }</Link<Route>>
+                                </li>
+                            }
+                            <RequirePermission permission={REVIEW_REPORTS}>
+                                <li class="nav-item">
+                                    <Link<Route> to={Route::ModerationQueue} classes="nav-link">{ "Moderation" }</Link<Route>>
+                                </li>
+                            </RequirePermission>
+                            <RequirePermission permission={MANAGE_USERS}>
+                                <li class="nav-item">
+                                    <Link<Route> to={Route::AdminUsers} classes="nav-link">{ "Users" }</Link<Route>>
+                                </li>
+                            </RequirePermission>
+                            <RequirePermission permission={MANAGE_ROLES}>
+                                <li class="nav-item">
+                                    <Link<Route> to={Route::AdminRoles} classes="nav-link">{ "Roles" }</Link<Route>>
+                                </li>
+                            </RequirePermission>
+                        </ul>
                     </div>
                 </div>
             </nav>
+            if user_ctx.is_authenticated() {
+                { for user_ctx.non_validated_emails().into_iter().map(|email| html!(<EmailBanner key={email.as_str()} email={email.clone()} />)) }
+            }
         </>
     )
 }
\ No newline at end of file
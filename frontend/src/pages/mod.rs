@@ -1,5 +1,10 @@
+pub mod admin;
+pub mod confirm_email;
 pub mod footer;
 pub mod header;
 pub mod home;
+pub mod moderation;
+pub mod my_reports;
 pub mod page_not_found;
-pub mod report;
\ No newline at end of file
+pub mod report;
+pub mod report_detail;
\ No newline at end of file
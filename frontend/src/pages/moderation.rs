@@ -0,0 +1,455 @@
+use crate::app::Route;
+use crate::components::document_button::DocumentButton;
+use crate::components::report_list::ReportList;
+use crate::components::stored_picture::StoredPicture;
+use crate::error::Error;
+use crate::hooks::use_user_context;
+use crate::pages::report::feedback;
+use crate::pages::report_detail::{history, report_view};
+use crate::services::moderation::{
+    moderated_report, moderation_queue, plate_history, rejection_reasons, report_forwards,
+    set_report_status, similar_pictures,
+};
+use crate::types::duplicate::{PlateHistory, PlateSpot};
+use crate::types::forward::{forward_class, Forward, InboundMessage, MessageKind};
+use crate::types::picture::SimilarPicture;
+use crate::types::report::{display_time, status_class, ReportQuery, ReportStatus, StatusUpdate};
+use crate::types::ErrorInfo;
+use web_sys::{HtmlSelectElement, HtmlTextAreaElement};
+use yew::prelude::*;
+use yew_hooks::use_async;
+use yew_router::prelude::*;
+
+/// Reports waiting for a moderator, oldest first, or the reports in the
+/// status filtered by
+#[function_component(ModerationQueue)]
+pub fn moderation_queue_page() -> Html {
+    let navigator = use_navigator();
+    let query = use_location()
+        .and_then(|l| l.query::<ReportQuery>().ok())
+        .unwrap_or_default();
+    let reports = {
+        let query = query.clone();
+        use_async(async move { moderation_queue(&query).await })
+    };
+    {
+        let reports = reports.clone();
+        use_effect_with_deps(
+            move |_| {
+                reports.run();
+                || ()
+            },
+            query.clone(),
+        );
+    }
+
+    let onquery = Callback::from(move |query: ReportQuery| {
+        if let Some(navigator) = &navigator {
+            navigator.push_with_query(&Route::ModerationQueue, &query).ok();
+        }
+    });
+
+    html!(
+        <section>
+            <h1 class="h3">{ "Moderation" }</h1>
+            if let Some(error) = &reports.error {
+                <div class="alert alert-danger" role="alert">{ error.to_string() }</div>
+            }
+            <ReportList
+                {query}
+                page={reports.data.clone()}
+                loading={reports.loading}
+                {onquery}
+                link={Callback::from(|id| Route::ModerationReport { id })}
+                any_status="Waiting for review" />
+        </section>
+    )
+}
+
+#[derive(Properties, Clone, PartialEq)]
+pub struct ModerationReportProps {
+    pub id: i64,
+}
+
+/// Label of the button moving a report to `status`
+fn action_label(status: ReportStatus) -> &'static str {
+    match status {
+        ReportStatus::Submitted => "Return to queue",
+        ReportStatus::UnderReview => "Start review",
+        ReportStatus::Forwarded => "Forward",
+        ReportStatus::Resolved => "Mark resolved",
+        ReportStatus::Rejected => "Reject",
+        ReportStatus::Withdrawn => "Withdraw",
+        ReportStatus::Draft => "Back to draft",
+    }
+}
+
+fn inbound_message(message: &InboundMessage) -> Html {
+    let kind = match message.kind {
+        MessageKind::Reply => html!(<span class="badge text-bg-success">{ "Reply" }</span>),
+        MessageKind::Bounce => html!(<span class="badge text-bg-danger">{ "Bounce" }</span>),
+    };
+    html!(
+        <li class="list-group-item">
+            <div class="d-flex gap-2 align-items-baseline">
+                { kind }
+                <strong class="me-auto text-break">{ message.subject.clone().unwrap_or_default() }</strong>
+                <small class="text-muted">{ display_time(&message.received_at) }</small>
+            </div>
+            if let Some(sender) = &message.sender {
+                <div class="small text-muted">{ sender }</div>
+            }
+            <p class="mb-0 mt-1 text-break" style="white-space: pre-wrap">{ &message.body }</p>
+        </li>
+    )
+}
+
+fn forward_entry(forward: &Forward) -> Html {
+    html!(
+        <div class="card mb-2" key={forward.id}>
+            <div class="card-header d-flex gap-2 align-items-baseline">
+                <span class={classes!("badge", forward_class(forward.status))}>{ forward.status.label() }</span>
+                <span class="me-auto">{ format!("{} <{}>", forward.authority, forward.recipient) }</span>
+                <small class="text-muted">{ display_time(&forward.sent_at) }</small>
+            </div>
+            if let Some(error) = &forward.error {
+                <div class="card-body small text-danger">{ error }</div>
+            }
+            if !forward.messages.is_empty() {
+                <ul class="list-group list-group-flush">
+                    { for forward.messages.iter().map(inbound_message) }
+                </ul>
+            }
+        </div>
+    )
+}
+
+/// Emails sent to the authorities with the replies and bounces threaded back
+fn correspondence(forwards: &[Forward]) -> Html {
+    if forwards.is_empty() {
+        return html!();
+    }
+    html!(
+        <>
+            <h2 class="h5">{ "Correspondence" }</h2>
+            <div class="mb-3">{ for forwards.iter().map(forward_entry) }</div>
+        </>
+    )
+}
+
+fn plate_spot(spot: &PlateSpot, current: i64) -> Html {
+    let place = spot.address.clone().unwrap_or_else(|| match (spot.latitude, spot.longitude) {
+        (Some(latitude), Some(longitude)) => format!("{latitude:.5}, {longitude:.5}"),
+        _ => "Unknown place".to_string(),
+    });
+    html!(
+        <li class="list-group-item">
+            <div class="d-flex gap-2 align-items-baseline">
+                <span class="me-auto">{ place }</span>
+                <span class="badge text-bg-secondary">{ spot.reports.len() }</span>
+            </div>
+            <div class="d-flex flex-wrap gap-2 small">
+                { for spot.reports.iter().map(|r| html!(
+                    <span key={r.id}>
+                        if r.id == current {
+                            <strong>{ display_time(&r.observed_at) }</strong>
+                        } else {
+                            <Link<Route> to={Route::ModerationReport { id: r.id }}>
+                                { display_time(&r.observed_at) }
+                            </Link<Route>>
+                        }
+                        <span class={classes!("badge", "ms-1", status_class(r.status))}>{ r.status.label() }</span>
+                    </span>
+                )) }
+            </div>
+        </li>
+    )
+}
+
+/// Where the car of the report was reported lately, shown once it has other
+/// reports than this one
+fn plate_history_panel(history: &PlateHistory, current: i64) -> Html {
+    if history.total < 2 {
+        return html!();
+    }
+    html!(
+        <div class="card mb-3">
+            <div class="card-header">
+                { format!(
+                    "{} {} has {} reports in the last {} days",
+                    history.plate, history.country, history.total, history.days
+                ) }
+            </div>
+            <ul class="list-group list-group-flush">
+                { for history.spots.iter().map(|s| plate_spot(s, current)) }
+            </ul>
+        </div>
+    )
+}
+
+fn similar_entry(similar: &SimilarPicture) -> Html {
+    let likeness = if similar.distance == 0 {
+        "Same photo".to_string()
+    } else {
+        format!("{} bits apart", similar.distance)
+    };
+    html!(
+        <li class="list-group-item">
+            <div class="row g-2 align-items-center">
+                <div class="col-3">
+                    <StoredPicture name={similar.picture.clone()} class={classes!("w-100", "rounded")} />
+                </div>
+                <div class="col-3">
+                    <StoredPicture name={similar.similar.clone()} class={classes!("w-100", "rounded")} />
+                </div>
+                <div class="col-6">
+                    <Link<Route> to={Route::ModerationReport { id: similar.report_id }}>
+                        { format!("Report {}", similar.plate) }
+                    </Link<Route>>
+                    <span class={classes!("badge", "ms-1", status_class(similar.status))}>{ similar.status.label() }</span>
+                    <div class="small text-muted">{ format!("Observed {}", display_time(&similar.observed_at)) }</div>
+                    <div class="d-flex flex-wrap gap-1 mt-1">
+                        <span class="badge text-bg-secondary">{ likeness }</span>
+                        if similar.same_reporter {
+                            <span class="badge text-bg-info">{ "Same reporter" }</span>
+                        } else {
+                            <span class="badge text-bg-warning">{ "Other reporter" }</span>
+                        }
+                    </div>
+                </div>
+            </div>
+        </li>
+    )
+}
+
+/// Pictures of other reports that look like the pictures of this one, a photo
+/// reused with another date or taken from someone else
+fn similar_panel(similar: &[SimilarPicture]) -> Html {
+    if similar.is_empty() {
+        return html!();
+    }
+    html!(
+        <div class="card border-warning mb-3">
+            <div class="card-header">{ "Similar pictures" }</div>
+            <ul class="list-group list-group-flush">
+                { for similar.iter().map(similar_entry) }
+            </ul>
+        </div>
+    )
+}
+
+/// One report with the status changes the moderator is allowed to make
+#[function_component(ModerationReport)]
+pub fn moderation_report_page(props: &ModerationReportProps) -> Html {
+    let id = props.id;
+    let user_ctx = use_user_context();
+    let detail = use_async(async move { moderated_report(id).await });
+    let reasons = use_async(async move { rejection_reasons().await });
+    let forwards = use_async(async move { report_forwards(id).await });
+    let plate = use_async(async move { plate_history(id).await });
+    let similar = use_async(async move { similar_pictures(id).await });
+    let note = use_state(String::new);
+    let reason = use_state(String::new);
+    // Status chosen, a rejection waits for its reason
+    let target = use_state(|| None::<ReportStatus>);
+    // The same for the request, which runs before the chosen status renders
+    let chosen = use_mut_ref(|| None::<ReportStatus>);
+    let errors = use_state(|| None::<ErrorInfo>);
+
+    {
+        let detail = detail.clone();
+        let reasons = reasons.clone();
+        let forwards = forwards.clone();
+        let plate = plate.clone();
+        let similar = similar.clone();
+        use_effect_with_deps(
+            move |_| {
+                detail.run();
+                reasons.run();
+                forwards.run();
+                plate.run();
+                similar.run();
+                || ()
+            },
+            id,
+        );
+    }
+
+    let update = {
+        let note = note.clone();
+        let reason = reason.clone();
+        let chosen = chosen.clone();
+        use_async(async move {
+            let status = *chosen.borrow();
+            let update = StatusUpdate {
+                status: status.unwrap_or_default(),
+                note: Some(note.trim().to_string()).filter(|n| !n.is_empty()),
+                reason: Some((*reason).clone()).filter(|r| !r.is_empty()),
+            };
+            set_report_status(id, update).await
+        })
+    };
+
+    // Show the report again once its status changed, with the plate history
+    // and similar pictures listing it, and the email sent or refused when it
+    // was forwarded
+    {
+        let detail = detail.clone();
+        let forwards = forwards.clone();
+        let plate = plate.clone();
+        let similar = similar.clone();
+        let note = note.clone();
+        let reason = reason.clone();
+        let target = target.clone();
+        let errors = errors.clone();
+        use_effect_with_deps(
+            move |(updated, error)| {
+                if updated.is_some() {
+                    note.set(String::new());
+                    reason.set(String::new());
+                    target.set(None);
+                    errors.set(None);
+                    detail.run();
+                    plate.run();
+                    similar.run();
+                }
+                if let Some(Error::UnprocessableEntity(info)) = error {
+                    errors.set(Some(info.clone()));
+                }
+                if updated.is_some() || error.is_some() {
+                    forwards.run();
+                }
+                || ()
+            },
+            (update.data.clone(), update.error.clone()),
+        );
+    }
+
+    let Some(detail_data) = &detail.data else {
+        return html!(
+            <section>
+                if let Some(Error::NotFound) = &detail.error {
+                    <div class="alert alert-warning" role="alert">{ "This report does not exist." }</div>
+                } else if let Some(error) = &detail.error {
+                    <div class="alert alert-danger" role="alert">{ error.to_string() }</div>
+                } else {
+                    <div class="text-center my-3"><span class="spinner-border" role="status"></span></div>
+                }
+            </section>
+        );
+    };
+    let report = &detail_data.report;
+    let is_reporter = report.reporter_id == Some(user_ctx.id);
+    let next = report.status.next(is_reporter, &user_ctx.permissions);
+
+    let on_note = {
+        let note = note.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlTextAreaElement = e.target_unchecked_into();
+            note.set(input.value());
+        })
+    };
+    let on_reason = {
+        let reason = reason.clone();
+        Callback::from(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            reason.set(select.value());
+        })
+    };
+    // Statuses needing a reason ask for it first, the others are set at once
+    let on_action = |status: ReportStatus| {
+        let target = target.clone();
+        let chosen = chosen.clone();
+        let errors = errors.clone();
+        let update = update.clone();
+        Callback::from(move |_: MouseEvent| {
+            errors.set(None);
+            target.set(Some(status));
+            *chosen.borrow_mut() = Some(status);
+            if !status.needs_reason() {
+                update.run();
+            }
+        })
+    };
+    let on_confirm = {
+        let update = update.clone();
+        Callback::from(move |_: MouseEvent| update.run())
+    };
+    let on_cancel = {
+        let target = target.clone();
+        let errors = errors.clone();
+        Callback::from(move |_: MouseEvent| {
+            errors.set(None);
+            target.set(None);
+        })
+    };
+
+    let action_error = match &update.error {
+        Some(Error::UnprocessableEntity(_)) | None => None,
+        Some(e) => Some(e.to_string()),
+    };
+    let rejecting = target.is_some_and(|t| t.needs_reason());
+
+    html!(
+        <section>
+            <Link<Route> to={Route::ModerationQueue} classes="small">
+                <i class="fa-solid fa-arrow-left me-1"></i>{ "Moderation" }
+            </Link<Route>>
+            <div class="d-flex align-items-center gap-2 my-2">
+                <h1 class="h3 mb-0 me-auto">{ format!("Report {}", report.plate) }</h1>
+                <span class={classes!("badge", "fs-6", status_class(report.status))}>{ report.status.label() }</span>
+            </div>
+            if let Some(error) = action_error {
+                <div class="alert alert-danger" role="alert">{ error }</div>
+            }
+            { report_view(report) }
+            if let Some(history) = &plate.data {
+                { plate_history_panel(history, report.id) }
+            }
+            { similar_panel(similar.data.as_deref().unwrap_or_default()) }
+            <div class="d-flex gap-2 mb-3">
+                <DocumentButton id={report.id} />
+            </div>
+            if !next.is_empty() {
+                <div class="card mb-3">
+                    <div class="card-body">
+                        <div class="form-floating mb-2">
+                            <textarea class="form-control" id="moderationNote" style="height: 80px"
+                                value={(*note).clone()} oninput={on_note}></textarea>
+                            <label for="moderationNote">{ "Note (optional)" }</label>
+                        </div>
+                        { feedback(&errors, &["note"]) }
+                        if rejecting {
+                            <select class="form-select mb-2" aria-label="Rejection reason" onchange={on_reason}>
+                                <option value="" selected={reason.is_empty()}>{ "Choose a rejection reason" }</option>
+                                { for reasons.data.iter().flatten().map(|r| html!(
+                                    <option value={r.clone()} selected={*r == *reason}>{ r }</option>
+                                )) }
+                            </select>
+                            { feedback(&errors, &["reason"]) }
+                            <div class="d-flex gap-2">
+                                <button type="button" class="btn btn-danger" onclick={on_confirm} disabled={update.loading}>
+                                    { "Reject report" }
+                                </button>
+                                <button type="button" class="btn btn-outline-secondary" onclick={on_cancel}>{ "Cancel" }</button>
+                            </div>
+                        } else {
+                            { feedback(&errors, &["status"]) }
+                            <div class="d-flex flex-wrap gap-2">
+                                { for next.iter().map(|status| html!(
+                                    <button type="button" key={status.as_str()}
+                                        class={classes!("btn", if status.needs_reason() { "btn-outline-danger" } else { "btn-primary" })}
+                                        onclick={on_action(*status)} disabled={update.loading}>
+                                        { action_label(*status) }
+                                    </button>
+                                )) }
+                            </div>
+                        }
+                    </div>
+                </div>
+            }
+            { history(&detail_data.history) }
+            { correspondence(forwards.data.as_deref().unwrap_or_default()) }
+        </section>
+    )
+}
@@ -0,0 +1,52 @@
+use crate::app::Route;
+use crate::components::report_list::ReportList;
+use crate::services::reports::my_reports;
+use crate::types::report::ReportQuery;
+use yew::prelude::*;
+use yew_hooks::use_async;
+use yew_router::prelude::*;
+
+/// Reports the user submitted, filtered by the query of the page address so
+/// going back from a report keeps the filter
+#[function_component(MyReports)]
+pub fn my_reports_page() -> Html {
+    let navigator = use_navigator();
+    let query = use_location()
+        .and_then(|l| l.query::<ReportQuery>().ok())
+        .unwrap_or_default();
+    let reports = {
+        let query = query.clone();
+        use_async(async move { my_reports(&query).await })
+    };
+    {
+        let reports = reports.clone();
+        use_effect_with_deps(
+            move |_| {
+                reports.run();
+                || ()
+            },
+            query.clone(),
+        );
+    }
+
+    let onquery = Callback::from(move |query: ReportQuery| {
+        if let Some(navigator) = &navigator {
+            navigator.push_with_query(&Route::MyReports, &query).ok();
+        }
+    });
+
+    html!(
+        <section>
+            <h1 class="h3">{ "My reports" }</h1>
+            if let Some(error) = &reports.error {
+                <div class="alert alert-danger" role="alert">{ error.to_string() }</div>
+            }
+            <ReportList
+                {query}
+                page={reports.data.clone()}
+                loading={reports.loading}
+                {onquery}
+                link={Callback::from(|id| Route::ReportDetail { id })} />
+        </section>
+    )
+}
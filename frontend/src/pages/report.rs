@@ -1,36 +1,524 @@
-use web_sys::HtmlInputElement;
+use web_sys::{File, FileList, HtmlInputElement, HtmlSelectElement, HtmlTextAreaElement};
+use crate::components::gallery::Gallery;
 use crate::components::location::Location;
-use crate::components::location::GeoLocation;
+use crate::components::location::{GeoLocation, PositionStatus};
+use crate::components::map::MapPicker;
+use crate::error::Error;
+use crate::hooks::{use_outbox, use_position};
+use crate::services::geocode::reverse_geocode;
+use crate::services::outbox::{enqueue, is_online, load_draft, save_draft, take_shared};
+use crate::services::pictures::process;
+use crate::services::reports::{attach_pictures, find_duplicates, submit_report};
+use crate::services::requests::get_token;
+use crate::types::duplicate::Duplicate;
+use crate::types::geolocation::GeoSettings;
+use crate::types::outbox::Draft;
+use crate::types::picture::{
+    mismatch_warnings, ImageSettings, PhotoMetadata, Progress, SelectedPicture,
+};
+use crate::types::report::{display_time, NewReport};
+use crate::types::ErrorInfo;
+use common::plate::COUNTRIES;
+use std::collections::HashMap;
+use tracing::warn;
+use yew::platform::spawn_local;
 use yew::prelude::*;
-use yew_hooks::{use_counter, use_geolocation};
+use yew_hooks::{use_async, use_counter, use_map, use_mount, UseMapHandle};
+
+/// Collect files from a file input or a drop event
+fn files(list: Option<FileList>) -> Vec<File> {
+    list.map(|list| (0..list.length()).filter_map(|i| list.get(i)).collect())
+        .unwrap_or_default()
+}
+
+/// Validation messages for the given fields rendered under an input group
+pub fn feedback(errors: &Option<ErrorInfo>, fields: &[&str]) -> Html {
+    let messages = errors
+        .as_ref()
+        .map(|e| e.messages(fields))
+        .unwrap_or_default();
+    if messages.is_empty() {
+        html!()
+    } else {
+        html!(
+            <div class="invalid-feedback d-block mt-n2 mb-2">
+                { for messages.iter().map(|m| html!(<div>{ m }</div>)) }
+            </div>
+        )
+    }
+}
+
+/// Shrink newly added pictures and read their EXIF metadata in the background
+#[derive(Clone)]
+struct Photos {
+    metadata: UseMapHandle<String, PhotoMetadata>,
+    progress: UseMapHandle<String, Progress>,
+    processed: UseMapHandle<String, Vec<u8>>,
+}
+
+impl Photos {
+    fn add(&self, files: Vec<File>) {
+        for file in &files {
+            self.progress.insert(file.name(), Progress::Reading);
+        }
+        let photos = self.clone();
+        spawn_local(async move {
+            for file in files {
+                let name = file.name();
+                let progress = photos.progress.clone();
+                let (photo, result) = process(&file, ImageSettings::default(), |p| {
+                    progress.insert(name.clone(), p);
+                })
+                .await;
+                photos.metadata.insert(name.clone(), photo);
+                if let Ok(bytes) = result {
+                    photos.processed.insert(name, bytes);
+                }
+            }
+        });
+    }
+
+    fn clear(&self) {
+        self.metadata.set(HashMap::new());
+        self.progress.set(HashMap::new());
+        self.processed.set(HashMap::new());
+    }
+
+    /// Some picture is still being converted
+    fn processing(&self) -> bool {
+        self.progress.current().values().any(Progress::is_processing)
+    }
+}
+
+/// What happened to a submitted report
+#[derive(Clone, Debug, PartialEq)]
+enum Submitted {
+    Sent,
+    /// No connection, the report waits in the outbox
+    Queued,
+    /// Held back as the car was already reported there, the reporter
+    /// decides what to do with it
+    Duplicates(Vec<Duplicate>),
+    /// The pictures were added to this report instead
+    Attached(i64),
+}
+
+/// What submitting does with the report
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Choice {
+    /// Look for reports it duplicates first
+    Check,
+    /// Submit it even though it duplicates some
+    New,
+    /// Add its pictures to a report it duplicates
+    AttachTo(i64),
+}
+
+/// Report the new one likely duplicates with a button adding the pictures
+fn duplicate_entry(duplicate: &Duplicate, onattach: Callback<i64>) -> Html {
+    let id = duplicate.id;
+    let place = match duplicate.distance_meters {
+        Some(meters) => format!("{meters:.0} m away"),
+        None => "at the same address".to_string(),
+    };
+    html!(
+        <li class="list-group-item d-flex align-items-center">
+            <span class="me-auto">
+                { format!(
+                    "Report #{id} from {}, {place}, {} pictures",
+                    display_time(&duplicate.observed_at),
+                    duplicate.pictures
+                ) }
+            </span>
+            <button type="button" class="btn btn-sm btn-primary"
+                onclick={Callback::from(move |_| onattach.emit(id))}>
+                {"Add my photos to it"}
+            </button>
+        </li>
+    )
+}
+
+pub fn invalid(errors: &Option<ErrorInfo>, fields: &[&str]) -> bool {
+    errors.as_ref().is_some_and(|e| !e.messages(fields).is_empty())
+}
 
 #[function_component(Report)]
 pub fn report() -> Html {
     let drag_over = use_counter(0);
     let gps = use_state(|| false);
+    // The GPS position is edited by hand, device readings no longer move it
+    let unlocked = use_state(|| false);
+    let high_accuracy = use_state(|| true);
+    let device = use_position(*gps, *high_accuracy);
+    let form = use_state(NewReport::default);
+    let pictures = use_state(Vec::<SelectedPicture>::new);
+    let errors = use_state(|| None::<ErrorInfo>);
+    let photos = Photos {
+        metadata: use_map(HashMap::new()),
+        progress: use_map(HashMap::new()),
+        processed: use_map(HashMap::new()),
+    };
+    let metadata = photos.metadata.clone();
+    // Plate errors are shown as the user types once the field was left
+    let plate_touched = use_state(|| false);
+    // Autosave starts once a saved draft was restored, so it isn't overwritten
+    let restored = use_state(|| false);
+    let draft_restored = use_state(|| false);
+    let outbox = use_outbox();
+    // Set right before running the submission, so it isn't read from the last render
+    let choice = use_mut_ref(|| Choice::Check);
+
+    let submit = {
+        let form = form.clone();
+        let pictures = pictures.clone();
+        let photos = photos.clone();
+        let outbox = outbox.clone();
+        let choice = choice.clone();
+        use_async(async move {
+            let choice = *choice.borrow();
+            let processed = photos.processed.current().clone();
+            let queue = || async {
+                enqueue(Draft::new(&form, &pictures), &pictures, &processed)
+                    .await
+                    .map_err(|e| {
+                        warn!("Failed to queue report: {e}");
+                        Error::RequestError
+                    })?;
+                outbox.refresh();
+                Ok(Submitted::Queued)
+            };
+            if !is_online() {
+                return queue().await;
+            }
+            let progress = photos.progress.clone();
+            let on_uploaded = |picture: &SelectedPicture| {
+                progress.insert(picture.name(), Progress::Uploaded);
+            };
+            let result = match choice {
+                // Only pictures can be added to another report, by signed in users
+                Choice::Check if !pictures.is_empty() && get_token().is_some() => {
+                    match find_duplicates((*form).clone()).await {
+                        Ok(found) if !found.is_empty() => return Ok(Submitted::Duplicates(found)),
+                        Ok(_) => submit_report((*form).clone(), &pictures, &processed, on_uploaded)
+                            .await
+                            .map(|_| Submitted::Sent),
+                        Err(e) => Err(e),
+                    }
+                }
+                Choice::Check | Choice::New => {
+                    submit_report((*form).clone(), &pictures, &processed, on_uploaded)
+                        .await
+                        .map(|_| Submitted::Sent)
+                }
+                Choice::AttachTo(id) => {
+                    attach_pictures(id, (*form).clone(), &pictures, &processed, on_uploaded)
+                        .await
+                        .map(|_| Submitted::Attached(id))
+                }
+            };
+            match result {
+                Ok(submitted) => Ok(submitted),
+                // The connection dropped on the way, keep the report for later
+                Err(Error::RequestError) => queue().await,
+                Err(e) => Err(e),
+            }
+        })
+    };
+
+    {
+        let form = form.clone();
+        let pictures = pictures.clone();
+        let photos = photos.clone();
+        let restored = restored.clone();
+        let draft_restored = draft_restored.clone();
+        use_mount(move || {
+            spawn_local(async move {
+                let mut selected = Vec::new();
+                match load_draft().await {
+                    Ok(Some((draft, restored))) => {
+                        form.set(draft.report);
+                        selected = restored;
+                        draft_restored.set(true);
+                    }
+                    Ok(None) => {}
+                    Err(e) => warn!("Failed to restore draft: {e}"),
+                }
+                match take_shared().await {
+                    Ok(shared) => selected.extend(shared.into_iter().map(SelectedPicture::new)),
+                    Err(e) => warn!("Failed to read shared pictures: {e}"),
+                }
+                photos.add(selected.iter().map(|p| p.file.clone()).collect());
+                pictures.set(selected);
+                restored.set(true);
+            });
+        });
+    }
+
+    // Autosave, an emptied form removes the draft
+    {
+        let restored = *restored;
+        use_effect_with_deps(
+            move |(report, selected)| {
+                if restored {
+                    let draft = Draft::new(report, selected);
+                    let files = selected.iter().map(|p| p.file.clone()).collect::<Vec<_>>();
+                    spawn_local(async move {
+                        if let Err(e) = save_draft(&draft, &files).await {
+                            warn!("Failed to save draft: {e}");
+                        }
+                    });
+                }
+                || ()
+            },
+            ((*form).clone(), (*pictures).clone()),
+        );
+    }
+
+    {
+        let form = form.clone();
+        let pictures = pictures.clone();
+        let errors = errors.clone();
+        let photos = photos.clone();
+        let plate_touched = plate_touched.clone();
+        let draft_restored = draft_restored.clone();
+        use_effect_with_deps(
+            move |submit| {
+                if matches!(
+                    submit.data,
+                    Some(Submitted::Sent | Submitted::Queued | Submitted::Attached(_))
+                ) {
+                    draft_restored.set(false);
+                    form.set(NewReport::default());
+                    plate_touched.set(false);
+                    pictures.set(Vec::new());
+                    photos.clear();
+                    errors.set(None);
+                }
+                if let Some(Error::UnprocessableEntity(info)) = &submit.error {
+                    errors.set(Some(info.clone()));
+                }
+                || ()
+            },
+            submit.clone(),
+        );
+    }
+
+    // Prefill date and position from the photos unless the user already set them
+    {
+        let form = form.clone();
+        let pictures = pictures.clone();
+        use_effect_with_deps(
+            move |metadata: &HashMap<String, PhotoMetadata>| {
+                let photos = pictures
+                    .iter()
+                    .filter_map(|p| metadata.get(&p.name()))
+                    .collect::<Vec<_>>();
+                let mut report = (*form).clone();
+                if report.observed_at.is_empty() {
+                    if let Some(time) = photos.iter().find_map(|m| m.input_time()) {
+                        report.observed_at = time;
+                    }
+                }
+                if report.latitude.is_none() && report.address.as_deref().unwrap_or_default().is_empty() {
+                    if let Some((latitude, longitude)) = photos.iter().find_map(|m| m.position) {
+                        report.latitude = Some(latitude);
+                        report.longitude = Some(longitude);
+                    }
+                }
+                if report != *form {
+                    form.set(report);
+                }
+                || ()
+            },
+            metadata.current().clone(),
+        );
+    }
 
     let gps_enabled = *gps;
-    let onclick_gps = { Callback::from(move |_| gps.set(!*gps)) };
+    let position = form.latitude.zip(form.longitude);
+    // Street address of the position, shown so it can be checked before sending
+    let nearby = use_async(async move {
+        let Some((latitude, longitude)) = position else {
+            return Ok(None);
+        };
+        match reverse_geocode(latitude, longitude).await {
+            Ok(place) => Ok(Some(place)),
+            Err(Error::NotFound) => Ok(None),
+            Err(e) => Err(e),
+        }
+    });
+    {
+        let nearby = nearby.clone();
+        use_effect_with_deps(
+            move |(gps_enabled, position)| {
+                if *gps_enabled && position.is_some() {
+                    nearby.run();
+                }
+                || ()
+            },
+            (gps_enabled, position),
+        );
+    }
+    let reading = device.state().reading;
+    // The position follows the device until manual editing is unlocked
+    {
+        let form = form.clone();
+        use_effect_with_deps(
+            move |(reading, unlocked)| {
+                if let (Some(reading), false) = (reading, unlocked) {
+                    if form.latitude.zip(form.longitude) != Some(reading.position()) {
+                        let mut report = (*form).clone();
+                        report.latitude = Some(reading.latitude);
+                        report.longitude = Some(reading.longitude);
+                        form.set(report);
+                    }
+                }
+                || ()
+            },
+            (reading, *unlocked),
+        );
+    }
+    let onclick_gps = {
+        let form = form.clone();
+        let unlocked = unlocked.clone();
+        Callback::from(move |_| {
+            let mut report = (*form).clone();
+            report.latitude = None;
+            report.longitude = None;
+            report.address = None;
+            form.set(report);
+            unlocked.set(false);
+            gps.set(!*gps);
+        })
+    };
+    let on_toggle_lock = {
+        let unlocked = unlocked.clone();
+        Callback::from(move |_| unlocked.set(!*unlocked))
+    };
+    let on_high_accuracy = {
+        let high_accuracy = high_accuracy.clone();
+        Callback::from(move |enabled: bool| high_accuracy.set(enabled))
+    };
+    let on_refresh = {
+        let device = device.clone();
+        Callback::from(move |_| device.refresh())
+    };
+
+    let on_plate_input = {
+        let form = form.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let mut report = (*form).clone();
+            report.plate = input.value();
+            form.set(report);
+        })
+    };
+
+    let on_plate_blur = {
+        let form = form.clone();
+        let plate_touched = plate_touched.clone();
+        Callback::from(move |_: FocusEvent| {
+            plate_touched.set(true);
+            if let Ok(plate) = form.parse_plate() {
+                let mut report = (*form).clone();
+                report.plate = plate.to_string();
+                form.set(report);
+            }
+        })
+    };
+
+    let on_country_change = {
+        let form = form.clone();
+        Callback::from(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            let mut report = (*form).clone();
+            report.country = select.value();
+            form.set(report);
+        })
+    };
+
+    let on_address_input = {
+        let form = form.clone();
+        Callback::from(move |address: String| {
+            let mut report = (*form).clone();
+            report.address = Some(address);
+            form.set(report);
+        })
+    };
+
+    let on_position = {
+        let form = form.clone();
+        Callback::from(move |(latitude, longitude): (f64, f64)| {
+            let mut report = (*form).clone();
+            report.latitude = Some(latitude);
+            report.longitude = Some(longitude);
+            form.set(report);
+        })
+    };
+
+    let on_date_input = {
+        let form = form.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let mut report = (*form).clone();
+            report.observed_at = input.value();
+            form.set(report);
+        })
+    };
+
+    let on_description_input = {
+        let form = form.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlTextAreaElement = e.target_unchecked_into();
+            let mut report = (*form).clone();
+            report.description = Some(input.value());
+            form.set(report);
+        })
+    };
 
     let on_image_select = {
+        let pictures = pictures.clone();
+        let photos = photos.clone();
         Callback::from(move |e: Event| {
             let input: HtmlInputElement = e.target_unchecked_into();
+            let added = files(input.files());
+            // Allow choosing the same file again after removing it
+            input.set_value("");
+            photos.add(added.clone());
+            let mut selected = (*pictures).clone();
+            selected.extend(added.into_iter().map(SelectedPicture::new));
+            pictures.set(selected);
         })
     };
 
     let on_image_drop = {
         let drag_over = drag_over.clone();
+        let pictures = pictures.clone();
+        let photos = photos.clone();
         Callback::from(move |e: DragEvent| {
             e.prevent_default();
             drag_over.set(0);
+            let added = files(e.data_transfer().and_then(|d| d.files()))
+                .into_iter()
+                .filter(|f| f.type_() == "image/jpeg")
+                .collect::<Vec<_>>();
+            photos.add(added.clone());
+            let mut selected = (*pictures).clone();
+            selected.extend(added.into_iter().map(SelectedPicture::new));
+            pictures.set(selected);
         })
     };
 
+    let on_pictures_change = {
+        let pictures = pictures.clone();
+        Callback::from(move |selected: Vec<SelectedPicture>| pictures.set(selected))
+    };
+
     let on_drag_enter = {
         let drag_over = drag_over.clone();
         Callback::from(move |e: DragEvent| {
             e.prevent_default();
-            if let Some(input) = e.data_transfer() {
+            if e.data_transfer().is_some() {
                 drag_over.increase();
             };
         })
@@ -39,12 +527,58 @@ pub fn report() -> Html {
     let on_drag_leave = {
         let drag_over = drag_over.clone();
         Callback::from(move |e: DragEvent| {
-            if let Some(input) = e.data_transfer() {
+            if e.data_transfer().is_some() {
                 drag_over.decrease();
             };
         })
     };
 
+    let on_submit = {
+        let submit = submit.clone();
+        let choice = choice.clone();
+        let errors = errors.clone();
+        let form = form.clone();
+        let plate_touched = plate_touched.clone();
+        let following_device = gps_enabled && !*unlocked;
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            errors.set(None);
+            if form.parse_plate().is_err() {
+                plate_touched.set(true);
+                return;
+            }
+            let max_accuracy = GeoSettings::default().max_accuracy;
+            if let Some(message) = reading
+                .filter(|_| following_device)
+                .and_then(|r| r.accuracy_error(max_accuracy))
+            {
+                let mut info = ErrorInfo::default();
+                info.add("location", &message);
+                errors.set(Some(info));
+                return;
+            }
+            *choice.borrow_mut() = Choice::Check;
+            submit.run();
+        })
+    };
+
+    let on_attach = {
+        let submit = submit.clone();
+        let choice = choice.clone();
+        Callback::from(move |id: i64| {
+            *choice.borrow_mut() = Choice::AttachTo(id);
+            submit.run();
+        })
+    };
+
+    let on_submit_new = {
+        let submit = submit.clone();
+        let choice = choice.clone();
+        Callback::from(move |_: MouseEvent| {
+            *choice.borrow_mut() = Choice::New;
+            submit.run();
+        })
+    };
 
     let drag_over_class = if (*drag_over) > 0 {
         Some("btn-secondary")
@@ -60,29 +594,123 @@ pub fn report() -> Html {
         };
     });
 
+    let photo_warnings = {
+        let metadata = metadata.current();
+        let position = form.latitude.zip(form.longitude);
+        pictures
+            .iter()
+            .filter_map(|p| {
+                metadata
+                    .get(&p.name())
+                    .map(|m| mismatch_warnings(m, &p.name(), &form.observed_at, position))
+            })
+            .flatten()
+            .collect::<Vec<_>>()
+    };
+    let photo_position = if gps_enabled { None } else { form.latitude.zip(form.longitude) };
+
+    let plate_error = if *plate_touched {
+        form.parse_plate().err().map(|e| e.to_string())
+    } else {
+        None
+    };
+
+    let on_discard_draft = {
+        let form = form.clone();
+        let pictures = pictures.clone();
+        let photos = photos.clone();
+        let draft_restored = draft_restored.clone();
+        Callback::from(move |_: MouseEvent| {
+            form.set(NewReport::default());
+            pictures.set(Vec::new());
+            photos.clear();
+            draft_restored.set(false);
+        })
+    };
+
+    let general_error = match &submit.error {
+        Some(Error::UnprocessableEntity(_)) | None => None,
+        Some(e) => Some(e.to_string()),
+    };
+
     html!(
         <section class="hero is-danger is-bold is-large">
             <div class="hero-body">
-                <div class="container">
+                <form class="container" onsubmit={on_submit} novalidate={true}>
+                    if let Some(Submitted::Attached(id)) = submit.data {
+                        <div class="alert alert-success" role="alert">
+                            { format!("Your photos were added to report #{id}, thank you.") }
+                        </div>
+                    }
+                    if let Some(Submitted::Duplicates(found)) = &submit.data {
+                        <div class="alert alert-warning" role="alert">
+                            <p>{"This car was already reported here around the same time."}</p>
+                            <ul class="list-group mb-2">
+                                { for found.iter().map(|d| duplicate_entry(d, on_attach.clone())) }
+                            </ul>
+                            <button type="button" class="btn btn-sm btn-outline-secondary"
+                                disabled={submit.loading} onclick={on_submit_new}>
+                                {"Submit as a new report"}
+                            </button>
+                        </div>
+                    }
+                    if submit.data == Some(Submitted::Sent) {
+                        <div class="alert alert-success" role="alert">{"Report submitted, thank you."}</div>
+                    }
+                    if submit.data == Some(Submitted::Queued) {
+                        <div class="alert alert-info" role="alert">
+                            {"You are offline. The report was saved and will be sent as soon as you are back online."}
+                        </div>
+                    }
+                    if *draft_restored {
+                        <div class="alert alert-secondary d-flex align-items-center" role="alert">
+                            <span class="me-auto">{"Your unfinished report was restored."}</span>
+                            <button type="button" class="btn btn-sm btn-outline-secondary" onclick={on_discard_draft}>{"Discard"}</button>
+                        </div>
+                    }
+                    if let Some(error) = general_error {
+                        <div class="alert alert-danger" role="alert">{ error }</div>
+                    }
                     <div class="input-group mb-2">
                         <span class="input-group-text">
                           <i class="fa-regular fa-id-card"></i>
                         </span>
                         <div class="form-floating">
                             <input
-                                class="form-control"
+                                class={classes!("form-control", (plate_error.is_some() || invalid(&errors, &["plate"])).then_some("is-invalid"))}
                                 type="text"
                                 id="SPZGroup"
                                 placeholder="SPZ"
+                                value={form.plate.clone()}
+                                oninput={on_plate_input}
+                                onblur={on_plate_blur}
                                 />
                             <label for="SPZGroup">{"SPZ"}</label>
                         </div>
+                        <select class="form-select flex-grow-0 w-auto" aria-label="Country" onchange={on_country_change}>
+                            { for COUNTRIES.iter().map(|(code, name)| html!(
+                                <option value={*code} title={*name} selected={*code == form.country()}>{ code }</option>
+                            )) }
+                        </select>
                     </div>
+                    if let Some(error) = plate_error {
+                        <div class="invalid-feedback d-block mt-n2 mb-2">{ error }</div>
+                    } else {
+                        { feedback(&errors, &["plate"]) }
+                    }
                     <div class="input-group mb-2">
                         if gps_enabled {
-                            <GeoLocation />
+                            <GeoLocation
+                                value={form.latitude.zip(form.longitude)}
+                                onchange={on_position.clone()}
+                                unlocked={*unlocked}
+                                ontoggle={on_toggle_lock}
+                                invalid={invalid(&errors, &["location", "latitude", "longitude"])} />
                         } else {
-                            <Location />
+                            <Location
+                                value={form.address.clone().unwrap_or_default()}
+                                oninput={on_address_input}
+                                invalid={invalid(&errors, &["location", "address"])} />
                         }
                         <button type="button" class="btn btn-secondary btn-lg" onclick={onclick_gps}>
                             if gps_enabled {
@@ -93,26 +721,60 @@ pub fn report() -> Html {
                         </button>
 
                     </div>
+                    if gps_enabled {
+                        <PositionStatus
+                            state={device.state()}
+                            unlocked={*unlocked}
+                            high_accuracy={*high_accuracy}
+                            onhighaccuracy={on_high_accuracy}
+                            onrefresh={on_refresh} />
+                        <MapPicker
+                            position={form.latitude.zip(form.longitude)}
+                            accuracy={reading.map(|r| (r.position(), r.accuracy))}
+                            editable={*unlocked}
+                            onchange={on_position} />
+                        if let (Some(_), Some(Some(place))) = (position, &nearby.data) {
+                            <div class="form-text mt-n1 mb-2">
+                                <i class="fa-solid fa-location-dot me-1"></i>
+                                { place.address.to_string() }
+                            </div>
+                        }
+                    }
+                    if let Some((latitude, longitude)) = photo_position {
+                        <div class="form-text mt-n2 mb-2">
+                            { format!("Using position {latitude:.5}, {longitude:.5} from the photo") }
+                        </div>
+                    }
+                    { feedback(&errors, &["location", "address", "latitude", "longitude"]) }
                     <div class="input-group mb-2">
                         <span class="input-group-text" title={"When is the report dated"}>
                           <i class="fa-regular fa-calendar-check"></i>
                         </span>
                         <div class="form-floating">
                             <input
-                                class="form-control"
+                                class={classes!("form-control", invalid(&errors, &["observed_at"]).then_some("is-invalid"))}
                                 type="datetime-local"
                                 id="dateGroup"
+                                value={form.observed_at.clone()}
+                                oninput={on_date_input}
                                 />
                             <label for="dateGroup">{"Date"}</label>
                         </div>
                     </div>
+                    { feedback(&errors, &["observed_at"]) }
                     <div class="input-group mb-2">
                         <div class="form-floating">
-                          <textarea class="form-control" id="floatingTextarea2" style="height: 100px"></textarea>
+                          <textarea
+                              class={classes!("form-control", invalid(&errors, &["description"]).then_some("is-invalid"))}
+                              id="floatingTextarea2"
+                              style="height: 100px"
+                              value={form.description.clone().unwrap_or_default()}
+                              oninput={on_description_input}></textarea>
                           <label for="floatingTextarea2">{"Description"}</label>
                         </div>
                     </div>
-                    <div>
+                    { feedback(&errors, &["description"]) }
+                    <div class="mb-2">
                         <div class="h5">
                             {"Pictures"}
                         </div>
@@ -122,8 +784,24 @@ pub fn report() -> Html {
                                 ondragleave={on_drag_leave}
                                 ondragenter={on_drag_enter}>{"Add image "}<i class="fa-regular fa-image fa-beat"></i></button>
                         <input ref={file_picker} type="file" accept="image/jpeg" style="display:none;" onchange={on_image_select} multiple={true}/>
+                        <Gallery
+                            pictures={(*pictures).clone()}
+                            progress={photos.progress.current().clone()}
+                            onchange={on_pictures_change} />
                     </div>
-                </div>
+                    { feedback(&errors, &["pictures"]) }
+                    if !photo_warnings.is_empty() {
+                        <div class="alert alert-warning" role="alert">
+                            { for photo_warnings.iter().map(|w| html!(<div>{ w }</div>)) }
+                        </div>
+                    }
+                    <button type="submit" class="btn btn-primary btn-lg w-100" disabled={submit.loading || photos.processing()}>
+                        if submit.loading {
+                            <span class="spinner-border spinner-border-sm me-2" role="status"></span>
+                        }
+                        {"Submit report"}
+                    </button>
+                </form>
             </div>
         </section>
     )
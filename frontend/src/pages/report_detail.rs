@@ -0,0 +1,419 @@
+use crate::app::Route;
+use crate::components::document_button::DocumentButton;
+use crate::components::map::MapPicker;
+use crate::components::stored_picture::StoredPicture;
+use crate::error::Error;
+use crate::pages::report::{feedback, invalid};
+use crate::services::reports::{amend_report, report_detail, submit_draft, withdraw_report};
+use crate::types::report::{
+    amendment, display_time, status_class, NewReport, Report, ReportStatus, StatusChange,
+};
+use crate::types::ErrorInfo;
+use common::plate::COUNTRIES;
+use web_sys::{HtmlInputElement, HtmlSelectElement, HtmlTextAreaElement};
+use yew::prelude::*;
+use yew_hooks::use_async;
+use yew_router::prelude::*;
+
+#[derive(Properties, Clone, PartialEq)]
+pub struct ReportDetailProps {
+    pub id: i64,
+}
+
+/// Term and description row of the report fields
+fn field(term: &str, description: Html) -> Html {
+    html!(
+        <>
+            <dt class="col-sm-3">{ term }</dt>
+            <dd class="col-sm-9">{ description }</dd>
+        </>
+    )
+}
+
+fn history_entry(change: &StatusChange) -> Html {
+    html!(
+        <li class="list-group-item d-flex gap-2 align-items-baseline">
+            <span class={classes!("badge", status_class(change.status))}>{ change.status.label() }</span>
+            <span class="me-auto">
+                if let Some(reason) = &change.reason {
+                    <strong class="me-1">{ reason }</strong>
+                }
+                { change.note.clone().unwrap_or_default() }
+            </span>
+            <small class="text-muted">{ display_time(&change.changed_at) }</small>
+        </li>
+    )
+}
+
+/// Status changes and amendments, oldest first
+pub fn history(changes: &[StatusChange]) -> Html {
+    html!(
+        <>
+            <h2 class="h5">{ "History" }</h2>
+            <ul class="list-group mb-3">
+                { for changes.iter().map(history_entry) }
+            </ul>
+        </>
+    )
+}
+
+/// Fields, map and pictures of a stored report
+pub fn report_view(report: &Report) -> Html {
+    let position = report.latitude.zip(report.longitude);
+    html!(
+        <>
+            <dl class="row">
+                { field("Plate", html!({ format!("{} ({})", report.plate, report.country) })) }
+                { field("Observed", html!({ display_time(&report.observed_at) })) }
+                { field("Submitted", html!({ display_time(&report.created_at) })) }
+                if let Some(address) = &report.address {
+                    { field("Address", html!({ address })) }
+                }
+                if let Some((latitude, longitude)) = position {
+                    { field("Position", html!({ format!("{latitude:.5}, {longitude:.5}") })) }
+                }
+                if let Some(resolved) = &report.resolved_address {
+                    { field("Nearest address", html!({ resolved.to_string() })) }
+                }
+                if let Some(description) = &report.description {
+                    { field("Description", html!(<span class="text-break">{ description }</span>)) }
+                }
+            </dl>
+            if position.is_some() {
+                <MapPicker {position} editable={false} onchange={Callback::noop()} />
+            }
+            <div class="row row-cols-2 row-cols-md-4 g-2 mb-3">
+                { for report.pictures.iter().map(|(picture, caption)| html!(
+                    <figure class="col mb-0" key={picture.as_str()}>
+                        <StoredPicture name={picture.clone()} alt={caption.clone().unwrap_or_default()}
+                            class={classes!("w-100", "rounded")} />
+                        if let Some(caption) = caption {
+                            <figcaption class="small text-muted">{ caption }</figcaption>
+                        }
+                    </figure>
+                )) }
+            </div>
+        </>
+    )
+}
+
+/// Everything about one report of the user, with amending and withdrawing
+/// while it waits to be processed
+#[function_component(ReportDetail)]
+pub fn report_detail_page(props: &ReportDetailProps) -> Html {
+    let id = props.id;
+    let detail = use_async(async move { report_detail(id).await });
+    // Form of the amendment being edited
+    let editing = use_state(|| None::<NewReport>);
+    let errors = use_state(|| None::<ErrorInfo>);
+    let reason = use_state(String::new);
+    let confirm_withdraw = use_state(|| false);
+
+    {
+        let detail = detail.clone();
+        use_effect_with_deps(
+            move |_| {
+                detail.run();
+                || ()
+            },
+            id,
+        );
+    }
+
+    let amend = {
+        let editing = editing.clone();
+        use_async(async move {
+            let form = (*editing).clone().unwrap_or_default();
+            amend_report(id, form).await
+        })
+    };
+    let withdraw = {
+        let reason = reason.clone();
+        use_async(async move {
+            let reason = Some(reason.trim().to_string()).filter(|r| !r.is_empty());
+            withdraw_report(id, reason).await
+        })
+    };
+    let send = use_async(async move { submit_draft(id).await });
+
+    // Show the stored report again once it changed
+    {
+        let detail = detail.clone();
+        let editing = editing.clone();
+        let errors = errors.clone();
+        let confirm_withdraw = confirm_withdraw.clone();
+        use_effect_with_deps(
+            move |(amended, withdrawn, sent, amend_error)| {
+                if amended.is_some() || withdrawn.is_some() || sent.is_some() {
+                    editing.set(None);
+                    errors.set(None);
+                    confirm_withdraw.set(false);
+                    detail.run();
+                }
+                if let Some(Error::UnprocessableEntity(info)) = amend_error {
+                    errors.set(Some(info.clone()));
+                }
+                || ()
+            },
+            (
+                amend.data.clone(),
+                withdraw.data.clone(),
+                send.data.clone(),
+                amend.error.clone(),
+            ),
+        );
+    }
+
+    let Some(detail_data) = &detail.data else {
+        return html!(
+            <section>
+                if let Some(Error::NotFound) = &detail.error {
+                    <div class="alert alert-warning" role="alert">{ "This report does not exist." }</div>
+                } else if let Some(error) = &detail.error {
+                    <div class="alert alert-danger" role="alert">{ error.to_string() }</div>
+                } else {
+                    <div class="text-center my-3"><span class="spinner-border" role="status"></span></div>
+                }
+            </section>
+        );
+    };
+    let report = &detail_data.report;
+    let is_open = report.status.is_open();
+
+    // Callback editing a copy of the amendment form
+    let edit = |change: fn(&mut NewReport, String)| {
+        let editing = editing.clone();
+        move |value: String| {
+            if let Some(mut form) = (*editing).clone() {
+                change(&mut form, value);
+                editing.set(Some(form));
+            }
+        }
+    };
+    let on_input = |change: fn(&mut NewReport, String)| {
+        let edit = edit(change);
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            edit(input.value());
+        })
+    };
+    let on_plate = on_input(|f, v| f.plate = v);
+    let on_date = on_input(|f, v| f.observed_at = v);
+    let on_address = on_input(|f, v| f.address = Some(v));
+    let on_country = {
+        let edit = edit(|f, v| f.country = v);
+        Callback::from(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            edit(select.value());
+        })
+    };
+    let on_description = {
+        let edit = edit(|f, v| f.description = Some(v));
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlTextAreaElement = e.target_unchecked_into();
+            edit(input.value());
+        })
+    };
+    let on_position = {
+        let editing = editing.clone();
+        Callback::from(move |(latitude, longitude): (f64, f64)| {
+            if let Some(mut form) = (*editing).clone() {
+                form.latitude = Some(latitude);
+                form.longitude = Some(longitude);
+                editing.set(Some(form));
+            }
+        })
+    };
+    let on_caption = |index: usize| {
+        let editing = editing.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            if let Some(mut form) = (*editing).clone() {
+                form.pictures[index].1 = Some(input.value());
+                editing.set(Some(form));
+            }
+        })
+    };
+    let on_remove_picture = |index: usize| {
+        let editing = editing.clone();
+        Callback::from(move |_: MouseEvent| {
+            if let Some(mut form) = (*editing).clone() {
+                form.pictures.remove(index);
+                editing.set(Some(form));
+            }
+        })
+    };
+    let on_edit = {
+        let editing = editing.clone();
+        let errors = errors.clone();
+        let report = report.clone();
+        Callback::from(move |_: MouseEvent| {
+            errors.set(None);
+            editing.set(Some(amendment(&report)));
+        })
+    };
+    let on_cancel = {
+        let editing = editing.clone();
+        let errors = errors.clone();
+        Callback::from(move |_: MouseEvent| {
+            errors.set(None);
+            editing.set(None);
+        })
+    };
+    let on_save = {
+        let amend = amend.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            amend.run();
+        })
+    };
+    let on_reason = {
+        let reason = reason.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            reason.set(input.value());
+        })
+    };
+    let on_confirm_withdraw = {
+        let confirm_withdraw = confirm_withdraw.clone();
+        Callback::from(move |_: MouseEvent| confirm_withdraw.set(!*confirm_withdraw))
+    };
+    let on_withdraw = {
+        let withdraw = withdraw.clone();
+        Callback::from(move |_: MouseEvent| withdraw.run())
+    };
+
+    let on_send = {
+        let send = send.clone();
+        Callback::from(move |_: MouseEvent| send.run())
+    };
+
+    let action_error = match (&amend.error, withdraw.error.as_ref().or(send.error.as_ref())) {
+        (Some(Error::UnprocessableEntity(_)), _) => None,
+        (Some(e), _) | (None, Some(e)) => Some(e.to_string()),
+        (None, None) => None,
+    };
+
+    let form = |form: &NewReport| {
+        let position = form.latitude.zip(form.longitude);
+        html!(
+            <form onsubmit={on_save.clone()} novalidate={true}>
+                <div class="input-group mb-2">
+                    <div class="form-floating">
+                        <input class={classes!("form-control", invalid(&errors, &["plate"]).then_some("is-invalid"))}
+                            type="text" id="amendPlate" placeholder="SPZ" value={form.plate.clone()} oninput={on_plate.clone()} />
+                        <label for="amendPlate">{ "SPZ" }</label>
+                    </div>
+                    <select class="form-select flex-grow-0 w-auto" aria-label="Country" onchange={on_country.clone()}>
+                        { for COUNTRIES.iter().map(|(code, name)| html!(
+                            <option value={*code} title={*name} selected={*code == form.country()}>{ code }</option>
+                        )) }
+                    </select>
+                </div>
+                { feedback(&errors, &["plate"]) }
+                if position.is_some() {
+                    <MapPicker {position} onchange={on_position.clone()} />
+                } else {
+                    <div class="form-floating mb-2">
+                        <input class={classes!("form-control", invalid(&errors, &["location", "address"]).then_some("is-invalid"))}
+                            type="text" id="amendAddress" placeholder="Address"
+                            value={form.address.clone().unwrap_or_default()} oninput={on_address.clone()} />
+                        <label for="amendAddress">{ "Address" }</label>
+                    </div>
+                }
+                { feedback(&errors, &["location", "address", "latitude", "longitude"]) }
+                <div class="form-floating mb-2">
+                    <input class={classes!("form-control", invalid(&errors, &["observed_at"]).then_some("is-invalid"))}
+                        type="datetime-local" id="amendDate" value={form.observed_at.clone()} oninput={on_date.clone()} />
+                    <label for="amendDate">{ "Date" }</label>
+                </div>
+                { feedback(&errors, &["observed_at"]) }
+                <div class="form-floating mb-2">
+                    <textarea class={classes!("form-control", invalid(&errors, &["description"]).then_some("is-invalid"))}
+                        id="amendDescription" style="height: 100px"
+                        value={form.description.clone().unwrap_or_default()} oninput={on_description.clone()}></textarea>
+                    <label for="amendDescription">{ "Description" }</label>
+                </div>
+                { feedback(&errors, &["description"]) }
+                <div class="row row-cols-2 row-cols-md-4 g-2 mb-2">
+                    { for form.pictures.iter().enumerate().map(|(index, (picture, caption))| html!(
+                        <div class="col" key={picture.as_str()}>
+                            <StoredPicture name={picture.clone()} class={classes!("w-100", "rounded")} />
+                            <div class="input-group input-group-sm mt-1">
+                                <input class="form-control" type="text" placeholder="Caption"
+                                    value={caption.clone().unwrap_or_default()} oninput={on_caption(index)} />
+                                <button type="button" class="btn btn-outline-danger" title="Remove" onclick={on_remove_picture(index)}>
+                                    <i class="fa-solid fa-trash"></i>
+                                </button>
+                            </div>
+                        </div>
+                    )) }
+                </div>
+                { feedback(&errors, &["pictures"]) }
+                <div class="d-flex gap-2 mb-3">
+                    <button type="submit" class="btn btn-primary" disabled={amend.loading}>
+                        if amend.loading {
+                            <span class="spinner-border spinner-border-sm me-2" role="status"></span>
+                        }
+                        { "Save changes" }
+                    </button>
+                    <button type="button" class="btn btn-outline-secondary" onclick={on_cancel.clone()}>{ "Cancel" }</button>
+                </div>
+            </form>
+        )
+    };
+
+    html!(
+        <section>
+            <Link<Route> to={Route::MyReports} classes="small">
+                <i class="fa-solid fa-arrow-left me-1"></i>{ "My reports" }
+            </Link<Route>>
+            <div class="d-flex align-items-center gap-2 my-2">
+                <h1 class="h3 mb-0 me-auto">{ format!("Report {}", report.plate) }</h1>
+                <span class={classes!("badge", "fs-6", status_class(report.status))}>{ report.status.label() }</span>
+            </div>
+            if let Some(error) = action_error {
+                <div class="alert alert-danger" role="alert">{ error }</div>
+            }
+            if let Some(amending) = &*editing {
+                { form(amending) }
+            } else {
+                { report_view(report) }
+                <div class="d-flex flex-wrap gap-2 mb-3">
+                    if report.status == ReportStatus::Draft {
+                        <button type="button" class="btn btn-primary" onclick={on_send} disabled={send.loading}>
+                            <i class="fa-solid fa-paper-plane me-1"></i>{ "Submit for review" }
+                        </button>
+                    }
+                    if is_open {
+                        <button type="button" class="btn btn-outline-primary" onclick={on_edit}>
+                            <i class="fa-solid fa-pen me-1"></i>{ "Amend" }
+                        </button>
+                        <button type="button" class="btn btn-outline-danger" onclick={on_confirm_withdraw.clone()}>
+                            <i class="fa-solid fa-rotate-left me-1"></i>{ "Withdraw" }
+                        </button>
+                    }
+                    <DocumentButton id={report.id} />
+                </div>
+                if is_open {
+                    if *confirm_withdraw {
+                        <div class="card border-danger mb-3">
+                            <div class="card-body">
+                                <p class="card-text">{ "The report will not be processed. This cannot be undone." }</p>
+                                <input class="form-control mb-2" type="text" placeholder="Reason (optional)"
+                                    value={(*reason).clone()} oninput={on_reason} />
+                                <div class="d-flex gap-2">
+                                    <button type="button" class="btn btn-danger" onclick={on_withdraw} disabled={withdraw.loading}>
+                                        { "Withdraw report" }
+                                    </button>
+                                    <button type="button" class="btn btn-outline-secondary" onclick={on_confirm_withdraw}>{ "Keep it" }</button>
+                                </div>
+                            </div>
+                        </div>
+                    }
+                }
+            }
+            { history(&detail_data.history) }
+        </section>
+    )
+}
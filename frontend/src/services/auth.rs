@@ -12,31 +12,34 @@ pub async fn current() -> Result<UserInfo, Error> {
 }
 
 /// Login a user
+#[allow(dead_code)]
 pub async fn login(login_info: LoginInfo) -> Result<UserInfo, Error> {
     request_put::<LoginInfo, UserInfo>("users".to_string(), login_info).await
 }
 
 /// Register a new user
+#[allow(dead_code)]
 pub async fn register(register_info: RegisterInfo) -> Result<RegisterResponse, Error> {
     request_post::<RegisterInfo, RegisterResponse>("users".to_string(), register_info).await
 }
 
 /// Get current user info
+#[allow(dead_code)]
 pub async fn logout() -> Result<ApiResult, Error> {
     let result = request_patch::<(), ApiResult>("users".to_string(), ()).await;
     set_token(None);
     result
 }
 
-/// Get current user info
+/// Confirm an email address with the code from the confirmation link
 pub async fn confirm_email(code: &str) -> Result<EmailConfirmationResult, Error> {
-    request_get::<EmailConfirmationResult>(format!("/users/email?code={code}")).await
+    request_get::<EmailConfirmationResult>(format!("users/email?code={code}")).await
 }
 
-/// Get current user info
+/// Send the confirmation link again
 pub async fn resend(user_id: i64, email: String) -> Result<ApiResult, Error> {
     request_patch::<EmailResendInfo, ApiResult>(
-        "/users/email".to_string(),
+        "users/email".to_string(),
         EmailResendInfo { user_id, email },
     )
     .await
@@ -0,0 +1,11 @@
+use crate::error::Error;
+use crate::services::requests::request_get;
+use crate::types::geocode::Place;
+
+/// Nearest known address of a position, `NotFound` when none is close enough
+pub async fn reverse_geocode(latitude: f64, longitude: f64) -> Result<Place, Error> {
+    request_get::<Place>(format!(
+        "geocode/reverse?latitude={latitude}&longitude={longitude}"
+    ))
+    .await
+}
@@ -1,3 +1,10 @@
 pub mod admin;
 pub mod auth;
+pub mod geocode;
+pub mod moderation;
+pub mod outbox;
+pub mod pictures;
+pub mod pwa;
+pub mod reports;
 pub mod requests;
+pub mod storage;
@@ -0,0 +1,39 @@
+use crate::error::Error;
+use crate::services::requests::{request_get, request_post};
+use crate::types::duplicate::PlateHistory;
+use crate::types::forward::Forward;
+use crate::types::picture::SimilarPicture;
+use crate::types::report::{Report, ReportDetail, ReportPage, ReportQuery, StatusUpdate};
+
+/// Page of the moderation queue, or of the reports in the status filtered by
+pub async fn moderation_queue(query: &ReportQuery) -> Result<ReportPage, Error> {
+    request_get::<ReportPage>(format!("moderation/reports?{}", query.to_query_string())).await
+}
+
+pub async fn moderated_report(id: i64) -> Result<ReportDetail, Error> {
+    request_get::<ReportDetail>(format!("moderation/reports/{id}")).await
+}
+
+pub async fn set_report_status(id: i64, update: StatusUpdate) -> Result<Report, Error> {
+    request_post::<StatusUpdate, Report>(format!("moderation/reports/{id}/status"), update).await
+}
+
+/// Emails sent to the authorities for a report with their replies and bounces
+pub async fn report_forwards(id: i64) -> Result<Vec<Forward>, Error> {
+    request_get::<Vec<Forward>>(format!("moderation/reports/{id}/forwards")).await
+}
+
+/// Recent reports of the plate of a report, by spot
+pub async fn plate_history(id: i64) -> Result<PlateHistory, Error> {
+    request_get::<PlateHistory>(format!("moderation/reports/{id}/plate")).await
+}
+
+/// Pictures of other reports looking like the pictures of a report
+pub async fn similar_pictures(id: i64) -> Result<Vec<SimilarPicture>, Error> {
+    request_get::<Vec<SimilarPicture>>(format!("moderation/reports/{id}/similar")).await
+}
+
+/// Reasons a report can be rejected for
+pub async fn rejection_reasons() -> Result<Vec<String>, Error> {
+    request_get::<Vec<String>>("moderation/reasons".to_string()).await
+}
@@ -0,0 +1,208 @@
+//! Autosaved drafts of the Report form and the outbox of reports waiting
+//! for a connection, both kept in IndexedDB so they survive reloads.
+
+use crate::services::reports::submit_report;
+use crate::services::storage::{
+    self, all_json, get_bytes, get_file, get_json, keys, put_bytes, put_file, put_json, StorageError,
+    DRAFTS, FILES, OUTBOX,
+};
+use crate::types::outbox::{Draft, OutboxItem, OutboxStatus};
+use crate::types::picture::SelectedPicture;
+use std::cell::Cell;
+use std::collections::HashMap;
+use web_sys::File;
+
+const DRAFT_KEY: &str = "current";
+const DRAFT_FILES: &str = "draft/";
+/// Pictures shared into the app, stored by the service worker
+const SHARED_FILES: &str = "shared/";
+
+thread_local! {
+    /// Set while the outbox is being sent so reports don't go out twice
+    static FLUSHING: Cell<bool> = const { Cell::new(false) };
+}
+
+fn now() -> f64 {
+    js_sys::Date::now()
+}
+
+/// Whether the browser believes it has a connection
+pub fn is_online() -> bool {
+    web_sys::window().is_none_or(|w| w.navigator().on_line())
+}
+
+fn draft_file(name: &str) -> String {
+    format!("{DRAFT_FILES}{name}")
+}
+
+fn outbox_files(id: &str) -> String {
+    format!("outbox/{id}/")
+}
+
+/// Save the form, files are only written when they were added
+pub async fn save_draft(draft: &Draft, files: &[File]) -> storage::Result<()> {
+    if draft.is_empty() {
+        return clear_draft().await;
+    }
+    put_json(DRAFTS, DRAFT_KEY, draft).await?;
+    let stored = keys(FILES, DRAFT_FILES).await?;
+    for file in files {
+        let key = draft_file(&file.name());
+        if !stored.contains(&key) {
+            put_file(&key, file).await?;
+        }
+    }
+    for key in stored {
+        if !files.iter().any(|f| draft_file(&f.name()) == key) {
+            storage::delete(FILES, &key).await?;
+        }
+    }
+    Ok(())
+}
+
+/// The saved draft with the pictures that could be read back
+pub async fn load_draft() -> storage::Result<Option<(Draft, Vec<SelectedPicture>)>> {
+    let Some(mut draft) = get_json::<Draft>(DRAFTS, DRAFT_KEY).await? else {
+        return Ok(None);
+    };
+    let mut pictures = Vec::new();
+    let mut kept = Vec::new();
+    for picture in draft.pictures {
+        if let Some(file) = get_file(&draft_file(&picture.name)).await? {
+            pictures.push(picture.restore(file));
+            kept.push(picture);
+        }
+    }
+    draft.pictures = kept;
+    Ok(Some((draft, pictures)))
+}
+
+pub async fn clear_draft() -> storage::Result<()> {
+    storage::delete(DRAFTS, DRAFT_KEY).await?;
+    for key in keys(FILES, DRAFT_FILES).await? {
+        storage::delete(FILES, &key).await?;
+    }
+    Ok(())
+}
+
+/// Pictures shared from other apps since the last visit, removed once taken
+pub async fn take_shared() -> storage::Result<Vec<File>> {
+    let mut files = Vec::new();
+    for key in keys(FILES, SHARED_FILES).await? {
+        if let Some(file) = get_file(&key).await? {
+            files.push(file);
+        }
+        storage::delete(FILES, &key).await?;
+    }
+    Ok(files)
+}
+
+/// Queue a report with its pictures, processed versions are kept so they
+/// don't have to be converted again
+pub async fn enqueue(
+    draft: Draft,
+    pictures: &[SelectedPicture],
+    processed: &HashMap<String, Vec<u8>>,
+) -> storage::Result<OutboxItem> {
+    let item = OutboxItem::new(uuid::Uuid::new_v4().to_string(), draft, now());
+    let prefix = outbox_files(&item.id);
+    for (index, picture) in pictures.iter().enumerate() {
+        put_file(&format!("{prefix}{index}/original"), &picture.file).await?;
+        if let Some(bytes) = processed.get(&picture.name()) {
+            put_bytes(&format!("{prefix}{index}/processed"), bytes).await?;
+        }
+    }
+    // Written last so a half stored report is never sent
+    put_json(OUTBOX, &item.id, &item).await?;
+    Ok(item)
+}
+
+/// Queued and failed reports, oldest first
+pub async fn pending() -> storage::Result<Vec<OutboxItem>> {
+    let mut items = all_json::<OutboxItem>(OUTBOX).await?;
+    items.sort_by(|a, b| a.queued_at.total_cmp(&b.queued_at));
+    Ok(items)
+}
+
+/// Drop a report and its pictures from the outbox
+pub async fn discard(id: &str) -> storage::Result<()> {
+    storage::delete(OUTBOX, id).await?;
+    for key in keys(FILES, &outbox_files(id)).await? {
+        storage::delete(FILES, &key).await?;
+    }
+    Ok(())
+}
+
+/// Send a report again without waiting for its backoff
+pub async fn retry(id: &str) -> storage::Result<()> {
+    if let Some(mut item) = get_json::<OutboxItem>(OUTBOX, id).await? {
+        item.retry(now());
+        put_json(OUTBOX, id, &item).await?;
+    }
+    Ok(())
+}
+
+async fn load_pictures(
+    item: &OutboxItem,
+) -> storage::Result<(Vec<SelectedPicture>, HashMap<String, Vec<u8>>)> {
+    let prefix = outbox_files(&item.id);
+    let mut pictures = Vec::new();
+    let mut processed = HashMap::new();
+    for (index, picture) in item.draft.pictures.iter().enumerate() {
+        let file = get_file(&format!("{prefix}{index}/original"))
+            .await?
+            .ok_or(StorageError::Corrupt)?;
+        if let Some(bytes) = get_bytes(&format!("{prefix}{index}/processed")).await? {
+            processed.insert(picture.name.clone(), bytes);
+        }
+        pictures.push(picture.restore(file));
+    }
+    Ok((pictures, processed))
+}
+
+/// Clears [`FLUSHING`] however sending ends
+struct FlushGuard;
+
+impl Drop for FlushGuard {
+    fn drop(&mut self) {
+        FLUSHING.with(|f| f.set(false));
+    }
+}
+
+/// Send every report that is due, `sending` is told which one is in
+/// flight. Returns how many were sent.
+pub async fn flush(sending: impl Fn(Option<String>)) -> storage::Result<usize> {
+    if !is_online() || FLUSHING.with(|f| f.replace(true)) {
+        return Ok(0);
+    }
+    let _guard = FlushGuard;
+    let mut sent = 0;
+    for mut item in pending().await? {
+        if !item.is_due(now()) {
+            continue;
+        }
+        sending(Some(item.id.clone()));
+        let result = match load_pictures(&item).await {
+            Ok((pictures, processed)) => {
+                submit_report(item.draft.report.clone(), &pictures, &processed, |_| ()).await
+            }
+            Err(e) => {
+                item.status = OutboxStatus::Failed(format!("Pictures could not be read: {e}"));
+                put_json(OUTBOX, &item.id, &item).await?;
+                continue;
+            }
+        };
+        match result {
+            Ok(_) => {
+                discard(&item.id).await?;
+                sent += 1;
+            }
+            Err(e) => {
+                item.failed(&e, now());
+                put_json(OUTBOX, &item.id, &item).await?;
+            }
+        }
+    }
+    sending(None);
+    Ok(sent)
+}
@@ -0,0 +1,252 @@
+use crate::error::Error;
+use crate::services::requests::{request_bytes, request_post_multipart};
+use crate::types::picture::{
+    ImageSettings, PhotoMetadata, PictureUpload, Progress, SelectedPicture,
+};
+use common::photo::{exif, metadata};
+use gloo::file::futures::read_as_bytes;
+use gloo::file::{Blob, ObjectUrl};
+use gloo::timers::future::TimeoutFuture;
+use image::codecs::jpeg::{JpegDecoder, JpegEncoder};
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageDecoder, ImageEncoder, ImageError, ImageResult};
+use reqwest::multipart::{Form, Part};
+use std::collections::HashMap;
+use std::io::Cursor;
+use web_sys::File;
+
+/// Decode a JPEG and turn it upright according to its EXIF orientation
+pub fn decode(bytes: &[u8]) -> ImageResult<DynamicImage> {
+    let mut decoder = JpegDecoder::new(Cursor::new(bytes))?;
+    let orientation = decoder.orientation()?;
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+    Ok(image)
+}
+
+/// Shrink the image so its longer side is at most `max_edge`
+pub fn downsize(image: DynamicImage, max_edge: u32) -> DynamicImage {
+    if image.width().max(image.height()) <= max_edge {
+        image
+    } else {
+        image.resize(max_edge, max_edge, FilterType::Triangle)
+    }
+}
+
+/// Encode a JPEG whose only metadata are the capture time and position
+pub fn encode(image: &DynamicImage, quality: u8, metadata: &PhotoMetadata) -> ImageResult<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut encoder = JpegEncoder::new_with_quality(&mut bytes, quality);
+    if let Some(exif) = exif(metadata) {
+        encoder
+            .set_exif_metadata(exif)
+            .map_err(ImageError::Unsupported)?;
+    }
+    let rgb = image.to_rgb8();
+    encoder.write_image(
+        rgb.as_raw(),
+        rgb.width(),
+        rgb.height(),
+        image::ExtendedColorType::Rgb8,
+    )?;
+    Ok(bytes)
+}
+
+/// Turn a JPEG clockwise by quarter turns, keeping its time and position
+pub fn rotate(bytes: &[u8], quarter_turns: u8, quality: u8) -> ImageResult<Vec<u8>> {
+    let mut image = decode(bytes)?;
+    for _ in 0..quarter_turns % 4 {
+        image = image.rotate90();
+    }
+    encode(&image, quality, &metadata(bytes))
+}
+
+/// Let the browser render the progress before the next blocking step
+async fn yield_now() {
+    TimeoutFuture::new(0).await;
+}
+
+/// Read a selected file and shrink it for upload, reporting each step.
+/// Returns the photo metadata and the processed JPEG, or the error when
+/// the file could not be processed and should be sent unchanged.
+pub async fn process(
+    file: &File,
+    settings: ImageSettings,
+    progress: impl Fn(Progress),
+) -> (PhotoMetadata, Result<Vec<u8>, String>) {
+    progress(Progress::Reading);
+    let file = gloo::file::File::from(file.clone());
+    let bytes = match read_as_bytes(&file).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            progress(Progress::Failed(e.to_string()));
+            return (PhotoMetadata::default(), Err(e.to_string()));
+        }
+    };
+    let photo = metadata(&bytes);
+
+    progress(Progress::Resizing);
+    yield_now().await;
+    let image = match decode(&bytes) {
+        Ok(image) => downsize(image, settings.max_edge),
+        Err(e) => {
+            progress(Progress::Failed(e.to_string()));
+            return (photo, Err(e.to_string()));
+        }
+    };
+
+    progress(Progress::Encoding);
+    yield_now().await;
+    let result = encode(&image, settings.quality, &photo).map_err(|e| e.to_string());
+    progress(match &result {
+        Ok(processed) => Progress::Ready {
+            original: bytes.len(),
+            size: processed.len(),
+        },
+        Err(e) => Progress::Failed(e.clone()),
+    });
+    (photo, result)
+}
+
+/// Upload JPEG pictures, the result has one entry per picture in the same
+/// order. Pictures with a processed version in `processed`, keyed by name,
+/// are sent in that form, others unchanged unless they were rotated. Areas
+/// to blur are sent in a `regions` field before their picture.
+pub async fn upload_pictures(
+    pictures: &[SelectedPicture],
+    processed: &HashMap<String, Vec<u8>>,
+) -> Result<Vec<PictureUpload>, Error> {
+    let mut form = Form::new();
+    for picture in pictures {
+        let mut bytes = match processed.get(&picture.name()) {
+            Some(bytes) => bytes.clone(),
+            None => read_as_bytes(&gloo::file::File::from(picture.file.clone()))
+                .await
+                .map_err(|_| Error::RequestError)?,
+        };
+        if picture.quarter_turns > 0 {
+            yield_now().await;
+            if let Ok(rotated) = rotate(&bytes, picture.quarter_turns, ImageSettings::default().quality) {
+                bytes = rotated;
+            }
+        }
+        if !picture.blur.is_empty() {
+            // The server sees the rotated picture, so turn the regions with it
+            let regions = picture
+                .blur
+                .iter()
+                .map(|r| r.rotated(picture.quarter_turns))
+                .collect::<Vec<_>>();
+            let json = serde_json::to_string(&regions).map_err(|_| Error::RequestError)?;
+            form = form.text("regions", json);
+        }
+        let part = Part::bytes(bytes)
+            .file_name(picture.name())
+            .mime_str("image/jpeg")
+            .map_err(|_| Error::RequestError)?;
+        form = form.part("picture", part);
+    }
+    request_post_multipart::<Vec<PictureUpload>>("pictures".to_string(), form).await
+}
+
+/// Published copy of an uploaded picture, fetched with the bearer token since
+/// the api does not serve pictures to anonymous `<img>` requests
+pub async fn fetch_picture(name: &str) -> Result<ObjectUrl, Error> {
+    let bytes = request_bytes(format!("pictures/{name}")).await?;
+    Ok(ObjectUrl::from(Blob::new_with_options(
+        bytes.as_slice(),
+        Some("image/jpeg"),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::photo::exif_fields;
+    use exif::{Field, In, Reader, Tag, Value};
+    use image::{Rgb, RgbImage};
+
+    /// Camera JPEG with orientation and a tag the upload doesn't need
+    fn photo(width: u32, height: u32) -> Vec<u8> {
+        let image = RgbImage::from_fn(width, height, |x, y| {
+            Rgb([(x % 256) as u8, (y % 256) as u8, 128])
+        });
+        let mut fields = exif_fields(&PhotoMetadata {
+            taken_at: Some("2023-05-01T12:34:56".to_string()),
+            position: Some((50.0875, 14.4213)),
+        });
+        fields.push(Field {
+            tag: Tag::Make,
+            ifd_num: In::PRIMARY,
+            value: Value::Ascii(vec![b"Phone".to_vec()]),
+        });
+        fields.push(Field {
+            tag: Tag::Orientation,
+            ifd_num: In::PRIMARY,
+            value: Value::Short(vec![6]),
+        });
+        let mut writer = exif::experimental::Writer::new();
+        for f in &fields {
+            writer.push_field(f);
+        }
+        let mut tiff = Cursor::new(Vec::new());
+        writer.write(&mut tiff, false).unwrap();
+
+        let mut jpeg = Vec::new();
+        let mut encoder = JpegEncoder::new_with_quality(&mut jpeg, 95);
+        encoder.set_exif_metadata(tiff.into_inner()).unwrap();
+        encoder.encode_image(&image).unwrap();
+        jpeg
+    }
+
+    #[test]
+    fn keeps_time_and_position() {
+        let photo = PhotoMetadata {
+            taken_at: Some("2023-05-01T12:34:56".to_string()),
+            position: Some((50.0875, -14.4213)),
+        };
+        let image = DynamicImage::ImageRgb8(RgbImage::new(8, 8));
+        let read = metadata(&encode(&image, 80, &photo).unwrap());
+        assert_eq!(read.taken_at, photo.taken_at);
+        let (lat, lon) = read.position.unwrap();
+        assert!((lat - 50.0875).abs() < 1e-5 && (lon + 14.4213).abs() < 1e-5);
+        assert_eq!(exif(&PhotoMetadata::default()), None);
+    }
+
+    #[test]
+    fn shrinks_and_strips() {
+        let original = photo(400, 300);
+        // Orientation 6 is rotated by 90 degrees, the upright picture is portrait
+        let image = decode(&original).unwrap();
+        assert_eq!((image.width(), image.height()), (300, 400));
+        let image = downsize(image, 200);
+        assert_eq!((image.width(), image.height()), (150, 200));
+
+        let processed = encode(&image, 70, &metadata(&original)).unwrap();
+        assert!(processed.len() < original.len());
+        let exif = Reader::new()
+            .read_from_container(&mut Cursor::new(&processed))
+            .unwrap();
+        assert!(exif.get_field(Tag::Make, In::PRIMARY).is_none());
+        assert!(exif.get_field(Tag::Orientation, In::PRIMARY).is_none());
+        assert_eq!(
+            metadata(&processed).taken_at.as_deref(),
+            Some("2023-05-01T12:34:56")
+        );
+    }
+
+    #[test]
+    fn rotates() {
+        let rotated = rotate(&photo(40, 30), 3, 80).unwrap();
+        // Orientation 6 already turned it upright, three more quarter turns make it landscape
+        let image = decode(&rotated).unwrap();
+        assert_eq!((image.width(), image.height()), (40, 30));
+        assert!(metadata(&rotated).position.is_some());
+    }
+
+    #[test]
+    fn small_pictures_keep_size() {
+        let image = decode(&photo(100, 50)).unwrap();
+        assert_eq!(downsize(image, 200).width(), 50);
+    }
+}
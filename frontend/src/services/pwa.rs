@@ -0,0 +1,79 @@
+//! Registration of the service worker in `sw.js` and switching to a new
+//! version once the user agrees.
+
+use tracing::warn;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{ServiceWorker, ServiceWorkerContainer, ServiceWorkerRegistration, ServiceWorkerState};
+use yew::platform::spawn_local;
+use yew::Callback;
+
+fn container() -> Option<ServiceWorkerContainer> {
+    let navigator = web_sys::window()?.navigator();
+    // Missing on insecure origins and in some private modes
+    js_sys::Reflect::has(&navigator, &JsValue::from_str("serviceWorker"))
+        .unwrap_or(false)
+        .then(|| navigator.service_worker())
+}
+
+/// A worker waiting to take over, unless this is the very first install
+fn waiting(container: &ServiceWorkerContainer, worker: Option<ServiceWorker>) -> Option<ServiceWorker> {
+    container.controller().and(worker)
+}
+
+/// Register the service worker, `on_update` gets a newer version waiting to activate
+pub fn register(on_update: Callback<ServiceWorker>) {
+    let Some(container) = container() else {
+        return;
+    };
+    spawn_local(async move {
+        let registration: ServiceWorkerRegistration =
+            match JsFuture::from(container.register("sw.js")).await {
+                Ok(registration) => registration.unchecked_into(),
+                Err(e) => {
+                    warn!("Failed to register the service worker: {e:?}");
+                    return;
+                }
+            };
+        // Downloaded during an earlier visit
+        if let Some(worker) = waiting(&container, registration.waiting()) {
+            on_update.emit(worker);
+        }
+        let found = registration.clone();
+        let on_update_found = Closure::<dyn FnMut()>::new(move || {
+            let Some(installing) = found.installing() else {
+                return;
+            };
+            let worker = installing.clone();
+            let container = container.clone();
+            let on_update = on_update.clone();
+            let on_state_change = Closure::<dyn FnMut()>::new(move || {
+                if worker.state() == ServiceWorkerState::Installed {
+                    if let Some(worker) = waiting(&container, Some(worker.clone())) {
+                        on_update.emit(worker);
+                    }
+                }
+            });
+            installing.set_onstatechange(Some(on_state_change.as_ref().unchecked_ref()));
+            on_state_change.forget();
+        });
+        registration.set_onupdatefound(Some(on_update_found.as_ref().unchecked_ref()));
+        on_update_found.forget();
+    });
+}
+
+/// Activate the waiting worker and reload the page once it controls it
+pub fn apply_update(worker: &ServiceWorker) {
+    if let Some(container) = container() {
+        let reload = Closure::once_into_js(|| {
+            if let Some(window) = web_sys::window() {
+                window.location().reload().ok();
+            }
+        });
+        container.set_oncontrollerchange(Some(reload.unchecked_ref()));
+    }
+    if let Err(e) = worker.post_message(&JsValue::from_str("skipWaiting")) {
+        warn!("Failed to activate the new version: {e:?}");
+    }
+}
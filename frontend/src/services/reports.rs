@@ -0,0 +1,112 @@
+use crate::error::Error;
+use crate::services::pictures::upload_pictures;
+use crate::services::requests::{request_bytes, request_get, request_post, request_put};
+use crate::types::auth::ApiResult;
+use crate::types::duplicate::Duplicate;
+use crate::types::picture::SelectedPicture;
+use crate::types::report::{NewReport, Report, ReportDetail, ReportPage, ReportQuery, Withdrawal};
+use crate::types::ErrorInfo;
+use gloo::file::{Blob, ObjectUrl};
+use std::collections::HashMap;
+
+/// Submit a new report
+pub async fn create_report(report: NewReport) -> Result<Report, Error> {
+    request_post::<NewReport, Report>("reports".to_string(), report).await
+}
+
+/// Page of the reports submitted by the current user
+pub async fn my_reports(query: &ReportQuery) -> Result<ReportPage, Error> {
+    request_get::<ReportPage>(format!("reports/mine?{}", query.to_query_string())).await
+}
+
+/// Report of the current user with its status history
+pub async fn report_detail(id: i64) -> Result<ReportDetail, Error> {
+    request_get::<ReportDetail>(format!("reports/{id}")).await
+}
+
+/// Replace the details of a report that hasn't been processed yet
+pub async fn amend_report(id: i64, report: NewReport) -> Result<Report, Error> {
+    request_put::<NewReport, Report>(format!("reports/{id}"), report).await
+}
+
+/// Send a draft for review
+pub async fn submit_draft(id: i64) -> Result<Report, Error> {
+    request_post::<(), Report>(format!("reports/{id}/submit"), ()).await
+}
+
+pub async fn withdraw_report(id: i64, reason: Option<String>) -> Result<Report, Error> {
+    request_post::<Withdrawal, Report>(format!("reports/{id}/withdraw"), Withdrawal { reason })
+        .await
+}
+
+/// Reports in moderation the report likely duplicates
+pub async fn find_duplicates(report: NewReport) -> Result<Vec<Duplicate>, Error> {
+    request_post::<NewReport, Vec<Duplicate>>("reports/duplicates".to_string(), report).await
+}
+
+/// Upload the pictures and reference them from the report. Pictures the api
+/// rejects fail the whole report with errors under `pictures`.
+async fn upload_report_pictures(
+    report: &mut NewReport,
+    pictures: &[SelectedPicture],
+    processed: &HashMap<String, Vec<u8>>,
+    on_uploaded: impl Fn(&SelectedPicture),
+) -> Result<(), Error> {
+    if pictures.is_empty() {
+        return Ok(());
+    }
+    let uploaded = upload_pictures(pictures, processed).await?;
+    let mut rejected = Vec::new();
+    for (picture, result) in pictures.iter().zip(&uploaded) {
+        match (&result.path, &result.error) {
+            (Some(path), None) => {
+                on_uploaded(picture);
+                report.pictures.push((path.clone(), picture.caption()));
+            }
+            (_, error) => rejected.push(format!(
+                "{}: {}",
+                picture.name(),
+                error.clone().unwrap_or_default()
+            )),
+        }
+    }
+    if !rejected.is_empty() {
+        return Err(Error::UnprocessableEntity(ErrorInfo {
+            errors: HashMap::from([("pictures".to_string(), rejected)]),
+        }));
+    }
+    Ok(())
+}
+
+/// Upload the pictures and submit the report referencing them
+pub async fn submit_report(
+    mut report: NewReport,
+    pictures: &[SelectedPicture],
+    processed: &HashMap<String, Vec<u8>>,
+    on_uploaded: impl Fn(&SelectedPicture),
+) -> Result<Report, Error> {
+    upload_report_pictures(&mut report, pictures, processed, on_uploaded).await?;
+    create_report(report).await
+}
+
+/// Upload the pictures and add them to a report the report duplicates
+/// instead of submitting it
+pub async fn attach_pictures(
+    id: i64,
+    mut report: NewReport,
+    pictures: &[SelectedPicture],
+    processed: &HashMap<String, Vec<u8>>,
+    on_uploaded: impl Fn(&SelectedPicture),
+) -> Result<ApiResult, Error> {
+    upload_report_pictures(&mut report, pictures, processed, on_uploaded).await?;
+    request_post::<NewReport, ApiResult>(format!("reports/{id}/pictures"), report).await
+}
+
+/// Complaint PDF of a report, fetched with the bearer token like pictures
+pub async fn report_document(id: i64) -> Result<ObjectUrl, Error> {
+    let bytes = request_bytes(format!("reports/{id}/document.pdf")).await?;
+    Ok(ObjectUrl::from(Blob::new_with_options(
+        bytes.as_slice(),
+        Some("application/pdf"),
+    )))
+}
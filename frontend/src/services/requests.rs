@@ -218,6 +218,30 @@ where
     }
 }
 
+/// Get request for a binary body, such as a picture
+pub async fn request_bytes(url: String) -> Result<Vec<u8>, Error> {
+    let url = format!("{API_ROOT}{url}");
+    let mut builder = reqwest::Client::new().get(&url);
+    if let Some(token) = get_token() {
+        builder = builder.bearer_auth(token);
+    }
+
+    debug!("url: {}", url);
+
+    let data = builder.send().await.map_err(|_| Error::RequestError)?;
+    match data.status().as_u16() {
+        200..=299 => Ok(data
+            .bytes()
+            .await
+            .map_err(|_| Error::RequestError)?
+            .to_vec()),
+        401 => Err(Error::Unauthorized(String::new())),
+        403 => Err(Error::Forbidden(String::new())),
+        404 => Err(Error::NotFound),
+        _ => Err(Error::RequestError),
+    }
+}
+
 /// Delete request
 #[allow(dead_code)]
 pub async fn request_delete<B, T>(url: String, body: B) -> Result<T, Error>
@@ -0,0 +1,176 @@
+//! Minimal async access to the browser's IndexedDB.
+//!
+//! Local storage only holds strings and a few megabytes, pictures of drafts
+//! and queued reports need IndexedDB. Values are either JSON strings or
+//! `File`s / byte arrays stored as they are. The service worker (`sw.js`)
+//! opens the same database, keep the name, version and stores in sync.
+
+use js_sys::{Array, Promise, Uint8Array};
+use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error as ThisError;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{File, IdbDatabase, IdbObjectStore, IdbOpenDbRequest, IdbRequest, IdbTransactionMode};
+
+const DATABASE: &str = "carreport";
+const VERSION: u32 = 1;
+/// Object stores created on first use
+const STORES: &[&str] = &[DRAFTS, OUTBOX, FILES];
+/// JSON of the unfinished Report form
+pub const DRAFTS: &str = "drafts";
+/// JSON of reports waiting to be sent
+pub const OUTBOX: &str = "outbox";
+/// Pictures of drafts, queued reports and ones shared from other apps
+pub const FILES: &str = "files";
+
+#[derive(ThisError, Clone, Debug, PartialEq, Eq)]
+pub enum StorageError {
+    #[error("IndexedDB is not available")]
+    Unavailable,
+
+    #[error("IndexedDB request failed: {0}")]
+    Request(String),
+
+    #[error("Stored value could not be read")]
+    Corrupt,
+}
+
+impl From<JsValue> for StorageError {
+    fn from(value: JsValue) -> Self {
+        Self::Request(value.as_string().unwrap_or_else(|| format!("{value:?}")))
+    }
+}
+
+pub type Result<T> = std::result::Result<T, StorageError>;
+
+/// Wait for a request to finish, resolving to its result
+async fn wait(request: &IdbRequest) -> Result<JsValue> {
+    let promise = Promise::new(&mut |resolve, reject| {
+        let success = request.clone();
+        let on_success = Closure::once_into_js(move || {
+            resolve
+                .call1(&JsValue::NULL, &success.result().unwrap_or(JsValue::UNDEFINED))
+                .ok();
+        });
+        let failure = request.clone();
+        let on_error = Closure::once_into_js(move || {
+            let message = failure
+                .error()
+                .ok()
+                .flatten()
+                .map_or_else(|| "unknown error".to_string(), |e| e.message());
+            reject.call1(&JsValue::NULL, &JsValue::from_str(&message)).ok();
+        });
+        request.set_onsuccess(Some(on_success.unchecked_ref()));
+        request.set_onerror(Some(on_error.unchecked_ref()));
+    });
+    Ok(JsFuture::from(promise).await?)
+}
+
+async fn open() -> Result<IdbDatabase> {
+    let factory = web_sys::window()
+        .and_then(|w| w.indexed_db().ok().flatten())
+        .ok_or(StorageError::Unavailable)?;
+    let request: IdbOpenDbRequest = factory.open_with_u32(DATABASE, VERSION)?;
+    let on_upgrade = Closure::once_into_js(move |event: web_sys::Event| {
+        let Some(request) = event.target().and_then(|t| t.dyn_into::<IdbOpenDbRequest>().ok()) else {
+            return;
+        };
+        let Ok(db) = request.result().and_then(|r| r.dyn_into::<IdbDatabase>()) else {
+            return;
+        };
+        for store in STORES {
+            if !db.object_store_names().contains(store) {
+                db.create_object_store(store).ok();
+            }
+        }
+    });
+    request.set_onupgradeneeded(Some(on_upgrade.unchecked_ref()));
+    Ok(wait(&request).await?.unchecked_into())
+}
+
+async fn store(name: &str, mode: IdbTransactionMode) -> Result<IdbObjectStore> {
+    let db = open().await?;
+    let transaction = db.transaction_with_str_and_mode(name, mode)?;
+    Ok(transaction.object_store(name)?)
+}
+
+/// Store a value under a key, replacing any previous one
+pub async fn put_value(name: &str, key: &str, value: &JsValue) -> Result<()> {
+    let store = store(name, IdbTransactionMode::Readwrite).await?;
+    wait(&store.put_with_key(value, &JsValue::from_str(key))?).await?;
+    Ok(())
+}
+
+pub async fn get_value(name: &str, key: &str) -> Result<Option<JsValue>> {
+    let store = store(name, IdbTransactionMode::Readonly).await?;
+    let value = wait(&store.get(&JsValue::from_str(key))?).await?;
+    Ok((!value.is_undefined()).then_some(value))
+}
+
+pub async fn delete(name: &str, key: &str) -> Result<()> {
+    let store = store(name, IdbTransactionMode::Readwrite).await?;
+    wait(&store.delete(&JsValue::from_str(key))?).await?;
+    Ok(())
+}
+
+/// Keys of a store starting with `prefix`
+pub async fn keys(name: &str, prefix: &str) -> Result<Vec<String>> {
+    let store = store(name, IdbTransactionMode::Readonly).await?;
+    let keys: Array = wait(&store.get_all_keys()?).await?.unchecked_into();
+    Ok(keys
+        .iter()
+        .filter_map(|k| k.as_string())
+        .filter(|k| k.starts_with(prefix))
+        .collect())
+}
+
+pub async fn put_json<T: Serialize>(name: &str, key: &str, value: &T) -> Result<()> {
+    let json = serde_json::to_string(value).map_err(|_| StorageError::Corrupt)?;
+    put_value(name, key, &JsValue::from_str(&json)).await
+}
+
+pub async fn get_json<T: DeserializeOwned>(name: &str, key: &str) -> Result<Option<T>> {
+    get_value(name, key)
+        .await?
+        .map(|value| {
+            value
+                .as_string()
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .ok_or(StorageError::Corrupt)
+        })
+        .transpose()
+}
+
+/// All JSON values of a store, unreadable ones are skipped
+pub async fn all_json<T: DeserializeOwned>(name: &str) -> Result<Vec<T>> {
+    let store = store(name, IdbTransactionMode::Readonly).await?;
+    let values: Array = wait(&store.get_all()?).await?.unchecked_into();
+    Ok(values
+        .iter()
+        .filter_map(|v| v.as_string())
+        .filter_map(|json| serde_json::from_str(&json).ok())
+        .collect())
+}
+
+pub async fn put_file(key: &str, file: &File) -> Result<()> {
+    put_value(FILES, key, file).await
+}
+
+pub async fn get_file(key: &str) -> Result<Option<File>> {
+    Ok(get_value(FILES, key)
+        .await?
+        .and_then(|v| v.dyn_into::<File>().ok()))
+}
+
+pub async fn put_bytes(key: &str, bytes: &[u8]) -> Result<()> {
+    put_value(FILES, key, &Uint8Array::from(bytes)).await
+}
+
+pub async fn get_bytes(key: &str) -> Result<Option<Vec<u8>>> {
+    Ok(get_value(FILES, key)
+        .await?
+        .and_then(|v| v.dyn_into::<Uint8Array>().ok())
+        .map(|a| a.to_vec()))
+}
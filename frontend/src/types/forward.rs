@@ -0,0 +1,10 @@
+pub use common::types::forward::{Forward, ForwardStatus, InboundMessage, MessageKind};
+
+/// Bootstrap background of the delivery badge
+pub fn forward_class(status: ForwardStatus) -> &'static str {
+    match status {
+        ForwardStatus::Sent => "text-bg-primary",
+        ForwardStatus::Failed | ForwardStatus::Bounced => "text-bg-danger",
+        ForwardStatus::Replied => "text-bg-success",
+    }
+}
@@ -0,0 +1,168 @@
+/// `PositionError` codes of the Geolocation API
+const PERMISSION_DENIED: u16 = 1;
+const TIMEOUT: u16 = 3;
+/// Readings less accurate than this many meters can't be submitted
+const DEFAULT_MAX_ACCURACY: f64 = 50.0;
+/// How long the device may take for one reading
+const DEFAULT_TIMEOUT_MS: u32 = 20_000;
+
+/// Limits of device readings, the defaults can be overridden when building
+/// with `GEO_MAX_ACCURACY` (meters) and `GEO_TIMEOUT_MS`
+#[derive(Clone, Debug, PartialEq)]
+pub struct GeoSettings {
+    pub max_accuracy: f64,
+    pub timeout_ms: u32,
+}
+
+impl Default for GeoSettings {
+    fn default() -> Self {
+        Self {
+            max_accuracy: option_env!("GEO_MAX_ACCURACY")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_MAX_ACCURACY),
+            timeout_ms: option_env!("GEO_TIMEOUT_MS")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_TIMEOUT_MS),
+        }
+    }
+}
+
+/// Position reported by the device
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Reading {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Radius of the area the device is in, in meters
+    pub accuracy: f64,
+}
+
+impl Reading {
+    pub fn position(&self) -> (f64, f64) {
+        (self.latitude, self.longitude)
+    }
+
+    /// Why the reading can't be used for a report, if it can't
+    pub fn accuracy_error(&self, max_accuracy: f64) -> Option<String> {
+        (self.accuracy > max_accuracy).then(|| {
+            format!(
+                "The position is only accurate to {:.0} m, at most {max_accuracy:.0} m is accepted. Wait for a better signal, refresh the position or enter it manually.",
+                self.accuracy
+            )
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GeoStatus {
+    /// Waiting for the device
+    #[default]
+    Locating,
+    Located,
+    /// The user or the browser refused access to the position
+    Denied,
+    /// The device could not determine its position
+    Unavailable,
+    /// No reading arrived in time
+    Timeout,
+    /// The browser has no Geolocation API, e.g. outside secure contexts
+    Unsupported,
+}
+
+impl GeoStatus {
+    pub fn from_error_code(code: u16) -> Self {
+        match code {
+            PERMISSION_DENIED => Self::Denied,
+            TIMEOUT => Self::Timeout,
+            _ => Self::Unavailable,
+        }
+    }
+
+    /// Explanation for the user, `None` while things go well
+    pub fn message(&self) -> Option<&'static str> {
+        match self {
+            Self::Locating | Self::Located => None,
+            Self::Denied => Some("Access to your position was denied. Allow it in the browser settings or enter the position manually."),
+            Self::Unavailable => Some("Your device could not determine its position."),
+            Self::Timeout => Some("Determining your position took too long."),
+            Self::Unsupported => Some("This browser cannot determine your position."),
+        }
+    }
+}
+
+/// What happened to the watched position
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GeoEvent {
+    /// Watching (re)started
+    Start,
+    Read(Reading),
+    Failed(GeoStatus),
+    /// Watching stopped, the next start begins without a reading
+    Reset,
+}
+
+/// Device position as far as it is known, the last reading is kept when a
+/// later one fails
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GeoState {
+    pub status: GeoStatus,
+    pub reading: Option<Reading>,
+}
+
+impl GeoState {
+    pub fn apply(self, event: GeoEvent) -> Self {
+        match event {
+            GeoEvent::Start => Self {
+                status: GeoStatus::Locating,
+                ..self
+            },
+            GeoEvent::Read(reading) => Self {
+                status: GeoStatus::Located,
+                reading: Some(reading),
+            },
+            GeoEvent::Failed(status) => Self { status, ..self },
+            GeoEvent::Reset => Self::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_last_reading() {
+        let reading = Reading {
+            latitude: 50.0755,
+            longitude: 14.4378,
+            accuracy: 12.0,
+        };
+        let state = GeoState::default();
+        assert_eq!(state.status, GeoStatus::Locating);
+        let state = state.apply(GeoEvent::Read(reading));
+        assert_eq!(state.status, GeoStatus::Located);
+        let state = state.apply(GeoEvent::Failed(GeoStatus::from_error_code(3)));
+        assert_eq!(state.status, GeoStatus::Timeout);
+        assert_eq!(state.reading, Some(reading));
+        let state = state.apply(GeoEvent::Start);
+        assert_eq!(state.status, GeoStatus::Locating);
+        assert_eq!(state.reading, Some(reading));
+        assert_eq!(state.apply(GeoEvent::Reset), GeoState::default());
+        assert_eq!(GeoStatus::from_error_code(1), GeoStatus::Denied);
+        assert_eq!(GeoStatus::from_error_code(2), GeoStatus::Unavailable);
+    }
+
+    #[test]
+    fn accuracy_threshold() {
+        let mut reading = Reading {
+            latitude: 50.0,
+            longitude: 14.0,
+            accuracy: 50.0,
+        };
+        assert_eq!(reading.accuracy_error(50.0), None);
+        reading.accuracy = 120.4;
+        assert!(reading
+            .accuracy_error(50.0)
+            .unwrap()
+            .starts_with("The position is only accurate to 120 m, at most 50 m"));
+    }
+}
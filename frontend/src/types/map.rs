@@ -0,0 +1,71 @@
+/// OpenStreetMap's own tile server, fine for development but not for heavy use
+const DEFAULT_TILES: &str = "https://tile.openstreetmap.org/{z}/{x}/{y}.png";
+const DEFAULT_ATTRIBUTION: &str =
+    "&copy; <a href=\"https://www.openstreetmap.org/copyright\">OpenStreetMap</a> contributors";
+/// Prague, until a position is known
+const DEFAULT_CENTER: (f64, f64) = (50.0755, 14.4378);
+
+/// Tiles and initial view of the location map, the defaults can be
+/// overridden when building with `MAP_TILES` (a Leaflet URL template such as
+/// `http://localhost:8080/{z}/{x}/{y}.png`), `MAP_ATTRIBUTION` and
+/// `MAP_CENTER` (`latitude,longitude`)
+#[derive(Clone, Debug, PartialEq)]
+pub struct MapSettings {
+    pub tiles: &'static str,
+    pub attribution: &'static str,
+    pub center: (f64, f64),
+    /// Zoom without a position
+    pub zoom: u8,
+    /// Zoom once a position is known, close enough to tell parking spots apart
+    pub detail_zoom: u8,
+}
+
+impl Default for MapSettings {
+    fn default() -> Self {
+        Self {
+            tiles: option_env!("MAP_TILES").unwrap_or(DEFAULT_TILES),
+            attribution: option_env!("MAP_ATTRIBUTION").unwrap_or(DEFAULT_ATTRIBUTION),
+            center: option_env!("MAP_CENTER")
+                .and_then(parse_position)
+                .unwrap_or(DEFAULT_CENTER),
+            zoom: 13,
+            detail_zoom: 18,
+        }
+    }
+}
+
+/// Parse a latitude or longitude typed by the user, accepting a decimal comma
+pub fn parse_coordinate(value: &str, limit: f64) -> Option<f64> {
+    value
+        .trim()
+        .replace(',', ".")
+        .parse::<f64>()
+        .ok()
+        .filter(|v| v.is_finite() && v.abs() <= limit)
+}
+
+/// Parse `latitude,longitude`
+pub fn parse_position(value: &str) -> Option<(f64, f64)> {
+    let (latitude, longitude) = value.split_once(',')?;
+    Some((
+        parse_coordinate(latitude, 90.0)?,
+        parse_coordinate(longitude, 180.0)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coordinates() {
+        assert_eq!(parse_coordinate(" 50,0875 ", 90.0), Some(50.0875));
+        assert_eq!(parse_coordinate("-14.5", 180.0), Some(-14.5));
+        assert_eq!(parse_coordinate("91", 90.0), None);
+        assert_eq!(parse_coordinate("NaN", 90.0), None);
+        assert_eq!(parse_coordinate("", 90.0), None);
+        assert_eq!(parse_position("50.1,14.4"), Some((50.1, 14.4)));
+        assert_eq!(parse_position("50.1"), None);
+        assert_eq!(parse_position("14.4,200"), None);
+    }
+}
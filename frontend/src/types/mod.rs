@@ -1,9 +1,9 @@
 pub mod auth;
+pub mod forward;
+pub mod geolocation;
+pub mod map;
+pub mod outbox;
+pub mod picture;
+pub mod report;
 
-use std::collections::HashMap;
-use serde::{Deserialize, Serialize, Serializer};
-
-#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
-pub struct ErrorInfo {
-    pub errors: HashMap<String, Vec<String>>,
-}
+pub use common::types::{duplicate, geocode, ErrorInfo};
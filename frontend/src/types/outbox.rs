@@ -0,0 +1,201 @@
+use crate::error::Error;
+use crate::types::picture::{BlurRegion, SelectedPicture};
+use crate::types::report::NewReport;
+use serde::{Deserialize, Serialize};
+
+/// Delay before the first retry of a report that could not be sent
+const FIRST_RETRY_MS: f64 = 5_000.0;
+/// Retries never wait longer than this
+const MAX_RETRY_MS: f64 = 30.0 * 60_000.0;
+
+/// Everything about a selected picture except the file itself, which is
+/// stored separately
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct DraftPicture {
+    pub name: String,
+    pub caption: String,
+    pub quarter_turns: u8,
+    pub blur: Vec<BlurRegion>,
+}
+
+impl From<&SelectedPicture> for DraftPicture {
+    fn from(picture: &SelectedPicture) -> Self {
+        Self {
+            name: picture.name(),
+            caption: picture.caption.clone(),
+            quarter_turns: picture.quarter_turns,
+            blur: picture.blur.clone(),
+        }
+    }
+}
+
+impl DraftPicture {
+    /// Picture selection restored from a stored file
+    pub fn restore(&self, file: web_sys::File) -> SelectedPicture {
+        SelectedPicture {
+            caption: self.caption.clone(),
+            quarter_turns: self.quarter_turns,
+            blur: self.blur.clone(),
+            ..SelectedPicture::new(file)
+        }
+    }
+}
+
+/// Unfinished Report form, autosaved so nothing is lost without signal
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Draft {
+    pub report: NewReport,
+    pub pictures: Vec<DraftPicture>,
+}
+
+impl Draft {
+    pub fn new(report: &NewReport, pictures: &[SelectedPicture]) -> Self {
+        Self {
+            report: report.clone(),
+            pictures: pictures.iter().map(DraftPicture::from).collect(),
+        }
+    }
+
+    /// Nothing worth keeping was entered
+    pub fn is_empty(&self) -> bool {
+        self.pictures.is_empty() && self.report == NewReport::default()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum OutboxStatus {
+    /// Waiting for the next attempt
+    Queued,
+    /// Rejected by the api, retrying won't help until the reporter acts
+    Failed(String),
+}
+
+/// Report waiting to be submitted
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OutboxItem {
+    pub id: String,
+    pub draft: Draft,
+    /// Times in milliseconds since the epoch
+    pub queued_at: f64,
+    pub next_attempt: f64,
+    /// Failed attempts so far
+    pub attempts: u32,
+    pub status: OutboxStatus,
+    /// Why the last attempt failed
+    pub last_error: Option<String>,
+}
+
+/// Wait before the next attempt after `attempts` failed ones, doubling up to a limit
+pub fn backoff(attempts: u32) -> f64 {
+    (FIRST_RETRY_MS * 2f64.powi(attempts.saturating_sub(1).min(30) as i32)).min(MAX_RETRY_MS)
+}
+
+/// Errors worth retrying: the api could not be reached or failed on its own
+pub fn is_transient(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::RequestError | Error::InternalServerError(_) | Error::DeserializeError
+    )
+}
+
+impl OutboxItem {
+    pub fn new(id: String, draft: Draft, now: f64) -> Self {
+        Self {
+            id,
+            draft,
+            queued_at: now,
+            next_attempt: now,
+            attempts: 0,
+            status: OutboxStatus::Queued,
+            last_error: None,
+        }
+    }
+
+    pub fn is_due(&self, now: f64) -> bool {
+        self.status == OutboxStatus::Queued && self.next_attempt <= now
+    }
+
+    /// Record a failed attempt
+    pub fn failed(&mut self, error: &Error, now: f64) {
+        self.attempts += 1;
+        self.last_error = Some(error.to_string());
+        if is_transient(error) {
+            self.next_attempt = now + backoff(self.attempts);
+        } else {
+            self.status = OutboxStatus::Failed(error.to_string());
+        }
+    }
+
+    /// Try again right away
+    pub fn retry(&mut self, now: f64) {
+        self.status = OutboxStatus::Queued;
+        self.next_attempt = now;
+    }
+
+    /// Short description for the pending reports list
+    pub fn label(&self) -> String {
+        let plate = &self.draft.report.plate;
+        let plate = if plate.is_empty() { "Unknown plate" } else { plate };
+        match self.draft.pictures.len() {
+            0 => plate.to_string(),
+            1 => format!("{plate}, 1 picture"),
+            n => format!("{plate}, {n} pictures"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ErrorInfo;
+
+    fn item() -> OutboxItem {
+        let draft = Draft {
+            report: NewReport {
+                plate: "1AB 2345".to_string(),
+                ..NewReport::default()
+            },
+            pictures: vec![DraftPicture {
+                name: "a.jpg".to_string(),
+                ..DraftPicture::default()
+            }],
+        };
+        OutboxItem::new("id".to_string(), draft, 1_000.0)
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_limit() {
+        assert_eq!(backoff(1), 5_000.0);
+        assert_eq!(backoff(2), 10_000.0);
+        assert_eq!(backoff(4), 40_000.0);
+        assert_eq!(backoff(20), MAX_RETRY_MS);
+        assert_eq!(backoff(u32::MAX), MAX_RETRY_MS);
+    }
+
+    #[test]
+    fn retries_only_transient_errors() {
+        let mut item = item();
+        assert!(item.is_due(1_000.0));
+        item.failed(&Error::RequestError, 2_000.0);
+        assert_eq!(item.status, OutboxStatus::Queued);
+        assert!(!item.is_due(6_999.0));
+        assert!(item.is_due(7_000.0));
+
+        item.failed(&Error::UnprocessableEntity(ErrorInfo::default()), 8_000.0);
+        assert!(matches!(item.status, OutboxStatus::Failed(_)));
+        assert_eq!(item.attempts, 2);
+        assert!(!item.is_due(f64::MAX));
+        item.retry(9_000.0);
+        assert!(item.is_due(9_000.0));
+    }
+
+    #[test]
+    fn draft_survives_storage() {
+        let item = item();
+        let json = serde_json::to_string(&item).unwrap();
+        assert_eq!(serde_json::from_str::<OutboxItem>(&json).unwrap(), item);
+        assert_eq!(item.label(), "1AB 2345, 1 picture");
+        assert!(Draft::default().is_empty());
+        assert!(!item.draft.is_empty());
+    }
+}
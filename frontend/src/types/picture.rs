@@ -0,0 +1,276 @@
+pub use common::photo::PhotoMetadata;
+pub use common::types::picture::{BlurRegion, PictureUpload, RegionKind, SimilarPicture};
+use gloo::file::ObjectUrl;
+use time::macros::format_description;
+use time::PrimitiveDateTime;
+use web_sys::File;
+
+/// Photo time further than this from the report date is suspicious
+const TIME_TOLERANCE_MINUTES: i64 = 30;
+/// Photo position further than this from the report location is suspicious
+const DISTANCE_TOLERANCE_METERS: f64 = 300.0;
+
+/// How pictures are shrunk before upload, the defaults can be overridden
+/// when building with `IMAGE_MAX_EDGE` and `IMAGE_QUALITY`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ImageSettings {
+    /// Longest side in pixels
+    pub max_edge: u32,
+    /// JPEG quality from 1 to 100
+    pub quality: u8,
+}
+
+impl Default for ImageSettings {
+    fn default() -> Self {
+        Self {
+            max_edge: option_env!("IMAGE_MAX_EDGE")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(2048),
+            quality: option_env!("IMAGE_QUALITY")
+                .and_then(|v| v.parse::<u8>().ok())
+                .unwrap_or(80)
+                .clamp(1, 100),
+        }
+    }
+}
+
+/// Where a selected picture is on its way to the server
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Progress {
+    Reading,
+    Resizing,
+    Encoding,
+    /// Processed, sizes in bytes
+    Ready { original: usize, size: usize },
+    /// Processing failed, the original file will be uploaded
+    Failed(String),
+    /// Accepted by the server
+    Uploaded,
+}
+
+impl Progress {
+    pub fn percent(&self) -> u8 {
+        match self {
+            Self::Reading => 10,
+            Self::Resizing => 30,
+            Self::Encoding => 60,
+            Self::Ready { .. } | Self::Failed(_) => 80,
+            Self::Uploaded => 100,
+        }
+    }
+
+    /// Still being read or converted, not ready for upload
+    pub fn is_processing(&self) -> bool {
+        matches!(self, Self::Reading | Self::Resizing | Self::Encoding)
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Self::Reading => "Reading".to_string(),
+            Self::Resizing => "Resizing".to_string(),
+            Self::Encoding => "Compressing".to_string(),
+            Self::Ready { original, size } => {
+                format!("{} → {}", file_size(*original), file_size(*size))
+            }
+            Self::Failed(error) => format!("Sent unchanged: {error}"),
+            Self::Uploaded => "Uploaded".to_string(),
+        }
+    }
+}
+
+/// Human readable size in bytes
+pub fn file_size(bytes: usize) -> String {
+    match bytes {
+        b if b >= 1_000_000 => format!("{:.1} MB", b as f64 / 1_000_000.0),
+        b if b >= 1_000 => format!("{} kB", b / 1_000),
+        b => format!("{b} B"),
+    }
+}
+
+/// Picture chosen in the Report form and not uploaded yet
+#[derive(Clone)]
+pub struct SelectedPicture {
+    pub file: File,
+    /// Thumbnail source, revoked once the last clone is dropped
+    pub url: ObjectUrl,
+    pub caption: String,
+    /// Clockwise rotation in quarter turns, applied before upload
+    pub quarter_turns: u8,
+    /// Areas to blur in addition to the detected ones, in the frame of the
+    /// picture before rotation
+    pub blur: Vec<BlurRegion>,
+}
+
+impl SelectedPicture {
+    pub fn new(file: File) -> Self {
+        Self {
+            url: ObjectUrl::from(gloo::file::File::from(file.clone())),
+            file,
+            caption: String::new(),
+            quarter_turns: 0,
+            blur: Vec::new(),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        self.file.name()
+    }
+
+    pub fn rotate(&mut self) {
+        self.quarter_turns = (self.quarter_turns + 1) % 4;
+    }
+
+    /// Caption for the report, if any was entered
+    pub fn caption(&self) -> Option<String> {
+        Some(self.caption.trim().to_string()).filter(|c| !c.is_empty())
+    }
+}
+
+impl PartialEq for SelectedPicture {
+    fn eq(&self, other: &Self) -> bool {
+        self.file == other.file
+            && *self.url == *other.url
+            && self.caption == other.caption
+            && self.quarter_turns == other.quarter_turns
+            && self.blur == other.blur
+    }
+}
+
+/// Smallest side of a drawn region, smaller ones are most likely clicks
+const MIN_REGION: f64 = 0.01;
+
+/// Region dragged between two points given in fractions of the picture
+pub fn drawn_region((x1, y1): (f64, f64), (x2, y2): (f64, f64)) -> Option<BlurRegion> {
+    let clamp = |v: f64| v.clamp(0.0, 1.0);
+    let (left, right) = (clamp(x1.min(x2)), clamp(x1.max(x2)));
+    let (top, bottom) = (clamp(y1.min(y2)), clamp(y1.max(y2)));
+    (right - left >= MIN_REGION && bottom - top >= MIN_REGION).then_some(BlurRegion {
+        kind: RegionKind::Manual,
+        x: left,
+        y: top,
+        width: right - left,
+        height: bottom - top,
+    })
+}
+
+/// Move an item to another position, shifting the ones in between
+pub fn move_item<T>(items: &mut Vec<T>, from: usize, to: usize) {
+    if from < items.len() && to < items.len() {
+        let item = items.remove(from);
+        items.insert(to, item);
+    }
+}
+
+fn parse_time(value: &str) -> Option<PrimitiveDateTime> {
+    let format = format_description!(
+        "[year]-[month]-[day]T[hour]:[minute][optional [:[second]]]"
+    );
+    PrimitiveDateTime::parse(value, format).ok()
+}
+
+/// Great circle distance in meters
+pub fn distance((lat1, lon1): (f64, f64), (lat2, lon2): (f64, f64)) -> f64 {
+    const EARTH_RADIUS: f64 = 6_371_000.0;
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let dphi = (lat2 - lat1).to_radians();
+    let dlambda = (lon2 - lon1).to_radians();
+    let a = (dphi / 2.0).sin().powi(2) + phi1.cos() * phi2.cos() * (dlambda / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * a.sqrt().asin()
+}
+
+/// Explain where the photo disagrees with the report
+pub fn mismatch_warnings(
+    photo: &PhotoMetadata,
+    name: &str,
+    observed_at: &str,
+    position: Option<(f64, f64)>,
+) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if let (Some(taken), Some(observed)) = (
+        photo.taken_at.as_deref().and_then(parse_time),
+        parse_time(observed_at),
+    ) {
+        if (taken - observed).whole_minutes().abs() > TIME_TOLERANCE_MINUTES {
+            warnings.push(format!(
+                "{name} was taken at {}, which does not match the report date",
+                photo.input_time().unwrap_or_default().replace('T', " ")
+            ));
+        }
+    }
+    if let (Some(taken), Some(position)) = (photo.position, position) {
+        let meters = distance(taken, position);
+        if meters > DISTANCE_TOLERANCE_METERS {
+            warnings.push(format!(
+                "{name} was taken {:.1} km away from the report location",
+                meters / 1000.0
+            ));
+        }
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn photo() -> PhotoMetadata {
+        PhotoMetadata {
+            taken_at: Some("2023-05-01T12:34:56".to_string()),
+            position: Some((50.0875, 14.4213)),
+        }
+    }
+
+    #[test]
+    fn reorder() {
+        let mut items = vec!['a', 'b', 'c', 'd'];
+        move_item(&mut items, 0, 2);
+        assert_eq!(items, ['b', 'c', 'a', 'd']);
+        move_item(&mut items, 3, 0);
+        assert_eq!(items, ['d', 'b', 'c', 'a']);
+        move_item(&mut items, 1, 9);
+        assert_eq!(items, ['d', 'b', 'c', 'a']);
+    }
+
+    #[test]
+    fn drawn_regions() {
+        let region = drawn_region((0.75, 0.5), (0.25, 1.25)).unwrap();
+        assert_eq!(region.kind, RegionKind::Manual);
+        assert_eq!(
+            (region.x, region.y, region.width, region.height),
+            (0.25, 0.5, 0.5, 0.5)
+        );
+        assert_eq!(drawn_region((0.5, 0.5), (0.505, 0.9)), None);
+    }
+
+    #[test]
+    fn progress_label() {
+        assert_eq!(file_size(999), "999 B");
+        assert_eq!(
+            Progress::Ready { original: 5_300_000, size: 420_000 }.label(),
+            "5.3 MB → 420 kB"
+        );
+        assert!(Progress::Encoding.is_processing());
+        assert!(!Progress::Failed("Not a JPEG".to_string()).is_processing());
+    }
+
+    #[test]
+    fn distance_prague_brno() {
+        let km = distance((50.0755, 14.4378), (49.1951, 16.6068)) / 1000.0;
+        assert!((km - 185.0).abs() < 2.0, "{km}");
+    }
+
+    #[test]
+    fn matching_report() {
+        let warnings = mismatch_warnings(&photo(), "a.jpg", "2023-05-01T12:50", Some((50.0876, 14.4214)));
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert!(mismatch_warnings(&photo(), "a.jpg", "", None).is_empty());
+    }
+
+    #[test]
+    fn mismatching_report() {
+        let warnings = mismatch_warnings(&photo(), "a.jpg", "2023-05-02T12:34", Some((49.1951, 16.6068)));
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("2023-05-01 12:34"));
+        assert!(warnings[1].contains("km away"));
+    }
+}
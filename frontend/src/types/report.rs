@@ -0,0 +1,58 @@
+pub use common::types::report::{
+    NewReport, Report, ReportDetail, ReportPage, ReportQuery, ReportStatus, StatusChange,
+    StatusUpdate, Withdrawal,
+};
+
+/// Bootstrap background of the status badge
+pub fn status_class(status: ReportStatus) -> &'static str {
+    match status {
+        ReportStatus::Draft => "text-bg-light",
+        ReportStatus::Submitted => "text-bg-primary",
+        ReportStatus::UnderReview => "text-bg-info",
+        ReportStatus::Forwarded => "text-bg-warning",
+        ReportStatus::Resolved => "text-bg-success",
+        ReportStatus::Rejected => "text-bg-danger",
+        ReportStatus::Withdrawn => "text-bg-secondary",
+    }
+}
+
+/// Form values of a stored report, so it can be amended
+pub fn amendment(report: &Report) -> NewReport {
+    NewReport {
+        plate: report.plate.clone(),
+        country: report.country.clone(),
+        latitude: report.latitude,
+        longitude: report.longitude,
+        address: report.address.clone(),
+        // `datetime-local` inputs show minutes only
+        observed_at: report.observed_at.chars().take(16).collect(),
+        description: report.description.clone(),
+        pictures: report.pictures.clone(),
+    }
+}
+
+/// `2023-06-01 12:30` for a stored `2023-06-01T12:30:00`
+pub fn display_time(time: &str) -> String {
+    time.chars().take(16).collect::<String>().replacen('T', " ", 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn amendment_of_report() {
+        let report = Report {
+            id: 3,
+            plate: "1AB 2345".to_string(),
+            country: "CZ".to_string(),
+            observed_at: "2023-06-01T12:30:00".to_string(),
+            pictures: vec![("a.jpg".to_string(), Some("Front".to_string()))],
+            ..Report::default()
+        };
+        let form = amendment(&report);
+        assert_eq!(form.observed_at, "2023-06-01T12:30");
+        assert_eq!(form.pictures, report.pictures);
+        assert_eq!(display_time(&report.observed_at), "2023-06-01 12:30");
+    }
+}
@@ -0,0 +1,173 @@
+// Service worker of the Invalid Parking app.
+//
+// Caches the app shell so the Report form opens without a connection,
+// receives pictures shared from other apps and waits with activating a new
+// version until the page asks for it. `__BUILD__` is replaced by a hash of
+// the build (see Trunk.toml) so every release installs a new worker.
+
+const VERSION = "__BUILD__";
+const SHELL = `shell-${VERSION}`;
+// Vendored files loaded by the styles rather than linked from index.html
+const VENDOR = [
+  "vendor/fontawesome/webfonts/fa-brands-400.woff2",
+  "vendor/fontawesome/webfonts/fa-regular-400.woff2",
+  "vendor/fontawesome/webfonts/fa-solid-900.woff2",
+  "vendor/fontawesome/webfonts/fa-v4compatibility.woff2",
+  "vendor/leaflet/images/layers.png",
+  "vendor/leaflet/images/layers-2x.png",
+  "vendor/leaflet/images/marker-icon.png",
+  "vendor/leaflet/images/marker-icon-2x.png",
+  "vendor/leaflet/images/marker-shadow.png",
+];
+
+// Shared pictures are handed to the app through the IndexedDB the app uses
+// for drafts, keep the name, version and stores in sync with storage.rs
+const DATABASE = "carreport";
+const DATABASE_VERSION = 1;
+const STORES = ["drafts", "outbox", "files"];
+const SHARED_PREFIX = "shared/";
+
+self.addEventListener("install", (event) => {
+  event.waitUntil(precache());
+});
+
+self.addEventListener("activate", (event) => {
+  event.waitUntil(
+    caches.keys().then((keys) =>
+      Promise.all(
+        keys
+          .filter((key) => key !== SHELL)
+          .map((key) => caches.delete(key)),
+      ),
+    ).then(() => self.clients.claim()),
+  );
+});
+
+self.addEventListener("message", (event) => {
+  if (event.data === "skipWaiting") {
+    self.skipWaiting();
+  }
+});
+
+self.addEventListener("fetch", (event) => {
+  const request = event.request;
+  const url = new URL(request.url);
+  if (request.method === "POST" && url.pathname === scopePath() + "share") {
+    event.respondWith(receiveShare(request));
+  } else if (request.method !== "GET") {
+    return;
+  } else if (request.mode === "navigate") {
+    event.respondWith(networkFirst(request));
+  } else if (isStatic(url)) {
+    event.respondWith(cacheFirst(request));
+  }
+});
+
+function scopePath() {
+  return new URL(self.registration.scope).pathname;
+}
+
+// Files of the app itself: the bundle and styles Trunk puts next to
+// index.html, the icons, the manifest and the vendored libraries. Anything
+// else on the origin, such as the API, is left to the network.
+function isStatic(url) {
+  if (url.origin !== self.location.origin || !url.pathname.startsWith(scopePath())) {
+    return false;
+  }
+  const path = url.pathname.slice(scopePath().length);
+  return /^(icons|vendor)\//.test(path) || /^[^/]+\.(css|js|wasm|webmanifest)$/.test(path);
+}
+
+// index.html, everything linked from it and the vendored files the styles
+// load. Vendored files change only with their version, so a copy cached by
+// the previous release is reused instead of downloaded again.
+async function precache() {
+  const index = await fetch("./", { cache: "no-cache" });
+  const html = (await index.clone().text()).replace(/<!--[\s\S]*?-->/g, "");
+  const shell = await caches.open(SHELL);
+  await shell.put("./", index);
+  const links = [...html.matchAll(/(?:href|src)="([^"#]+)"/g)].map((match) => match[1]);
+  const urls = new Set(
+    [...links, ...VENDOR]
+      .map((link) => new URL(link, self.registration.scope))
+      .filter(isStatic)
+      .map(String),
+  );
+  await Promise.all(
+    [...urls].map(async (url) => {
+      const vendored = new URL(url).pathname.startsWith(scopePath() + "vendor/");
+      const response =
+        (vendored && (await caches.match(url))) || (await fetch(url).catch(() => null));
+      if (response && response.ok) {
+        await shell.put(url, response);
+      }
+    }),
+  );
+}
+
+// Pages always come from the network when there is one so releases show up
+async function networkFirst(request) {
+  try {
+    const response = await fetch(request);
+    if (response.ok) {
+      const cache = await caches.open(SHELL);
+      await cache.put("./", response.clone());
+    }
+    return response;
+  } catch (error) {
+    const cached = await caches.match("./");
+    if (cached) {
+      return cached;
+    }
+    throw error;
+  }
+}
+
+async function cacheFirst(request) {
+  const cached = await caches.match(request);
+  if (cached) {
+    return cached;
+  }
+  const response = await fetch(request);
+  if (response.ok) {
+    const cache = await caches.open(SHELL);
+    await cache.put(request, response.clone());
+  }
+  return response;
+}
+
+function openDatabase() {
+  return new Promise((resolve, reject) => {
+    const request = indexedDB.open(DATABASE, DATABASE_VERSION);
+    request.onupgradeneeded = () => {
+      for (const store of STORES) {
+        if (!request.result.objectStoreNames.contains(store)) {
+          request.result.createObjectStore(store);
+        }
+      }
+    };
+    request.onsuccess = () => resolve(request.result);
+    request.onerror = () => reject(request.error);
+  });
+}
+
+// Store shared pictures for the Report form and open it
+async function receiveShare(request) {
+  try {
+    const form = await request.formData();
+    const files = form.getAll("pictures").filter((file) => file instanceof File);
+    const db = await openDatabase();
+    await new Promise((resolve, reject) => {
+      const transaction = db.transaction("files", "readwrite");
+      const store = transaction.objectStore("files");
+      const stamp = Date.now();
+      files.forEach((file, index) => store.put(file, `${SHARED_PREFIX}${stamp}-${index}`));
+      transaction.oncomplete = resolve;
+      transaction.onerror = () => reject(transaction.error);
+    });
+    db.close();
+  } catch (error) {
+    console.error("Failed to receive shared pictures", error);
+  }
+  return Response.redirect(self.registration.scope, 303);
+}
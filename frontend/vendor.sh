@@ -0,0 +1,43 @@
+#!/bin/sh
+# Download the Bootstrap, Font Awesome and Leaflet releases the app uses into
+# vendor/, which is committed so the app needs no CDN. Files already there are
+# kept, delete the library's directory after changing its version. Files with
+# a published hash are checked against it.
+set -eu
+cd "$(dirname "$0")"
+
+# fetch <url> <path> [sha256|sha384 base64 digest]
+fetch() {
+  [ -f "vendor/$2" ] && return
+  mkdir -p "$(dirname "vendor/$2")"
+  curl -fsSL "$1" -o "vendor/$2.tmp"
+  if [ $# -gt 2 ]; then
+    algorithm=${3%%-*}
+    digest=$(openssl dgst "-$algorithm" -binary "vendor/$2.tmp" | openssl base64 -A)
+    if [ "$algorithm-$digest" != "$3" ]; then
+      rm "vendor/$2.tmp"
+      echo "$1 does not match $3" >&2
+      exit 1
+    fi
+  fi
+  mv "vendor/$2.tmp" "vendor/$2"
+}
+
+bootstrap=https://cdn.jsdelivr.net/npm/bootstrap@5.3.0/dist
+fetch "$bootstrap/css/bootstrap.min.css" bootstrap/bootstrap.min.css \
+  sha384-9ndCyUaIbzAi2FUVXJi0CjmCapSmO7SnpJef0486qhLnuZ2cdeRhO02iuK6FUUVM
+
+fontawesome=https://cdnjs.cloudflare.com/ajax/libs/font-awesome/6.2.0
+fetch "$fontawesome/css/all.min.css" fontawesome/css/all.min.css
+for font in fa-brands-400 fa-regular-400 fa-solid-900 fa-v4compatibility; do
+  fetch "$fontawesome/webfonts/$font.woff2" "fontawesome/webfonts/$font.woff2"
+  fetch "$fontawesome/webfonts/$font.ttf" "fontawesome/webfonts/$font.ttf"
+done
+
+# Hashes of the integrity attributes on https://leafletjs.com/download.html
+leaflet=https://unpkg.com/leaflet@1.9.4/dist
+fetch "$leaflet/leaflet.css" leaflet/leaflet.css sha256-p4NxAoJBhIIN+hmNHrzRCf9tD/miZyoHS5obTRR9BMY=
+fetch "$leaflet/leaflet.js" leaflet/leaflet.js sha256-20nQCchB9co0qIjJZRGuk2/Z9VM+kNiyxNV1lvTlZBo=
+for image in layers layers-2x marker-icon marker-icon-2x marker-shadow; do
+  fetch "$leaflet/images/$image.png" "leaflet/images/$image.png"
+done